@@ -0,0 +1,185 @@
+use bytes::{Bytes, BytesMut};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use futures::{executor::block_on, io::Cursor};
+use mqttea_core::{
+    bench::{self, Ack},
+    v5::{
+        client::ConnectOptions,
+        commons::{packet::Packet, qos::QoS},
+        packet::{
+            auth::{Auth, AuthProperties, AuthReasonCode},
+            connack::{ConnAck, ConnAckProperties},
+            connect::{will::Will, Connect},
+            disconnect::{Disconnect, DisconnectProperties},
+            ping::{PingReq, PingResp},
+            publish::Publish,
+            suback::{SubAck, SubAckReasonCode},
+            subscribe::{Subscribe, SubscriptionOptions},
+            unsuback::{UnSubAck, UnSubAckReasonCode},
+            unsubscribe::UnSubscribe,
+        },
+    },
+};
+
+/// Sizes of the variable part of each packet (payload, strings, or number of topic filters)
+const SIZES: [usize; 3] = [16, 1024, 16 * 1024];
+
+/// One packet of every type, where `size` drives whatever part of the packet can grow
+fn packets(size: usize) -> Vec<(&'static str, Packet)> {
+    let text = "x".repeat(size);
+    let payload = Bytes::from(vec![0x5A; size]);
+    let filters = (size / 16).max(1);
+
+    let will = Will {
+        topic: String::from("clients/bench-client/status"),
+        payload: payload.clone(),
+        ..Default::default()
+    };
+
+    vec![
+        (
+            "connect",
+            Packet::Connect(Connect::from(&ConnectOptions {
+                client_id: String::from("bench-client"),
+                will: Some(will),
+                ..Default::default()
+            })),
+        ),
+        (
+            "connack",
+            Packet::ConnAck(ConnAck {
+                properties: ConnAckProperties {
+                    reason_string: Some(text.clone()),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }),
+        ),
+        (
+            "publish",
+            Packet::Publish(Publish {
+                qos: QoS::One,
+                pkid: Some(1),
+                topic: String::from("factory/line-1/sensor"),
+                payload: payload.clone(),
+                ..Default::default()
+            }),
+        ),
+        ("puback", bench::ack(Ack::PubAck, 1, Some(text.clone()))),
+        ("pubrec", bench::ack(Ack::PubRec, 1, Some(text.clone()))),
+        ("pubrel", bench::ack(Ack::PubRel, 1, Some(text.clone()))),
+        ("pubcomp", bench::ack(Ack::PubComp, 1, Some(text.clone()))),
+        (
+            "subscribe",
+            Packet::Subscribe(Subscribe {
+                pkid: 1,
+                payload: (0..filters)
+                    .map(|i| {
+                        (
+                            format!("factory/line-{i}/+"),
+                            SubscriptionOptions::default(),
+                        )
+                    })
+                    .collect(),
+                ..Default::default()
+            }),
+        ),
+        (
+            "suback",
+            Packet::SubAck(SubAck {
+                pkid: 1,
+                payload: vec![SubAckReasonCode::GrantedQoS1; filters],
+                ..Default::default()
+            }),
+        ),
+        (
+            "unsubscribe",
+            Packet::UnSubscribe(UnSubscribe {
+                pkid: 1,
+                payload: (0..filters)
+                    .map(|i| format!("factory/line-{i}/+"))
+                    .collect(),
+                ..Default::default()
+            }),
+        ),
+        (
+            "unsuback",
+            Packet::UnSubAck(UnSubAck {
+                pkid: 1,
+                payload: vec![UnSubAckReasonCode::Success; filters],
+                ..Default::default()
+            }),
+        ),
+        ("pingreq", Packet::PingReq(PingReq::default())),
+        ("pingresp", Packet::PingResp(PingResp)),
+        (
+            "disconnect",
+            Packet::Disconnect(Disconnect {
+                properties: DisconnectProperties {
+                    reason_string: Some(text.clone()),
+                    ..Default::default()
+                },
+                ..Default::default()
+            }),
+        ),
+        (
+            "auth",
+            Packet::Auth(Auth {
+                reason_code: AuthReasonCode::ContinueAuthentication,
+                properties: AuthProperties {
+                    auth_method: Some(String::from("SCRAM-SHA-256")),
+                    auth_data: Some(payload.clone()),
+                    ..Default::default()
+                },
+            }),
+        ),
+    ]
+}
+
+fn codec(c: &mut Criterion) {
+    for size in SIZES {
+        for (name, packet) in packets(size) {
+            // packets without a variable part are only measured once
+            if matches!(name, "pingreq" | "pingresp") && size != SIZES[0] {
+                continue;
+            }
+
+            let mut encoded = BytesMut::new();
+            bench::encode(&packet, &mut encoded).unwrap();
+            let encoded = encoded.freeze();
+
+            let mut group = c.benchmark_group(format!("codec/{name}"));
+            group.throughput(Throughput::Bytes(encoded.len() as u64));
+
+            group.bench_with_input(BenchmarkId::new("encode", size), &packet, |b, packet| {
+                let mut buf = BytesMut::with_capacity(encoded.len());
+                b.iter(|| {
+                    buf.clear();
+                    bench::encode(packet, &mut buf).unwrap();
+                })
+            });
+
+            group.bench_with_input(BenchmarkId::new("decode", size), &encoded, |b, encoded| {
+                b.iter(|| bench::decode(&mut encoded.clone()).unwrap())
+            });
+
+            group.bench_with_input(BenchmarkId::new("write", size), &packet, |b, packet| {
+                let mut stream = Cursor::new(Vec::with_capacity(encoded.len()));
+                b.iter(|| {
+                    stream.get_mut().clear();
+                    stream.set_position(0);
+                    block_on(bench::write(packet, &mut stream)).unwrap();
+                })
+            });
+
+            group.bench_with_input(BenchmarkId::new("read", size), &encoded, |b, encoded| {
+                b.iter(|| block_on(bench::read(&mut Cursor::new(encoded.as_ref()))).unwrap())
+            });
+
+            group.finish();
+        }
+    }
+}
+
+criterion_group!(benches, codec);
+criterion_main!(benches);
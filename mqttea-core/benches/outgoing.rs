@@ -0,0 +1,163 @@
+use std::{
+    io::IoSlice,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use bytes::Bytes;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use futures::{executor::block_on, AsyncWrite};
+use mqttea_core::{
+    bench,
+    v5::{
+        client::network::outgoing::OutgoingBuffer,
+        commons::{packet::Packet, qos::QoS},
+        packet::publish::Publish,
+    },
+};
+
+/// Discards everything written to it, while counting the calls that would have been syscalls on a socket
+#[derive(Debug, Default)]
+struct SyscallCounter {
+    writes: usize,
+    flushes: usize,
+}
+
+impl AsyncWrite for SyscallCounter {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<std::io::Result<usize>> {
+        self.writes += 1;
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<std::io::Result<usize>> {
+        self.writes += 1;
+        Poll::Ready(Ok(bufs.iter().map(|b| b.len()).sum()))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        self.flushes += 1;
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+fn publishes(count: usize, payload_size: usize) -> Vec<Packet> {
+    let payload = Bytes::from(vec![0x5A; payload_size]);
+    (0..count)
+        .map(|i| {
+            Packet::Publish(Publish {
+                qos: QoS::One,
+                topic: String::from("factory/line-1/sensor"),
+                pkid: Some(i as u16 + 1),
+                payload: payload.clone(),
+                ..Default::default()
+            })
+        })
+        .collect()
+}
+
+/// Every packet written straight to the stream, a `write_all` per field (what the event loop did before `OutgoingBuffer`)
+fn streamed(packets: &[Packet], stream: &mut SyscallCounter) {
+    for packet in packets {
+        block_on(bench::write(packet, stream)).unwrap();
+    }
+}
+
+/// Every packet encoded into `OutgoingBuffer` on its own, then written with a vectored write + flush
+fn per_packet(packets: &[Packet], outgoing: &mut OutgoingBuffer, stream: &mut SyscallCounter) {
+    for packet in packets {
+        outgoing.push(packet).unwrap();
+        block_on(outgoing.flush(stream)).unwrap();
+    }
+}
+
+/// Everything queued is written with a single vectored write + flush
+fn batched(packets: &[Packet], outgoing: &mut OutgoingBuffer, stream: &mut SyscallCounter) {
+    for packet in packets {
+        outgoing.push(packet).unwrap();
+    }
+    block_on(outgoing.flush(stream)).unwrap();
+}
+
+fn report_syscalls(packets: &[Packet]) {
+    let mut outgoing = OutgoingBuffer::default();
+
+    let mut stream = SyscallCounter::default();
+    streamed(packets, &mut stream);
+    let unbuffered = (stream.writes, stream.flushes);
+
+    let mut stream = SyscallCounter::default();
+    per_packet(packets, &mut outgoing, &mut stream);
+    let unbatched = (stream.writes, stream.flushes);
+
+    let mut stream = SyscallCounter::default();
+    batched(packets, &mut outgoing, &mut stream);
+
+    println!(
+        "{} packets: streamed = {} writes + {} flushes, per-packet = {} writes + {} flushes, batched = {} writes + {} flushes",
+        packets.len(),
+        unbuffered.0,
+        unbuffered.1,
+        unbatched.0,
+        unbatched.1,
+        stream.writes,
+        stream.flushes
+    );
+}
+
+fn outgoing(c: &mut Criterion) {
+    let mut group = c.benchmark_group("outgoing");
+
+    for payload_size in [16, 1024, 64 * 1024] {
+        let packets = publishes(32, payload_size);
+        report_syscalls(&packets);
+
+        let bytes = (payload_size * packets.len()) as u64;
+        group.throughput(Throughput::Bytes(bytes));
+
+        group.bench_with_input(
+            BenchmarkId::new("streamed", payload_size),
+            &packets,
+            |b, packets| {
+                let mut stream = SyscallCounter::default();
+                b.iter(|| streamed(packets, &mut stream))
+            },
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("per_packet", payload_size),
+            &packets,
+            |b, packets| {
+                let mut outgoing = OutgoingBuffer::default();
+                let mut stream = SyscallCounter::default();
+                b.iter(|| per_packet(packets, &mut outgoing, &mut stream))
+            },
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("batched", payload_size),
+            &packets,
+            |b, packets| {
+                let mut outgoing = OutgoingBuffer::default();
+                let mut stream = SyscallCounter::default();
+                b.iter(|| batched(packets, &mut outgoing, &mut stream))
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, outgoing);
+criterion_main!(benches);
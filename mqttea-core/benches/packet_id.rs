@@ -0,0 +1,80 @@
+use std::{
+    sync::Barrier,
+    thread,
+    time::{Duration, Instant},
+};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use mqttea_core::bench::PacketIds;
+
+/// Allocates (and releases) every packet id the manager can hand out,
+/// which is the worst case for the linear scan over the shards
+fn exhaust(c: &mut Criterion) {
+    let mut group = c.benchmark_group("packet_id/exhaust");
+
+    for max in [64, 1024, u16::MAX] {
+        group.throughput(Throughput::Elements(max as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(max), &max, |b, &max| {
+            let pkids = PacketIds::new(max);
+            let mut ids = Vec::with_capacity(max as usize);
+            b.iter(|| {
+                while let Ok(id) = pkids.allocate() {
+                    ids.push(id);
+                }
+                for id in ids.drain(..) {
+                    pkids.release(id);
+                }
+            })
+        });
+    }
+
+    group.finish();
+}
+
+/// `threads` publishers sharing a manager, each allocating and releasing an id `iters` times
+fn contended(pkids: &PacketIds, threads: usize, iters: u64) -> Duration {
+    let barrier = Barrier::new(threads + 1);
+
+    thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| {
+                barrier.wait();
+                for _ in 0..iters {
+                    // the manager can be momentarily full when every other thread holds an id
+                    if let Ok(id) = pkids.allocate() {
+                        pkids.release(id);
+                    }
+                }
+            });
+        }
+
+        barrier.wait();
+        let start = Instant::now();
+        // the scope joins the threads before returning
+        start
+    })
+    .elapsed()
+}
+
+fn contention(c: &mut Criterion) {
+    let mut group = c.benchmark_group("packet_id/contention");
+
+    for max in [16, u16::MAX] {
+        for threads in [1, 2, 4, 8] {
+            group.throughput(Throughput::Elements(threads as u64));
+            group.bench_with_input(
+                BenchmarkId::new(format!("receive_max={max}"), threads),
+                &threads,
+                |b, &threads| {
+                    let pkids = PacketIds::new(max);
+                    b.iter_custom(|iters| contended(&pkids, threads, iters))
+                },
+            );
+        }
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, exhaust, contention);
+criterion_main!(benches);
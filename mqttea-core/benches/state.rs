@@ -0,0 +1,174 @@
+use std::num::NonZero;
+
+use bytes::Bytes;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use mqttea_core::{
+    bench::{self, Ack, Session},
+    v5::{
+        client::ConnectOptions,
+        commons::{packet::Packet, qos::QoS},
+        packet::{
+            publish::Publish,
+            suback::{SubAck, SubAckReasonCode},
+            subscribe::Subscribe,
+        },
+    },
+};
+
+/// Receive Maximums to compare, the active packets are tracked in vectors of this size
+const RECEIVE_MAX: [u16; 3] = [16, 1024, u16::MAX];
+
+fn options(receive_max: u16) -> ConnectOptions {
+    let receive_max = NonZero::new(receive_max).unwrap();
+    ConnectOptions {
+        server_receive_max: receive_max,
+        client_receive_max: receive_max,
+        ..Default::default()
+    }
+}
+
+fn publish(qos: QoS, pkid: Option<u16>) -> Publish {
+    Publish {
+        qos,
+        pkid,
+        topic: String::from("factory/line-1/sensor"),
+        payload: Bytes::from_static(b"21.5"),
+        ..Default::default()
+    }
+}
+
+/// QoS 1 PUBLISH sent by us, and its PUBACK
+fn outgoing_qos1(session: &Session) {
+    let pkid = session.allocate().unwrap();
+    session
+        .outgoing(Packet::Publish(publish(QoS::One, Some(pkid))))
+        .unwrap();
+    session
+        .incoming(&mut bench::ack(Ack::PubAck, pkid, None))
+        .unwrap();
+}
+
+/// QoS 2 PUBLISH sent by us, and the PUBREC, PUBREL, PUBCOMP exchange that follows
+fn outgoing_qos2(session: &Session) {
+    let pkid = session.allocate().unwrap();
+    session
+        .outgoing(Packet::Publish(publish(QoS::Two, Some(pkid))))
+        .unwrap();
+    let pubrel = session
+        .incoming(&mut bench::ack(Ack::PubRec, pkid, None))
+        .unwrap()
+        .unwrap();
+    session.outgoing(pubrel).unwrap();
+    session
+        .incoming(&mut bench::ack(Ack::PubComp, pkid, None))
+        .unwrap();
+}
+
+/// QoS 1 PUBLISH received from the server, the PUBACK is generated by the state
+fn incoming_qos1(session: &Session, pkid: u16) {
+    let mut packet = Packet::Publish(publish(QoS::One, Some(pkid)));
+    let puback = session.incoming(&mut packet).unwrap().unwrap();
+    session.outgoing(puback).unwrap();
+}
+
+/// QoS 2 PUBLISH received from the server, and the PUBREC, PUBREL, PUBCOMP exchange that follows
+fn incoming_qos2(session: &Session, pkid: u16) {
+    let mut packet = Packet::Publish(publish(QoS::Two, Some(pkid)));
+    let pubrec = session.incoming(&mut packet).unwrap().unwrap();
+    session.outgoing(pubrec).unwrap();
+    let pubcomp = session
+        .incoming(&mut bench::ack(Ack::PubRel, pkid, None))
+        .unwrap()
+        .unwrap();
+    session.outgoing(pubcomp).unwrap();
+}
+
+fn subscribe(session: &Session) {
+    let pkid = session.allocate().unwrap();
+    session
+        .outgoing(Packet::Subscribe(Subscribe {
+            pkid,
+            payload: vec![(String::from("factory/+/sensor"), Default::default())],
+            ..Default::default()
+        }))
+        .unwrap();
+    session
+        .incoming(&mut Packet::SubAck(SubAck {
+            pkid,
+            payload: vec![SubAckReasonCode::GrantedQoS0],
+            ..Default::default()
+        }))
+        .unwrap();
+}
+
+fn state(c: &mut Criterion) {
+    let mut group = c.benchmark_group("state");
+
+    for receive_max in RECEIVE_MAX {
+        let options = options(receive_max);
+
+        group.bench_with_input(
+            BenchmarkId::new("new", receive_max),
+            &options,
+            |b, options| b.iter(|| Session::new(options)),
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("outgoing_qos1", receive_max),
+            &options,
+            |b, options| {
+                let session = Session::new(options);
+                b.iter(|| outgoing_qos1(&session))
+            },
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("outgoing_qos2", receive_max),
+            &options,
+            |b, options| {
+                let session = Session::new(options);
+                b.iter(|| outgoing_qos2(&session))
+            },
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("incoming_qos1", receive_max),
+            &options,
+            |b, options| {
+                let session = Session::new(options);
+                let mut pkid = 0;
+                b.iter(|| {
+                    pkid = pkid % receive_max + 1;
+                    incoming_qos1(&session, pkid)
+                })
+            },
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("incoming_qos2", receive_max),
+            &options,
+            |b, options| {
+                let session = Session::new(options);
+                let mut pkid = 0;
+                b.iter(|| {
+                    pkid = pkid % receive_max + 1;
+                    incoming_qos2(&session, pkid)
+                })
+            },
+        );
+
+        group.bench_with_input(
+            BenchmarkId::new("subscribe", receive_max),
+            &options,
+            |b, options| {
+                let session = Session::new(options);
+                b.iter(|| subscribe(&session))
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, state);
+criterion_main!(benches);
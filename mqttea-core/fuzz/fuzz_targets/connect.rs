@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use mqttea_core::{fuzzing, v5::commons::packet_type::PacketType};
+
+fuzz_target!(|data: &[u8]| {
+    mqttea_fuzz::bounded(data, || fuzzing::packet_of(PacketType::Connect, data));
+});
@@ -0,0 +1,8 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use mqttea_core::fuzzing;
+
+fuzz_target!(|data: &[u8]| {
+    mqttea_fuzz::bounded(data, || fuzzing::fixed_header(data));
+});
@@ -0,0 +1,52 @@
+//! Allocation accounting for the fuzz targets: decoding must never allocate much more than what it was given,
+//! otherwise a peer can exhaust our memory by advertising lengths it never sends.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Allowed bytes allocated per byte of input
+const PER_BYTE: usize = 64;
+/// Fixed allowance, covering scratch buffers (e.g. a Two Byte Integer length prefix is read into a buffer of its size)
+const BASE: usize = 256 * 1024;
+
+static LIVE: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+struct Counting;
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let live = LIVE.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK.fetch_max(live, Ordering::Relaxed);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        LIVE.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// Runs `f`, asserting that the memory it holds at any point stays proportional to the size of `data`
+pub fn bounded(data: &[u8], f: impl FnOnce()) {
+    let before = LIVE.load(Ordering::Relaxed);
+    PEAK.store(before, Ordering::Relaxed);
+
+    f();
+
+    let peak = PEAK.load(Ordering::Relaxed) - before;
+    let limit = data.len() * PER_BYTE + BASE;
+    assert!(
+        peak <= limit,
+        "{peak} bytes allocated for {} bytes of input",
+        data.len()
+    );
+}
@@ -0,0 +1,135 @@
+//! Entry points into the crate's internals for the criterion benches under `benches/`.
+//! Nothing here is part of the public API.
+
+use std::sync::Arc;
+
+use bytes::{Bytes, BytesMut};
+use futures::{AsyncReadExt, AsyncWriteExt};
+
+use crate::v5::{
+    client::{packet_id::PacketIdManager, state::State, ConnectOptions},
+    commons::{error::MQTTError, packet::Packet},
+    packet::{
+        puback::{PubAck, PubAckProperties},
+        pubcomp::{PubComp, PubCompProperties},
+        pubrec::{PubRec, PubRecProperties},
+        pubrel::{PubRel, PubRelProperties},
+    },
+    traits::{
+        bufferio::BufferIO,
+        pkid_mgr::{PacketIdAlloc, PacketIdRelease},
+        streamio::StreamIO,
+    },
+};
+
+pub fn encode(packet: &Packet, buf: &mut BytesMut) -> Result<(), MQTTError> {
+    BufferIO::write(packet, buf)
+}
+
+pub fn decode(buf: &mut Bytes) -> Result<Packet, MQTTError> {
+    <Packet as BufferIO>::read(buf)
+}
+
+pub async fn write<W: AsyncWriteExt + Unpin>(
+    packet: &Packet,
+    stream: &mut W,
+) -> Result<(), MQTTError> {
+    StreamIO::write(packet, stream).await
+}
+
+pub async fn read<R: AsyncReadExt + Unpin>(stream: &mut R) -> Result<Packet, MQTTError> {
+    <Packet as StreamIO>::read(stream).await
+}
+
+/// The packets acknowledging a PUBLISH, at QoS 1 and QoS 2
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ack {
+    PubAck,
+    PubRec,
+    PubRel,
+    PubComp,
+}
+
+/// A PUBACK, PUBREC, PUBREL or PUBCOMP for `pkid`
+pub fn ack(ack: Ack, pkid: u16, reason_string: Option<String>) -> Packet {
+    match ack {
+        Ack::PubAck => Packet::PubAck(PubAck {
+            pkid,
+            properties: PubAckProperties {
+                reason_string,
+                ..Default::default()
+            },
+            ..Default::default()
+        }),
+        Ack::PubRec => Packet::PubRec(PubRec {
+            pkid,
+            properties: PubRecProperties {
+                reason_string,
+                ..Default::default()
+            },
+            ..Default::default()
+        }),
+        Ack::PubRel => Packet::PubRel(PubRel {
+            pkid,
+            properties: PubRelProperties {
+                reason_string,
+                ..Default::default()
+            },
+            ..Default::default()
+        }),
+        Ack::PubComp => Packet::PubComp(PubComp {
+            pkid,
+            properties: PubCompProperties {
+                reason_string,
+                ..Default::default()
+            },
+            ..Default::default()
+        }),
+    }
+}
+
+#[derive(Debug)]
+pub struct PacketIds(PacketIdManager);
+
+impl PacketIds {
+    pub fn new(max_packets: u16) -> Self {
+        Self(PacketIdManager::new(max_packets))
+    }
+
+    pub fn allocate(&self) -> Result<u16, MQTTError> {
+        self.0.allocate()
+    }
+
+    pub fn release(&self, id: u16) {
+        self.0.release(id)
+    }
+}
+
+/// The client's session state, with its packet ids allocated the way `MqttClient` does
+#[derive(Debug)]
+pub struct Session {
+    state: State<PacketIdManager>,
+    pkids: Arc<PacketIdManager>,
+}
+
+impl Session {
+    pub fn new(options: &ConnectOptions) -> Self {
+        let pkids = Arc::new(PacketIdManager::new(options.server_receive_max.get()));
+        let mut state = State::from(options);
+        state.pkid_mgr = Some(pkids.clone());
+
+        Self { state, pkids }
+    }
+
+    pub fn allocate(&self) -> Result<u16, MQTTError> {
+        self.pkids.allocate()
+    }
+
+    pub fn outgoing(&self, packet: Packet) -> Result<(), MQTTError> {
+        self.state.handle_outgoing_packet(packet)
+    }
+
+    pub fn incoming(&self, packet: &mut Packet) -> Result<Option<Packet>, MQTTError> {
+        self.state.handle_incoming_packet(packet)
+    }
+}
@@ -0,0 +1,346 @@
+//! Checks shared by the fuzz targets under `fuzz/`.
+//! Decoders are fed untrusted bytes: they must fail without panicking, and whatever they accept
+//! must encode into bytes that decode back to the same value, on every decoding path.
+
+use bytes::{Bytes, BytesMut};
+use futures::{executor::block_on, io::Cursor};
+
+use crate::v5::{
+    commons::{
+        fixed_header::FixedHeader, packet::Packet, packet_type::PacketType, property::Property,
+        qos::QoS,
+    },
+    packet::{
+        auth::{Auth, AuthProperties, AuthReasonCode},
+        connack::{ConnAck, ConnAckProperties, ConnAckReasonCode},
+        connect::{will::Will, Connect},
+        disconnect::{Disconnect, DisconnectProperties, DisconnectReasonCode},
+        ping::{PingReq, PingResp},
+        puback::{PubAck, PubAckProperties, PubAckReasonCode},
+        pubcomp::PubComp,
+        publish::{Publish, PublishProperties},
+        pubrec::PubRec,
+        pubrel::PubRel,
+        suback::{SubAck, SubAckReasonCode},
+        subscribe::{Subscribe, SubscriptionOptions},
+        unsuback::{UnSubAck, UnSubAckReasonCode},
+        unsubscribe::UnSubscribe,
+    },
+    traits::{bufferio::BufferIO, primitives::codec::BinaryCodec, streamio::StreamIO},
+};
+
+/// Encodes the packet the same way it is written to the network
+pub fn encode(packet: &Packet) -> Vec<u8> {
+    let mut buf = BytesMut::new();
+    BufferIO::write(packet, &mut buf).expect("decoded packets can be encoded");
+    buf.to_vec()
+}
+
+pub fn fixed_header(data: &[u8]) {
+    let Ok(header) = <FixedHeader as BufferIO>::read(&mut Bytes::copy_from_slice(data)) else {
+        return;
+    };
+
+    let codec = FixedHeader::read_from(&mut Bytes::copy_from_slice(data)).unwrap();
+    assert_eq!(codec, header);
+
+    let mut buf = BytesMut::new();
+    header.write_to(&mut buf).unwrap();
+    assert_eq!(buf.len(), 1 + header.header_len);
+    assert_eq!(FixedHeader::read_from(&mut buf.freeze()).unwrap(), header);
+}
+
+/// Decodes a whole packet, Fixed Header included
+pub fn packet(data: &[u8]) {
+    let mut buf = Bytes::copy_from_slice(data);
+    let Ok(packet) = <Packet as BufferIO>::read(&mut buf) else {
+        return;
+    };
+    let consumed = &data[..data.len() - buf.len()];
+
+    let codec = Packet::read_from(&mut Bytes::copy_from_slice(data)).unwrap();
+    assert_eq!(codec, packet);
+
+    // the stream is only given the packet's own bytes, so neither path can borrow from the next packet
+    let mut stream = Cursor::new(consumed);
+    let streamed = block_on(<Packet as StreamIO>::read(&mut stream)).unwrap();
+    assert_eq!(streamed, packet);
+
+    // whatever is accepted must survive an encode/decode cycle unchanged
+    let encoded = encode(&packet);
+    let decoded = <Packet as BufferIO>::read(&mut Bytes::from(encoded.clone())).unwrap();
+    assert_eq!(decoded, packet);
+    assert_eq!(encode(&decoded), encoded);
+}
+
+/// Decodes `data` as the Variable Header and Payload of a `packet_type` packet.
+/// PUBLISH takes its flags (DUP, QoS and RETAIN) from the first byte, every other packet uses its reserved flags
+pub fn packet_of(packet_type: PacketType, data: &[u8]) {
+    let (flags, body) = match (packet_type, data.split_first()) {
+        (PacketType::Publish, Some((flags, body))) => (flags & 0b1111, body),
+        (PacketType::Publish, None) => return,
+        (PacketType::PubRel | PacketType::Subscribe | PacketType::UnSubscribe, _) => (0b0010, data),
+        _ => (0, data),
+    };
+
+    let mut buf = BytesMut::new();
+    FixedHeader::new(packet_type, flags, body.len())
+        .write_to(&mut buf)
+        .unwrap();
+    buf.extend_from_slice(body);
+
+    packet(&buf)
+}
+
+pub fn property(data: &[u8]) {
+    let Ok(property) = Property::read(&mut Bytes::copy_from_slice(data)) else {
+        return;
+    };
+
+    let mut buf = BytesMut::new();
+    BufferIO::write(&property, &mut buf).unwrap();
+    let encoded = buf.freeze();
+
+    let mut read = encoded.clone();
+    assert_eq!(Property::read(&mut read).unwrap(), property);
+    assert!(read.is_empty());
+}
+
+/// A valid packet to seed the corpus of the fuzz targets with
+pub struct Seed {
+    pub packet_type: PacketType,
+    /// Input of the whole packet decoder (`packet`)
+    pub packet: Vec<u8>,
+    /// Input of the decoder for this type of packet (`packet_of`)
+    pub body: Vec<u8>,
+}
+
+pub fn seeds() -> Vec<Seed> {
+    let user_property = vec![(String::from("region"), String::from("eu-west"))];
+
+    let packets = vec![
+        Packet::Connect(Connect::default()),
+        Packet::Connect(Connect {
+            client_id: String::from("sensor-42"),
+            username: Some(String::from("tea")),
+            password: Some(String::from("kettle")),
+            will: Some(Will {
+                topic: String::from("sensors/42/status"),
+                payload: Bytes::from_static(b"offline"),
+                qos: QoS::One,
+                retain: true,
+                ..Default::default()
+            }),
+            keep_alive: 60,
+            ..Default::default()
+        }),
+        Packet::ConnAck(ConnAck::default()),
+        Packet::ConnAck(ConnAck {
+            reason: ConnAckReasonCode::NotAuthorized,
+            properties: ConnAckProperties {
+                reason_string: Some(String::from("banned")),
+                user_property: user_property.clone(),
+                ..Default::default()
+            },
+            ..Default::default()
+        }),
+        Packet::Publish(Publish {
+            topic: String::from("sensors/42/temperature"),
+            payload: Bytes::from_static(b"21.5"),
+            ..Default::default()
+        }),
+        Packet::Publish(Publish {
+            dup: true,
+            retain: true,
+            qos: QoS::Two,
+            topic: String::from("sensors/42/humidity"),
+            pkid: Some(7),
+            properties: PublishProperties {
+                payload_format_indicator: Some(1),
+                topic_alias: Some(3),
+                subscription_identifier: vec![1, 16_384],
+                user_property: user_property.clone(),
+                ..Default::default()
+            },
+            payload: Bytes::from_static(b"40%"),
+        }),
+        Packet::PubAck(PubAck {
+            pkid: 1,
+            ..Default::default()
+        }),
+        Packet::PubAck(PubAck {
+            pkid: 2,
+            reason_code: PubAckReasonCode::QuotaExceeded,
+            properties: PubAckProperties {
+                reason_string: Some(String::from("slow down")),
+                user_property: user_property.clone(),
+            },
+        }),
+        Packet::PubRec(PubRec {
+            pkid: 3,
+            ..Default::default()
+        }),
+        Packet::PubRel(PubRel {
+            pkid: 4,
+            ..Default::default()
+        }),
+        Packet::PubComp(PubComp {
+            pkid: 5,
+            ..Default::default()
+        }),
+        Packet::Subscribe(Subscribe {
+            pkid: 6,
+            payload: vec![
+                (
+                    String::from("sensors/+/temperature"),
+                    SubscriptionOptions::default(),
+                ),
+                (
+                    String::from("alerts/#"),
+                    SubscriptionOptions {
+                        qos: QoS::Two,
+                        no_local: true,
+                        ..Default::default()
+                    },
+                ),
+            ],
+            ..Default::default()
+        }),
+        Packet::SubAck(SubAck {
+            pkid: 6,
+            payload: vec![
+                SubAckReasonCode::GrantedQoS0,
+                SubAckReasonCode::NotAuthorized,
+            ],
+            ..Default::default()
+        }),
+        Packet::UnSubscribe(UnSubscribe {
+            pkid: 8,
+            payload: vec![String::from("alerts/#")],
+            ..Default::default()
+        }),
+        Packet::UnSubAck(UnSubAck {
+            pkid: 8,
+            payload: vec![UnSubAckReasonCode::NoSubscriptionExisted],
+            ..Default::default()
+        }),
+        Packet::PingReq(PingReq::default()),
+        Packet::PingResp(PingResp),
+        Packet::Disconnect(Disconnect::default()),
+        Packet::Disconnect(Disconnect {
+            reason_code: DisconnectReasonCode::ServerBusy,
+            properties: DisconnectProperties {
+                reason_string: Some(String::from("maintenance")),
+                ..Default::default()
+            },
+        }),
+        Packet::Auth(Auth::default()),
+        Packet::Auth(Auth {
+            reason_code: AuthReasonCode::ContinueAuthentication,
+            properties: AuthProperties {
+                auth_method: Some(String::from("SCRAM-SHA-256")),
+                auth_data: Some(Bytes::from_static(b"n,,n=tea,r=nonce")),
+                ..Default::default()
+            },
+        }),
+    ];
+
+    packets
+        .iter()
+        .map(|packet| {
+            let encoded = encode(packet);
+            let mut buf = Bytes::from(encoded.clone());
+            let header = <FixedHeader as BufferIO>::read(&mut buf).unwrap();
+
+            let mut body = Vec::new();
+            if header.packet_type == PacketType::Publish {
+                body.push(header.flags.unwrap_or(0));
+            }
+            body.extend_from_slice(&buf);
+
+            Seed {
+                packet_type: header.packet_type,
+                packet: encoded,
+                body,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::{collection::vec, prelude::*, sample::select};
+
+    use super::*;
+
+    #[test]
+    fn seeds_are_accepted() {
+        for seed in seeds() {
+            let mut buf = Bytes::from(seed.packet.clone());
+            let packet = <Packet as BufferIO>::read(&mut buf).unwrap();
+            assert_eq!(packet.packet_type(), seed.packet_type);
+
+            super::packet(&seed.packet);
+            packet_of(seed.packet_type, &seed.body);
+        }
+    }
+
+    #[test]
+    fn short_and_oversized_inputs_are_rejected() {
+        // `Bytes::read` used to panic on a missing length prefix
+        packet_of(PacketType::Connect, b"\x00");
+        packet_of(PacketType::Publish, b"\x02\x00");
+        // a Remaining Length of 268_435_455 with nothing behind it
+        packet(b"\x30\xff\xff\xff\x7f");
+        // non minimal Variable Byte Integers, in the Fixed Header and in a Subscription Identifier
+        fixed_header(b"\xc0\x80\x00");
+        packet(b"\x3b\x09\x00\x00\x00\x09\x04\x0b\xd6\xd6\x00");
+        assert!(<Packet as BufferIO>::read(&mut Bytes::from_static(b"\xc0\x80\x00")).is_err());
+    }
+
+    /// Regenerates the seed corpus of the fuzz targets: `cargo test --features fuzzing write_corpus -- --ignored`
+    #[test]
+    #[ignore]
+    fn write_corpus() {
+        let corpus = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("fuzz/corpus");
+        let write = |target: &str, name: String, data: &[u8]| {
+            let dir = corpus.join(target);
+            std::fs::create_dir_all(&dir).unwrap();
+            std::fs::write(dir.join(name), data).unwrap();
+        };
+
+        for (i, seed) in seeds().into_iter().enumerate() {
+            let target = format!("{:?}", seed.packet_type).to_lowercase();
+            write("packet", format!("{target}-{i}"), &seed.packet);
+            write("fixed_header", format!("{target}-{i}"), &seed.packet);
+            write(&target, format!("seed-{i}"), &seed.body);
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn arbitrary_bytes(data in vec(any::<u8>(), 0..64)) {
+            fixed_header(&data);
+            packet(&data);
+            property(&data);
+        }
+
+        #[test]
+        fn mutated_seeds(
+            seed in select(seeds().into_iter().map(|seed| (seed.packet_type, seed.body)).collect::<Vec<_>>()),
+            mutations in vec((any::<prop::sample::Index>(), any::<u8>()), 1..4),
+            truncate in any::<prop::sample::Index>(),
+        ) {
+            let (packet_type, mut body) = seed;
+            if body.is_empty() {
+                return Ok(());
+            }
+            for (at, byte) in mutations {
+                let at = at.index(body.len());
+                body[at] = byte;
+            }
+            packet_of(packet_type, &body);
+            packet_of(packet_type, &body[..truncate.index(body.len())]);
+        }
+    }
+}
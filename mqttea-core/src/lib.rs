@@ -1,7 +1,20 @@
+//! Without the default `std` feature, only the packet codec (`v5::packet`, `v5::commons`) is built, on `core` and `alloc`
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
+
+#[cfg(feature = "bench")]
+#[doc(hidden)]
+pub mod bench;
 pub mod constants;
+#[cfg(any(test, feature = "fuzzing"))]
+#[doc(hidden)]
+pub mod fuzzing;
 #[cfg(test)]
 mod retest_utils;
 pub mod v5;
+#[cfg(feature = "wasm")]
+pub mod wasm;
 
 // #[cfg(all(feature = "syncx", feature = "asyncx"))]
 // compile_error!("feature \"syncx\" and feature \"asyncx\" cannot be enabled at the same time");
@@ -0,0 +1,580 @@
+//! Proptest strategies generating packets that respect the specification,
+//! so that whatever is written is expected to be read back unchanged.
+
+use std::borrow::Cow;
+
+use bytes::Bytes;
+use proptest::{collection::vec, option, prelude::*, sample::select};
+
+use crate::v5::{
+    commons::{packet::Packet, property::Property, qos::QoS, version::Version},
+    packet::{
+        auth::{Auth, AuthProperties, AuthReasonCode},
+        connack::{ConnAck, ConnAckProperties, ConnAckReasonCode},
+        connect::{will::Will, will::WillProperties, Connect, ConnectProperties},
+        disconnect::{Disconnect, DisconnectProperties, DisconnectReasonCode},
+        ping::{PingReq, PingResp},
+        puback::{PubAck, PubAckProperties, PubAckReasonCode},
+        pubcomp::{PubComp, PubCompProperties, PubCompReasonCode},
+        publish::{Publish, PublishProperties},
+        pubrec::{PubRec, PubRecProperties, PubRecReasonCode},
+        pubrel::{PubRel, PubRelProperties, PubRelReasonCode},
+        suback::{SubAck, SubAckProperties, SubAckReasonCode},
+        subscribe::{RetainHandling, Subscribe, SubscribeProperties, SubscriptionOptions},
+        unsuback::{UnSubAck, UnSubAckProperties, UnSubAckReasonCode},
+        unsubscribe::{UnSubscribe, UnSubscribeProperties},
+    },
+};
+
+/// Largest value a Variable Byte Integer can hold
+const VBI_MAX: usize = 268_435_455;
+
+/// Every value of a Reason Code enum (e.g. `PubAckReasonCode`)
+pub(crate) fn reason_code<T>() -> impl Strategy<Value = T>
+where
+    T: TryFrom<u8> + Clone + std::fmt::Debug + 'static,
+{
+    select(
+        (0..=u8::MAX)
+            .filter_map(|c| T::try_from(c).ok())
+            .collect::<Vec<_>>(),
+    )
+}
+
+pub(crate) fn qos() -> impl Strategy<Value = QoS> {
+    prop_oneof![Just(QoS::Zero), Just(QoS::One), Just(QoS::Two)]
+}
+
+/// UTF-8 Encoded String (1.5.4), which must not include the null character
+pub(crate) fn utf8() -> impl Strategy<Value = String> {
+    "\\PC{0,12}"
+}
+
+pub(crate) fn binary() -> impl Strategy<Value = Bytes> {
+    vec(any::<u8>(), 0..24).prop_map(Bytes::from)
+}
+
+pub(crate) fn topic_name() -> impl Strategy<Value = String> {
+    "[a-z0-9]{1,8}(/[a-zA-Z0-9 _-]{0,8}){0,3}"
+}
+
+pub(crate) fn topic_filter() -> impl Strategy<Value = String> {
+    let level = prop_oneof![Just(String::from("+")), "[a-z0-9]{1,6}"];
+    (vec(level, 1..4), any::<bool>()).prop_map(|(levels, multi_level)| {
+        let mut filter = levels.join("/");
+        if multi_level {
+            filter.push_str("/#");
+        }
+        filter
+    })
+}
+
+pub(crate) fn user_property() -> impl Strategy<Value = Vec<(String, String)>> {
+    vec((utf8(), utf8()), 0..3)
+}
+
+fn non_zero_u16() -> impl Strategy<Value = u16> {
+    1..=u16::MAX
+}
+
+fn non_zero_u32() -> impl Strategy<Value = u32> {
+    1..=u32::MAX
+}
+
+fn subscription_identifier() -> impl Strategy<Value = usize> {
+    1..=VBI_MAX
+}
+
+/// A packet that only carries a Reason String and User Properties (e.g. PUBACK, SUBACK)
+macro_rules! reason_properties {
+    ($name:ident, $properties:ident) => {
+        pub(crate) fn $name() -> impl Strategy<Value = $properties> {
+            (option::of(utf8()), user_property()).prop_map(|(reason_string, user_property)| {
+                $properties {
+                    reason_string,
+                    user_property,
+                }
+            })
+        }
+    };
+}
+
+reason_properties!(puback_properties, PubAckProperties);
+reason_properties!(pubrec_properties, PubRecProperties);
+reason_properties!(pubrel_properties, PubRelProperties);
+reason_properties!(pubcomp_properties, PubCompProperties);
+reason_properties!(suback_properties, SubAckProperties);
+reason_properties!(unsuback_properties, UnSubAckProperties);
+
+pub(crate) fn will() -> impl Strategy<Value = Will> {
+    let properties = (
+        option::of(any::<u32>()),
+        option::of(0..=1u8),
+        option::of(any::<u32>()),
+        option::of(utf8()),
+        option::of(topic_name()),
+        option::of(binary()),
+        user_property(),
+    );
+
+    (properties, topic_name(), binary(), qos(), any::<bool>()).prop_map(
+        |(
+            (delay, pfi, expiry, content_type, response_topic, correlation, user),
+            topic,
+            payload,
+            qos,
+            retain,
+        )| {
+            // a Payload Format Indicator of 1 requires the payload to be UTF-8 Encoded Character Data
+            let payload = match pfi {
+                Some(1) => Bytes::from(String::from_utf8_lossy(&payload).into_owned()),
+                _ => payload,
+            };
+
+            Will {
+                properties: WillProperties {
+                    delay_interval: delay,
+                    payload_format_indicator: pfi,
+                    message_expiry_interval: expiry,
+                    content_type,
+                    response_topic,
+                    correlation_data: correlation,
+                    user_property: user,
+                },
+                topic,
+                payload,
+                qos,
+                retain,
+            }
+        },
+    )
+}
+
+pub(crate) fn connect() -> impl Strategy<Value = Packet> {
+    let properties = (
+        option::of(any::<u32>()),
+        option::of(non_zero_u16()),
+        option::of(non_zero_u32()),
+        option::of(any::<u16>()),
+        option::of(0..=1u8),
+        option::of(0..=1u8),
+        user_property(),
+        option::of((utf8(), option::of(binary()))),
+    )
+        .prop_map(
+            |(session_expiry, receive_max, max_packet, alias_max, rri, rpi, user, auth)| {
+                let (authentication_method, authentication_data) = match auth {
+                    Some((method, data)) => (Some(method), data),
+                    None => (None, None),
+                };
+
+                ConnectProperties {
+                    session_expiry_interval: session_expiry,
+                    receive_maximum: receive_max,
+                    maximum_packet_size: max_packet,
+                    topic_alias_maximum: alias_max,
+                    request_response_information: rri,
+                    request_problem_information: rpi,
+                    user_property: user,
+                    authentication_method,
+                    authentication_data,
+                }
+            },
+        );
+
+    (
+        "[a-zA-Z0-9]{0,23}",
+        option::of(utf8()),
+        option::of(utf8()),
+        option::of(will()),
+        any::<bool>(),
+        any::<u16>(),
+        properties,
+    )
+        .prop_map(
+            |(client_id, username, password, will, clean_start, keep_alive, properties)| {
+                Packet::Connect(Connect {
+                    client_id,
+                    username,
+                    password,
+                    version: Version::V5,
+                    will,
+                    clean_start,
+                    keep_alive,
+                    properties,
+                })
+            },
+        )
+}
+
+pub(crate) fn connack() -> impl Strategy<Value = Packet> {
+    let availability = (
+        option::of(any::<bool>()),
+        option::of(any::<bool>()),
+        option::of(any::<bool>()),
+        option::of(any::<bool>()),
+        option::of(any::<bool>()),
+    );
+    let information = (
+        option::of(utf8()),
+        option::of(utf8()),
+        option::of(utf8()),
+        option::of(utf8()),
+        option::of(utf8()),
+        option::of(binary()),
+    );
+    let limits = (
+        option::of(any::<u32>()),
+        option::of(non_zero_u16()),
+        option::of(non_zero_u32()),
+        option::of(any::<u16>()),
+        option::of(any::<u16>()),
+    );
+
+    (
+        any::<bool>(),
+        reason_code::<ConnAckReasonCode>(),
+        availability,
+        information,
+        limits,
+        user_property(),
+    )
+        .prop_map(
+            |(session_present, reason, availability, information, limits, user_property)| {
+                let (maximum_qos, retain, wildcard, subscription_ids, shared) = availability;
+                let (
+                    assigned_client_id,
+                    reason_string,
+                    response_info,
+                    server_reference,
+                    method,
+                    data,
+                ) = information;
+                let (session_expiry, receive_max, max_packet, alias_max, keep_alive) = limits;
+
+                Packet::ConnAck(ConnAck {
+                    session_present: session_present && reason == ConnAckReasonCode::Success,
+                    reason,
+                    properties: ConnAckProperties {
+                        session_expiry_interval: session_expiry,
+                        receive_maximum: receive_max,
+                        maximum_qos,
+                        retain_available: retain,
+                        maximum_packet_size: max_packet,
+                        assigned_client_id,
+                        topic_alias_maximum: alias_max,
+                        reason_string,
+                        user_property,
+                        wildcard_subscription_available: wildcard,
+                        subscription_identifiers_available: subscription_ids,
+                        shared_subscription_available: shared,
+                        server_keep_alive: keep_alive,
+                        response_information: response_info,
+                        server_reference,
+                        authentication_method: method,
+                        authentication_data: data,
+                    },
+                })
+            },
+        )
+}
+
+pub(crate) fn publish() -> impl Strategy<Value = Packet> {
+    let properties = (
+        option::of(0..=1u8),
+        option::of(any::<u32>()),
+        option::of(non_zero_u16()),
+        option::of(topic_name()),
+        option::of(binary()),
+        user_property(),
+        vec(subscription_identifier(), 0..3),
+        option::of(utf8()),
+    );
+
+    (
+        qos(),
+        any::<bool>(),
+        any::<bool>(),
+        non_zero_u16(),
+        topic_name(),
+        properties,
+        vec(any::<u8>(), 0..1024),
+    )
+        .prop_map(|(qos, dup, retain, pkid, topic, properties, payload)| {
+            let (pfi, expiry, alias, response_topic, correlation, user, ids, content_type) =
+                properties;
+            let payload = match pfi {
+                Some(1) => Bytes::from(String::from_utf8_lossy(&payload).into_owned()),
+                _ => Bytes::from(payload),
+            };
+
+            Packet::Publish(Publish {
+                dup: dup && qos != QoS::Zero,
+                retain,
+                qos,
+                topic,
+                pkid: (qos != QoS::Zero).then_some(pkid),
+                properties: PublishProperties {
+                    payload_format_indicator: pfi,
+                    message_expiry_internal: expiry,
+                    topic_alias: alias,
+                    response_topic,
+                    correlation_data: correlation,
+                    user_property: user,
+                    subscription_identifier: ids,
+                    content_type,
+                },
+                payload,
+            })
+        })
+}
+
+pub(crate) fn puback() -> impl Strategy<Value = Packet> {
+    (
+        non_zero_u16(),
+        reason_code::<PubAckReasonCode>(),
+        puback_properties(),
+    )
+        .prop_map(|(pkid, reason_code, properties)| {
+            Packet::PubAck(PubAck {
+                pkid,
+                reason_code,
+                properties,
+            })
+        })
+}
+
+pub(crate) fn pubrec() -> impl Strategy<Value = Packet> {
+    (
+        non_zero_u16(),
+        reason_code::<PubRecReasonCode>(),
+        pubrec_properties(),
+    )
+        .prop_map(|(pkid, reason_code, properties)| {
+            Packet::PubRec(PubRec {
+                pkid,
+                reason_code,
+                properties,
+            })
+        })
+}
+
+pub(crate) fn pubrel() -> impl Strategy<Value = Packet> {
+    (
+        non_zero_u16(),
+        reason_code::<PubRelReasonCode>(),
+        pubrel_properties(),
+    )
+        .prop_map(|(pkid, reason_code, properties)| {
+            Packet::PubRel(PubRel {
+                pkid,
+                reason_code,
+                properties,
+            })
+        })
+}
+
+pub(crate) fn pubcomp() -> impl Strategy<Value = Packet> {
+    (
+        non_zero_u16(),
+        reason_code::<PubCompReasonCode>(),
+        pubcomp_properties(),
+    )
+        .prop_map(|(pkid, reason_code, properties)| {
+            Packet::PubComp(PubComp {
+                pkid,
+                reason_code,
+                properties,
+            })
+        })
+}
+
+pub(crate) fn subscription_options() -> impl Strategy<Value = SubscriptionOptions> {
+    let retain_handling = prop_oneof![
+        Just(RetainHandling::Zero),
+        Just(RetainHandling::One),
+        Just(RetainHandling::Two)
+    ];
+
+    (qos(), any::<bool>(), any::<bool>(), retain_handling).prop_map(
+        |(qos, no_local, retain_as_published, retain_handling)| SubscriptionOptions {
+            qos,
+            no_local,
+            retain_as_published,
+            retain_handling,
+        },
+    )
+}
+
+pub(crate) fn subscribe() -> impl Strategy<Value = Packet> {
+    let properties = (option::of(subscription_identifier()), user_property()).prop_map(
+        |(subscription_id, user_property)| SubscribeProperties {
+            subscription_id,
+            user_property,
+        },
+    );
+
+    (
+        non_zero_u16(),
+        properties,
+        vec((topic_filter(), subscription_options()), 1..4),
+    )
+        .prop_map(|(pkid, properties, payload)| {
+            Packet::Subscribe(Subscribe {
+                pkid,
+                properties,
+                payload,
+            })
+        })
+}
+
+pub(crate) fn suback() -> impl Strategy<Value = Packet> {
+    (
+        non_zero_u16(),
+        vec(reason_code::<SubAckReasonCode>(), 1..4),
+        suback_properties(),
+    )
+        .prop_map(|(pkid, payload, properties)| {
+            Packet::SubAck(SubAck {
+                pkid,
+                payload,
+                properties,
+            })
+        })
+}
+
+pub(crate) fn unsubscribe() -> impl Strategy<Value = Packet> {
+    let properties =
+        user_property().prop_map(|user_property| UnSubscribeProperties { user_property });
+
+    (non_zero_u16(), properties, vec(topic_filter(), 1..4)).prop_map(
+        |(pkid, properties, payload)| {
+            Packet::UnSubscribe(UnSubscribe {
+                pkid,
+                properties,
+                payload,
+            })
+        },
+    )
+}
+
+pub(crate) fn unsuback() -> impl Strategy<Value = Packet> {
+    (
+        non_zero_u16(),
+        unsuback_properties(),
+        vec(reason_code::<UnSubAckReasonCode>(), 1..4),
+    )
+        .prop_map(|(pkid, properties, payload)| {
+            Packet::UnSubAck(UnSubAck {
+                pkid,
+                properties,
+                payload,
+            })
+        })
+}
+
+pub(crate) fn disconnect() -> impl Strategy<Value = Packet> {
+    let properties = (
+        option::of(any::<u32>()),
+        option::of(utf8()),
+        user_property(),
+        option::of(utf8()),
+    )
+        .prop_map(
+            |(session_expiry_interval, reason_string, user_property, server_reference)| {
+                DisconnectProperties {
+                    session_expiry_interval,
+                    reason_string,
+                    user_property,
+                    server_reference,
+                }
+            },
+        );
+
+    (reason_code::<DisconnectReasonCode>(), properties).prop_map(|(reason_code, properties)| {
+        Packet::Disconnect(Disconnect {
+            reason_code,
+            properties,
+        })
+    })
+}
+
+pub(crate) fn auth() -> impl Strategy<Value = Packet> {
+    let properties = (
+        utf8(),
+        option::of(binary()),
+        option::of(utf8()),
+        user_property(),
+    )
+        .prop_map(
+            |(method, auth_data, reason_string, user_property)| AuthProperties {
+                auth_method: Some(method),
+                auth_data,
+                reason_string,
+                user_property,
+            },
+        );
+
+    (reason_code::<AuthReasonCode>(), properties).prop_map(|(reason_code, properties)| {
+        Packet::Auth(Auth {
+            reason_code,
+            properties,
+        })
+    })
+}
+
+pub(crate) fn packet() -> impl Strategy<Value = Packet> {
+    prop_oneof![
+        connect(),
+        connack(),
+        publish(),
+        puback(),
+        pubrec(),
+        pubrel(),
+        pubcomp(),
+        subscribe(),
+        suback(),
+        unsubscribe(),
+        unsuback(),
+        Just(()).prop_map(|_| Packet::PingReq(PingReq::default())),
+        Just(()).prop_map(|_| Packet::PingResp(PingResp)),
+        disconnect(),
+        auth(),
+    ]
+}
+
+/// Every `Property` variant, with a value
+pub(crate) fn property() -> impl Strategy<Value = Property<'static>> {
+    let owned = |s: String| Some(Cow::Owned(s));
+    let data = |b: Bytes| Some(Cow::Owned(b.to_vec()));
+
+    prop_oneof![
+        any::<u8>().prop_map(|v| Property::PayloadFormatIndicator(Some(v))),
+        any::<u32>().prop_map(|v| Property::MessageExpiryInterval(Some(v))),
+        utf8().prop_map(move |v| Property::ContentType(owned(v))),
+        topic_name().prop_map(move |v| Property::ResponseTopic(owned(v))),
+        binary().prop_map(move |v| Property::CorrelationData(data(v))),
+        subscription_identifier().prop_map(|v| Property::SubscriptionIdentifier(Cow::Owned(v))),
+        any::<u32>().prop_map(|v| Property::SessionExpiryInterval(Some(v))),
+        utf8().prop_map(move |v| Property::AssignedClientIdentifier(owned(v))),
+        any::<u16>().prop_map(|v| Property::ServerKeepAlive(Some(v))),
+        utf8().prop_map(move |v| Property::AuthenticationMethod(owned(v))),
+        binary().prop_map(move |v| Property::AuthenticationData(data(v))),
+        any::<u8>().prop_map(|v| Property::RequestProblemInformation(Some(v))),
+        any::<u32>().prop_map(|v| Property::WillDelayInterval(Some(v))),
+        any::<u8>().prop_map(|v| Property::RequestResponseInformation(Some(v))),
+        utf8().prop_map(move |v| Property::ResponseInformation(owned(v))),
+        utf8().prop_map(move |v| Property::ServerReference(owned(v))),
+        utf8().prop_map(move |v| Property::ReasonString(owned(v))),
+        any::<u16>().prop_map(|v| Property::ReceiveMaximum(Some(v))),
+        any::<u16>().prop_map(|v| Property::TopicAliasMaximum(Some(v))),
+        any::<u16>().prop_map(|v| Property::TopicAlias(Some(v))),
+        any::<u8>().prop_map(|v| Property::MaximumQoS(Some(v))),
+        any::<u8>().prop_map(|v| Property::RetainAvailable(Some(v))),
+        (utf8(), utf8()).prop_map(|v| Property::UserProperty(Cow::Owned(v))),
+        any::<u32>().prop_map(|v| Property::MaximumPacketSize(Some(v))),
+        any::<u8>().prop_map(|v| Property::WildCardSubscription(Some(v))),
+        any::<u8>().prop_map(|v| Property::SubscriptionIdentifierAvailable(Some(v))),
+        any::<u8>().prop_map(|v| Property::SharedSubscriptionAvailable(Some(v))),
+    ]
+}
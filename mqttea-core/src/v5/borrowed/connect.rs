@@ -0,0 +1,279 @@
+use crate::{
+    constants::PROTOCOL_NAME,
+    v5::utils::topic::validate_topic_name,
+    v5::{
+        commons::{
+            error::MQTTError,
+            qos::QoS,
+            version::Version,
+            violation::{ensure, validate_payload_format, Validate, Violation},
+        },
+        packet::{
+            connack::{ConnAck as OwnedConnAck, ConnAckReasonCode},
+            connect::ConnectFlags,
+            disconnect::DisconnectReasonCode,
+        },
+        traits::primitives::codec::BinaryCodec,
+    },
+};
+
+use super::{
+    binary_len,
+    property::{self, Allowed},
+    read_binary, read_str, write_binary, write_str, Body, Properties, Property,
+};
+
+/// CONNECT (3.1)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Connect<'a> {
+    pub client_id: &'a str,
+    pub username: Option<&'a str>,
+    /// Binary Data (3.1.3.6)
+    pub password: Option<&'a [u8]>,
+    pub will: Option<Will<'a>>,
+    pub clean_start: bool,
+    pub keep_alive: u16,
+    pub properties: Properties<'a>,
+}
+
+impl Default for Connect<'_> {
+    fn default() -> Self {
+        Self {
+            client_id: "",
+            username: None,
+            password: None,
+            will: None,
+            clean_start: true,
+            keep_alive: 0,
+            properties: Properties::default(),
+        }
+    }
+}
+
+/// The Will Message a CONNECT carries (3.1.3.2 - 3.1.3.4)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Will<'a> {
+    pub properties: Properties<'a>,
+    pub topic: &'a str,
+    pub payload: &'a [u8],
+    pub qos: QoS,
+    pub retain: bool,
+}
+
+impl Will<'_> {
+    fn length(&self) -> usize {
+        property::length(&self.properties)
+            + binary_len(self.topic.as_bytes())
+            + binary_len(self.payload)
+    }
+}
+
+impl Validate for Connect<'_> {
+    fn validate(&self) -> Result<(), Violation> {
+        let (mut authentication_method, mut authentication_data) = (false, false);
+        for property in self.properties.iter() {
+            match property {
+                Property::ReceiveMaximum(0) => Err(Violation::protocol(
+                    "3.1.2.11.3",
+                    "Receive Maximum must not be 0",
+                ))?,
+                Property::MaximumPacketSize(0) => Err(Violation::protocol(
+                    "3.1.2.11.4",
+                    "Maximum Packet Size must not be 0",
+                ))?,
+                Property::RequestResponseInformation(2..) => Err(Violation::protocol(
+                    "3.1.2.11.6",
+                    "Request Response Information must be either 0 or 1",
+                ))?,
+                Property::RequestProblemInformation(2..) => Err(Violation::protocol(
+                    "3.1.2.11.7",
+                    "Request Problem Information must be either 0 or 1",
+                ))?,
+                Property::AuthenticationMethod(_) => authentication_method = true,
+                Property::AuthenticationData(_) => authentication_data = true,
+                _ => {}
+            }
+        }
+        ensure(
+            !authentication_data || authentication_method,
+            Violation::protocol(
+                "3.1.2.11.10",
+                "Authentication Data requires an Authentication Method",
+            ),
+        )?;
+
+        match &self.will {
+            Some(will) => will.validate(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Validate for Will<'_> {
+    fn validate(&self) -> Result<(), Violation> {
+        ensure(
+            !self.topic.is_empty(),
+            Violation::new(
+                "MQTT-4.7.3-1",
+                DisconnectReasonCode::TopicNameInvalid,
+                "Will Topic must be at least one character long",
+            ),
+        )?;
+        validate_topic_name(self.topic, "MQTT-4.7.0-1")?;
+
+        let mut payload_format_indicator = None;
+        for property in self.properties.iter() {
+            match property {
+                Property::ResponseTopic(topic) => validate_topic_name(topic, "3.1.3.2.6")?,
+                Property::PayloadFormatIndicator(indicator) => {
+                    payload_format_indicator = Some(indicator)
+                }
+                _ => {}
+            }
+        }
+        validate_payload_format(payload_format_indicator, self.payload, "3.1.3.2.3")
+    }
+}
+
+impl<'a> Body<'a> for Connect<'a> {
+    fn length(&self) -> usize {
+        // protocol name + version + connect flags + keep alive
+        binary_len(PROTOCOL_NAME.as_bytes())
+            + 1
+            + 1
+            + 2
+            + property::length(&self.properties)
+            + binary_len(self.client_id.as_bytes())
+            + self.will.as_ref().map_or(0, Will::length)
+            + self
+                .username
+                .map_or(0, |username| binary_len(username.as_bytes()))
+            + self.password.map_or(0, binary_len)
+    }
+
+    fn write(&self, w: &mut &mut [u8]) -> Result<(), MQTTError> {
+        write_str(w, PROTOCOL_NAME)?;
+        (Version::V5 as u8).write_to(w)?;
+
+        let mut flags = ConnectFlags {
+            clean_start: self.clean_start,
+            password: self.password.is_some(),
+            username: self.username.is_some(),
+            ..Default::default()
+        };
+        if let Some(will) = &self.will {
+            flags.will_flag = true;
+            flags.will_qos = will.qos;
+            flags.will_retain = will.retain;
+        }
+        u8::from(flags).write_to(w)?;
+        self.keep_alive.write_to(w)?;
+        Allowed::CONNECT.write(&self.properties, w)?;
+
+        write_str(w, self.client_id)?;
+        if let Some(will) = &self.will {
+            Allowed::WILL.write(&will.properties, w)?;
+            write_str(w, will.topic)?;
+            write_binary(w, will.payload)?;
+        }
+        if let Some(username) = self.username {
+            write_str(w, username)?;
+        }
+        if let Some(password) = self.password {
+            write_binary(w, password)?;
+        }
+        Ok(())
+    }
+
+    fn read(_: u8, r: &mut &'a [u8]) -> Result<Self, MQTTError> {
+        if read_str(r)? != PROTOCOL_NAME {
+            return Err(MQTTError::MalformedPacket);
+        }
+        let version = u8::read_from(r)?;
+        if version != Version::V5 as u8 {
+            return Err(MQTTError::VersionNotSupported(version));
+        }
+
+        let flags = ConnectFlags::try_from(u8::read_from(r)?)?;
+        let keep_alive = u16::read_from(r)?;
+        let properties = Allowed::CONNECT.read(r)?;
+        let client_id = read_str(r)?;
+
+        let will = match flags.will_flag {
+            true => Some(Will {
+                properties: Allowed::WILL.read(r)?,
+                topic: read_str(r)?,
+                payload: read_binary(r)?,
+                qos: flags.will_qos,
+                retain: flags.will_retain,
+            }),
+            false => None,
+        };
+        let username = flags.username.then(|| read_str(r)).transpose()?;
+        let password = flags.password.then(|| read_binary(r)).transpose()?;
+
+        Ok(Self {
+            client_id,
+            username,
+            password,
+            will,
+            clean_start: flags.clean_start,
+            keep_alive,
+            properties,
+        })
+    }
+}
+
+/// CONNACK (3.2)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ConnAck<'a> {
+    pub session_present: bool,
+    pub reason: ConnAckReasonCode,
+    pub properties: Properties<'a>,
+}
+
+impl Validate for ConnAck<'_> {
+    fn validate(&self) -> Result<(), Violation> {
+        ensure(
+            !self.session_present || self.reason == ConnAckReasonCode::Success,
+            Violation::protocol(
+                "MQTT-3.2.2-6",
+                "Session Present must be 0 when the Reason Code is not 0",
+            ),
+        )?;
+
+        self.properties
+            .iter()
+            .try_for_each(|property| match property {
+                Property::ReceiveMaximum(0) => Err(Violation::protocol(
+                    "3.2.2.3.3",
+                    "Receive Maximum must not be 0",
+                )),
+                Property::MaximumPacketSize(0) => Err(Violation::protocol(
+                    "3.2.2.3.6",
+                    "Maximum Packet Size must not be 0",
+                )),
+                _ => Ok(()),
+            })
+    }
+}
+
+impl<'a> Body<'a> for ConnAck<'a> {
+    fn length(&self) -> usize {
+        1 + 1 + property::length(&self.properties) // connect acknowledge flags + reason code
+    }
+
+    fn write(&self, w: &mut &mut [u8]) -> Result<(), MQTTError> {
+        u8::from(self.session_present).write_to(w)?;
+        u8::from(self.reason).write_to(w)?;
+        Allowed::CONNACK.write(&self.properties, w)
+    }
+
+    fn read(_: u8, r: &mut &'a [u8]) -> Result<Self, MQTTError> {
+        Ok(Self {
+            session_present: OwnedConnAck::session_present(u8::read_from(r)?)?,
+            reason: ConnAckReasonCode::decode(u8::read_from(r)?)?,
+            properties: Allowed::CONNACK.read(r)?,
+        })
+    }
+}
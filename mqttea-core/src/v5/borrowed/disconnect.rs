@@ -0,0 +1,84 @@
+use crate::v5::{
+    commons::{
+        error::MQTTError,
+        violation::{ensure, Validate, Violation},
+    },
+    packet::{auth::AuthReasonCode, disconnect::DisconnectReasonCode},
+    traits::primitives::codec::BinaryCodec,
+};
+
+use super::{
+    property::{self, Allowed},
+    Body, Properties, Property,
+};
+
+/// DISCONNECT and AUTH end with a Reason Code and properties, both of which can be omitted
+/// when the Reason Code is the default one and there are no Properties
+macro_rules! reason_and_properties {
+    ($(#[$doc:meta])* $name:ident, $reason_code:ty, $default:ident, $allowed:expr) => {
+        $(#[$doc])*
+        #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+        pub struct $name<'a> {
+            pub reason_code: $reason_code,
+            pub properties: Properties<'a>,
+        }
+
+        impl $name<'_> {
+            fn is_short(&self) -> bool {
+                self.reason_code == <$reason_code>::$default && self.properties.is_empty()
+            }
+        }
+
+        impl<'a> Body<'a> for $name<'a> {
+            fn length(&self) -> usize {
+                match self.is_short() {
+                    true => 0,
+                    false => 1 + property::length(&self.properties),
+                }
+            }
+
+            fn write(&self, w: &mut &mut [u8]) -> Result<(), MQTTError> {
+                if self.is_short() {
+                    return Ok(());
+                }
+                u8::from(self.reason_code).write_to(w)?;
+                $allowed.write(&self.properties, w)
+            }
+
+            fn read(_: u8, r: &mut &'a [u8]) -> Result<Self, MQTTError> {
+                if r.is_empty() {
+                    return Ok(Self::default());
+                }
+
+                Ok(Self {
+                    reason_code: <$reason_code>::decode(u8::read_from(r)?)?,
+                    properties: property::read_if_any($allowed, r)?,
+                })
+            }
+        }
+    };
+}
+
+reason_and_properties!(
+    /// DISCONNECT (3.14)
+    Disconnect, DisconnectReasonCode, NormalDisconnection, Allowed::DISCONNECT
+);
+reason_and_properties!(
+    /// AUTH (3.15)
+    Auth, AuthReasonCode, Success, Allowed::AUTH
+);
+
+impl Validate for Auth<'_> {
+    fn validate(&self) -> Result<(), Violation> {
+        if self.is_short() {
+            return Ok(());
+        }
+
+        ensure(
+            self.properties
+                .iter()
+                .any(|property| matches!(property, Property::AuthenticationMethod(_))),
+            Violation::protocol("3.15.2.2.2", "AUTH must contain an Authentication Method"),
+        )
+    }
+}
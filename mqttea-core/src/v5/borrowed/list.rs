@@ -0,0 +1,134 @@
+use core::{fmt, slice};
+
+use crate::v5::commons::error::MQTTError;
+
+/// Something a packet lists: its properties, the Topic Filters of a SUBSCRIBE, the Reason Codes of a SUBACK...
+pub trait Item<'a>: Copy {
+    /// The number of bytes the item is encoded in
+    fn length(&self) -> usize;
+
+    fn write(&self, w: &mut &mut [u8]) -> Result<(), MQTTError>;
+
+    fn read(r: &mut &'a [u8]) -> Result<Self, MQTTError>;
+}
+
+/// The items a packet lists: the slice they were given in when the packet is encoded,
+/// the bytes they were read from when the packet was decoded
+#[derive(Clone, Copy)]
+pub struct List<'a, T>(Repr<'a, T>);
+
+#[derive(Clone, Copy)]
+enum Repr<'a, T> {
+    Items(&'a [T]),
+    /// Only ever holds items that were read successfully
+    Encoded(&'a [u8]),
+}
+
+impl<'a, T: Item<'a>> List<'a, T> {
+    pub fn new(items: &'a [T]) -> Self {
+        Self(Repr::Items(items))
+    }
+
+    pub fn iter(&self) -> Iter<'a, T> {
+        match self.0 {
+            Repr::Items(items) => Iter(IterRepr::Items(items.iter())),
+            Repr::Encoded(bytes) => Iter(IterRepr::Encoded(bytes)),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        match self.0 {
+            Repr::Items(items) => items.is_empty(),
+            Repr::Encoded(bytes) => bytes.is_empty(),
+        }
+    }
+
+    /// The number of bytes the items are encoded in
+    pub(crate) fn length(&self) -> usize {
+        match self.0 {
+            Repr::Items(items) => items.iter().map(Item::length).sum(),
+            Repr::Encoded(bytes) => bytes.len(),
+        }
+    }
+
+    pub(crate) fn write(&self, w: &mut &mut [u8]) -> Result<(), MQTTError> {
+        self.iter().try_for_each(|item| item.write(w))
+    }
+
+    /// Reads every item `bytes` holds, each being handed to `check`
+    pub(crate) fn read(
+        bytes: &'a [u8],
+        mut check: impl FnMut(&T) -> Result<(), MQTTError>,
+    ) -> Result<Self, MQTTError> {
+        let mut r = bytes;
+        while !r.is_empty() {
+            check(&T::read(&mut r)?)?;
+        }
+        Ok(Self(Repr::Encoded(bytes)))
+    }
+}
+
+impl<'a, T> Default for List<'a, T> {
+    fn default() -> Self {
+        Self(Repr::Items(&[]))
+    }
+}
+
+impl<'a, T: Item<'a>> From<&'a [T]> for List<'a, T> {
+    fn from(items: &'a [T]) -> Self {
+        Self::new(items)
+    }
+}
+
+impl<'a, T: Item<'a>, const N: usize> From<&'a [T; N]> for List<'a, T> {
+    fn from(items: &'a [T; N]) -> Self {
+        Self::new(items)
+    }
+}
+
+impl<'a, T: Item<'a>> IntoIterator for List<'a, T> {
+    type Item = T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Lists are equal when they hold the same items, however they came to be
+impl<'a, T: Item<'a> + PartialEq> PartialEq for List<'a, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl<'a, T: Item<'a> + Eq> Eq for List<'a, T> {}
+
+impl<'a, T: Item<'a> + fmt::Debug> fmt::Debug for List<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+/// The items of a [`List`]
+#[derive(Clone)]
+pub struct Iter<'a, T>(IterRepr<'a, T>);
+
+#[derive(Clone)]
+enum IterRepr<'a, T> {
+    Items(slice::Iter<'a, T>),
+    Encoded(&'a [u8]),
+}
+
+impl<'a, T: Item<'a>> Iterator for Iter<'a, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        match &mut self.0 {
+            IterRepr::Items(items) => items.next().copied(),
+            IterRepr::Encoded([]) => None,
+            // the bytes were read successfully once already
+            IterRepr::Encoded(bytes) => T::read(bytes).ok(),
+        }
+    }
+}
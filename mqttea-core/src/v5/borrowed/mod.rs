@@ -0,0 +1,405 @@
+//! Packets encoded from, and decoded into, the strings and bytes they borrow (`&str`, `&[u8]`) rather than `String`s and `Bytes`.
+//!
+//! Nothing here allocates: packets are written to a `&mut [u8]` the caller provides,
+//! and read off a `&[u8]` the packet decoded keeps borrowing from
+
+mod connect;
+mod disconnect;
+mod list;
+mod property;
+mod publish;
+mod subscribe;
+
+pub use connect::{ConnAck, Connect, Will};
+pub use disconnect::{Auth, Disconnect};
+pub use list::{Iter, List};
+pub use property::{Properties, Property};
+pub use publish::{PubAck, PubComp, PubRec, PubRel, Publish};
+pub use subscribe::{SubAck, Subscribe, UnSubAck, UnSubscribe};
+
+use crate::v5::{
+    commons::{
+        error::MQTTError,
+        fixed_header::FixedHeader,
+        packet_type::PacketType,
+        violation::{Validate, Violation},
+    },
+    traits::primitives::{codec::BinaryCodec, io::ByteWrite, varint::VarInt},
+};
+
+/// A packet borrowing the strings and bytes it carries
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Packet<'a> {
+    Connect(Connect<'a>),
+    ConnAck(ConnAck<'a>),
+    Publish(Publish<'a>),
+    PubAck(PubAck<'a>),
+    PubRec(PubRec<'a>),
+    PubRel(PubRel<'a>),
+    PubComp(PubComp<'a>),
+    Subscribe(Subscribe<'a>),
+    SubAck(SubAck<'a>),
+    UnSubscribe(UnSubscribe<'a>),
+    UnSubAck(UnSubAck<'a>),
+    PingReq,
+    PingResp,
+    Disconnect(Disconnect<'a>),
+    Auth(Auth<'a>),
+}
+
+/// The Variable Header and Payload of a packet, which follow its Fixed Header
+trait Body<'a>: Sized {
+    /// Bits 3-0 of the first byte of the Fixed Header
+    fn flags(&self) -> u8 {
+        0
+    }
+
+    /// The Remaining Length
+    fn length(&self) -> usize;
+
+    fn write(&self, w: &mut &mut [u8]) -> Result<(), MQTTError>;
+
+    /// Reads the body off `r`, which holds exactly the Remaining Length
+    fn read(flags: u8, r: &mut &'a [u8]) -> Result<Self, MQTTError>;
+}
+
+/// Applies `$f` to the body of every packet but PINGREQ and PINGRESP, which have none
+macro_rules! body {
+    ($packet:expr, $body:ident => $f:expr, $ping:expr) => {
+        match $packet {
+            Packet::Connect($body) => $f,
+            Packet::ConnAck($body) => $f,
+            Packet::Publish($body) => $f,
+            Packet::PubAck($body) => $f,
+            Packet::PubRec($body) => $f,
+            Packet::PubRel($body) => $f,
+            Packet::PubComp($body) => $f,
+            Packet::Subscribe($body) => $f,
+            Packet::SubAck($body) => $f,
+            Packet::UnSubscribe($body) => $f,
+            Packet::UnSubAck($body) => $f,
+            Packet::Disconnect($body) => $f,
+            Packet::Auth($body) => $f,
+            Packet::PingReq | Packet::PingResp => $ping,
+        }
+    };
+}
+
+impl<'a> Packet<'a> {
+    pub fn packet_type(&self) -> PacketType {
+        match self {
+            Self::Connect(_) => PacketType::Connect,
+            Self::ConnAck(_) => PacketType::ConnAck,
+            Self::Publish(_) => PacketType::Publish,
+            Self::PubAck(_) => PacketType::PubAck,
+            Self::PubRec(_) => PacketType::PubRec,
+            Self::PubRel(_) => PacketType::PubRel,
+            Self::PubComp(_) => PacketType::PubComp,
+            Self::Subscribe(_) => PacketType::Subscribe,
+            Self::SubAck(_) => PacketType::SubAck,
+            Self::UnSubscribe(_) => PacketType::UnSubscribe,
+            Self::UnSubAck(_) => PacketType::UnSubAck,
+            Self::PingReq => PacketType::PingReq,
+            Self::PingResp => PacketType::PingResp,
+            Self::Disconnect(_) => PacketType::Disconnect,
+            Self::Auth(_) => PacketType::Auth,
+        }
+    }
+
+    fn remaining_length(&self) -> usize {
+        body!(self, body => body.length(), 0)
+    }
+
+    /// The number of bytes the packet is encoded in, Fixed Header included
+    pub fn size(&self) -> usize {
+        let len = self.remaining_length();
+        1 + <usize as VarInt>::encoded_len(len) + len
+    }
+
+    /// Writes the packet to the front of `buf`, returning the number of bytes written.
+    /// Fails with `IncompleteData` when `buf` can't hold the whole packet, leaving it untouched
+    pub fn encode(&self, buf: &mut [u8]) -> Result<usize, MQTTError> {
+        let size = self.size();
+        if buf.len() < size {
+            return Err(MQTTError::IncompleteData("buffer", size, buf.len()));
+        }
+
+        let mut w = &mut buf[..size];
+        let flags = body!(self, body => body.flags(), 0);
+        FixedHeader::new(self.packet_type(), flags, self.remaining_length()).write_to(&mut w)?;
+        body!(self, body => body.write(&mut w)?, ());
+        Ok(size)
+    }
+
+    /// Reads the packet at the front of `buf`, returning it along with the number of bytes it was encoded in.
+    ///
+    /// Fails with `IncompleteData` when `buf` doesn't hold the whole packet yet, and with an error that isn't
+    /// when the packet is malformed
+    pub fn decode(buf: &'a [u8]) -> Result<(Self, usize), MQTTError> {
+        let mut r = buf;
+        let header = FixedHeader::read_from(&mut r)?;
+        header.validate()?;
+
+        let start = buf.len() - r.len();
+        let size = start + header.remaining_length;
+        if buf.len() < size {
+            return Err(MQTTError::IncompleteData("Packet", size, buf.len()));
+        }
+
+        let mut body = &buf[start..size];
+        let flags = header.flags.unwrap_or(0);
+        let packet = Self::read_body(header.packet_type, flags, &mut body)
+            .and_then(|packet| match body.is_empty() {
+                true => Ok(packet),
+                // the body ended before the Remaining Length did
+                false => Err(MQTTError::MalformedPacket),
+            })
+            // the whole packet is at hand, a body cut short is malformed rather than incomplete
+            .map_err(|err| match err {
+                MQTTError::IncompleteData(..) => MQTTError::MalformedPacket,
+                err => err,
+            })?;
+
+        Ok((packet, size))
+    }
+
+    fn read_body(packet_type: PacketType, flags: u8, r: &mut &'a [u8]) -> Result<Self, MQTTError> {
+        Ok(match packet_type {
+            PacketType::Connect => Self::Connect(Body::read(flags, r)?),
+            PacketType::ConnAck => Self::ConnAck(Body::read(flags, r)?),
+            PacketType::Publish => Self::Publish(Body::read(flags, r)?),
+            PacketType::PubAck => Self::PubAck(Body::read(flags, r)?),
+            PacketType::PubRec => Self::PubRec(Body::read(flags, r)?),
+            PacketType::PubRel => Self::PubRel(Body::read(flags, r)?),
+            PacketType::PubComp => Self::PubComp(Body::read(flags, r)?),
+            PacketType::Subscribe => Self::Subscribe(Body::read(flags, r)?),
+            PacketType::SubAck => Self::SubAck(Body::read(flags, r)?),
+            PacketType::UnSubscribe => Self::UnSubscribe(Body::read(flags, r)?),
+            PacketType::UnSubAck => Self::UnSubAck(Body::read(flags, r)?),
+            PacketType::PingReq => Self::PingReq,
+            PacketType::PingResp => Self::PingResp,
+            PacketType::Disconnect => Self::Disconnect(Body::read(flags, r)?),
+            PacketType::Auth => Self::Auth(Body::read(flags, r)?),
+        })
+    }
+}
+
+impl Validate for Packet<'_> {
+    fn validate(&self) -> Result<(), Violation> {
+        let result = match self {
+            Self::Connect(packet) => packet.validate(),
+            Self::ConnAck(packet) => packet.validate(),
+            Self::Publish(packet) => packet.validate(),
+            Self::PubAck(packet) => packet.validate(),
+            Self::PubRec(packet) => packet.validate(),
+            Self::PubRel(packet) => packet.validate(),
+            Self::PubComp(packet) => packet.validate(),
+            Self::Subscribe(packet) => packet.validate(),
+            Self::SubAck(packet) => packet.validate(),
+            Self::UnSubscribe(packet) => packet.validate(),
+            Self::UnSubAck(packet) => packet.validate(),
+            Self::Auth(packet) => packet.validate(),
+            // every rule on DISCONNECT depends on the direction or on the CONNECT that opened the session
+            Self::PingReq | Self::PingResp | Self::Disconnect(_) => Ok(()),
+        };
+
+        result.map_err(|violation| violation.on(self.packet_type()))
+    }
+}
+
+/// Takes `n` bytes off the front of `r`
+fn take<'a>(r: &mut &'a [u8], n: usize) -> Result<&'a [u8], MQTTError> {
+    if r.len() < n {
+        return Err(MQTTError::IncompleteData("buffer", n, r.len()));
+    }
+    let (taken, rest) = r.split_at(n);
+    *r = rest;
+    Ok(taken)
+}
+
+/// 1.5.6 Binary Data
+fn read_binary<'a>(r: &mut &'a [u8]) -> Result<&'a [u8], MQTTError> {
+    let len = u16::read_from(r)?;
+    take(r, len as usize)
+}
+
+/// 1.5.4 UTF-8 Encoded String
+fn read_str<'a>(r: &mut &'a [u8]) -> Result<&'a str, MQTTError> {
+    core::str::from_utf8(read_binary(r)?).map_err(|_| {
+        Violation::malformed("MQTT-1.5.4-1", "UTF-8 Encoded String must be well-formed").into()
+    })
+}
+
+fn write_binary(w: &mut &mut [u8], bytes: &[u8]) -> Result<(), MQTTError> {
+    let len = u16::try_from(bytes.len()).map_err(|_| MQTTError::PayloadTooLong)?;
+    len.write_to(w)?;
+    w.write_all(bytes)
+}
+
+fn write_str(w: &mut &mut [u8], value: &str) -> Result<(), MQTTError> {
+    write_binary(w, value.as_bytes())
+}
+
+/// The length of a length prefixed string or Binary Data
+fn binary_len(bytes: &[u8]) -> usize {
+    2 + bytes.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::{
+        retest_utils::strategies,
+        v5::{
+            commons::{packet::Packet as OwnedPacket, qos::QoS},
+            packet::{
+                publish::{Publish as OwnedPublish, PublishProperties},
+                subscribe::{Subscribe as OwnedSubscribe, SubscriptionOptions},
+            },
+            traits::bufferio::BufferIO,
+        },
+    };
+
+    fn owned_encode(packet: &OwnedPacket) -> BytesMut {
+        let mut buf = BytesMut::new();
+        BufferIO::write(packet, &mut buf).unwrap();
+        buf
+    }
+
+    proptest! {
+        #[test]
+        fn decodes_what_the_owned_codec_encodes(packet in strategies::packet()) {
+            let encoded = owned_encode(&packet);
+            let (decoded, size) = Packet::decode(&encoded).unwrap();
+            prop_assert_eq!(size, encoded.len());
+            prop_assert_eq!(decoded.packet_type(), packet.packet_type());
+            prop_assert_eq!(decoded.validate(), Ok(()));
+
+            let mut buf = [0u8; 4096];
+            prop_assert_eq!(decoded.size(), encoded.len());
+            let written = decoded.encode(&mut buf).unwrap();
+            prop_assert_eq!(&buf[..written], &encoded[..]);
+        }
+
+        #[test]
+        fn incomplete_packets_ask_for_more(packet in strategies::packet(), cut in any::<prop::sample::Index>()) {
+            let encoded = owned_encode(&packet);
+            let cut = cut.index(encoded.len());
+            prop_assert!(matches!(
+                Packet::decode(&encoded[..cut]),
+                Err(MQTTError::IncompleteData(..))
+            ));
+        }
+    }
+
+    #[test]
+    fn encodes_as_the_owned_codec_does() {
+        let user_property = [Property::UserProperty("sensor", "kitchen")];
+        let publish = Publish {
+            qos: QoS::One,
+            pkid: Some(7),
+            topic: "sensors/kitchen/temperature",
+            payload: b"21.5",
+            properties: List::new(&user_property),
+            ..Default::default()
+        };
+        let owned = OwnedPacket::Publish(OwnedPublish {
+            qos: QoS::One,
+            pkid: Some(7),
+            topic: String::from("sensors/kitchen/temperature"),
+            payload: bytes::Bytes::from_static(b"21.5"),
+            properties: PublishProperties {
+                user_property: vec![(String::from("sensor"), String::from("kitchen"))],
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+
+        let mut buf = [0u8; 64];
+        let written = Packet::Publish(publish).encode(&mut buf).unwrap();
+        assert_eq!(&buf[..written], &owned_encode(&owned)[..]);
+
+        let filters = [
+            ("sensors/+/temperature", SubscriptionOptions::default()),
+            (
+                "$share/group/alerts/#",
+                SubscriptionOptions {
+                    qos: QoS::Two,
+                    ..Default::default()
+                },
+            ),
+        ];
+        let subscribe = Subscribe {
+            pkid: 8,
+            payload: List::new(&filters),
+            ..Default::default()
+        };
+        let owned = OwnedPacket::Subscribe(OwnedSubscribe {
+            pkid: 8,
+            payload: filters
+                .iter()
+                .map(|(filter, options)| (filter.to_string(), *options))
+                .collect(),
+            ..Default::default()
+        });
+        let written = Packet::Subscribe(subscribe).encode(&mut buf).unwrap();
+        assert_eq!(&buf[..written], &owned_encode(&owned)[..]);
+
+        let (decoded, size) = Packet::decode(&buf[..written]).unwrap();
+        assert_eq!(size, written);
+        assert_eq!(decoded, Packet::Subscribe(subscribe));
+    }
+
+    #[test]
+    fn refuses_buffers_too_small() {
+        let publish = Packet::Publish(Publish {
+            topic: "a/b",
+            payload: b"hello",
+            ..Default::default()
+        });
+        let mut buf = [0xAAu8; 8];
+        assert_eq!(
+            publish.encode(&mut buf),
+            Err(MQTTError::IncompleteData("buffer", publish.size(), 8))
+        );
+        assert_eq!(buf, [0xAA; 8]);
+    }
+
+    #[test]
+    fn refuses_properties_not_allowed_on_the_packet() {
+        let properties = [Property::TopicAlias(1)];
+        let filters = [("a/b", SubscriptionOptions::default())];
+        let subscribe = Packet::Subscribe(Subscribe {
+            pkid: 1,
+            payload: List::new(&filters),
+            properties: List::new(&properties),
+        });
+        let mut buf = [0u8; 32];
+        assert!(matches!(
+            subscribe.encode(&mut buf),
+            Err(MQTTError::ProtocolViolation(Violation {
+                spec: "2.2.2.2",
+                ..
+            }))
+        ));
+
+        let properties = [
+            Property::ReasonString("first"),
+            Property::ReasonString("second"),
+        ];
+        let disconnect = Packet::Disconnect(Disconnect {
+            properties: List::new(&properties),
+            ..Default::default()
+        });
+        assert!(matches!(
+            disconnect.encode(&mut buf),
+            Err(MQTTError::ProtocolViolation(Violation {
+                spec: "2.2.2.2",
+                ..
+            }))
+        ));
+    }
+}
@@ -0,0 +1,289 @@
+use crate::v5::{
+    commons::{error::MQTTError, packet_type::PacketType, violation::Violation},
+    traits::primitives::{codec::BinaryCodec, varint::VarInt},
+};
+
+use super::{binary_len, list::Item, read_binary, read_str, take, write_binary, write_str, List};
+
+/// A property (2.2.2.2), borrowing the strings and bytes it carries
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Property<'a> {
+    PayloadFormatIndicator(u8),
+    MessageExpiryInterval(u32),
+    ContentType(&'a str),
+    ResponseTopic(&'a str),
+    CorrelationData(&'a [u8]),
+    SubscriptionIdentifier(usize),
+    SessionExpiryInterval(u32),
+    AssignedClientIdentifier(&'a str),
+    ServerKeepAlive(u16),
+    AuthenticationMethod(&'a str),
+    AuthenticationData(&'a [u8]),
+    RequestProblemInformation(u8),
+    WillDelayInterval(u32),
+    RequestResponseInformation(u8),
+    ResponseInformation(&'a str),
+    ServerReference(&'a str),
+    ReasonString(&'a str),
+    ReceiveMaximum(u16),
+    TopicAliasMaximum(u16),
+    TopicAlias(u16),
+    MaximumQoS(u8),
+    RetainAvailable(u8),
+    UserProperty(&'a str, &'a str),
+    MaximumPacketSize(u32),
+    WildCardSubscription(u8),
+    SubscriptionIdentifierAvailable(u8),
+    SharedSubscriptionAvailable(u8),
+}
+
+/// The properties of a packet
+pub type Properties<'a> = List<'a, Property<'a>>;
+
+impl Property<'_> {
+    /// The Property Identifier
+    pub fn id(&self) -> u8 {
+        match self {
+            Self::PayloadFormatIndicator(_) => 1,
+            Self::MessageExpiryInterval(_) => 2,
+            Self::ContentType(_) => 3,
+            Self::ResponseTopic(_) => 8,
+            Self::CorrelationData(_) => 9,
+            Self::SubscriptionIdentifier(_) => 11,
+            Self::SessionExpiryInterval(_) => 17,
+            Self::AssignedClientIdentifier(_) => 18,
+            Self::ServerKeepAlive(_) => 19,
+            Self::AuthenticationMethod(_) => 21,
+            Self::AuthenticationData(_) => 22,
+            Self::RequestProblemInformation(_) => 23,
+            Self::WillDelayInterval(_) => 24,
+            Self::RequestResponseInformation(_) => 25,
+            Self::ResponseInformation(_) => 26,
+            Self::ServerReference(_) => 28,
+            Self::ReasonString(_) => 31,
+            Self::ReceiveMaximum(_) => 33,
+            Self::TopicAliasMaximum(_) => 34,
+            Self::TopicAlias(_) => 35,
+            Self::MaximumQoS(_) => 36,
+            Self::RetainAvailable(_) => 37,
+            Self::UserProperty(..) => 38,
+            Self::MaximumPacketSize(_) => 39,
+            Self::WildCardSubscription(_) => 40,
+            Self::SubscriptionIdentifierAvailable(_) => 41,
+            Self::SharedSubscriptionAvailable(_) => 42,
+        }
+    }
+}
+
+impl<'a> Item<'a> for Property<'a> {
+    fn length(&self) -> usize {
+        let value = match self {
+            Self::PayloadFormatIndicator(_)
+            | Self::RequestProblemInformation(_)
+            | Self::RequestResponseInformation(_)
+            | Self::MaximumQoS(_)
+            | Self::RetainAvailable(_)
+            | Self::WildCardSubscription(_)
+            | Self::SubscriptionIdentifierAvailable(_)
+            | Self::SharedSubscriptionAvailable(_) => 1,
+            Self::ServerKeepAlive(_)
+            | Self::ReceiveMaximum(_)
+            | Self::TopicAliasMaximum(_)
+            | Self::TopicAlias(_) => 2,
+            Self::MessageExpiryInterval(_)
+            | Self::SessionExpiryInterval(_)
+            | Self::WillDelayInterval(_)
+            | Self::MaximumPacketSize(_) => 4,
+            Self::SubscriptionIdentifier(id) => <usize as VarInt>::encoded_len(*id),
+            Self::ContentType(value)
+            | Self::ResponseTopic(value)
+            | Self::AssignedClientIdentifier(value)
+            | Self::AuthenticationMethod(value)
+            | Self::ResponseInformation(value)
+            | Self::ServerReference(value)
+            | Self::ReasonString(value) => binary_len(value.as_bytes()),
+            Self::CorrelationData(value) | Self::AuthenticationData(value) => binary_len(value),
+            Self::UserProperty(key, value) => {
+                binary_len(key.as_bytes()) + binary_len(value.as_bytes())
+            }
+        };
+        1 + value
+    }
+
+    fn write(&self, w: &mut &mut [u8]) -> Result<(), MQTTError> {
+        self.id().write_to(w)?;
+        match self {
+            Self::PayloadFormatIndicator(value)
+            | Self::RequestProblemInformation(value)
+            | Self::RequestResponseInformation(value)
+            | Self::MaximumQoS(value)
+            | Self::RetainAvailable(value)
+            | Self::WildCardSubscription(value)
+            | Self::SubscriptionIdentifierAvailable(value)
+            | Self::SharedSubscriptionAvailable(value) => value.write_to(w),
+            Self::ServerKeepAlive(value)
+            | Self::ReceiveMaximum(value)
+            | Self::TopicAliasMaximum(value)
+            | Self::TopicAlias(value) => value.write_to(w),
+            Self::MessageExpiryInterval(value)
+            | Self::SessionExpiryInterval(value)
+            | Self::WillDelayInterval(value)
+            | Self::MaximumPacketSize(value) => value.write_to(w),
+            Self::SubscriptionIdentifier(id) => id.encode(w).map(|_| ()),
+            Self::ContentType(value)
+            | Self::ResponseTopic(value)
+            | Self::AssignedClientIdentifier(value)
+            | Self::AuthenticationMethod(value)
+            | Self::ResponseInformation(value)
+            | Self::ServerReference(value)
+            | Self::ReasonString(value) => write_str(w, value),
+            Self::CorrelationData(value) | Self::AuthenticationData(value) => {
+                write_binary(w, value)
+            }
+            Self::UserProperty(key, value) => {
+                write_str(w, key)?;
+                write_str(w, value)
+            }
+        }
+    }
+
+    fn read(r: &mut &'a [u8]) -> Result<Self, MQTTError> {
+        Ok(match u8::read_from(r)? {
+            1 => Self::PayloadFormatIndicator(u8::read_from(r)?),
+            2 => Self::MessageExpiryInterval(u32::read_from(r)?),
+            3 => Self::ContentType(read_str(r)?),
+            8 => Self::ResponseTopic(read_str(r)?),
+            9 => Self::CorrelationData(read_binary(r)?),
+            11 => Self::SubscriptionIdentifier(<usize as VarInt>::decode(r)?.0),
+            17 => Self::SessionExpiryInterval(u32::read_from(r)?),
+            18 => Self::AssignedClientIdentifier(read_str(r)?),
+            19 => Self::ServerKeepAlive(u16::read_from(r)?),
+            21 => Self::AuthenticationMethod(read_str(r)?),
+            22 => Self::AuthenticationData(read_binary(r)?),
+            23 => Self::RequestProblemInformation(u8::read_from(r)?),
+            24 => Self::WillDelayInterval(u32::read_from(r)?),
+            25 => Self::RequestResponseInformation(u8::read_from(r)?),
+            26 => Self::ResponseInformation(read_str(r)?),
+            28 => Self::ServerReference(read_str(r)?),
+            31 => Self::ReasonString(read_str(r)?),
+            33 => Self::ReceiveMaximum(u16::read_from(r)?),
+            34 => Self::TopicAliasMaximum(u16::read_from(r)?),
+            35 => Self::TopicAlias(u16::read_from(r)?),
+            36 => Self::MaximumQoS(u8::read_from(r)?),
+            37 => Self::RetainAvailable(u8::read_from(r)?),
+            38 => Self::UserProperty(read_str(r)?, read_str(r)?),
+            39 => Self::MaximumPacketSize(u32::read_from(r)?),
+            40 => Self::WildCardSubscription(u8::read_from(r)?),
+            41 => Self::SubscriptionIdentifierAvailable(u8::read_from(r)?),
+            42 => Self::SharedSubscriptionAvailable(u8::read_from(r)?),
+            id => return Err(MQTTError::UnknownProperty(id)),
+        })
+    }
+}
+
+/// The properties a packet may carry, as listed in 2.2.2.2, one bit per Property Identifier
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Allowed {
+    packet: PacketType,
+    ids: u64,
+    /// Those that may be included more than once
+    repeatable: u64,
+}
+
+const fn ids(ids: &[u8]) -> u64 {
+    let mut mask = 0;
+    let mut i = 0;
+    while i < ids.len() {
+        mask |= 1 << ids[i];
+        i += 1;
+    }
+    mask
+}
+
+impl Allowed {
+    const fn new(packet: PacketType, allowed: &[u8]) -> Self {
+        Self {
+            packet,
+            ids: ids(allowed) | ids(&[38]),
+            repeatable: ids(&[38]),
+        }
+    }
+
+    pub(crate) const CONNECT: Self =
+        Self::new(PacketType::Connect, &[17, 21, 22, 23, 25, 33, 34, 39]);
+    pub(crate) const WILL: Self = Self::new(PacketType::Connect, &[1, 2, 3, 8, 9, 24]);
+    pub(crate) const CONNACK: Self = Self::new(
+        PacketType::ConnAck,
+        &[
+            17, 18, 19, 21, 22, 26, 28, 31, 33, 34, 36, 37, 39, 40, 41, 42,
+        ],
+    );
+    pub(crate) const PUBLISH: Self = Self {
+        repeatable: ids(&[11, 38]),
+        ..Self::new(PacketType::Publish, &[1, 2, 3, 8, 9, 11, 35])
+    };
+    pub(crate) const SUBSCRIBE: Self = Self::new(PacketType::Subscribe, &[11]);
+    pub(crate) const UNSUBSCRIBE: Self = Self::new(PacketType::UnSubscribe, &[]);
+    pub(crate) const DISCONNECT: Self = Self::new(PacketType::Disconnect, &[17, 28, 31]);
+    pub(crate) const AUTH: Self = Self::new(PacketType::Auth, &[21, 22, 31]);
+
+    /// PUBACK, PUBREC, PUBREL, PUBCOMP, SUBACK and UNSUBACK carry a Reason String and User Properties
+    pub(crate) const fn ack(packet: PacketType) -> Self {
+        Self::new(packet, &[31])
+    }
+
+    /// Checks the properties one after the other
+    fn checker(self) -> impl FnMut(&Property) -> Result<(), MQTTError> {
+        let mut seen = 0u64;
+        move |property| {
+            let id = 1u64 << property.id();
+            if self.ids & id == 0 {
+                return Err(Violation::unexpected_property(self.packet).into());
+            }
+            if seen & id & !self.repeatable != 0 {
+                return Err(Violation::protocol(
+                    "2.2.2.2",
+                    "Property must not be included more than once",
+                )
+                .on(self.packet)
+                .into());
+            }
+            seen |= id;
+            Ok(())
+        }
+    }
+
+    /// Reads the Property Length, and the properties that follow it
+    pub(crate) fn read<'a>(self, r: &mut &'a [u8]) -> Result<Properties<'a>, MQTTError> {
+        let (len, _) = <usize as VarInt>::decode(r)?;
+        List::read(take(r, len)?, self.checker())
+    }
+
+    /// Writes the Property Length, and the properties that follow it
+    pub(crate) fn write(self, properties: &Properties, w: &mut &mut [u8]) -> Result<(), MQTTError> {
+        let mut check = self.checker();
+        properties
+            .iter()
+            .try_for_each(|property| check(&property))?;
+        properties.length().encode(w)?;
+        properties.write(w)
+    }
+}
+
+/// The length of the Property Length and of the properties that follow it
+pub(crate) fn length(properties: &Properties) -> usize {
+    let len = properties.length();
+    <usize as VarInt>::encoded_len(len) + len
+}
+
+/// Reads the properties following a Reason Code, which may be left out along with their Property Length
+/// when the packet ends with the Reason Code
+pub(crate) fn read_if_any<'a>(
+    allowed: Allowed,
+    r: &mut &'a [u8],
+) -> Result<Properties<'a>, MQTTError> {
+    match r.is_empty() {
+        true => Ok(Properties::default()),
+        false => allowed.read(r),
+    }
+}
@@ -0,0 +1,221 @@
+use crate::v5::{
+    commons::{
+        error::MQTTError,
+        packet_type::PacketType,
+        qos::QoS,
+        violation::{ensure, validate_payload_format, Validate, Violation},
+    },
+    packet::{
+        disconnect::DisconnectReasonCode, puback::PubAckReasonCode, pubcomp::PubCompReasonCode,
+        pubrec::PubRecReasonCode, pubrel::PubRelReasonCode,
+    },
+    traits::primitives::{codec::BinaryCodec, io::ByteWrite},
+    utils::topic::validate_topic_name,
+};
+
+use super::{
+    binary_len,
+    property::{self, Allowed},
+    read_str, take, write_str, Body, Properties, Property,
+};
+
+/// PUBLISH (3.3)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Publish<'a> {
+    pub dup: bool,
+    pub retain: bool,
+    pub qos: QoS,
+    pub topic: &'a str,
+    pub pkid: Option<u16>,
+    pub properties: Properties<'a>,
+    pub payload: &'a [u8],
+}
+
+impl Validate for Publish<'_> {
+    fn validate(&self) -> Result<(), Violation> {
+        ensure(
+            self.qos != QoS::Zero || !self.dup,
+            Violation::protocol("MQTT-3.3.1-2", "DUP flag must be 0 for QoS 0 messages"),
+        )?;
+
+        match (self.qos, self.pkid) {
+            (QoS::Zero, Some(_)) => Err(Violation::protocol(
+                "MQTT-2.2.1-2",
+                "PUBLISH with QoS 0 must not contain a Packet Identifier",
+            )),
+            (QoS::One | QoS::Two, None | Some(0)) => Err(Violation::protocol(
+                "MQTT-2.2.1-3",
+                "PUBLISH with QoS > 0 must have a non-zero Packet Identifier",
+            )),
+            _ => Ok(()),
+        }?;
+
+        let mut topic_alias = None;
+        let mut payload_format_indicator = None;
+        for property in self.properties.iter() {
+            match property {
+                Property::TopicAlias(alias) => topic_alias = Some(alias),
+                Property::PayloadFormatIndicator(indicator) => {
+                    payload_format_indicator = Some(indicator)
+                }
+                Property::ResponseTopic(topic) => validate_topic_name(topic, "MQTT-3.3.2-14")?,
+                Property::SubscriptionIdentifier(id) => ensure(
+                    id != 0,
+                    Violation::protocol("3.3.2.3.8", "Subscription Identifier must not be 0"),
+                )?,
+                _ => {}
+            }
+        }
+
+        ensure(
+            !self.topic.is_empty() || topic_alias.is_some(),
+            Violation::protocol(
+                "3.3.2.3.4",
+                "Topic Name must not be empty when there is no Topic Alias",
+            ),
+        )?;
+        validate_topic_name(self.topic, "MQTT-3.3.2-2")?;
+        ensure(
+            topic_alias != Some(0),
+            Violation::new(
+                "MQTT-3.3.2-8",
+                DisconnectReasonCode::TopicAliasInvalid,
+                "Topic Alias must not be 0",
+            ),
+        )?;
+
+        validate_payload_format(payload_format_indicator, self.payload, "3.3.2.3.2")
+    }
+}
+
+impl<'a> Body<'a> for Publish<'a> {
+    fn flags(&self) -> u8 {
+        (self.dup as u8) << 3 | (self.qos as u8) << 1 | (self.retain as u8)
+    }
+
+    fn length(&self) -> usize {
+        let pkid = match self.qos {
+            QoS::Zero => 0,
+            _ => 2,
+        };
+        binary_len(self.topic.as_bytes())
+            + pkid
+            + property::length(&self.properties)
+            + self.payload.len()
+    }
+
+    fn write(&self, w: &mut &mut [u8]) -> Result<(), MQTTError> {
+        write_str(w, self.topic)?;
+        if self.qos != QoS::Zero {
+            self.pkid.ok_or(MQTTError::PacketIdRequired)?.write_to(w)?;
+        }
+        Allowed::PUBLISH.write(&self.properties, w)?;
+        w.write_all(self.payload)
+    }
+
+    fn read(flags: u8, r: &mut &'a [u8]) -> Result<Self, MQTTError> {
+        let qos = (flags & 0b0110) >> 1;
+        let qos = QoS::try_from(qos).map_err(|_| MQTTError::UnsupportedQoS(qos))?;
+
+        let topic = read_str(r)?;
+        let pkid = match qos {
+            QoS::Zero => None,
+            _ => Some(u16::read_from(r)?),
+        };
+        let properties = Allowed::PUBLISH.read(r)?;
+
+        Ok(Self {
+            dup: (flags & 0b1000) != 0,
+            retain: (flags & 0b1) != 0,
+            qos,
+            topic,
+            pkid,
+            properties,
+            // the payload isn't length prefixed, it is whatever remains of the packet (3.3.3)
+            payload: take(r, r.len())?,
+        })
+    }
+}
+
+/// PUBACK, PUBREC, PUBREL and PUBCOMP only differ in their Reason Codes
+macro_rules! ack {
+    ($(#[$doc:meta])* $name:ident, $reason_code:ty, $packet_type:ident, $flags:expr, $spec:literal, $description:literal) => {
+        $(#[$doc])*
+        #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+        pub struct $name<'a> {
+            pub pkid: u16,
+            pub reason_code: $reason_code,
+            pub properties: Properties<'a>,
+        }
+
+        impl $name<'_> {
+            /// The Reason Code and Property Length can be omitted when the Reason Code is Success
+            /// and there are no Properties
+            fn is_short(&self) -> bool {
+                self.reason_code == <$reason_code>::Success && self.properties.is_empty()
+            }
+        }
+
+        impl Validate for $name<'_> {
+            fn validate(&self) -> Result<(), Violation> {
+                ensure(self.pkid != 0, Violation::protocol($spec, $description))
+            }
+        }
+
+        impl<'a> Body<'a> for $name<'a> {
+            fn flags(&self) -> u8 {
+                $flags
+            }
+
+            fn length(&self) -> usize {
+                match self.is_short() {
+                    true => 2,
+                    false => 2 + 1 + property::length(&self.properties),
+                }
+            }
+
+            fn write(&self, w: &mut &mut [u8]) -> Result<(), MQTTError> {
+                self.pkid.write_to(w)?;
+                if self.is_short() {
+                    return Ok(());
+                }
+                u8::from(self.reason_code).write_to(w)?;
+                Allowed::ack(PacketType::$packet_type).write(&self.properties, w)
+            }
+
+            fn read(_: u8, r: &mut &'a [u8]) -> Result<Self, MQTTError> {
+                let pkid = u16::read_from(r)?;
+                if r.is_empty() {
+                    return Ok(Self { pkid, ..Default::default() });
+                }
+
+                Ok(Self {
+                    pkid,
+                    reason_code: <$reason_code>::decode(u8::read_from(r)?)?,
+                    properties: property::read_if_any(Allowed::ack(PacketType::$packet_type), r)?,
+                })
+            }
+        }
+    };
+}
+
+ack!(
+    /// PUBACK (3.4)
+    PubAck, PubAckReasonCode, PubAck, 0, "MQTT-2.2.1-5",
+    "PUBACK must contain the Packet Identifier of the PUBLISH packet"
+);
+ack!(
+    /// PUBREC (3.5)
+    PubRec, PubRecReasonCode, PubRec, 0, "MQTT-2.2.1-5",
+    "PUBREC must contain the Packet Identifier of the PUBLISH packet"
+);
+ack!(
+    /// PUBREL (3.6)
+    PubRel, PubRelReasonCode, PubRel, 0b0010, "MQTT-2.2.1-5",
+    "PUBREL must contain the Packet Identifier of the PUBLISH packet"
+);
+ack!(
+    /// PUBCOMP (3.7)
+    PubComp, PubCompReasonCode, PubComp, 0, "MQTT-2.2.1-5",
+    "PUBCOMP must contain the Packet Identifier of the PUBLISH packet"
+);
@@ -0,0 +1,292 @@
+use crate::v5::{
+    commons::{
+        error::MQTTError,
+        packet_type::PacketType,
+        violation::{ensure, Validate, Violation},
+    },
+    packet::{
+        suback::SubAckReasonCode, subscribe::SubscriptionOptions, unsuback::UnSubAckReasonCode,
+    },
+    traits::primitives::codec::BinaryCodec,
+    utils::topic::{is_shared_subscription, validate_topic_filter},
+};
+
+use super::{
+    binary_len,
+    list::{Item, List},
+    property::{self, Allowed},
+    read_str, write_str, Body, Properties, Property,
+};
+
+/// A Topic Filter and its Subscription Options (3.8.3)
+impl<'a> Item<'a> for (&'a str, SubscriptionOptions) {
+    fn length(&self) -> usize {
+        binary_len(self.0.as_bytes()) + 1
+    }
+
+    fn write(&self, w: &mut &mut [u8]) -> Result<(), MQTTError> {
+        write_str(w, self.0)?;
+        u8::from(self.1).write_to(w)
+    }
+
+    fn read(r: &mut &'a [u8]) -> Result<Self, MQTTError> {
+        Ok((
+            read_str(r)?,
+            SubscriptionOptions::try_from(u8::read_from(r)?)?,
+        ))
+    }
+}
+
+/// A Topic Filter (3.10.3)
+impl<'a> Item<'a> for &'a str {
+    fn length(&self) -> usize {
+        binary_len(self.as_bytes())
+    }
+
+    fn write(&self, w: &mut &mut [u8]) -> Result<(), MQTTError> {
+        write_str(w, self)
+    }
+
+    fn read(r: &mut &'a [u8]) -> Result<Self, MQTTError> {
+        read_str(r)
+    }
+}
+
+macro_rules! reason_code_item {
+    ($name:ty) => {
+        impl<'a> Item<'a> for $name {
+            fn length(&self) -> usize {
+                1
+            }
+
+            fn write(&self, w: &mut &mut [u8]) -> Result<(), MQTTError> {
+                u8::from(*self).write_to(w)
+            }
+
+            fn read(r: &mut &'a [u8]) -> Result<Self, MQTTError> {
+                <$name>::decode(u8::read_from(r)?)
+            }
+        }
+    };
+}
+
+reason_code_item!(SubAckReasonCode);
+reason_code_item!(UnSubAckReasonCode);
+
+/// SUBSCRIBE (3.8)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Subscribe<'a> {
+    pub pkid: u16,
+    pub properties: Properties<'a>,
+    pub payload: List<'a, (&'a str, SubscriptionOptions)>,
+}
+
+impl Validate for Subscribe<'_> {
+    fn validate(&self) -> Result<(), Violation> {
+        ensure(
+            self.pkid != 0,
+            Violation::protocol(
+                "MQTT-2.2.1-3",
+                "SUBSCRIBE must have a non-zero Packet Identifier",
+            ),
+        )?;
+        ensure(
+            !self
+                .properties
+                .iter()
+                .any(|property| property == Property::SubscriptionIdentifier(0)),
+            Violation::protocol("3.8.2.1.2", "Subscription Identifier must not be 0"),
+        )?;
+        ensure(
+            !self.payload.is_empty(),
+            Violation::protocol(
+                "MQTT-3.8.3-2",
+                "SUBSCRIBE must contain at least one Topic Filter",
+            ),
+        )?;
+
+        for (filter, options) in self.payload.iter() {
+            validate_topic_filter(filter)?;
+            ensure(
+                !(options.no_local && is_shared_subscription(filter)),
+                Violation::protocol(
+                    "MQTT-3.8.3-4",
+                    "No Local must not be set on a Shared Subscription",
+                ),
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> Body<'a> for Subscribe<'a> {
+    fn flags(&self) -> u8 {
+        0b0010
+    }
+
+    fn length(&self) -> usize {
+        2 + property::length(&self.properties) + self.payload.length()
+    }
+
+    fn write(&self, w: &mut &mut [u8]) -> Result<(), MQTTError> {
+        self.pkid.write_to(w)?;
+        Allowed::SUBSCRIBE.write(&self.properties, w)?;
+        self.payload.write(w)
+    }
+
+    fn read(_: u8, r: &mut &'a [u8]) -> Result<Self, MQTTError> {
+        Ok(Self {
+            pkid: u16::read_from(r)?,
+            properties: Allowed::SUBSCRIBE.read(r)?,
+            payload: List::read(core::mem::take(r), |_| Ok(()))?,
+        })
+    }
+}
+
+/// SUBACK (3.9)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SubAck<'a> {
+    pub pkid: u16,
+    pub properties: Properties<'a>,
+    pub payload: List<'a, SubAckReasonCode>,
+}
+
+impl Validate for SubAck<'_> {
+    fn validate(&self) -> Result<(), Violation> {
+        ensure(
+            self.pkid != 0,
+            Violation::protocol(
+                "MQTT-2.2.1-6",
+                "SUBACK must contain the Packet Identifier of the SUBSCRIBE packet",
+            ),
+        )?;
+        ensure(
+            !self.payload.is_empty(),
+            Violation::protocol(
+                "MQTT-3.8.4-6",
+                "SUBACK must contain a Reason Code for each Topic Filter",
+            ),
+        )
+    }
+}
+
+impl<'a> Body<'a> for SubAck<'a> {
+    fn length(&self) -> usize {
+        2 + property::length(&self.properties) + self.payload.length()
+    }
+
+    fn write(&self, w: &mut &mut [u8]) -> Result<(), MQTTError> {
+        self.pkid.write_to(w)?;
+        Allowed::ack(PacketType::SubAck).write(&self.properties, w)?;
+        self.payload.write(w)
+    }
+
+    fn read(_: u8, r: &mut &'a [u8]) -> Result<Self, MQTTError> {
+        Ok(Self {
+            pkid: u16::read_from(r)?,
+            properties: Allowed::ack(PacketType::SubAck).read(r)?,
+            payload: List::read(core::mem::take(r), |_| Ok(()))?,
+        })
+    }
+}
+
+/// UNSUBSCRIBE (3.10)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UnSubscribe<'a> {
+    pub pkid: u16,
+    pub properties: Properties<'a>,
+    pub payload: List<'a, &'a str>,
+}
+
+impl Validate for UnSubscribe<'_> {
+    fn validate(&self) -> Result<(), Violation> {
+        ensure(
+            self.pkid != 0,
+            Violation::protocol(
+                "MQTT-2.2.1-3",
+                "UNSUBSCRIBE must have a non-zero Packet Identifier",
+            ),
+        )?;
+        ensure(
+            !self.payload.is_empty(),
+            Violation::protocol(
+                "MQTT-3.10.3-2",
+                "UNSUBSCRIBE must contain at least one Topic Filter",
+            ),
+        )?;
+
+        self.payload.iter().try_for_each(validate_topic_filter)
+    }
+}
+
+impl<'a> Body<'a> for UnSubscribe<'a> {
+    fn flags(&self) -> u8 {
+        0b0010
+    }
+
+    fn length(&self) -> usize {
+        2 + property::length(&self.properties) + self.payload.length()
+    }
+
+    fn write(&self, w: &mut &mut [u8]) -> Result<(), MQTTError> {
+        self.pkid.write_to(w)?;
+        Allowed::UNSUBSCRIBE.write(&self.properties, w)?;
+        self.payload.write(w)
+    }
+
+    fn read(_: u8, r: &mut &'a [u8]) -> Result<Self, MQTTError> {
+        Ok(Self {
+            pkid: u16::read_from(r)?,
+            properties: Allowed::UNSUBSCRIBE.read(r)?,
+            payload: List::read(core::mem::take(r), |_| Ok(()))?,
+        })
+    }
+}
+
+/// UNSUBACK (3.11)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UnSubAck<'a> {
+    pub pkid: u16,
+    pub properties: Properties<'a>,
+    pub payload: List<'a, UnSubAckReasonCode>,
+}
+
+impl Validate for UnSubAck<'_> {
+    fn validate(&self) -> Result<(), Violation> {
+        ensure(
+            self.pkid != 0,
+            Violation::protocol(
+                "MQTT-2.2.1-6",
+                "UNSUBACK must contain the Packet Identifier of the UNSUBSCRIBE packet",
+            ),
+        )?;
+        ensure(
+            !self.payload.is_empty(),
+            Violation::protocol(
+                "MQTT-3.10.4-5",
+                "UNSUBACK must contain a Reason Code for each Topic Filter",
+            ),
+        )
+    }
+}
+
+impl<'a> Body<'a> for UnSubAck<'a> {
+    fn length(&self) -> usize {
+        2 + property::length(&self.properties) + self.payload.length()
+    }
+
+    fn write(&self, w: &mut &mut [u8]) -> Result<(), MQTTError> {
+        self.pkid.write_to(w)?;
+        Allowed::ack(PacketType::UnSubAck).write(&self.properties, w)?;
+        self.payload.write(w)
+    }
+
+    fn read(_: u8, r: &mut &'a [u8]) -> Result<Self, MQTTError> {
+        Ok(Self {
+            pkid: u16::read_from(r)?,
+            properties: Allowed::ack(PacketType::UnSubAck).read(r)?,
+            payload: List::read(core::mem::take(r), |_| Ok(()))?,
+        })
+    }
+}
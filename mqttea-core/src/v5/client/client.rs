@@ -23,11 +23,25 @@ where
             max_size,
         }
     }
+
+    /// Number of QoS 1 and QoS 2 PUBLISH, SUBSCRIBE and UNSUBSCRIBE packets awaiting acknowledgement
+    pub fn in_flight(&self) -> u16 {
+        self.pkid_alloc.in_flight()
+    }
+
+    /// Number of packets that can still be sent before the server's Receive Maximum is reached
+    pub fn available(&self) -> u16 {
+        self.pkid_alloc.available()
+    }
 }
 
 mod asyncx {
     use super::MqttClient;
 
+    /// Stands in for the Packet Identifier while a packet is validated (it has the same encoded size),
+    /// so that only packets that are going to be sent wait for, and hold on to, an identifier
+    const PENDING_PKID: u16 = 0;
+
     use bytes::Bytes;
 
     use crate::v5::{
@@ -38,13 +52,53 @@ mod asyncx {
             subscribe::{Subscribe, SubscribeProperties, SubscriptionOptions},
             unsubscribe::{UnSubscribe, UnSubscribeProperties},
         },
-        traits::{pkid_mgr::PacketIdAlloc, streamio::StreamIO, utils::Utils},
+        traits::{
+            pkid_mgr::{PacketIdAlloc, PacketIdRelease},
+            streamio::StreamIO,
+            utils::Utils,
+        },
     };
 
+    /// A Packet Identifier allocated for a packet that isn't queued yet, released when dropped before it is:
+    /// the channel to the event loop is closed, or the caller gave up while waiting for room in it
+    struct Reserved<'a, T: PacketIdRelease> {
+        pkids: &'a T,
+        id: Option<u16>,
+    }
+
+    impl<T: PacketIdRelease> Reserved<'_, T> {
+        fn id(&self) -> u16 {
+            self.id.unwrap_or(PENDING_PKID)
+        }
+
+        /// The packet was queued, its acknowledgement releases the identifier from now on
+        fn queued(mut self) {
+            self.id = None;
+        }
+    }
+
+    impl<T: PacketIdRelease> Drop for Reserved<'_, T> {
+        fn drop(&mut self) {
+            if let Some(id) = self.id.take() {
+                self.pkids.release(id);
+            }
+        }
+    }
+
     impl<T> MqttClient<T>
     where
         T: PacketIdAlloc,
     {
+        async fn reserve(&self) -> Reserved<'_, T> {
+            Reserved {
+                id: Some(self.pkid_alloc.allocate_async().await),
+                pkids: &self.pkid_alloc,
+            }
+        }
+
+        /// QoS 1 and QoS 2 messages wait for a Packet Identifier when the server's Receive Maximum has been reached,
+        /// so publishers are held back to the rate at which the server acknowledges them.
+        /// Returns the Packet Identifier the acknowledgements will carry, `None` at QoS 0
         pub async fn publish<U, V>(
             &self,
             topic: U,
@@ -52,24 +106,19 @@ mod asyncx {
             retain: bool,
             payload: V,
             properties: Option<PublishProperties>,
-        ) -> Result<(), MQTTError>
+        ) -> Result<Option<u16>, MQTTError>
         where
             U: Into<String>,
             V: Into<Bytes>,
         {
-            let pkid = match qos {
-                QoS::Zero => None,
-                _ => Some(self.pkid_alloc.allocate()?),
-            };
-
             let properties = properties.unwrap_or(Default::default());
 
-            let packet = Publish {
+            let mut packet = Publish {
                 dup: false,
                 retain,
                 qos,
                 topic: topic.into(),
-                pkid,
+                pkid: (qos != QoS::Zero).then_some(PENDING_PKID),
                 payload: payload.into(),
                 properties,
             };
@@ -77,53 +126,71 @@ mod asyncx {
             packet.is_valid(self.max_size)?;
             packet.validate_topic(&packet.topic)?;
 
+            let reserved = match packet.pkid {
+                Some(_) => Some(self.reserve().await),
+                None => None,
+            };
+            packet.pkid = reserved.as_ref().map(Reserved::id);
+
+            let pkid = packet.pkid;
             self.tx.send(Packet::Publish(packet)).await?;
+            if let Some(reserved) = reserved {
+                reserved.queued();
+            }
 
-            Ok(())
+            Ok(pkid)
         }
 
+        /// Returns the Packet Identifier the SUBACK will carry
         pub async fn subscribe(
             &self,
             payload: Vec<(String, SubscriptionOptions)>,
             properties: Option<SubscribeProperties>,
-        ) -> Result<(), MQTTError> {
-            let pkid = self.pkid_alloc.allocate()?;
+        ) -> Result<u16, MQTTError> {
             let properties = properties.unwrap_or(Default::default());
 
-            let packet = Subscribe {
-                pkid,
+            let mut packet = Subscribe {
+                pkid: PENDING_PKID,
                 payload,
                 properties,
             };
 
             packet.is_valid(self.max_size)?;
+            let reserved = self.reserve().await;
+            packet.pkid = reserved.id();
 
+            let pkid = packet.pkid;
             self.tx.send(Packet::Subscribe(packet)).await?;
+            reserved.queued();
 
-            Ok(())
+            Ok(pkid)
         }
 
+        /// Returns the Packet Identifier the UNSUBACK will carry
         pub async fn unsubscribe<P>(
             &self,
             payload: P,
             properties: Option<UnSubscribeProperties>,
-        ) -> Result<(), MQTTError>
+        ) -> Result<u16, MQTTError>
         where
             P: Into<Vec<String>>,
         {
-            let pkid = self.pkid_alloc.allocate()?;
             let properties = properties.unwrap_or(Default::default());
 
-            let packet = UnSubscribe {
-                pkid,
+            let mut packet = UnSubscribe {
+                pkid: PENDING_PKID,
                 properties,
                 payload: payload.into(),
             };
 
             packet.is_valid(self.max_size)?;
+            let reserved = self.reserve().await;
+            packet.pkid = reserved.id();
+            let pkid = packet.pkid;
             self.tx.send(Packet::UnSubscribe(packet)).await?;
+            reserved.queued();
 
-            Ok(())
+            Ok(pkid)
         }
 
         pub async fn disconnect(&self) -> Result<(), MQTTError> {
@@ -136,3 +203,56 @@ mod asyncx {
 }
 
 mod syncx {}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use futures::{executor::block_on, FutureExt};
+
+    use super::*;
+    use crate::v5::{
+        client::packet_id::PacketIdManager, commons::qos::QoS,
+        packet::subscribe::SubscriptionOptions,
+    };
+
+    fn client(capacity: usize) -> (MqttClient<PacketIdManager>, async_channel::Receiver<Packet>) {
+        let (tx, rx) = async_channel::bounded(capacity);
+        let pkids = Arc::new(PacketIdManager::new(2));
+        (MqttClient::new(tx, pkids, 1024), rx)
+    }
+
+    #[test]
+    fn releases_the_packet_id_of_a_publish_given_up_on_a_full_channel() {
+        let (client, rx) = client(1);
+        block_on(client.publish("a/b", QoS::Zero, false, "full", None)).unwrap();
+
+        // allocates an identifier, then waits for room in the channel until it is dropped
+        let publish = client.publish("a/b", QoS::One, false, "dropped", None);
+        assert!(publish.now_or_never().is_none());
+        let subscribe = client.subscribe(
+            vec![(String::from("a/+"), SubscriptionOptions::default())],
+            None,
+        );
+        assert!(subscribe.now_or_never().is_none());
+        assert_eq!(client.in_flight(), 0);
+
+        rx.try_recv().unwrap();
+        let pkid = block_on(client.publish("a/b", QoS::One, false, "sent", None)).unwrap();
+        assert!(pkid.is_some());
+        assert_eq!(client.in_flight(), 1);
+    }
+
+    #[test]
+    fn releases_the_packet_id_when_the_event_loop_is_gone() {
+        let (client, rx) = client(1);
+        drop(rx);
+
+        for _ in 0..3 {
+            assert!(block_on(client.publish("a/b", QoS::Two, false, "lost", None)).is_err());
+            assert!(block_on(client.unsubscribe(vec![String::from("a/b")], None)).is_err());
+        }
+        assert_eq!(client.in_flight(), 0);
+        assert_eq!(client.available(), 2);
+    }
+}
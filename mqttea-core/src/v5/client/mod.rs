@@ -7,9 +7,11 @@ use super::packet::connect::will::Will;
 pub(crate) mod client;
 pub mod handler;
 pub mod network;
-pub(crate) mod packet_id;
+pub mod packet_id;
 pub(crate) mod state;
 
+pub use client::MqttClient;
+
 #[derive(Debug)]
 pub struct ConnectOptions {
     /// Whether the user want's to handle all acks manually, or they want us to do this for them
@@ -8,8 +8,18 @@ use super::{packet_id::PacketIdManager, ConnectOptions};
 
 #[cfg(feature = "asyncx")]
 pub mod asyncx;
+#[cfg(feature = "asyncx")]
+pub mod duplex;
+#[cfg(feature = "asyncx")]
+pub mod outgoing;
 #[cfg(feature = "syncx")]
 pub mod syncx;
+#[cfg(feature = "tls")]
+pub mod tls;
+#[cfg(all(unix, feature = "unix"))]
+pub mod unix;
+#[cfg(feature = "websocket")]
+pub mod websocket;
 
 // mod not_used;
 
@@ -1,20 +1,35 @@
-use std::{sync::Arc, time::Instant};
+use std::{sync::Arc, time::Duration};
+
+#[cfg(not(target_arch = "wasm32"))]
+use std::time::Instant;
+#[cfg(target_arch = "wasm32")]
+use web_time::Instant;
 
 use async_channel::Receiver;
-use futures::{select, AsyncReadExt, AsyncWriteExt, FutureExt};
+use futures::{future, select, AsyncReadExt, AsyncWriteExt, FutureExt};
+use futures_timer::Delay;
 
 use crate::v5::{
     client::{client::MqttClient, handler::AsyncHandler, state::State, ConnectOptions},
-    commons::{error::MQTTError, packet::Packet, packet_type::PacketType},
+    commons::{
+        error::{MQTTError, Refusal},
+        packet::Packet,
+        packet_type::PacketType,
+        violation::{Validate, Violation},
+    },
     packet::{
-        connack::{reason_code::ConnAckReasonCode, ConnAck},
+        connack::{ConnAck, ConnAckReasonCode},
         connect::Connect,
+        disconnect::{Disconnect, DisconnectProperties},
         ping::PingReq,
     },
     traits::streamio::StreamIO,
 };
 
-use super::PacketIdManager;
+use super::{outgoing::OutgoingBuffer, PacketIdManager};
+
+/// Upper bound on the number of queued packets coalesced into a single write
+const MAX_BATCH: usize = 64;
 
 pub enum NetworkStatus {
     IncomingDisconnect,
@@ -28,6 +43,7 @@ pub struct Network<S> {
     options: ConnectOptions,
     state: State<PacketIdManager>,
     rx: Receiver<Packet>,
+    outgoing: OutgoingBuffer,
 }
 
 impl<S> Network<S>
@@ -49,10 +65,12 @@ where
             // pkids,
             state,
             rx,
+            outgoing: OutgoingBuffer::with_capacity(max_size.min(8 * 1024)),
         };
 
         let connack = network.connect().await?;
-        let server_receive_max = connack.properties.receive_maximum.unwrap_or(100);
+        // the Receive Maximum is 65,535 when the server does not send one (3.2.2.3.3)
+        let server_receive_max = connack.properties.receive_maximum.unwrap_or(u16::MAX);
         let pkids = Arc::new(PacketIdManager::new(server_receive_max));
 
         network.state.pkid_mgr = Some(pkids.clone());
@@ -61,21 +79,49 @@ where
     }
 
     async fn connect(&mut self) -> Result<ConnAck, MQTTError> {
-        Packet::Connect(Connect::from(&self.options))
-            .write(&mut self.stream)
-            .await?;
+        self.outgoing
+            .push(&Packet::Connect(Connect::from(&self.options)))?;
+        self.outgoing.flush(&mut self.stream).await?;
 
-        let packet = Packet::read(&mut self.stream).await?;
+        let incoming = Packet::read(&mut self.stream).await;
+        let packet = self.validate(incoming).await?;
 
         let Packet::ConnAck(connack) = packet else {
-            return Err(MQTTError::ConnectionError); // this needs to be return an Error that contains the packet received
+            return Err(MQTTError::UnexpectedPacket(packet.packet_type()));
         };
 
         if connack.reason == ConnAckReasonCode::Success {
             return Ok(connack);
         }
 
-        Err(MQTTError::ConnectionRefused(connack.reason.into()))
+        Err(Refusal::from(connack).into())
+    }
+
+    /// Confirms that a packet read off the stream respects the specification.
+    /// The connection is closed with the appropriate DISCONNECT Reason Code if it does not
+    async fn validate(&mut self, incoming: Result<Packet, MQTTError>) -> Result<Packet, MQTTError> {
+        let result = incoming.and_then(|packet| Ok(packet.validate().map(|_| packet)?));
+
+        match result {
+            Err(MQTTError::ProtocolViolation(violation)) => {
+                self.disconnect_with(&violation).await?;
+                Err(MQTTError::ProtocolViolation(violation))
+            }
+            result => result,
+        }
+    }
+
+    async fn disconnect_with(&mut self, violation: &Violation) -> Result<(), MQTTError> {
+        let packet = Disconnect {
+            reason_code: violation.reason,
+            properties: DisconnectProperties {
+                reason_string: Some(violation.to_string()),
+                ..Default::default()
+            },
+        };
+
+        self.outgoing.push(&Packet::Disconnect(packet))?;
+        self.outgoing.flush(&mut self.stream).await
     }
 
     pub async fn run<H>(&mut self, handler: &mut H) -> Result<NetworkStatus, MQTTError>
@@ -88,18 +134,33 @@ where
             None
         };
 
-        let keep_alive = self.options.keep_alive;
+        let keep_alive = Duration::from_secs(self.options.keep_alive as u64);
         let mut expecting_pingresp = false;
-        let max_timeout = keep_alive as u64 * (3 / 2);
+        // the PINGRESP is given one and a half times the Keep Alive to arrive (3.1.2.10)
+        let max_timeout = keep_alive * 3 / 2;
 
         // let result = self.state.handle_incoming_packet(&mut data);
         // result.unwrap().unwrap().write(&mut self.stream);
 
         loop {
+            // wakes the loop up when a PINGREQ is due, or the PINGRESP is overdue
+            let mut tick = match last_ping {
+                Some(last_time) => {
+                    let wait = if expecting_pingresp {
+                        max_timeout
+                    } else {
+                        keep_alive
+                    };
+                    Delay::new(wait.saturating_sub(last_time.elapsed())).left_future()
+                }
+                None => future::pending().right_future(),
+            }
+            .fuse();
+
             select! {
                 // receiving incoming packets
                 incoming = Packet::read(&mut self.stream).fuse() => {
-                    let mut packet = incoming?;
+                    let mut packet = self.validate(incoming).await?;
 
                     match packet {
                         Packet::PingResp(_) => {
@@ -117,39 +178,45 @@ where
                             handler.handle(packet).await;
 
                             if let Some(response) = result {
-                                response.write(&mut self.stream).await?;
+                                self.outgoing.push(&response)?;
+                                self.outgoing.flush(&mut self.stream).await?;
                             }
                         }
                     }
                 },
                 outgoing = self.rx.recv().fuse() => {
-                    let packet = outgoing?;
+                    let mut next = Some(outgoing?);
+                    let mut disconnect = false;
+
+                    // coalesce everything that is already queued into a single write
+                    while let Some(packet) = next.take() {
+                        disconnect = packet.packet_type() == PacketType::Disconnect;
 
-                    let disconnect = packet.packet_type() == PacketType::Disconnect;
+                        self.outgoing.push(&packet)?;
+                        self.state.handle_outgoing_packet(packet)?;
 
-                    packet.write(&mut self.stream).await?;
-                    self.state.handle_outgoing_packet(packet)?;
+                        if disconnect || self.outgoing.len() >= MAX_BATCH {
+                            break;
+                        }
+                        next = self.rx.try_recv().ok();
+                    }
+
+                    self.outgoing.flush(&mut self.stream).await?;
                     last_ping = Some(Instant::now());
 
                     if disconnect {
                         return Ok(NetworkStatus::OutgoingDisconnect)
                     }
                 },
-                 default => {
-                     let Some(last_time) = last_ping else { continue; };
-                     let since = last_time.elapsed().as_secs();
+                _ = tick => {
                     if expecting_pingresp {
-                        if since >= max_timeout {
-                            return Ok(NetworkStatus::Timeout);
-                        }
-                        continue;
+                        return Ok(NetworkStatus::Timeout);
                     }
 
-                    if last_ping.is_some_and(|t| t.elapsed().as_secs() >= keep_alive as u64) {
-                        Packet::PingReq(PingReq::default()).write(&mut self.stream).await?;
-                        last_ping = Some(Instant::now());
-                        expecting_pingresp = true;
-                    }
+                    self.outgoing.push(&Packet::PingReq(PingReq::default()))?;
+                    self.outgoing.flush(&mut self.stream).await?;
+                    last_ping = Some(Instant::now());
+                    expecting_pingresp = true;
                 },
             };
         }
@@ -0,0 +1,194 @@
+use std::{
+    collections::VecDeque,
+    io,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+};
+
+use futures::{AsyncRead, AsyncWrite};
+
+/// One direction of a duplex: what has been written and not read yet
+#[derive(Debug)]
+struct Pipe {
+    buf: VecDeque<u8>,
+    capacity: usize,
+    /// Set once the writer closed it, or either end was dropped
+    closed: bool,
+    reader: Option<Waker>,
+    writer: Option<Waker>,
+}
+
+impl Pipe {
+    fn new(capacity: usize) -> Arc<Mutex<Self>> {
+        Arc::new(Mutex::new(Self {
+            buf: VecDeque::with_capacity(capacity),
+            capacity,
+            closed: false,
+            reader: None,
+            writer: None,
+        }))
+    }
+
+    fn close(&mut self) {
+        self.closed = true;
+        wake(&mut self.reader);
+        wake(&mut self.writer);
+    }
+}
+
+fn wake(waker: &mut Option<Waker>) {
+    if let Some(waker) = waker.take() {
+        waker.wake();
+    }
+}
+
+/// One end of an in-memory connection made with [`duplex`], what is written to it being read from the other end.
+///
+/// It isn't tied to any runtime, and is handed to [`Network::new`](super::asyncx::Network::new) like any other stream
+#[derive(Debug)]
+pub struct DuplexStream {
+    read: Arc<Mutex<Pipe>>,
+    write: Arc<Mutex<Pipe>>,
+}
+
+/// Two connected streams held in memory, e.g. to wire a client to a broker running in the same process without any socket.
+///
+/// Each direction holds up to `capacity` bytes, writes waiting for the other end to read once it is full.
+/// Dropping an end is seen by the other as the connection closing: its reads end, and its writes fail with `BrokenPipe`
+pub fn duplex(capacity: usize) -> (DuplexStream, DuplexStream) {
+    assert!(capacity > 0, "a duplex can't hold 0 bytes");
+
+    let (one, two) = (Pipe::new(capacity), Pipe::new(capacity));
+    let a = DuplexStream {
+        read: one.clone(),
+        write: two.clone(),
+    };
+    let b = DuplexStream {
+        read: two,
+        write: one,
+    };
+    (a, b)
+}
+
+impl AsyncRead for DuplexStream {
+    fn poll_read(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        let mut pipe = self.read.lock().unwrap();
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+        if pipe.buf.is_empty() {
+            if pipe.closed {
+                return Poll::Ready(Ok(0));
+            }
+            pipe.reader = Some(cx.waker().clone());
+            return Poll::Pending;
+        }
+
+        let len = buf.len().min(pipe.buf.len());
+        for (dst, src) in buf.iter_mut().zip(pipe.buf.drain(..len)) {
+            *dst = src;
+        }
+        wake(&mut pipe.writer);
+        Poll::Ready(Ok(len))
+    }
+}
+
+impl AsyncWrite for DuplexStream {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let mut pipe = self.write.lock().unwrap();
+        if pipe.closed {
+            return Poll::Ready(Err(io::ErrorKind::BrokenPipe.into()));
+        }
+        if buf.is_empty() {
+            return Poll::Ready(Ok(0));
+        }
+
+        let len = buf.len().min(pipe.capacity - pipe.buf.len());
+        if len == 0 {
+            pipe.writer = Some(cx.waker().clone());
+            return Poll::Pending;
+        }
+        pipe.buf.extend(&buf[..len]);
+        wake(&mut pipe.reader);
+        Poll::Ready(Ok(len))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    /// Ends what the other end reads once it has read everything written so far, like shutting down the write half of a socket
+    fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.write.lock().unwrap().close();
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl Drop for DuplexStream {
+    fn drop(&mut self) {
+        self.read.lock().unwrap().close();
+        self.write.lock().unwrap().close();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{executor::block_on, future, AsyncReadExt, AsyncWriteExt};
+
+    use super::*;
+
+    #[test]
+    fn carries_bytes_both_ways() {
+        let (mut a, mut b) = duplex(64);
+        block_on(async {
+            a.write_all(b"ping").await.unwrap();
+            b.write_all(b"pong").await.unwrap();
+
+            let mut buf = [0; 4];
+            b.read_exact(&mut buf).await.unwrap();
+            assert_eq!(&buf, b"ping");
+            a.read_exact(&mut buf).await.unwrap();
+            assert_eq!(&buf, b"pong");
+        });
+    }
+
+    #[test]
+    fn waits_for_the_reader_once_full() {
+        let (mut a, mut b) = duplex(3);
+        let payload = (0..=255).collect::<Vec<u8>>();
+        block_on(async {
+            let write = async {
+                a.write_all(&payload).await.unwrap();
+                a.close().await.unwrap();
+            };
+            let mut received = Vec::new();
+            let read = b.read_to_end(&mut received);
+
+            let (_, read) = future::join(write, read).await;
+            assert_eq!(read.unwrap(), payload.len());
+            assert_eq!(received, payload);
+        });
+    }
+
+    #[test]
+    fn closes_when_the_other_end_is_dropped() {
+        let (mut a, b) = duplex(8);
+        block_on(async {
+            a.write_all(b"unread").await.unwrap();
+            drop(b);
+
+            let err = a.write_all(b"more").await.unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+            assert_eq!(a.read(&mut [0; 8]).await.unwrap(), 0);
+        });
+    }
+}
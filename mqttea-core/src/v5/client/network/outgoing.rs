@@ -0,0 +1,277 @@
+use std::io::IoSlice;
+
+use bytes::{Buf, Bytes, BytesMut};
+use futures::AsyncWriteExt;
+
+use crate::v5::{
+    commons::{error::MQTTError, packet::Packet},
+    traits::bufferio::BufferIO,
+};
+
+/// Publish payloads smaller than this are copied into the shared buffer,
+/// anything larger is queued as its own segment (without copying) and sent with a vectored write
+const INLINE_PAYLOAD_MAX: usize = 512;
+
+/// Maximum number of `IoSlice`s handed to a single `write_vectored` call (IOV_MAX is 1024 on most platforms)
+const MAX_IO_SLICES: usize = 64;
+
+/// Collects encoded outgoing packets so that everything queued within a single iteration of the event loop
+/// is written to the stream with as few `write_vectored` calls (and exactly one flush) as possible.
+///
+/// Packets are encoded into a reusable `BytesMut`, while large publish payloads are kept as the
+/// (reference-counted) `Bytes` provided by the user, so they are never copied before reaching the stream.
+#[derive(Debug, Default)]
+pub struct OutgoingBuffer {
+    buf: BytesMut,
+    /// Encoded segments in the order in which they must be written to the stream
+    segments: Vec<Bytes>,
+    packets: usize,
+}
+
+impl OutgoingBuffer {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            buf: BytesMut::with_capacity(capacity),
+            segments: Vec::new(),
+            packets: 0,
+        }
+    }
+
+    /// Number of packets queued since the last flush
+    pub fn len(&self) -> usize {
+        self.packets
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.packets == 0
+    }
+
+    /// Encodes the packet and queues it for the next flush
+    pub fn push(&mut self, packet: &Packet) -> Result<(), MQTTError> {
+        match packet {
+            Packet::Publish(publish) if publish.payload.len() > INLINE_PAYLOAD_MAX => {
+                publish.write_head(&mut self.buf)?;
+                self.seal();
+                self.segments.push(publish.payload.clone());
+            }
+            packet => packet.write(&mut self.buf)?,
+        }
+
+        self.packets += 1;
+        Ok(())
+    }
+
+    /// Moves whatever has been encoded into the shared buffer so far into its own segment
+    fn seal(&mut self) {
+        if !self.buf.is_empty() {
+            let segment = self.buf.split().freeze();
+            self.segments.push(segment);
+        }
+    }
+
+    /// Writes every queued packet to the stream and flushes it.
+    /// The internal buffer's allocation is reclaimed once the written segments are dropped
+    pub async fn flush<W>(&mut self, stream: &mut W) -> Result<(), MQTTError>
+    where
+        W: AsyncWriteExt + Unpin,
+    {
+        if self.is_empty() {
+            return Ok(());
+        }
+        self.seal();
+
+        let mut index = 0;
+        while index < self.segments.len() {
+            let slices = self.segments[index..]
+                .iter()
+                .take(MAX_IO_SLICES)
+                .map(|segment| IoSlice::new(segment))
+                .collect::<Vec<_>>();
+
+            let mut written = stream.write_vectored(&slices).await?;
+            if written == 0 {
+                return Err(std::io::Error::from(std::io::ErrorKind::WriteZero).into());
+            }
+
+            // drop fully written segments, and advance into the first partially written one
+            while written > 0 {
+                let segment = &mut self.segments[index];
+                let n = written.min(segment.len());
+                segment.advance(n);
+                written -= n;
+                if segment.is_empty() {
+                    index += 1;
+                }
+            }
+        }
+
+        stream.flush().await?;
+
+        self.segments.clear();
+        self.packets = 0;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        pin::Pin,
+        task::{Context, Poll},
+    };
+
+    use bytes::Bytes;
+    use futures::{executor::block_on, AsyncWrite};
+
+    use super::*;
+    use crate::v5::{
+        commons::qos::QoS,
+        packet::{ping::PingReq, publish::Publish},
+        traits::streamio::StreamIO,
+    };
+
+    /// Records every write/flush issued against it, as each of these would be a syscall on a socket
+    #[derive(Debug, Default)]
+    struct CountingWriter {
+        data: Vec<u8>,
+        writes: usize,
+        flushes: usize,
+        /// Maximum number of bytes accepted per call, in order to simulate short writes
+        limit: Option<usize>,
+    }
+
+    impl AsyncWrite for CountingWriter {
+        fn poll_write(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<std::io::Result<usize>> {
+            self.writes += 1;
+            let n = self.limit.unwrap_or(usize::MAX).min(buf.len());
+            self.data.extend_from_slice(&buf[..n]);
+            Poll::Ready(Ok(n))
+        }
+
+        fn poll_write_vectored(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            bufs: &[IoSlice<'_>],
+        ) -> Poll<std::io::Result<usize>> {
+            self.writes += 1;
+            let mut remaining = self.limit.unwrap_or(usize::MAX);
+            let mut written = 0;
+            for buf in bufs {
+                let n = remaining.min(buf.len());
+                self.data.extend_from_slice(&buf[..n]);
+                remaining -= n;
+                written += n;
+            }
+            Poll::Ready(Ok(written))
+        }
+
+        fn poll_flush(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+        ) -> Poll<std::io::Result<()>> {
+            self.flushes += 1;
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    fn packets() -> Vec<Packet> {
+        vec![
+            Packet::Publish(Publish {
+                qos: QoS::One,
+                topic: String::from("sensors/temperature"),
+                pkid: Some(7),
+                payload: Bytes::from(vec![0xAB; 2048]),
+                ..Default::default()
+            }),
+            Packet::PingReq(PingReq::default()),
+            Packet::Publish(Publish {
+                topic: String::from("sensors/humidity"),
+                payload: Bytes::from_static(b"43%"),
+                ..Default::default()
+            }),
+        ]
+    }
+
+    fn encoded(packets: &[Packet]) -> Vec<u8> {
+        let mut buf = BytesMut::new();
+        packets
+            .iter()
+            .for_each(|p| BufferIO::write(p, &mut buf).unwrap());
+        buf.to_vec()
+    }
+
+    #[test]
+    fn coalesces_queued_packets_into_a_single_write_and_flush() {
+        let packets = packets();
+        let mut stream = CountingWriter::default();
+        let mut outgoing = OutgoingBuffer::default();
+
+        packets.iter().for_each(|p| outgoing.push(p).unwrap());
+        assert_eq!(outgoing.len(), 3);
+        block_on(outgoing.flush(&mut stream)).unwrap();
+
+        assert_eq!(stream.writes, 1);
+        assert_eq!(stream.flushes, 1);
+        assert_eq!(stream.data, encoded(&packets));
+        assert!(outgoing.is_empty());
+    }
+
+    #[test]
+    fn issues_fewer_writes_than_writing_each_field_to_the_stream() {
+        let packets = packets();
+
+        let mut unbuffered = CountingWriter::default();
+        for packet in &packets {
+            block_on(StreamIO::write(packet, &mut unbuffered)).unwrap();
+        }
+
+        let mut batched = CountingWriter::default();
+        let mut outgoing = OutgoingBuffer::default();
+        packets.iter().for_each(|p| outgoing.push(p).unwrap());
+        block_on(outgoing.flush(&mut batched)).unwrap();
+
+        assert!(unbuffered.writes > 10 * batched.writes);
+    }
+
+    #[test]
+    fn resumes_after_short_writes() {
+        let packets = packets();
+        let mut stream = CountingWriter {
+            limit: Some(100),
+            ..Default::default()
+        };
+        let mut outgoing = OutgoingBuffer::default();
+
+        packets.iter().for_each(|p| outgoing.push(p).unwrap());
+        block_on(outgoing.flush(&mut stream)).unwrap();
+
+        let expected = encoded(&packets);
+        assert_eq!(stream.writes, expected.len().div_ceil(100));
+        assert_eq!(stream.flushes, 1);
+        assert_eq!(stream.data, expected);
+    }
+
+    #[test]
+    fn large_payloads_are_not_copied() {
+        let payload = Bytes::from(vec![1u8; 4096]);
+        let packet = Packet::Publish(Publish {
+            topic: String::from("a/b"),
+            payload: payload.clone(),
+            ..Default::default()
+        });
+
+        let mut outgoing = OutgoingBuffer::default();
+        outgoing.push(&packet).unwrap();
+
+        assert_eq!(outgoing.segments.len(), 2);
+        assert_eq!(outgoing.segments[1].as_ptr(), payload.as_ptr());
+    }
+}
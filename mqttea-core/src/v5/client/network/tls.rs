@@ -0,0 +1,273 @@
+use std::{
+    fs, io,
+    path::PathBuf,
+    sync::{Arc, RwLock},
+    time::{Duration, SystemTime},
+};
+
+use futures::{AsyncRead, AsyncWrite, Future};
+pub use futures_rustls::client::TlsStream;
+use futures_rustls::rustls::{crypto::ring, version::TLS13, ClientConfig, RootCertStore};
+use futures_timer::Delay;
+use rustls_pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer, ServerName};
+
+use crate::v5::{
+    client::{client::MqttClient, ConnectOptions},
+    commons::error::MQTTError,
+};
+
+use super::{asyncx::Network, PacketIdManager};
+
+/// The protocol negotiated through ALPN, as registered with IANA for MQTT over TLS
+pub const ALPN: &[u8] = b"mqtt";
+
+/// What a TLS connection to the broker is made with, every certificate and key being PEM encoded
+#[derive(Debug, Clone, Default)]
+pub struct TlsOptions {
+    /// Name the broker's certificate must be valid for, also sent as the SNI
+    pub host: String,
+    /// The certificate authorities the broker's certificate is checked against
+    pub ca: Vec<u8>,
+    /// Certificate chain presented to brokers that ask the client for one
+    pub client_cert: Option<Vec<u8>>,
+    /// Private key of the client certificate
+    pub client_key: Option<Vec<u8>>,
+}
+
+/// Where the PEM files making up `TlsOptions` are, so that they can be read again once rotated
+#[derive(Debug, Clone, Default)]
+pub struct TlsFiles {
+    pub host: String,
+    pub ca: PathBuf,
+    pub client_cert: Option<PathBuf>,
+    pub client_key: Option<PathBuf>,
+}
+
+impl TlsFiles {
+    pub fn load(&self) -> io::Result<TlsOptions> {
+        Ok(TlsOptions {
+            host: self.host.clone(),
+            ca: fs::read(&self.ca)?,
+            client_cert: self.client_cert.as_ref().map(fs::read).transpose()?,
+            client_key: self.client_key.as_ref().map(fs::read).transpose()?,
+        })
+    }
+
+    fn paths(&self) -> Vec<PathBuf> {
+        [
+            Some(&self.ca),
+            self.client_cert.as_ref(),
+            self.client_key.as_ref(),
+        ]
+        .into_iter()
+        .flatten()
+        .cloned()
+        .collect()
+    }
+}
+
+/// Wraps the stream to the broker in a TLS 1.3 session.
+///
+/// Clones share their material, so that reloading it through any of them applies to every connection made from then on,
+/// reconnections included. Sessions already established carry on with the material they were made with
+#[derive(Clone)]
+pub struct TlsConnector {
+    current: Arc<RwLock<Current>>,
+}
+
+#[derive(Clone)]
+struct Current {
+    connector: futures_rustls::TlsConnector,
+    server_name: ServerName<'static>,
+}
+
+impl TlsConnector {
+    pub fn new(options: &TlsOptions) -> io::Result<Self> {
+        Ok(Self {
+            current: Arc::new(RwLock::new(Current::new(options)?)),
+        })
+    }
+
+    /// Replaces the material handshakes are made with, `self` being left as is when `options` are invalid
+    pub fn reload(&self, options: &TlsOptions) -> io::Result<()> {
+        let current = Current::new(options)?;
+        *self.current.write().unwrap() = current;
+        Ok(())
+    }
+
+    /// Reloads `files` whenever one of them is modified from now on, checking every `interval`, until the future is dropped.
+    /// Files caught halfway through being rewritten are read again on the next check, the previous material being kept meanwhile
+    pub fn watch(self, files: TlsFiles, interval: Duration) -> impl Future<Output = ()> {
+        watch_files(files.paths(), interval, move || self.reload(&files.load()?))
+    }
+
+    /// Performs the TLS handshake over `stream`, which is usually a freshly opened TCP connection
+    pub async fn connect<S>(&self, stream: S) -> io::Result<TlsStream<S>>
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        let current = self.current.read().unwrap().clone();
+        current.connector.connect(current.server_name, stream).await
+    }
+}
+
+impl Current {
+    fn new(options: &TlsOptions) -> io::Result<Self> {
+        let server_name = ServerName::try_from(options.host.clone())
+            .map_err(|err| invalid(format!("host: {err}")))?;
+
+        let client_auth = match (&options.client_cert, &options.client_key) {
+            (Some(cert), Some(key)) => Some((
+                certificates(cert, "client_cert")?,
+                private_key(key, "client_key")?,
+            )),
+            (None, None) => None,
+            _ => {
+                return Err(invalid(String::from(
+                    "client_cert and client_key go together",
+                )))
+            }
+        };
+
+        let roots = roots(&options.ca, "ca")?;
+        let builder = ClientConfig::builder_with_provider(Arc::new(ring::default_provider()))
+            .with_protocol_versions(&[&TLS13])
+            .map_err(|err| invalid(err.to_string()))?
+            .with_root_certificates(roots);
+        let mut config = match client_auth {
+            Some((chain, key)) => builder
+                .with_client_auth_cert(chain, key)
+                .map_err(|err| invalid(format!("client_cert: {err}")))?,
+            None => builder.with_no_client_auth(),
+        };
+        config.alpn_protocols = vec![ALPN.to_vec()];
+
+        Ok(Self {
+            connector: Arc::new(config).into(),
+            server_name,
+        })
+    }
+}
+
+impl std::fmt::Debug for TlsConnector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let current = self.current.read().unwrap();
+        f.debug_struct("TlsConnector")
+            .field("server_name", &current.server_name)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S> Network<TlsStream<S>>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    /// Connects to the broker through a TLS session established over `stream`
+    pub async fn with_tls(
+        options: ConnectOptions,
+        stream: S,
+        connector: &TlsConnector,
+    ) -> Result<(Self, MqttClient<PacketIdManager>), MQTTError> {
+        let stream = connector.connect(stream).await?;
+        Self::new(options, stream).await
+    }
+}
+
+/// The certificates of a PEM bundle, `field` naming it in the error when it holds none or can't be read
+pub fn certificates(pem: &[u8], field: &str) -> io::Result<Vec<CertificateDer<'static>>> {
+    let certificates = CertificateDer::pem_slice_iter(pem)
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| invalid(format!("{field}: {err}")))?;
+    if certificates.is_empty() {
+        return Err(invalid(format!("{field}: no certificate found")));
+    }
+    Ok(certificates)
+}
+
+/// The first private key of a PEM file, `field` naming it in the error
+pub fn private_key(pem: &[u8], field: &str) -> io::Result<PrivateKeyDer<'static>> {
+    PrivateKeyDer::from_pem_slice(pem).map_err(|err| invalid(format!("{field}: {err}")))
+}
+
+/// The certificate authorities of a PEM bundle, which the certificate of the other end is checked against
+pub fn roots(pem: &[u8], field: &str) -> io::Result<RootCertStore> {
+    let mut roots = RootCertStore::empty();
+    for cert in certificates(pem, field)? {
+        roots
+            .add(cert)
+            .map_err(|err| invalid(format!("{field}: {err}")))?;
+    }
+    Ok(roots)
+}
+
+/// Calls `reload` whenever one of `paths` is modified from now on, checking every `interval`, until the future is dropped.
+/// A failed `reload`, e.g. on files caught halfway through being rewritten, is tried again on the next check
+pub fn watch_files<F>(
+    paths: Vec<PathBuf>,
+    interval: Duration,
+    mut reload: F,
+) -> impl Future<Output = ()>
+where
+    F: FnMut() -> io::Result<()>,
+{
+    let mut seen = modified(&paths);
+    async move {
+        loop {
+            Delay::new(interval).await;
+            let modified = modified(&paths);
+            if modified != seen && reload().is_ok() {
+                seen = modified;
+            }
+        }
+    }
+}
+
+/// When each file was last modified, `None` for those that can't be read
+fn modified(paths: &[PathBuf]) -> Vec<Option<SystemTime>> {
+    paths
+        .iter()
+        .map(|path| {
+            fs::metadata(path)
+                .and_then(|metadata| metadata.modified())
+                .ok()
+        })
+        .collect()
+}
+
+fn invalid(reason: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, reason)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(ca: &str) -> TlsOptions {
+        TlsOptions {
+            host: String::from("localhost"),
+            ca: ca.as_bytes().to_vec(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn refuses_a_bundle_without_certificates() {
+        let err = TlsConnector::new(&options("")).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert_eq!(err.to_string(), "ca: no certificate found");
+
+        let garbage = "-----BEGIN CERTIFICATE-----\n!!!\n-----END CERTIFICATE-----\n";
+        let err = TlsConnector::new(&options(garbage)).unwrap_err();
+        assert!(err.to_string().starts_with("ca:"));
+    }
+
+    #[test]
+    fn refuses_a_client_certificate_without_its_key() {
+        let options = TlsOptions {
+            client_cert: Some(Vec::new()),
+            ..options("")
+        };
+        let err = TlsConnector::new(&options).unwrap_err();
+        assert_eq!(err.to_string(), "client_cert and client_key go together");
+    }
+}
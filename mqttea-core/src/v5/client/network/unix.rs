@@ -0,0 +1,21 @@
+use std::path::Path;
+
+pub use async_net::unix::UnixStream;
+
+use crate::v5::{
+    client::{client::MqttClient, ConnectOptions},
+    commons::error::MQTTError,
+};
+
+use super::{asyncx::Network, PacketIdManager};
+
+impl Network<UnixStream> {
+    /// Connects to a broker listening on the Unix domain socket at `path`, e.g. one running next to the client
+    pub async fn connect_unix(
+        options: ConnectOptions,
+        path: impl AsRef<Path>,
+    ) -> Result<(Self, MqttClient<PacketIdManager>), MQTTError> {
+        let stream = UnixStream::connect(path).await?;
+        Self::new(options, stream).await
+    }
+}
@@ -0,0 +1,333 @@
+use std::{
+    io,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use async_tungstenite::{
+    tungstenite::{
+        client::IntoClientRequest,
+        handshake::server::{ErrorResponse, Request, Response},
+        http::{header::SEC_WEBSOCKET_PROTOCOL, HeaderValue, StatusCode},
+        Error as WsError, Message,
+    },
+    WebSocketStream,
+};
+use bytes::{Buf, Bytes};
+use futures::{AsyncRead, AsyncWrite, Sink, Stream};
+
+use crate::v5::{
+    client::{client::MqttClient, ConnectOptions},
+    commons::error::MQTTError,
+};
+
+use super::{asyncx::Network, PacketIdManager};
+
+/// The WebSocket subprotocol MQTT is carried in (MQTT-6.0.0-3, MQTT-6.0.0-4)
+pub const SUBPROTOCOL: &str = "mqtt";
+
+/// A WebSocket connection, read and written as the byte stream MQTT packets are carried in.
+///
+/// Packets are carried in binary frames, where a packet may span several frames and a frame may hold several packets (MQTT-6.0.0-2).
+/// Each write is sent as a frame of its own
+#[derive(Debug)]
+pub struct WsStream<S> {
+    ws: WebSocketStream<S>,
+    /// What is left of the last frame received
+    frame: Bytes,
+}
+
+/// Opens a WebSocket to `url` (`ws://` or `wss://`) over `stream`, offering the `mqtt` subprotocol.
+/// For `wss://`, `stream` is the TLS session to the broker
+pub async fn connect<S>(url: &str, stream: S) -> io::Result<WsStream<S>>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut request = url.into_client_request().map_err(into_io)?;
+    request.headers_mut().insert(
+        SEC_WEBSOCKET_PROTOCOL,
+        HeaderValue::from_static(SUBPROTOCOL),
+    );
+
+    let (ws, response) = async_tungstenite::client_async(request, stream)
+        .await
+        .map_err(into_io)?;
+    if !offers_mqtt(response.headers().get(SEC_WEBSOCKET_PROTOCOL)) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "the server did not agree to the mqtt subprotocol",
+        ));
+    }
+
+    Ok(WsStream::new(ws))
+}
+
+/// The broker's side of the opening handshake, refusing clients that don't offer the `mqtt` subprotocol
+pub async fn accept<S>(stream: S) -> io::Result<WsStream<S>>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    // the refusal is tungstenite's response type, whatever its size
+    #[allow(clippy::result_large_err)]
+    let callback = |request: &Request, mut response: Response| {
+        let offered = request
+            .headers()
+            .get_all(SEC_WEBSOCKET_PROTOCOL)
+            .iter()
+            .any(|protocols| offers_mqtt(Some(protocols)));
+        if !offered {
+            let mut refusal =
+                ErrorResponse::new(Some(String::from("expected the mqtt subprotocol")));
+            *refusal.status_mut() = StatusCode::BAD_REQUEST;
+            return Err(refusal);
+        }
+        response.headers_mut().insert(
+            SEC_WEBSOCKET_PROTOCOL,
+            HeaderValue::from_static(SUBPROTOCOL),
+        );
+        Ok(response)
+    };
+
+    let ws = async_tungstenite::accept_hdr_async(stream, callback)
+        .await
+        .map_err(into_io)?;
+    Ok(WsStream::new(ws))
+}
+
+/// Whether `mqtt` is among the comma separated subprotocols of the header
+fn offers_mqtt(protocols: Option<&HeaderValue>) -> bool {
+    protocols
+        .and_then(|protocols| protocols.to_str().ok())
+        .is_some_and(|protocols| {
+            protocols
+                .split(',')
+                .any(|protocol| protocol.trim() == SUBPROTOCOL)
+        })
+}
+
+impl<S> WsStream<S> {
+    fn new(ws: WebSocketStream<S>) -> Self {
+        Self {
+            ws,
+            frame: Bytes::new(),
+        }
+    }
+
+    pub fn get_ref(&self) -> &S
+    where
+        S: AsyncRead + AsyncWrite + Unpin,
+    {
+        self.ws.get_ref()
+    }
+}
+
+impl<S> AsyncRead for WsStream<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        while self.frame.is_empty() {
+            match ready!(Pin::new(&mut self.ws).poll_next(cx)) {
+                Some(Ok(Message::Binary(data))) => self.frame = data,
+                // MQTT-6.0.0-1 only binary frames may carry MQTT Control Packets
+                Some(Ok(Message::Text(_))) => {
+                    return Poll::Ready(Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "received a text frame",
+                    )))
+                }
+                // pings are answered by the WebSocket itself
+                Some(Ok(Message::Ping(_) | Message::Pong(_) | Message::Frame(_))) => {}
+                Some(Ok(Message::Close(_))) | None => return Poll::Ready(Ok(0)),
+                Some(Err(err)) => return Poll::Ready(Err(into_io(err))),
+            }
+        }
+
+        let n = buf.len().min(self.frame.len());
+        buf[..n].copy_from_slice(&self.frame[..n]);
+        self.frame.advance(n);
+        Poll::Ready(Ok(n))
+    }
+}
+
+impl<S> AsyncWrite for WsStream<S>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.poll_write_vectored(cx, &[io::IoSlice::new(buf)])
+    }
+
+    /// Sends every slice in a single frame, rather than one frame per slice
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        let mut ws = Pin::new(&mut self.ws);
+        ready!(ws.as_mut().poll_ready(cx)).map_err(into_io)?;
+
+        let frame = bufs
+            .iter()
+            .flat_map(|buf| buf.iter().copied())
+            .collect::<Vec<_>>();
+        let n = frame.len();
+        ws.start_send(Message::Binary(frame.into()))
+            .map_err(into_io)?;
+        Poll::Ready(Ok(n))
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.ws).poll_flush(cx).map_err(into_io)
+    }
+
+    fn poll_close(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        match ready!(Pin::new(&mut self.ws).poll_close(cx)) {
+            Ok(()) | Err(WsError::ConnectionClosed | WsError::AlreadyClosed) => Poll::Ready(Ok(())),
+            Err(err) => Poll::Ready(Err(into_io(err))),
+        }
+    }
+}
+
+impl<S> Network<WsStream<S>>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    /// Connects to the broker through a WebSocket to `url` opened over `stream`
+    pub async fn with_websocket(
+        options: ConnectOptions,
+        url: &str,
+        stream: S,
+    ) -> Result<(Self, MqttClient<PacketIdManager>), MQTTError> {
+        let stream = connect(url, stream).await?;
+        Self::new(options, stream).await
+    }
+}
+
+fn into_io(err: WsError) -> io::Error {
+    match err {
+        WsError::Io(err) => err,
+        WsError::ConnectionClosed | WsError::AlreadyClosed => {
+            io::Error::from(io::ErrorKind::BrokenPipe)
+        }
+        err => io::Error::new(io::ErrorKind::InvalidData, err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_tungstenite::tungstenite::protocol::Role;
+    use futures::{executor::block_on, io::Cursor, AsyncWriteExt, StreamExt};
+
+    use super::*;
+    use crate::v5::{
+        commons::packet::Packet, packet::publish::Publish, traits::streamio::StreamIO,
+    };
+
+    fn publish(topic: &str) -> Packet {
+        Packet::Publish(Publish {
+            topic: topic.to_string(),
+            payload: Bytes::from_static(b"21.5"),
+            ..Default::default()
+        })
+    }
+
+    fn encode(packet: &Packet) -> Vec<u8> {
+        let mut stream = Cursor::new(Vec::new());
+        block_on(StreamIO::write(packet, &mut stream)).unwrap();
+        stream.into_inner()
+    }
+
+    /// What a peer in `role` puts on the wire when sending `messages`
+    fn frames(role: Role, messages: Vec<Message>) -> Vec<u8> {
+        block_on(async {
+            let mut ws =
+                WebSocketStream::from_raw_socket(Cursor::new(Vec::new()), role, None).await;
+            for message in messages {
+                ws.send(message).await.unwrap();
+            }
+            ws.into_inner().into_inner()
+        })
+    }
+
+    #[test]
+    fn reads_packets_split_across_and_coalesced_within_frames() {
+        let (first, second) = (encode(&publish("a/b")), encode(&publish("c/d")));
+        let (head, tail) = first.split_at(3);
+        let frames = frames(
+            Role::Server,
+            vec![
+                Message::binary(head.to_vec()),
+                Message::Ping(Bytes::new()),
+                Message::binary([tail, &second].concat()),
+            ],
+        );
+
+        block_on(async {
+            let ws =
+                WebSocketStream::from_raw_socket(Cursor::new(frames), Role::Client, None).await;
+            let mut stream = WsStream::new(ws);
+            assert_eq!(
+                Packet::read_async(&mut stream, 1024).await.unwrap(),
+                publish("a/b")
+            );
+            assert_eq!(
+                Packet::read_async(&mut stream, 1024).await.unwrap(),
+                publish("c/d")
+            );
+        });
+    }
+
+    #[test]
+    fn refuses_text_frames() {
+        let frames = frames(Role::Server, vec![Message::text("a/b")]);
+
+        block_on(async {
+            let ws =
+                WebSocketStream::from_raw_socket(Cursor::new(frames), Role::Client, None).await;
+            let err = Packet::read_async(&mut WsStream::new(ws), 1024).await;
+            assert!(err.is_err());
+        });
+    }
+
+    #[test]
+    fn writes_a_frame_per_write() {
+        let (first, second) = (encode(&publish("a/b")), encode(&publish("c/d")));
+        let written = block_on(async {
+            let ws =
+                WebSocketStream::from_raw_socket(Cursor::new(Vec::new()), Role::Client, None).await;
+            let mut stream = WsStream::new(ws);
+            let slices = [io::IoSlice::new(&first), io::IoSlice::new(&second)];
+            let written = stream.write_vectored(&slices).await.unwrap();
+            assert_eq!(written, first.len() + second.len());
+            stream.flush().await.unwrap();
+            stream.ws.into_inner().into_inner()
+        });
+
+        block_on(async {
+            let mut ws =
+                WebSocketStream::from_raw_socket(Cursor::new(written), Role::Server, None).await;
+            let message = ws.next().await.unwrap().unwrap();
+            assert_eq!(message, Message::binary([first, second].concat()));
+            // nothing but the end of the stream follows
+            assert!(!matches!(ws.next().await, Some(Ok(_))));
+        });
+    }
+
+    #[test]
+    fn finds_mqtt_among_the_offered_subprotocols() {
+        let offers = |value: &'static str| offers_mqtt(Some(&HeaderValue::from_static(value)));
+        assert!(offers("mqtt"));
+        assert!(offers("mqttv3.1, mqtt"));
+        assert!(!offers("mqttv3.1"));
+        assert!(!offers_mqtt(None));
+    }
+}
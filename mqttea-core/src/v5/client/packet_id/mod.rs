@@ -1,90 +1,205 @@
-use std::sync::atomic::{AtomicU16, Ordering};
+use std::{
+    collections::VecDeque,
+    future::Future,
+    sync::{
+        atomic::{fence, AtomicU16, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
 
 mod shard;
+mod waiter;
 use shard::PacketIdShard;
+pub use waiter::Allocate;
+use waiter::Waiter;
 
-use crate::v5::{
-    commons::error::MQTTError,
-    traits::pkid_mgr::{PacketIdAlloc, PacketIdRelease},
+use crate::{
+    constants::MAX,
+    v5::{
+        commons::error::MQTTError,
+        traits::pkid_mgr::{PacketIdAlloc, PacketIdRelease},
+    },
 };
 
+/// Allocates the Packet Identifiers of a single client session.
+/// Identifiers are handed out in turn over 1..=65535 (wrapping around), so an identifier that was just released
+/// is not reused straight away, while no more than the server's Receive Maximum are ever in flight (3.3.4)
 #[derive(Debug)]
 pub struct PacketIdManager {
     shards: Vec<PacketIdShard>,
     allocated: AtomicU16,
-    max_packets: u16,
+    /// Index (Packet Identifier - 1) the search for the next free identifier starts from
+    next: AtomicU16,
+    receive_max: u16,
+    /// Callers of `allocate_async` waiting for an identifier, served in order of arrival
+    waiters: Mutex<VecDeque<Arc<Waiter>>>,
+    /// Length of `waiters`, so that `release` only takes the lock when someone is waiting
+    waiting: AtomicUsize,
 }
 
 impl PacketIdManager {
     const BITS: usize = usize::BITS as usize;
 
-    pub(crate) fn new(max_packets: u16) -> Self {
-        let num_shards = (max_packets as usize + Self::BITS - 1) / Self::BITS;
+    pub fn new(receive_max: u16) -> Self {
+        let num_shards = MAX.div_ceil(Self::BITS);
         let shards = (0..num_shards)
             .map(|_| PacketIdShard::default())
             .collect::<Vec<_>>();
+
+        // the bits past 65535 do not map to a Packet Identifier, so they are never handed out
+        let unused = num_shards * Self::BITS - MAX;
+        let last = &shards[num_shards - 1].0;
+        last.store(!(usize::MAX >> unused), Ordering::Relaxed);
+
         Self {
             shards,
             allocated: AtomicU16::new(0),
-            max_packets,
+            next: AtomicU16::new(0),
+            receive_max,
+            waiters: Mutex::new(VecDeque::new()),
+            waiting: AtomicUsize::new(0),
         }
     }
-}
 
-impl PacketIdAlloc for PacketIdManager {
-    fn allocate(&self) -> Result<u16, MQTTError> {
-        let allocated = self.allocated.fetch_add(1, Ordering::AcqRel);
-        if allocated >= self.max_packets {
-            // rollback
+    /// Allocates a Packet Identifier, waiting for one to be released if the Receive Maximum has been reached.
+    ///
+    /// Waiters are served in the order they started waiting, and each `release` wakes exactly one of them with an identifier.
+    /// Dropping the future gives up its place in the queue, or releases the identifier it was given if it had not been polled since
+    pub fn allocate_async(&self) -> Allocate<'_> {
+        Allocate::new(self)
+    }
+
+    /// Number of Packet Identifiers currently in use
+    pub fn in_flight(&self) -> u16 {
+        self.allocated.load(Ordering::Acquire)
+    }
+
+    /// Number of Packet Identifiers that can still be allocated before the Receive Maximum is reached
+    pub fn available(&self) -> u16 {
+        self.receive_max.saturating_sub(self.in_flight())
+    }
+
+    pub fn receive_max(&self) -> u16 {
+        self.receive_max
+    }
+
+    fn try_allocate(&self) -> Option<u16> {
+        // reserve a slot under the Receive Maximum before looking for an identifier
+        self.allocated
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| {
+                (n < self.receive_max).then(|| n + 1)
+            })
+            .ok()?;
+
+        let id = self.find_free();
+        if id.is_none() {
+            // It should be almost impossible for this to occur, but its better taken care of than not!
             self.allocated.fetch_sub(1, Ordering::Release);
-            return Err(MQTTError::PacketIdGenerationError);
         }
+        id
+    }
+
+    /// Serves the callers of `allocate_async` at the front of the queue with the identifiers that are free
+    fn wake_next(&self) {
+        let mut waiters = self.waiters.lock().unwrap();
+        while !waiters.is_empty() {
+            let Some(id) = self.try_allocate() else {
+                break;
+            };
+            let waiter = waiters.pop_front().unwrap();
+            self.waiting.fetch_sub(1, Ordering::SeqCst);
+            waiter.grant(id);
+        }
+    }
+
+    fn find_free(&self) -> Option<u16> {
+        let start = self.next.load(Ordering::Relaxed) as usize;
+        let first = start / Self::BITS;
 
-        for (shard_index, shard) in self.shards.iter().enumerate() {
-            if let Some(id) = shard.allocate() {
+        // the shard holding the cursor is visited twice: from the cursor on the first pass, and from its start on the last
+        for i in 0..=self.shards.len() {
+            let shard_index = (first + i) % self.shards.len();
+            let from = if i == 0 {
+                (start % Self::BITS) as u32
+            } else {
+                0
+            };
+
+            if let Some(id) = self.shards[shard_index].allocate(from) {
+                let index = shard_index * Self::BITS + id as usize;
+                self.next
+                    .store(((index + 1) % MAX) as u16, Ordering::Relaxed);
                 // packet must always be non-zero
-                let packet_id = (shard_index * (Self::BITS) + id as usize) + 1;
-                return Ok(packet_id as u16);
+                return Some(index as u16 + 1);
             }
         }
 
-        // It should be almost impossible for this to occur, but its better taken care of than not!
-        self.allocated.fetch_sub(1, Ordering::Release);
-        return Err(MQTTError::PacketIdGenerationError);
+        None
+    }
+
+    fn locate(id: u16) -> Option<(usize, u8)> {
+        let id = (id as usize).checked_sub(1)?;
+        Some((id / Self::BITS, (id % Self::BITS) as u8))
+    }
+}
+
+impl PacketIdAlloc for PacketIdManager {
+    fn allocate(&self) -> Result<u16, MQTTError> {
+        // callers of `allocate_async` already waiting are first in line
+        if self.waiting.load(Ordering::SeqCst) > 0 {
+            return Err(MQTTError::PacketIdGenerationError);
+        }
+
+        self.try_allocate()
+            .ok_or(MQTTError::PacketIdGenerationError)
+    }
+
+    fn allocate_async(&self) -> impl Future<Output = u16> + Send {
+        PacketIdManager::allocate_async(self)
+    }
+
+    fn in_flight(&self) -> u16 {
+        PacketIdManager::in_flight(self)
+    }
+
+    fn available(&self) -> u16 {
+        PacketIdManager::available(self)
     }
 }
 
 impl PacketIdRelease for PacketIdManager {
-    /// Returns whether the packetId is in use or free (Not yet tested)
+    /// Returns whether the packetId is in use or free
     fn is_occupied(&self, id: u16) -> bool {
-        let id = (id - 1) as usize;
-        let shard_index = id / Self::BITS;
-        let actual_index_in_shard = (id % Self::BITS) as u8;
-        let result = self
-            .shards
-            .get(shard_index)
-            .and_then(|shard| Some(shard.release(actual_index_in_shard)));
-        return result.is_some();
+        Self::locate(id).is_some_and(|(shard_index, index)| {
+            self.shards
+                .get(shard_index)
+                .is_some_and(|shard| shard.is_allocated(index))
+        })
     }
 
     fn release(&self, id: u16) {
-        let id = (id - 1) as usize;
-        let shard_index = id / Self::BITS;
-        let actual_index_in_shard = (id % Self::BITS) as u8;
-        let result = self
+        let Some((shard_index, index)) = Self::locate(id) else {
+            return;
+        };
+
+        let released = self
             .shards
             .get(shard_index)
-            .and_then(|shard| Some(shard.release(actual_index_in_shard)));
-        if result.is_some_and(|r| r) {
+            .is_some_and(|shard| shard.release(index));
+        if released {
             let _ = self
                 .allocated
-                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1));
+                .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| n.checked_sub(1));
+
+            // pairs with the fence in `Allocate::poll`: either the waiter sees the freed slot, or we see the waiter
+            fence(Ordering::SeqCst);
+            if self.waiting.load(Ordering::SeqCst) > 0 {
+                self.wake_next();
+            }
         }
     }
 }
 
-// use futures::execut
-
 #[cfg(test)]
 mod test {
     use std::sync::atomic::Ordering;
@@ -94,13 +209,13 @@ mod test {
     #[test]
     fn creates_a_packetid_manager() {
         let mgr = PacketIdManager::new(2);
-        assert_eq!(mgr.shards.len(), 1);
+        assert_eq!(mgr.shards.len() * PacketIdManager::BITS, MAX + 1);
         assert_eq!(mgr.allocated.load(Ordering::Relaxed), 0);
 
         let mgr = PacketIdManager::new(123);
-        assert_eq!(mgr.shards.len(), 2);
-        assert_eq!(mgr.max_packets, 123);
-        assert_eq!(mgr.allocated.load(Ordering::Relaxed), 0);
+        assert_eq!(mgr.receive_max(), 123);
+        assert_eq!(mgr.in_flight(), 0);
+        assert_eq!(mgr.available(), 123);
     }
 
     #[test]
@@ -126,6 +241,110 @@ mod test {
         assert_eq!(mgr.allocated.load(Ordering::Relaxed), 0);
     }
 
+    #[test]
+    fn respects_the_receive_maximum() {
+        let mgr = PacketIdManager::new(3);
+        let ids = (0..3).map(|_| mgr.allocate().unwrap()).collect::<Vec<_>>();
+        assert_eq!(ids, vec![1, 2, 3]);
+        assert_eq!((mgr.in_flight(), mgr.available()), (3, 0));
+        assert_eq!(mgr.allocate(), Err(MQTTError::PacketIdGenerationError));
+
+        mgr.release(2);
+        assert_eq!((mgr.in_flight(), mgr.available()), (2, 1));
+        // the cursor moves on rather than reusing the identifier that was just released
+        assert_eq!(mgr.allocate(), Ok(4));
+        assert_eq!(mgr.allocate(), Err(MQTTError::PacketIdGenerationError));
+    }
+
+    #[test]
+    fn wraps_around_the_whole_packet_id_space() {
+        let mgr = PacketIdManager::new(u16::MAX);
+        let kept = mgr.allocate().unwrap();
+        assert_eq!(kept, 1);
+
+        for expected in 2..=u16::MAX {
+            let id = mgr.allocate().unwrap();
+            assert_eq!(id, expected);
+            mgr.release(id);
+        }
+
+        // 1 is still in flight, so the search wraps around past it
+        assert_eq!(mgr.allocate(), Ok(2));
+        mgr.release(kept);
+        assert_eq!(mgr.allocate(), Ok(3));
+        assert_eq!(mgr.in_flight(), 2);
+    }
+
+    #[test]
+    fn can_allocate_every_packet_id() {
+        let mgr = PacketIdManager::new(u16::MAX);
+        for expected in 1..=u16::MAX {
+            assert_eq!(mgr.allocate(), Ok(expected));
+        }
+        assert_eq!(mgr.in_flight(), u16::MAX);
+        assert_eq!(mgr.available(), 0);
+        assert_eq!(mgr.allocate(), Err(MQTTError::PacketIdGenerationError));
+
+        mgr.release(40_000);
+        assert_eq!(mgr.allocate(), Ok(40_000));
+    }
+
+    #[test]
+    fn is_occupied_does_not_release_the_packet_id() {
+        let mgr = PacketIdManager::new(2);
+        let packet_id = mgr.allocate().unwrap();
+
+        assert!(mgr.is_occupied(packet_id));
+        assert!(mgr.is_occupied(packet_id));
+        assert!(!mgr.is_occupied(2));
+        assert!(!mgr.is_occupied(0));
+        assert!(!mgr.is_occupied(1000));
+
+        mgr.release(packet_id);
+        assert!(!mgr.is_occupied(packet_id));
+    }
+
+    #[test]
+    fn releasing_an_id_twice_frees_a_single_slot() {
+        let mgr = PacketIdManager::new(2);
+        let first = mgr.allocate().unwrap();
+        let second = mgr.allocate().unwrap();
+
+        mgr.release(first);
+        mgr.release(first);
+        assert_eq!((mgr.in_flight(), mgr.available()), (1, 1));
+
+        // the slot freed twice is still only one slot
+        assert!(mgr.allocate().is_ok());
+        assert_eq!(mgr.allocate(), Err(MQTTError::PacketIdGenerationError));
+        mgr.release(second);
+        assert_eq!((mgr.in_flight(), mgr.available()), (1, 1));
+    }
+
+    #[test]
+    fn keeps_count_under_concurrent_allocate_and_release() {
+        // few identifiers spread over threads, so that they keep sharing the same shard
+        let mgr = PacketIdManager::new(8);
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    for _ in 0..20_000 {
+                        let Ok(id) = mgr.allocate() else {
+                            continue;
+                        };
+                        assert!(mgr.is_occupied(id));
+                        mgr.release(id);
+                    }
+                });
+            }
+        });
+
+        assert_eq!((mgr.in_flight(), mgr.available()), (0, 8));
+        let ids = (0..8).map(|_| mgr.allocate().unwrap()).collect::<Vec<_>>();
+        assert!(ids.iter().all(|&id| mgr.is_occupied(id)));
+        assert_eq!(mgr.allocate(), Err(MQTTError::PacketIdGenerationError));
+    }
+
     #[test]
     // release
     fn must_not_panick_if_packet_id_is_out_of_bounds_or_does_not_exist() {
@@ -137,5 +356,157 @@ mod test {
 
         mgr.release(67);
         assert_eq!(mgr.allocated.load(Ordering::Relaxed), 0);
+
+        mgr.release(0);
+        mgr.release(u16::MAX);
+        assert_eq!(mgr.allocated.load(Ordering::Relaxed), 0);
+    }
+
+    mod allocate_async {
+        use std::{
+            future::Future,
+            pin::pin,
+            sync::atomic::AtomicUsize,
+            task::{Context, Poll},
+            thread,
+        };
+
+        use futures::{
+            executor::block_on,
+            task::{waker, ArcWake},
+        };
+
+        use super::*;
+
+        #[derive(Default)]
+        struct WakeCount(AtomicUsize);
+
+        impl ArcWake for WakeCount {
+            fn wake_by_ref(arc_self: &Arc<Self>) {
+                arc_self.0.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        impl WakeCount {
+            fn get(&self) -> usize {
+                self.0.load(Ordering::SeqCst)
+            }
+        }
+
+        fn poll<F: Future>(
+            future: std::pin::Pin<&mut F>,
+            wakes: &Arc<WakeCount>,
+        ) -> Poll<F::Output> {
+            let waker = waker(wakes.clone());
+            future.poll(&mut Context::from_waker(&waker))
+        }
+
+        #[test]
+        fn is_ready_when_an_id_is_free() {
+            let mgr = PacketIdManager::new(2);
+            assert_eq!(block_on(mgr.allocate_async()), 1);
+            assert_eq!(block_on(mgr.allocate_async()), 2);
+            assert_eq!(mgr.in_flight(), 2);
+        }
+
+        #[test]
+        fn waiters_are_served_in_order_one_per_release() {
+            let mgr = PacketIdManager::new(1);
+            let first = mgr.allocate().unwrap();
+
+            let wakes = (0..3)
+                .map(|_| Arc::new(WakeCount::default()))
+                .collect::<Vec<_>>();
+            let mut a = pin!(mgr.allocate_async());
+            let mut b = pin!(mgr.allocate_async());
+            let mut c = pin!(mgr.allocate_async());
+            assert!(poll(a.as_mut(), &wakes[0]).is_pending());
+            assert!(poll(b.as_mut(), &wakes[1]).is_pending());
+            assert!(poll(c.as_mut(), &wakes[2]).is_pending());
+
+            // nobody jumps the queue, not even a synchronous allocation
+            assert_eq!(mgr.allocate(), Err(MQTTError::PacketIdGenerationError));
+
+            mgr.release(first);
+            assert_eq!(
+                wakes.iter().map(|w| w.get()).collect::<Vec<_>>(),
+                vec![1, 0, 0]
+            );
+            assert!(poll(c.as_mut(), &wakes[2]).is_pending());
+            assert!(poll(b.as_mut(), &wakes[1]).is_pending());
+            assert_eq!(poll(a.as_mut(), &wakes[0]), Poll::Ready(2));
+
+            mgr.release(2);
+            assert_eq!(
+                wakes.iter().map(|w| w.get()).collect::<Vec<_>>(),
+                vec![1, 1, 0]
+            );
+            assert_eq!(poll(b.as_mut(), &wakes[1]), Poll::Ready(3));
+
+            mgr.release(3);
+            assert_eq!(poll(c.as_mut(), &wakes[2]), Poll::Ready(4));
+            assert_eq!(mgr.in_flight(), 1);
+            assert_eq!(mgr.waiting.load(Ordering::SeqCst), 0);
+        }
+
+        #[test]
+        fn cancelled_waiters_leave_the_queue() {
+            let mgr = PacketIdManager::new(1);
+            let first = mgr.allocate().unwrap();
+            let wakes = Arc::new(WakeCount::default());
+
+            {
+                let mut cancelled = pin!(mgr.allocate_async());
+                assert!(poll(cancelled.as_mut(), &wakes).is_pending());
+            }
+            assert_eq!(mgr.waiting.load(Ordering::SeqCst), 0);
+
+            let mut waiting = pin!(mgr.allocate_async());
+            assert!(poll(waiting.as_mut(), &wakes).is_pending());
+            mgr.release(first);
+            assert_eq!(wakes.get(), 1);
+            assert_eq!(poll(waiting.as_mut(), &wakes), Poll::Ready(2));
+        }
+
+        #[test]
+        fn ids_given_to_cancelled_waiters_are_not_lost() {
+            let mgr = PacketIdManager::new(1);
+            let first = mgr.allocate().unwrap();
+            let wakes = Arc::new(WakeCount::default());
+
+            let mut next = Box::pin(mgr.allocate_async());
+            let mut after = pin!(mgr.allocate_async());
+            assert!(poll(next.as_mut(), &wakes).is_pending());
+            assert!(poll(after.as_mut(), &wakes).is_pending());
+
+            // `next` is given an identifier, but dropped before it sees it
+            mgr.release(first);
+            drop(next);
+
+            assert_eq!(poll(after.as_mut(), &wakes), Poll::Ready(3));
+            assert_eq!(mgr.in_flight(), 1);
+        }
+
+        #[test]
+        fn never_exceeds_the_receive_maximum_under_contention() {
+            let mgr = PacketIdManager::new(4);
+            let peak = AtomicUsize::new(0);
+
+            thread::scope(|scope| {
+                for _ in 0..8 {
+                    scope.spawn(|| {
+                        for _ in 0..2_000 {
+                            let id = block_on(mgr.allocate_async());
+                            peak.fetch_max(mgr.in_flight() as usize, Ordering::SeqCst);
+                            mgr.release(id);
+                        }
+                    });
+                }
+            });
+
+            assert!(peak.load(Ordering::SeqCst) <= 4);
+            assert_eq!(mgr.in_flight(), 0);
+            assert_eq!(mgr.waiting.load(Ordering::SeqCst), 0);
+        }
     }
 }
@@ -7,11 +7,12 @@ use std::sync::atomic::{AtomicUsize, Ordering};
 pub(super) struct PacketIdShard(pub(super) AtomicUsize); // Each bit manages 64 OR 32 packet id's (Usize::BITS = 64 OR 32)
 
 impl PacketIdShard {
-    // Allocate an available packet ID
-    pub(super) fn allocate(&self) -> Option<u16> {
+    // Allocate the first available packet ID at or after `from`
+    pub(super) fn allocate(&self, from: u32) -> Option<u16> {
+        if from >= usize::BITS { return None; }
         let mut bitmap = self.0.load(Ordering::Relaxed);
         loop {
-            let free_index = (!bitmap).trailing_zeros();
+            let free_index = (!bitmap & (usize::MAX << from)).trailing_zeros();
             if free_index >= usize::BITS { return None; } // break here
 
             let new_bitmap = bitmap | (1 << free_index);
@@ -26,12 +27,18 @@ impl PacketIdShard {
     }
 
     /// Release a packet ID
-    /// Returns `true` if the release was successful, otherwise, it returns false
+    /// Returns `true` if the packet ID was allocated, i.e. only the call that actually frees it gets `true`
     pub(super) fn release(&self, id: u8) -> bool {
         if id >= usize::BITS as u8 { return false }
-        let result= self.0.fetch_and(!(1 << id), Ordering::Release);
-        let new = self.0.load(Ordering::Relaxed);
-        new != result
+        // the previous bitmap alone tells, any later load may already see other ids allocated or released
+        let previous = self.0.fetch_and(!(1 << id), Ordering::Release);
+        previous & (1 << id) != 0
+    }
+
+    /// Returns `true` if the packet ID is currently allocated
+    pub(super) fn is_allocated(&self, id: u8) -> bool {
+        if id >= usize::BITS as u8 { return false }
+        self.0.load(Ordering::Acquire) & (1 << id) != 0
     }
 
     /// Returns the number of already allocated packet ids in this shard
@@ -52,24 +59,33 @@ mod tests {
         #[test]
         fn should_return_the_nearest_id() {
             let shard = PacketIdShard(0x1DF.into());
-            let next_index = shard.allocate();
+            let next_index = shard.allocate(0);
             let expected = 0b000111011111usize;
             assert_eq!(next_index, Some((!expected).trailing_zeros() as u16));
         }
         
         
+        #[test]
+        fn should_skip_the_ids_before_from() {
+            let shard = PacketIdShard(0x1DF.into());
+            assert_eq!(shard.allocate(2), Some(5));
+            assert_eq!(shard.allocate(10), Some(10));
+            assert_eq!(shard.allocate(0), Some(9));
+            assert_eq!(shard.allocate(usize::BITS), None);
+        }
+
         #[test]
         fn should_return_none_if_there_is_no_more_space() {
             // 18_446_744_073_709_551_615 (usize::MAX on 64-bit platform)
             if cfg!(target_pointer_width = "64") {
-                let value = 0xFFFFFFFFFFFFFFFF;
+                let value = usize::MAX;
                 let shard = PacketIdShard(value.into());
-                let next_index = shard.allocate();
+                let next_index = shard.allocate(0);
                 assert_eq!(next_index, None);
             } else if cfg!(target_pointer_width = "32") {
                 let value = 0xFFFFFFFF;
                 let shard = PacketIdShard(value.into());
-                let next_index = shard.allocate();
+                let next_index = shard.allocate(0);
                 assert_eq!(next_index, None);
             } else {
                 assert!(false, "Unknown architecture")
@@ -122,9 +138,39 @@ mod tests {
             let expected = 0b000111011111usize;
             assert_eq!(shard.0.load(Ordering::Relaxed), expected);
             
-            shard.release(5);
+            assert!(!shard.release(5));
             let new_value = shard.0.load(Ordering::Relaxed);
             assert_eq!(new_value, expected);
         }
+
+        #[test]
+        fn should_only_report_the_release_that_freed_the_id() {
+            let shard = PacketIdShard(0x1DF.into());
+            assert!(shard.release(3));
+            assert!(!shard.release(3));
+            assert_eq!(shard.0.load(Ordering::Relaxed), 0b000111010111);
+        }
+
+        #[test]
+        fn should_tell_releases_apart_while_other_ids_change() {
+            let shard = PacketIdShard::default();
+            std::thread::scope(|scope| {
+                for _ in 0..4 {
+                    scope.spawn(|| {
+                        for _ in 0..50_000 {
+                            let id = shard.allocate(0).unwrap();
+                            assert!(shard.release(id as u8));
+                        }
+                    });
+                }
+                // nobody allocates the last id, so releasing it never frees anything
+                scope.spawn(|| {
+                    for _ in 0..200_000 {
+                        assert!(!shard.release(usize::BITS as u8 - 1));
+                    }
+                });
+            });
+            assert_eq!(shard.count(), 0);
+        }
     }
 }
\ No newline at end of file
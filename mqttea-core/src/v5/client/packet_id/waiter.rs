@@ -0,0 +1,118 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{fence, Ordering},
+        Arc, Mutex,
+    },
+    task::{Context, Poll, Waker},
+};
+
+use super::PacketIdManager;
+use crate::v5::traits::pkid_mgr::PacketIdRelease;
+
+/// A caller of `allocate_async` queued until a Packet Identifier is released
+#[derive(Debug, Default)]
+pub(super) struct Waiter(Mutex<Slot>);
+
+#[derive(Debug, Default)]
+struct Slot {
+    /// Packet Identifier allocated for this waiter by `release`
+    id: Option<u16>,
+    waker: Option<Waker>,
+}
+
+impl Waiter {
+    pub(super) fn grant(&self, id: u16) {
+        let waker = {
+            let mut slot = self.0.lock().unwrap();
+            slot.id = Some(id);
+            slot.waker.take()
+        };
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+/// Future returned by [`PacketIdManager::allocate_async`]
+#[derive(Debug)]
+#[must_use = "futures do nothing unless you `.await` or poll them"]
+pub struct Allocate<'a> {
+    manager: &'a PacketIdManager,
+    waiter: Option<Arc<Waiter>>,
+}
+
+impl<'a> Allocate<'a> {
+    pub(super) fn new(manager: &'a PacketIdManager) -> Self {
+        Self {
+            manager,
+            waiter: None,
+        }
+    }
+}
+
+impl Future for Allocate<'_> {
+    type Output = u16;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        if let Some(waiter) = &self.waiter {
+            let mut slot = waiter.0.lock().unwrap();
+            if let Some(id) = slot.id.take() {
+                drop(slot);
+                self.waiter = None;
+                return Poll::Ready(id);
+            }
+
+            slot.waker = Some(cx.waker().clone());
+            return Poll::Pending;
+        }
+
+        let manager = self.manager;
+        let mut queue = manager.waiters.lock().unwrap();
+
+        let waiter = Arc::new(Waiter::default());
+        waiter.0.lock().unwrap().waker = Some(cx.waker().clone());
+        queue.push_back(waiter.clone());
+        manager.waiting.fetch_add(1, Ordering::SeqCst);
+        fence(Ordering::SeqCst);
+
+        // Only the waiter at the front may take a free identifier, so that nobody jumps the queue.
+        // Trying once queued also covers a `release` that happened before `waiting` was raised
+        if queue.len() == 1 {
+            if let Some(id) = manager.try_allocate() {
+                queue.pop_front();
+                manager.waiting.fetch_sub(1, Ordering::SeqCst);
+                return Poll::Ready(id);
+            }
+        }
+
+        drop(queue);
+        self.waiter = Some(waiter);
+        Poll::Pending
+    }
+}
+
+impl Drop for Allocate<'_> {
+    fn drop(&mut self) {
+        let Some(waiter) = self.waiter.take() else {
+            return;
+        };
+
+        // identifiers are granted with the queue locked, so once it is held the waiter is either still queued or already served
+        {
+            let mut queue = self.manager.waiters.lock().unwrap();
+            if let Some(position) = queue.iter().position(|w| Arc::ptr_eq(w, &waiter)) {
+                queue.remove(position);
+                self.manager.waiting.fetch_sub(1, Ordering::SeqCst);
+                return;
+            }
+        }
+
+        // cancelled after being given an identifier: release it (to the next waiter) rather than leak it
+        let id = waiter.0.lock().unwrap().id.take();
+        if let Some(id) = id {
+            self.manager.release(id);
+        }
+    }
+}
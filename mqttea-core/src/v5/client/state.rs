@@ -1,13 +1,18 @@
-use std::sync::{Arc, Mutex};
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+};
 
 use crate::v5::{
-    commons::{error::MQTTError, packet::Packet, packet_type::PacketType, qos::QoS},
+    commons::{
+        error::MQTTError, packet::Packet, packet_type::PacketType, qos::QoS, violation::Violation,
+    },
     packet::{
-        disconnect::Disconnect,
+        disconnect::{Disconnect, DisconnectReasonCode},
         puback::PubAck,
         pubcomp::{PubComp, PubCompReasonCode},
         publish::Publish,
-        pubrec::{properties::PubRecReasonCode, PubRec},
+        pubrec::{PubRec, PubRecReasonCode},
         pubrel::PubRel,
         suback::SubAck,
         subscribe::Subscribe,
@@ -20,6 +25,9 @@ use crate::v5::{
 
 use super::ConnectOptions;
 
+/// Packets in flight that room is made for when the session starts
+const INITIAL_IN_FLIGHT: usize = 16;
+
 #[derive(Debug, PartialEq, Eq)]
 enum Direction {
     InBound,
@@ -32,16 +40,30 @@ struct TopicAlias {
     incoming: Mutex<Vec<Option<String>>>,
 }
 
-#[derive(Debug)]
+/// Packets in flight, keyed by their Packet Identifier.
+/// Identifiers can be anywhere in 1..=65535 whatever the Receive Maximum, but no more than the Receive Maximum are in flight at once
+#[derive(Debug, Default)]
 struct ActivePkids {
     /// all pkids generated by the server and received by us(client)
-    server: Mutex<Vec<Option<PacketType>>>,
+    server: Mutex<HashMap<u16, PacketType>>,
     /// all pkids generated by us (the client), and sent to the server
-    client: Mutex<Vec<Option<PacketType>>>,
-    unacked_publish: Mutex<Vec<Option<Publish>>>,
+    client: Mutex<HashMap<u16, PacketType>>,
+    unacked_publish: Mutex<HashMap<u16, Publish>>,
+}
+
+/// Removes `pkid` from `packets` if it is awaiting `expected`
+fn take_if(
+    packets: &Mutex<HashMap<u16, PacketType>>,
+    pkid: u16,
+    expected: PacketType,
+) -> Option<PacketType> {
+    let mut packets = packets.lock().unwrap();
+    match packets.get(&pkid) {
+        Some(pt) if *pt == expected => packets.remove(&pkid),
+        _ => None,
+    }
 }
 
-// Todo!!: All hashsets needs to be changed to vec/VecDeque to improve performance/caching?
 #[derive(Debug)]
 pub(crate) struct State<T> {
     inbound_topic_alias_max: u16,
@@ -70,10 +92,13 @@ where
                 incoming: Mutex::new(vec![None; value.outbound_topic_alias_max as usize]),
             },
 
+            // the maps grow with the packets in flight rather than being sized for the Receive Maximum up front
             active_packets: ActivePkids {
-                server: Mutex::new(vec![None; incoming_max]),
-                client: Mutex::new(vec![None; outgoing_max]),
-                unacked_publish: Mutex::new(vec![None; outgoing_max]),
+                server: Mutex::new(HashMap::with_capacity(incoming_max.min(INITIAL_IN_FLIGHT))),
+                client: Mutex::new(HashMap::with_capacity(outgoing_max.min(INITIAL_IN_FLIGHT))),
+                unacked_publish: Mutex::new(HashMap::with_capacity(
+                    outgoing_max.min(INITIAL_IN_FLIGHT),
+                )),
             },
 
             manual_ack: value.manual_ack,
@@ -119,31 +144,55 @@ where
             (topic, Some(alias)) if topic.len() == 0 => {
                 let alias = parse_alias(alias, max)?;
                 let value = record[alias as usize].clone();
-                value.ok_or(MQTTError::UnknownData(format!(
-                    "Unrecognized Topic Alias {alias}"
-                )))
+                value.ok_or(MQTTError::ProtocolViolation(
+                    Violation::new(
+                        "3.3.2.3.4",
+                        DisconnectReasonCode::TopicAliasInvalid,
+                        "Topic Alias must have been mapped to a Topic Name",
+                    )
+                    .on(PacketType::Publish),
+                ))
             }
-            _ => Err(MQTTError::UnknownData(format!(
-                "Expected Topic or Alias but found none"
-            ))),
+            _ => Err(MQTTError::ProtocolViolation(
+                Violation::protocol(
+                    "3.3.2.3.4",
+                    "PUBLISH must carry either a Topic Name or a Topic Alias",
+                )
+                .on(PacketType::Publish),
+            )),
         }
     }
 
     fn handle_outgoing_publish(&self, packet: Publish) -> Result<(), MQTTError> {
-        // Confirm that the packet identifier is not a duplicate before we proceed with anything
+        // Confirm that the packet identifier was allocated by us, and is not already in flight before we proceed with anything
         if let Some(pid) = packet.pkid {
-            if self.pkid_mgr.as_ref().unwrap().is_occupied(pid) {
+            if !self.pkid_mgr.as_ref().unwrap().is_occupied(pid) {
+                return Err(MQTTError::UnknownPacketId(PacketType::Publish, pid));
+            }
+            if self
+                .active_packets
+                .client
+                .lock()
+                .unwrap()
+                .contains_key(&pid)
+            {
                 return Err(MQTTError::PacketIdConflict(pid));
             }
         }
 
         self.parse_topic_and_try_update(&packet, Direction::OutBound)?;
 
-        if packet.qos != QoS::Zero {
-            self.active_packets.client.lock().unwrap()[packet.pkid.unwrap() as usize]
-                .replace(PacketType::Publish);
-            self.active_packets.unacked_publish.lock().unwrap()[packet.pkid.unwrap() as usize]
-                .replace(packet);
+        if let (true, Some(pkid)) = (packet.qos != QoS::Zero, packet.pkid) {
+            self.active_packets
+                .client
+                .lock()
+                .unwrap()
+                .insert(pkid, PacketType::Publish);
+            self.active_packets
+                .unacked_publish
+                .lock()
+                .unwrap()
+                .insert(pkid, packet);
         }
 
         Ok(())
@@ -154,7 +203,13 @@ where
         packet.topic = topic;
 
         if let Some(pid) = packet.pkid {
-            if self.active_packets.server.lock().unwrap()[pid as usize].is_some() {
+            if self
+                .active_packets
+                .server
+                .lock()
+                .unwrap()
+                .contains_key(&pid)
+            {
                 return Err(MQTTError::PacketIdConflict(pid));
             }
         }
@@ -165,7 +220,11 @@ where
 
         let pkid = packet.pkid.unwrap();
         if packet.qos == QoS::Two && !self.manual_ack {
-            self.active_packets.server.lock().unwrap()[pkid as usize] = Some(PacketType::PubRec);
+            self.active_packets
+                .server
+                .lock()
+                .unwrap()
+                .insert(pkid, PacketType::PubRec);
         }
 
         let result = match (packet.qos, self.manual_ack) {
@@ -198,28 +257,34 @@ where
         &self,
         packet: &PubAck,
     ) -> Result<Option<Packet>, MQTTError> {
-        let pkid = packet.pkid as usize;
-
         // release the pkid, and remove the packet from the state
-        let prev =
-            self.active_packets.client.lock().unwrap()[pkid].take_if(|p| *p == PacketType::Publish);
+        let prev = take_if(
+            &self.active_packets.client,
+            packet.pkid,
+            PacketType::Publish,
+        );
 
         if prev.is_none() {
-            return Err(MQTTError::UnknownData(format!(
-                "Unknown Pakcet Id: {}",
-                packet.pkid
-            )));
+            return Err(MQTTError::UnknownPacketId(PacketType::PubAck, packet.pkid));
         }
 
         self.pkid_mgr.as_ref().unwrap().release(packet.pkid);
-        self.active_packets.unacked_publish.lock().unwrap()[pkid] = None;
+        self.active_packets
+            .unacked_publish
+            .lock()
+            .unwrap()
+            .remove(&packet.pkid);
 
         return Ok(None);
     }
 
     // we don't need to confirm anything locally
     pub(crate) fn handle_outgoing_pubrec(&self, packet: PubRec) -> Result<(), MQTTError> {
-        self.active_packets.server.lock().unwrap()[packet.pkid as usize] = Some(PacketType::PubRec);
+        self.active_packets
+            .server
+            .lock()
+            .unwrap()
+            .insert(packet.pkid, PacketType::PubRec);
         Ok(())
     }
 
@@ -227,28 +292,32 @@ where
         &self,
         packet: &PubRec,
     ) -> Result<Option<Packet>, MQTTError> {
-        let pkid = packet.pkid as usize;
-
-        match self.active_packets.client.lock().unwrap().get_mut(pkid) {
-            Some(pt) if *pt == Some(PacketType::Publish) => {
-                // MUST NOT re-send the PUBLISH once it has sent the corresponding PUBREL packet [MQTT-4.3.3-6].
-                self.active_packets.client.lock().unwrap()[pkid] = None;
-                if packet.reason_code == PubRecReasonCode::NoMatchingSubscribers
-                    || packet.reason_code == PubRecReasonCode::Success
-                {
-                    *pt = Some(PacketType::PubRel);
-                } else {
-                    *pt = None;
-                    return Ok(None);
-                }
-            }
-            _ => {
-                return Err(MQTTError::UnknownData(format!(
-                    "Unknown pubrec Pakcet Id: {}",
-                    packet.pkid
-                )));
-            }
-        };
+        let pkid = packet.pkid;
+
+        if take_if(&self.active_packets.client, pkid, PacketType::Publish).is_none() {
+            return Err(MQTTError::UnknownPacketId(PacketType::PubRec, pkid));
+        }
+
+        // MUST NOT re-send the PUBLISH once it has sent the corresponding PUBREL packet [MQTT-4.3.3-6].
+        self.active_packets
+            .unacked_publish
+            .lock()
+            .unwrap()
+            .remove(&pkid);
+
+        if packet.reason_code != PubRecReasonCode::NoMatchingSubscribers
+            && packet.reason_code != PubRecReasonCode::Success
+        {
+            // an unsuccessful PUBREC ends the exchange, and frees the Packet Identifier (4.3.3)
+            self.pkid_mgr.as_ref().unwrap().release(pkid);
+            return Ok(None);
+        }
+
+        self.active_packets
+            .client
+            .lock()
+            .unwrap()
+            .insert(pkid, PacketType::PubRel);
 
         if self.manual_ack {
             return Ok(None);
@@ -263,7 +332,11 @@ where
 
     // we don't need to confirm anything locally, if it's autohandled good, if it's not, then it's up to the user
     pub(crate) fn handle_outgoing_pubrel(&self, packet: PubRel) -> Result<(), MQTTError> {
-        self.active_packets.client.lock().unwrap()[packet.pkid as usize] = Some(PacketType::PubRel);
+        self.active_packets
+            .client
+            .lock()
+            .unwrap()
+            .insert(packet.pkid, PacketType::PubRel);
         Ok(())
     }
 
@@ -271,10 +344,7 @@ where
         &self,
         packet: &PubRel,
     ) -> Result<Option<Packet>, MQTTError> {
-        let pkid = packet.pkid as usize;
-
-        let prev = self.active_packets.server.lock().unwrap()[pkid]
-            .take_if(|pt| *pt == PacketType::PubRec);
+        let prev = take_if(&self.active_packets.server, packet.pkid, PacketType::PubRec);
 
         if self.manual_ack {
             return Ok(None);
@@ -299,15 +369,10 @@ where
     }
 
     fn handle_incoming_pubcomp(&self, packet: &PubComp) -> Result<Option<Packet>, MQTTError> {
-        let pkid = packet.pkid as usize;
-        let prev = self.active_packets.client.lock().unwrap()[pkid]
-            .take_if(|pt| *pt == PacketType::PubRel);
+        let prev = take_if(&self.active_packets.client, packet.pkid, PacketType::PubRel);
 
         if prev.is_none() {
-            return Err(MQTTError::UnknownData(format!(
-                "Unknown Packet Id: {}",
-                packet.pkid
-            )));
+            return Err(MQTTError::UnknownPacketId(PacketType::PubComp, packet.pkid));
         }
 
         self.pkid_mgr.as_ref().unwrap().release(packet.pkid);
@@ -316,21 +381,24 @@ where
     }
 
     fn handle_outgoing_subscribe(&self, packet: Subscribe) -> Result<(), MQTTError> {
-        self.active_packets.client.lock().unwrap()[packet.pkid as usize] =
-            Some(PacketType::Subscribe);
+        self.active_packets
+            .client
+            .lock()
+            .unwrap()
+            .insert(packet.pkid, PacketType::Subscribe);
 
         Ok(())
     }
 
     fn handle_incoming_suback(&self, packet: &SubAck) -> Result<Option<Packet>, MQTTError> {
-        let prev = self.active_packets.client.lock().unwrap()[packet.pkid as usize]
-            .take_if(|pt| *pt == PacketType::Subscribe);
+        let prev = take_if(
+            &self.active_packets.client,
+            packet.pkid,
+            PacketType::Subscribe,
+        );
 
         if prev.is_none() {
-            return Err(MQTTError::UnknownData(format!(
-                "Unknown Suback Packet Id: {}",
-                packet.pkid
-            )));
+            return Err(MQTTError::UnknownPacketId(PacketType::SubAck, packet.pkid));
         }
 
         self.pkid_mgr.as_ref().unwrap().release(packet.pkid);
@@ -338,19 +406,26 @@ where
     }
 
     fn handle_outgoing_unsubscribe(&self, packet: UnSubscribe) -> Result<(), MQTTError> {
-        self.active_packets.client.lock().unwrap()[packet.pkid as usize] =
-            Some(PacketType::UnSubscribe);
+        self.active_packets
+            .client
+            .lock()
+            .unwrap()
+            .insert(packet.pkid, PacketType::UnSubscribe);
 
         Ok(())
     }
 
     fn handle_incoming_unsuback(&self, packet: &UnSubAck) -> Result<Option<Packet>, MQTTError> {
-        let prev = self.active_packets.client.lock().unwrap()[packet.pkid as usize];
+        let prev = take_if(
+            &self.active_packets.client,
+            packet.pkid,
+            PacketType::UnSubscribe,
+        );
         if prev.is_none() {
-            return Err(MQTTError::UnknownData(format!(
-                "Unknown Suback Packet Id: {}",
-                packet.pkid
-            )));
+            return Err(MQTTError::UnknownPacketId(
+                PacketType::UnSubAck,
+                packet.pkid,
+            ));
         }
 
         self.pkid_mgr.as_ref().unwrap().release(packet.pkid);
@@ -398,3 +473,106 @@ where
     /// for more information
     pub(crate) fn retransmit_all() {}
 }
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZero;
+
+    use bytes::Bytes;
+
+    use super::*;
+    use crate::v5::{client::packet_id::PacketIdManager, traits::pkid_mgr::PacketIdAlloc};
+
+    fn state(receive_max: u16) -> (State<PacketIdManager>, Arc<PacketIdManager>) {
+        let receive_max = NonZero::new(receive_max).unwrap();
+        let options = ConnectOptions {
+            server_receive_max: receive_max,
+            client_receive_max: receive_max,
+            ..Default::default()
+        };
+
+        let pkids = Arc::new(PacketIdManager::new(receive_max.get()));
+        let mut state = State::from(&options);
+        state.pkid_mgr = Some(pkids.clone());
+        (state, pkids)
+    }
+
+    fn publish(qos: QoS, pkid: u16) -> Publish {
+        Publish {
+            qos,
+            pkid: Some(pkid),
+            topic: String::from("sensors/42/temperature"),
+            payload: Bytes::from_static(b"21.5"),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn outgoing_qos2_publish_releases_its_packet_id_on_pubcomp() {
+        let (state, pkids) = state(2);
+        let pkid = pkids.allocate().unwrap();
+        assert_eq!(pkids.allocate(), Ok(2));
+
+        state
+            .handle_outgoing_packet(Packet::Publish(publish(QoS::Two, 2)))
+            .unwrap();
+        state
+            .handle_outgoing_packet(Packet::Publish(publish(QoS::Two, pkid)))
+            .unwrap();
+
+        let pubrel = state
+            .handle_incoming_packet(&mut Packet::PubRec(PubRec {
+                pkid,
+                ..Default::default()
+            }))
+            .unwrap();
+        assert_eq!(
+            pubrel,
+            Some(Packet::PubRel(PubRel {
+                pkid,
+                ..Default::default()
+            }))
+        );
+        assert!(!state
+            .active_packets
+            .unacked_publish
+            .lock()
+            .unwrap()
+            .contains_key(&pkid));
+
+        state.handle_outgoing_packet(pubrel.unwrap()).unwrap();
+        state
+            .handle_incoming_packet(&mut Packet::PubComp(PubComp {
+                pkid,
+                ..Default::default()
+            }))
+            .unwrap();
+
+        assert!(!pkids.is_occupied(pkid));
+        assert_eq!(pkids.in_flight(), 1);
+        // packet ids carry on past the Receive Maximum
+        assert_eq!(pkids.allocate(), Ok(3));
+        state
+            .handle_outgoing_packet(Packet::Publish(publish(QoS::Two, 3)))
+            .unwrap();
+    }
+
+    #[test]
+    fn outgoing_publish_must_use_an_allocated_packet_id() {
+        let (state, pkids) = state(2);
+        let pkid = pkids.allocate().unwrap();
+
+        assert_eq!(
+            state.handle_outgoing_packet(Packet::Publish(publish(QoS::One, 2))),
+            Err(MQTTError::UnknownPacketId(PacketType::Publish, 2))
+        );
+
+        state
+            .handle_outgoing_packet(Packet::Publish(publish(QoS::One, pkid)))
+            .unwrap();
+        assert_eq!(
+            state.handle_outgoing_packet(Packet::Publish(publish(QoS::One, pkid))),
+            Err(MQTTError::PacketIdConflict(pkid))
+        );
+    }
+}
@@ -1,8 +1,109 @@
-use std::string::FromUtf8Error;
+use alloc::{
+    boxed::Box,
+    string::{FromUtf8Error, String, ToString},
+    vec::Vec,
+};
+use core::fmt::Display;
 
+#[cfg(feature = "std")]
 use async_channel::{RecvError, SendError};
 
+#[cfg(feature = "std")]
 use super::packet::Packet;
+use super::{packet_type::PacketType, reason_code::ReasonCode, violation::Violation};
+use crate::v5::{
+    borrowed,
+    packet::{connack::ConnAck, disconnect::Disconnect},
+};
+
+/// Broad classification of an `MQTTError`, for callers that only need to decide how to react to a failure
+/// (e.g. retry on `Io`, alert on `Refused`, give up on `Protocol`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
+    /// The underlying transport failed or timed out
+    Io,
+    /// A packet was well formed, but breached the rules of the protocol
+    Protocol,
+    /// Bytes received could not be decoded into a packet
+    Malformed,
+    /// The other side of the connection replied with an unsuccessful Reason Code
+    Refused,
+    /// A size, quota or identifier limit was reached
+    Limits,
+    /// The connection, or the channel feeding it, has been closed
+    Closed,
+}
+
+/// An unsuccessful Reason Code received from the other side of the connection,
+/// along with the diagnostic information that came with it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Refusal {
+    pub packet: PacketType,
+    pub reason: ReasonCode,
+    pub reason_string: Option<String>,
+    pub user_property: Vec<(String, String)>,
+}
+
+impl Refusal {
+    pub fn new(packet: PacketType, reason: ReasonCode) -> Self {
+        Self {
+            packet,
+            reason,
+            reason_string: None,
+            user_property: Vec::new(),
+        }
+    }
+}
+
+impl Display for Refusal {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{:?} refused: {}", self.packet, self.reason)?;
+        if let Some(reason_string) = &self.reason_string {
+            write!(f, " ({reason_string})")?;
+        }
+        Ok(())
+    }
+}
+
+impl From<ConnAck> for Refusal {
+    fn from(value: ConnAck) -> Self {
+        Self {
+            packet: PacketType::ConnAck,
+            reason: value.reason.into(),
+            reason_string: value.properties.reason_string,
+            user_property: value.properties.user_property,
+        }
+    }
+}
+
+impl From<borrowed::ConnAck<'_>> for Refusal {
+    fn from(value: borrowed::ConnAck<'_>) -> Self {
+        let mut refusal = Self::new(PacketType::ConnAck, value.reason.into());
+        for property in value.properties {
+            match property {
+                borrowed::Property::ReasonString(reason) => {
+                    refusal.reason_string = Some(reason.to_string())
+                }
+                borrowed::Property::UserProperty(key, value) => refusal
+                    .user_property
+                    .push((key.to_string(), value.to_string())),
+                _ => {}
+            }
+        }
+        refusal
+    }
+}
+
+impl From<Disconnect> for Refusal {
+    fn from(value: Disconnect) -> Self {
+        Self {
+            packet: PacketType::Disconnect,
+            reason: value.reason_code.into(),
+            reason_string: value.properties.reason_string,
+            user_property: value.properties.user_property,
+        }
+    }
+}
 
 #[derive(Debug, thiserror::Error, PartialEq, Eq)]
 pub enum MQTTError {
@@ -10,8 +111,6 @@ pub enum MQTTError {
     MalformedPacket,
     #[error("Payload too long")]
     PayloadTooLong,
-    #[error("Incomplete Packet")]
-    IncompletePacket,
     #[error("Received QoS: {0} which is unsupported")]
     UnsupportedQoS(u8),
     #[error("Incomplete Data: {0} Expected {1} bytes but found {2}")]
@@ -22,20 +121,26 @@ pub enum MQTTError {
     DuplicateProperty(String), // property converted to string
     #[error("Error generating utf-8 string from {0}")]
     Utf8Error(FromUtf8Error),
-    #[error("{0} is not allowed on: {1}")]
-    UnexpectedProperty(String, String),
     #[error("Version {0} not supported")]
     VersionNotSupported(u8),
-    #[error("{0}")]
-    UnknownData(String),
+    #[error("Unknown packet type: {0}")]
+    UnknownPacketType(u8),
+    #[error("Unknown {0:?} Reason Code: {1}")]
+    UnknownReasonCode(PacketType, u8),
+    #[error("Unexpected {0:?} packet")]
+    UnexpectedPacket(PacketType),
     #[error("Packet Identifier is only expected when QoS level is 1 or 2")]
     PublishPacketId,
     #[error("Protocol Error: {0}")]
     ProtocolError(&'static str),
+    #[error("Protocol Violation: {0}")]
+    ProtocolViolation(Violation),
     #[error("Insufficient bytes on the stream")]
     InsufficientBytes,
     #[error("Packet Id Conflict: {0}")]
     PacketIdConflict(u16),
+    #[error("Unknown {0:?} Packet Id: {1}")]
+    UnknownPacketId(PacketType, u16),
     #[error("Invalid Property: {0}")]
     InvalidProperty(String),
     #[error("Packet Id required")]
@@ -43,23 +148,21 @@ pub enum MQTTError {
     #[error("UnImplemented")]
     UnImplemented,
 
-    #[error("Stream ended prematurely")]
-    StreamEndedPrematurely,
+    #[cfg(feature = "std")]
+    #[error("IO Error: {1}")]
+    IoError(std::io::ErrorKind, String),
 
-    #[error("IO Error: {0}")]
-    IoError(String),
+    #[cfg(feature = "embedded")]
+    #[error("IO Error: {0:?}")]
+    EmbeddedIoError(embedded_io::ErrorKind),
 
-    // to be moved
-    #[error("Connection Error")]
-    ConnectionError,
-    #[error("Connection Refused {0}")]
-    ConnectionRefused(u8),
+    #[error("{0}")]
+    Refused(Box<Refusal>),
 
     #[error("Timeout Error")]
     TimeoutError,
 
-    #[error("Incoming Disconnect")]
-    IncomingDisconnect,
+    #[cfg(feature = "std")]
     #[error("No more outgoing packets {0}")]
     NoOutgoingPackets(#[from] RecvError),
 
@@ -72,11 +175,180 @@ pub enum MQTTError {
     InvalidTopic(&'static str),
 
     #[error("Channel Error: Channel Closed")]
-    ChannelClosed(#[from] SendError<Packet>),
+    ChannelClosed,
+}
+
+impl MQTTError {
+    pub fn kind(&self) -> ErrorKind {
+        match self {
+            #[cfg(feature = "std")]
+            Self::IoError(..) => ErrorKind::Io,
+            #[cfg(feature = "embedded")]
+            Self::EmbeddedIoError(_) => ErrorKind::Io,
+            Self::TimeoutError => ErrorKind::Io,
+            Self::MalformedPacket
+            | Self::UnsupportedQoS(_)
+            | Self::IncompleteData(..)
+            | Self::UnknownProperty(_)
+            | Self::Utf8Error(_)
+            | Self::UnknownPacketType(_)
+            | Self::UnknownReasonCode(..)
+            | Self::InsufficientBytes => ErrorKind::Malformed,
+            Self::ProtocolViolation(violation) if violation.is_malformed() => ErrorKind::Malformed,
+            Self::DuplicateProperty(_)
+            | Self::VersionNotSupported(_)
+            | Self::UnexpectedPacket(_)
+            | Self::PublishPacketId
+            | Self::ProtocolError(_)
+            | Self::ProtocolViolation(_)
+            | Self::PacketIdConflict(_)
+            | Self::UnknownPacketId(..)
+            | Self::InvalidProperty(_)
+            | Self::PacketIdRequired
+            | Self::UnImplemented
+            | Self::InvalidTopic(_) => ErrorKind::Protocol,
+            Self::Refused(_) => ErrorKind::Refused,
+            Self::PayloadTooLong | Self::PacketIdGenerationError | Self::MaxPacketSizeExceed(_) => {
+                ErrorKind::Limits
+            }
+            #[cfg(feature = "std")]
+            Self::NoOutgoingPackets(_) => ErrorKind::Closed,
+            Self::ChannelClosed => ErrorKind::Closed,
+        }
+    }
+
+    /// The `std::io::ErrorKind` of the transport failure behind this error, if any
+    #[cfg(feature = "std")]
+    pub fn io_kind(&self) -> Option<std::io::ErrorKind> {
+        match self {
+            Self::IoError(kind, _) => Some(*kind),
+            #[cfg(feature = "embedded")]
+            Self::EmbeddedIoError(kind) => Some((*kind).into()),
+            Self::TimeoutError => Some(std::io::ErrorKind::TimedOut),
+            _ => None,
+        }
+    }
+
+    /// The packet responsible for this error, when it is known
+    pub fn packet_type(&self) -> Option<PacketType> {
+        match self {
+            Self::UnknownReasonCode(packet, _)
+            | Self::UnexpectedPacket(packet)
+            | Self::UnknownPacketId(packet, _) => Some(*packet),
+            Self::ProtocolViolation(violation) => violation.packet,
+            Self::Refused(refusal) => Some(refusal.packet),
+            _ => None,
+        }
+    }
+
+    /// The normative statement (or section) of the specification this error breaches, if any
+    pub fn spec(&self) -> Option<&'static str> {
+        match self {
+            Self::ProtocolViolation(violation) => Some(violation.spec),
+            _ => None,
+        }
+    }
+
+    /// The Reason Code, reason string and user properties sent by the other side, when it refused an operation
+    pub fn refusal(&self) -> Option<&Refusal> {
+        match self {
+            Self::Refused(refusal) => Some(refusal),
+            _ => None,
+        }
+    }
 }
 
+#[cfg(feature = "std")]
 impl From<std::io::Error> for MQTTError {
     fn from(value: std::io::Error) -> Self {
-        Self::IoError(value.to_string())
+        Self::IoError(value.kind(), value.to_string())
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<SendError<Packet>> for MQTTError {
+    fn from(_: SendError<Packet>) -> Self {
+        Self::ChannelClosed
+    }
+}
+
+impl From<Violation> for MQTTError {
+    fn from(value: Violation) -> Self {
+        Self::ProtocolViolation(value)
+    }
+}
+
+impl From<Refusal> for MQTTError {
+    fn from(value: Refusal) -> Self {
+        Self::Refused(Box::new(value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v5::packet::{
+        connack::{ConnAckProperties, ConnAckReasonCode},
+        disconnect::DisconnectReasonCode,
+    };
+
+    #[test]
+    fn io_errors_keep_their_kind() {
+        let err = MQTTError::from(std::io::Error::from(std::io::ErrorKind::ConnectionReset));
+        assert_eq!(err.kind(), ErrorKind::Io);
+        assert_eq!(err.io_kind(), Some(std::io::ErrorKind::ConnectionReset));
+    }
+
+    #[test]
+    fn refusals_keep_the_servers_diagnostics() {
+        let connack = ConnAck {
+            reason: ConnAckReasonCode::NotAuthorized,
+            properties: ConnAckProperties {
+                reason_string: Some(String::from("client is banned until 10:00")),
+                user_property: vec![(String::from("region"), String::from("eu-west"))],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+
+        let err = MQTTError::from(Refusal::from(connack));
+        assert_eq!(err.kind(), ErrorKind::Refused);
+        assert_eq!(err.packet_type(), Some(PacketType::ConnAck));
+
+        let refusal = err.refusal().unwrap();
+        assert_eq!(refusal.reason, ReasonCode::NotAuthorized);
+        assert_eq!(refusal.user_property[0].1, "eu-west");
+        assert_eq!(
+            err.to_string(),
+            "ConnAck refused: Not authorized (client is banned until 10:00)"
+        );
+    }
+
+    #[test]
+    fn violations_keep_the_spec_reference() {
+        let violation =
+            Violation::protocol("MQTT-3.8.3-2", "SUBSCRIBE must contain a Topic Filter")
+                .on(PacketType::Subscribe);
+        let err = MQTTError::from(violation);
+        assert_eq!(err.kind(), ErrorKind::Protocol);
+        assert_eq!(err.spec(), Some("MQTT-3.8.3-2"));
+        assert_eq!(err.packet_type(), Some(PacketType::Subscribe));
+
+        let err = MQTTError::from(Violation::malformed("MQTT-3.3.1-4", "QoS must not be 3"));
+        assert_eq!(err.kind(), ErrorKind::Malformed);
+    }
+
+    #[test]
+    fn error_kinds() {
+        assert_eq!(MQTTError::ChannelClosed.kind(), ErrorKind::Closed);
+        assert_eq!(MQTTError::PacketIdGenerationError.kind(), ErrorKind::Limits);
+        assert_eq!(
+            MQTTError::from(DisconnectReasonCode::ServerBusy).kind(),
+            ErrorKind::Refused
+        );
+        assert_eq!(
+            MQTTError::UnknownReasonCode(PacketType::PubAck, 0x01).kind(),
+            ErrorKind::Malformed
+        );
     }
 }
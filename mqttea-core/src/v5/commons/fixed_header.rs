@@ -10,7 +10,10 @@ use crate::v5::{
     },
 };
 
-use super::packet_type::PacketType;
+use super::{
+    packet_type::PacketType,
+    violation::{ensure, Validate, Violation},
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub(crate) struct FixedHeader {
@@ -41,6 +44,33 @@ impl FixedHeader {
     }
 }
 
+impl Validate for FixedHeader {
+    /// 2.1.3 Flags: bits 3-0 of the first byte are reserved for every packet but PUBLISH
+    fn validate(&self) -> Result<(), Violation> {
+        let flags = self.flags.unwrap_or(0);
+
+        let result = match self.packet_type {
+            PacketType::Publish => ensure(
+                (flags & 0b0110) >> 1 != 3,
+                Violation::malformed(
+                    "MQTT-3.3.1-4",
+                    "PUBLISH packet must not have both QoS bits set",
+                ),
+            ),
+            PacketType::PubRel | PacketType::Subscribe | PacketType::UnSubscribe => ensure(
+                flags == 0b0010,
+                Violation::malformed("MQTT-2.1.3-1", "Reserved fixed header flags must be 0b0010"),
+            ),
+            _ => ensure(
+                flags == 0,
+                Violation::malformed("MQTT-2.1.3-1", "Reserved fixed header flags must be 0b0000"),
+            ),
+        };
+
+        result.map_err(|violation| violation.on(self.packet_type))
+    }
+}
+
 impl BinaryCodec for FixedHeader {
     fn write_to<W: ByteWrite>(&self, w: &mut W) -> Result<(), MQTTError> {
         let flags = self.flags.unwrap_or(0);
@@ -51,7 +81,19 @@ impl BinaryCodec for FixedHeader {
     }
 
     fn read_from<R: ByteRead>(r: &mut R) -> Result<Self, MQTTError> {
-        Ok(())
+        let byte0 = u8::read_from(r)?;
+        let packet = byte0 & 0b11110000;
+        let packet_type =
+            PacketType::try_from(packet).map_err(|_| MQTTError::UnknownPacketType(packet))?;
+
+        let (remaining_length, header_len) = <usize as VarInt>::decode(r)?;
+
+        Ok(Self {
+            packet_type,
+            flags: Some(byte0 & 0b00001111).filter(|n| *n != 0),
+            remaining_length,
+            header_len,
+        })
     }
 }
 
@@ -85,9 +127,8 @@ pub(crate) mod syncx {
 
             let byte0 = u8::read(buf)?;
             let packet = byte0 & 0b11110000;
-            let packet_type = PacketType::try_from(packet).map_err(|_| {
-                MQTTError::UnknownData(format!("Unexpected packet type: {}", packet))
-            })?;
+            let packet_type =
+                PacketType::try_from(packet).map_err(|_| MQTTError::UnknownPacketType(packet))?;
 
             let (remaining_length, header_len) = Self::decode(buf)?;
 
@@ -101,6 +142,7 @@ pub(crate) mod syncx {
     }
 }
 
+#[cfg(feature = "asyncx")]
 pub(crate) mod asyncx {
     use crate::v5::commons::fixed_header::FixedHeader;
     use crate::v5::commons::packet_type::PacketType;
@@ -113,7 +155,7 @@ pub(crate) mod asyncx {
 
     impl StreamIO for FixedHeader {
         fn length(&self) -> usize {
-            0
+            self.remaining_length
         }
 
         async fn read<R>(stream: &mut R) -> Result<Self, MQTTError>
@@ -122,9 +164,8 @@ pub(crate) mod asyncx {
         {
             let byte0 = u8::read(stream).await?;
             let packet = byte0 & 0b11110000;
-            let packet_type = PacketType::try_from(packet).map_err(|_| {
-                MQTTError::UnknownData(format!("Unexpected packet type: {}", packet))
-            })?;
+            let packet_type =
+                PacketType::try_from(packet).map_err(|_| MQTTError::UnknownPacketType(packet))?;
 
             let (remaining_length, header_len) = Self::decode(stream).await?;
 
@@ -1,9 +1,10 @@
+pub mod error;
 pub mod packet;
 pub mod packet_type;
 pub mod property;
 pub mod qos;
 pub mod reason_code;
+pub mod violation;
 
-pub(crate) mod error;
 pub(crate) mod fixed_header;
 pub(crate) mod version; // good
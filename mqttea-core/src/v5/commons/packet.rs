@@ -18,7 +18,12 @@ use crate::v5::{
     traits::read_data::ReadData,
 };
 
-use super::{error::MQTTError, fixed_header::FixedHeader, packet_type::PacketType};
+use super::{
+    error::MQTTError,
+    fixed_header::FixedHeader,
+    packet_type::PacketType,
+    violation::{Validate, Violation},
+};
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum Packet {
@@ -46,7 +51,7 @@ impl Default for Packet {
 }
 
 impl Packet {
-    pub(crate) fn packet_type(&self) -> PacketType {
+    pub fn packet_type(&self) -> PacketType {
         match self {
             Self::Connect(_) => PacketType::Connect,
             Self::ConnAck(_) => PacketType::ConnAck,
@@ -65,15 +70,100 @@ impl Packet {
             Self::Auth(_) => PacketType::Auth,
         }
     }
+
+    /// Number of bytes the packet takes on the wire, fixed header included
+    pub fn size(&self) -> usize {
+        use crate::v5::traits::bufferio::BufferIO;
+
+        1 + BufferIO::variable_length(self) + BufferIO::length(self)
+    }
 }
 
 impl ReadData for Packet {}
 
+impl Validate for Packet {
+    fn validate(&self) -> Result<(), Violation> {
+        let result = match self {
+            Self::Connect(packet) => packet.validate(),
+            Self::ConnAck(packet) => packet.validate(),
+            Self::Publish(packet) => packet.validate(),
+            Self::PubAck(packet) => packet.validate(),
+            Self::PubRec(packet) => packet.validate(),
+            Self::PubRel(packet) => packet.validate(),
+            Self::PubComp(packet) => packet.validate(),
+            Self::Subscribe(packet) => packet.validate(),
+            Self::SubAck(packet) => packet.validate(),
+            Self::UnSubscribe(packet) => packet.validate(),
+            Self::UnSubAck(packet) => packet.validate(),
+            Self::PingReq(packet) => packet.validate(),
+            Self::PingResp(packet) => packet.validate(),
+            Self::Disconnect(packet) => packet.validate(),
+            Self::Auth(packet) => packet.validate(),
+        };
+
+        result.map_err(|violation| violation.on(self.packet_type()))
+    }
+}
+
 pub(crate) mod syncx {
     use super::*;
-    use crate::v5::{commons::error::MQTTError, traits::bufferio::BufferIO};
+    use crate::v5::traits::{
+        bufferio::BufferIO,
+        primitives::{
+            codec::BinaryCodec,
+            io::{ByteRead, ByteWrite},
+        },
+    };
+
+    /// Frames the packet off the reader: the Fixed Header is read first, followed by exactly `remaining_length` bytes
+    impl BinaryCodec for Packet {
+        fn read_from<R: ByteRead>(r: &mut R) -> Result<Self, MQTTError> {
+            let header = FixedHeader::read_from(r)?;
+
+            let mut buf = bytes::BytesMut::new();
+            header.write_to(&mut buf)?;
+
+            // grow the buffer as the body arrives rather than trusting the advertised length upfront
+            let mut chunk = [0u8; 4096];
+            let mut remaining = header.remaining_length;
+            while remaining > 0 {
+                let n = remaining.min(chunk.len());
+                r.read_exact(&mut chunk[..n])?;
+                buf.extend_from_slice(&chunk[..n]);
+                remaining -= n;
+            }
+
+            <Packet as BufferIO>::read(&mut buf.freeze())
+        }
+
+        fn write_to<W: ByteWrite>(&self, w: &mut W) -> Result<(), MQTTError> {
+            let mut buf = bytes::BytesMut::new();
+            BufferIO::write(self, &mut buf)?;
+            w.write_all(&buf)
+        }
+    }
 
     impl BufferIO for Packet {
+        fn length(&self) -> usize {
+            match self {
+                Self::Connect(packet) => packet.length(),
+                Self::ConnAck(packet) => packet.length(),
+                Self::Publish(packet) => packet.length(),
+                Self::PubAck(packet) => packet.length(),
+                Self::PubRec(packet) => packet.length(),
+                Self::PubRel(packet) => packet.length(),
+                Self::PubComp(packet) => packet.length(),
+                Self::Subscribe(packet) => packet.length(),
+                Self::SubAck(packet) => packet.length(),
+                Self::UnSubscribe(packet) => packet.length(),
+                Self::UnSubAck(packet) => packet.length(),
+                Self::PingReq(packet) => packet.length(),
+                Self::PingResp(packet) => packet.length(),
+                Self::Disconnect(packet) => packet.length(),
+                Self::Auth(packet) => packet.length(),
+            }
+        }
+
         fn write(&self, buf: &mut bytes::BytesMut) -> Result<(), MQTTError> {
             match self {
                 Self::Connect(packet) => packet.write(buf),
@@ -95,9 +185,19 @@ pub(crate) mod syncx {
         }
 
         fn read(buf: &mut bytes::Bytes) -> Result<Self, MQTTError> {
-            // let x = Self::Connect(Connect::default());
-
             let header = FixedHeader::read(buf)?;
+            header.validate()?;
+
+            // packets are only ever handed their own Variable Header and Payload
+            if buf.len() < header.remaining_length {
+                return Err(MQTTError::IncompleteData(
+                    "Packet",
+                    header.remaining_length,
+                    buf.len(),
+                ));
+            }
+            let buf = &mut buf.split_to(header.remaining_length);
+
             match header.packet_type {
                 PacketType::Connect => Ok(Packet::Connect(Connect::read(buf)?)),
                 PacketType::ConnAck => Ok(Packet::ConnAck(ConnAck::read(buf)?)),
@@ -124,15 +224,13 @@ pub(crate) mod syncx {
                 PacketType::PingResp => Ok(Packet::PingResp(PingResp::read(buf)?)),
                 PacketType::Auth => Ok(Packet::Auth(Auth::read(buf)?)),
                 PacketType::Disconnect => Ok(Packet::Disconnect(Disconnect::read(buf)?)),
-                _ => Err(MQTTError::UnknownData(format!(
-                    "Unexpected Packet type {:?}",
-                    header.packet_type
-                ))),
+                _ => Err(MQTTError::UnexpectedPacket(header.packet_type)),
             }
         }
     }
 }
 
+#[cfg(feature = "asyncx")]
 mod asyncx {
     use crate::v5::traits::streamio::StreamIO;
 
@@ -169,39 +267,242 @@ mod asyncx {
             Self: Default,
         {
             let header = FixedHeader::read(stream).await?;
-            match header.packet_type {
-                PacketType::Connect => Ok(Packet::Connect(Connect::read(stream).await?)),
-                PacketType::ConnAck => Ok(Packet::ConnAck(ConnAck::read(stream).await?)),
-                PacketType::Publish => Ok(Packet::Publish(
-                    Publish::read_with_fixedheader(stream, &header).await?,
-                )),
-                PacketType::PubAck => Ok(Packet::PubAck(
-                    PubAck::read_with_fixedheader(stream, &header).await?,
-                )),
-                PacketType::PubRec => Ok(Packet::PubRec(
-                    PubRec::read_with_fixedheader(stream, &header).await?,
-                )),
-                PacketType::PubRel => Ok(Packet::PubRel(
-                    PubRel::read_with_fixedheader(stream, &header).await?,
-                )),
-                PacketType::PubComp => Ok(Packet::PubComp(
-                    PubComp::read_with_fixedheader(stream, &header).await?,
-                )),
-                PacketType::Subscribe => Ok(Packet::Subscribe(Subscribe::read(stream).await?)),
-                PacketType::SubAck => Ok(Packet::SubAck(SubAck::read(stream).await?)),
-                PacketType::UnSubscribe => {
-                    Ok(Packet::UnSubscribe(UnSubscribe::read(stream).await?))
-                }
-                PacketType::UnSubAck => Ok(Packet::UnSubAck(UnSubAck::read(stream).await?)),
-                PacketType::PingReq => Ok(Packet::PingReq(PingReq::read(stream).await?)),
-                PacketType::PingResp => Ok(Packet::PingResp(PingResp::read(stream).await?)),
-                PacketType::Auth => Ok(Packet::Auth(Auth::read(stream).await?)),
-                PacketType::Disconnect => Ok(Packet::Disconnect(Disconnect::read(stream).await?)),
-                _ => Err(MQTTError::UnknownData(format!(
-                    "Unexpected Packet type {:?}",
-                    header.packet_type
-                ))),
+            header.validate()?;
+            read_packet(stream, &header).await
+        }
+    }
+
+    impl Packet {
+        /// Reads the next packet off `stream`.
+        /// A packet larger than `max_size` bytes is refused from its fixed header, before the rest of it is read
+        pub async fn read_async<R>(stream: &mut R, max_size: usize) -> Result<Self, MQTTError>
+        where
+            R: futures::AsyncReadExt + Unpin,
+        {
+            let header = FixedHeader::read(stream).await?;
+            header.validate()?;
+
+            let size = 1 + header.header_len + header.remaining_length;
+            if size > max_size {
+                return Err(MQTTError::MaxPacketSizeExceed(size));
             }
+
+            read_packet(stream, &header).await
+        }
+    }
+
+    /// Reads the rest of the packet that `header` starts
+    async fn read_packet<R>(stream: &mut R, header: &FixedHeader) -> Result<Packet, MQTTError>
+    where
+        R: futures::AsyncReadExt + Unpin,
+    {
+        match header.packet_type {
+            PacketType::Connect => Ok(Packet::Connect(Connect::read(stream).await?)),
+            PacketType::ConnAck => Ok(Packet::ConnAck(ConnAck::read(stream).await?)),
+            PacketType::Publish => Ok(Packet::Publish(
+                Publish::read_with_fixedheader(stream, header).await?,
+            )),
+            PacketType::PubAck => Ok(Packet::PubAck(
+                PubAck::read_with_fixedheader(stream, header).await?,
+            )),
+            PacketType::PubRec => Ok(Packet::PubRec(
+                PubRec::read_with_fixedheader(stream, header).await?,
+            )),
+            PacketType::PubRel => Ok(Packet::PubRel(
+                PubRel::read_with_fixedheader(stream, header).await?,
+            )),
+            PacketType::PubComp => Ok(Packet::PubComp(
+                PubComp::read_with_fixedheader(stream, header).await?,
+            )),
+            PacketType::Subscribe => Ok(Packet::Subscribe(
+                Subscribe::read_with_fixedheader(stream, header).await?,
+            )),
+            PacketType::SubAck => Ok(Packet::SubAck(
+                SubAck::read_with_fixedheader(stream, header).await?,
+            )),
+            PacketType::UnSubscribe => Ok(Packet::UnSubscribe(
+                UnSubscribe::read_with_fixedheader(stream, header).await?,
+            )),
+            PacketType::UnSubAck => Ok(Packet::UnSubAck(
+                UnSubAck::read_with_fixedheader(stream, header).await?,
+            )),
+            PacketType::PingReq => Ok(Packet::PingReq(PingReq::read(stream).await?)),
+            PacketType::PingResp => Ok(Packet::PingResp(PingResp::read(stream).await?)),
+            PacketType::Auth => Ok(Packet::Auth(
+                Auth::read_with_fixedheader(stream, header).await?,
+            )),
+            PacketType::Disconnect => Ok(Packet::Disconnect(
+                Disconnect::read_with_fixedheader(stream, header).await?,
+            )),
+            _ => Err(MQTTError::UnexpectedPacket(header.packet_type)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::{Bytes, BytesMut};
+    use futures::{executor::block_on, io::Cursor};
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::{
+        retest_utils::strategies,
+        v5::traits::{bufferio::BufferIO, primitives::codec::BinaryCodec, streamio::StreamIO},
+    };
+
+    fn buffer_encode(packet: &Packet) -> Bytes {
+        let mut buf = BytesMut::new();
+        BufferIO::write(packet, &mut buf).unwrap();
+        buf.freeze()
+    }
+
+    fn stream_encode(packet: &Packet) -> Vec<u8> {
+        let mut stream = Cursor::new(Vec::new());
+        block_on(StreamIO::write(packet, &mut stream)).unwrap();
+        stream.into_inner()
+    }
+
+    proptest! {
+        #[test]
+        fn generated_packets_are_valid(packet in strategies::packet()) {
+            prop_assert_eq!(packet.validate(), Ok(()));
+        }
+
+        #[test]
+        fn buffer_round_trip(packet in strategies::packet()) {
+            let mut buf = buffer_encode(&packet);
+            let read = <Packet as BufferIO>::read(&mut buf).unwrap();
+
+            prop_assert_eq!(read, packet);
+            prop_assert!(buf.is_empty());
+        }
+
+        #[test]
+        fn stream_round_trip(packet in strategies::packet()) {
+            let mut stream = Cursor::new(stream_encode(&packet));
+            let read = block_on(<Packet as StreamIO>::read(&mut stream)).unwrap();
+
+            prop_assert_eq!(read, packet);
+            prop_assert_eq!(stream.position() as usize, stream.get_ref().len());
+        }
+
+        #[test]
+        fn buffer_and_stream_encodings_match(packet in strategies::packet()) {
+            prop_assert_eq!(stream_encode(&packet), buffer_encode(&packet).to_vec());
+        }
+
+        #[test]
+        fn codec_round_trip(packet in strategies::packet()) {
+            let mut buf = BytesMut::new();
+            packet.write_to(&mut buf).unwrap();
+            prop_assert_eq!(&buf[..], &buffer_encode(&packet)[..]);
+
+            let mut buf = buf.freeze();
+            let read = Packet::read_from(&mut buf).unwrap();
+            prop_assert_eq!(read, packet);
+            prop_assert!(buf.is_empty());
+        }
+
+        #[test]
+        fn slice_round_trip(packet in strategies::packet()) {
+            let encoded = buffer_encode(&packet);
+            let mut storage = vec![0u8; encoded.len() + 1];
+            let mut w = &mut storage[..];
+            packet.write_to(&mut w).unwrap();
+            prop_assert_eq!(w.len(), 1);
+            prop_assert_eq!(&storage[..encoded.len()], &encoded[..]);
+
+            let mut r = &storage[..];
+            let read = Packet::read_from(&mut r).unwrap();
+            prop_assert_eq!(read, packet);
+            prop_assert_eq!(r, &[0u8][..]);
+        }
+
+        #[test]
+        fn length_is_the_remaining_length(packet in strategies::packet()) {
+            let mut buf = buffer_encode(&packet);
+            let total = buf.len();
+            let header = <FixedHeader as BufferIO>::read(&mut buf).unwrap();
+
+            prop_assert_eq!(header.remaining_length, BufferIO::length(&packet));
+            prop_assert_eq!(header.remaining_length, buf.len());
+            prop_assert_eq!(total - buf.len(), 1 + header.header_len);
+            prop_assert_eq!(packet.size(), total);
+        }
+
+        #[test]
+        fn packets_stop_at_their_remaining_length(first in strategies::packet(), second in strategies::packet()) {
+            let mut buf = BytesMut::from(&buffer_encode(&first)[..]);
+            buf.extend_from_slice(&buffer_encode(&second));
+
+            let mut stream = Cursor::new(buf.to_vec());
+            prop_assert_eq!(&block_on(<Packet as StreamIO>::read(&mut stream)).unwrap(), &first);
+            prop_assert_eq!(&block_on(<Packet as StreamIO>::read(&mut stream)).unwrap(), &second);
+
+            let mut buf = buf.freeze();
+            prop_assert_eq!(<Packet as BufferIO>::read(&mut buf).unwrap(), first);
+            prop_assert_eq!(<Packet as BufferIO>::read(&mut buf).unwrap(), second);
+        }
+    }
+
+    /// Packets whose Reason Code and Properties are omitted on the wire, which random generation rarely hits
+    #[test]
+    fn omitted_reason_codes_round_trip() {
+        let packets = [
+            Packet::PubAck(PubAck {
+                pkid: 1,
+                ..Default::default()
+            }),
+            Packet::PubRec(PubRec {
+                pkid: 2,
+                ..Default::default()
+            }),
+            Packet::PubRel(PubRel {
+                pkid: 3,
+                ..Default::default()
+            }),
+            Packet::PubComp(PubComp {
+                pkid: 4,
+                ..Default::default()
+            }),
+            Packet::Disconnect(Disconnect::default()),
+            Packet::Auth(Auth::default()),
+        ];
+
+        for packet in packets {
+            let mut buf = buffer_encode(&packet);
+            assert_eq!(stream_encode(&packet), buf.to_vec());
+            assert_eq!(buf.len(), 2 + BufferIO::length(&packet));
+
+            let mut stream = Cursor::new(buf.to_vec());
+            assert_eq!(
+                block_on(<Packet as StreamIO>::read(&mut stream)).unwrap(),
+                packet
+            );
+            assert_eq!(<Packet as BufferIO>::read(&mut buf).unwrap(), packet);
         }
     }
+
+    #[test]
+    fn read_async_refuses_packets_over_the_maximum_size() {
+        let packet = Packet::Publish(Publish {
+            topic: String::from("a/b"),
+            payload: Bytes::from_static(&[0; 64]),
+            ..Default::default()
+        });
+        let encoded = stream_encode(&packet);
+
+        let mut stream = Cursor::new(encoded.clone());
+        let read = block_on(Packet::read_async(&mut stream, encoded.len())).unwrap();
+        assert_eq!(read, packet);
+
+        let mut stream = Cursor::new(encoded.clone());
+        assert_eq!(
+            block_on(Packet::read_async(&mut stream, encoded.len() - 1)),
+            Err(MQTTError::MaxPacketSizeExceed(encoded.len()))
+        );
+        // only the fixed header was consumed
+        assert_eq!(stream.position(), 2);
+    }
 }
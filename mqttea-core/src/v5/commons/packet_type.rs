@@ -7,7 +7,7 @@ use super::error::MQTTError;
 
 #[allow(dead_code)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, FromU8, Default)]
-pub(crate) enum PacketType {
+pub enum PacketType {
     #[default]
     Connect = 0x10, // 0b0001_0000
     ConnAck = 0x20,     // 0b0010_0000
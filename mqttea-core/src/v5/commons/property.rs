@@ -1,8 +1,10 @@
-use std::borrow::Cow;
-use std::fmt::Display;
-use std::future::Future;
+use alloc::{borrow::Cow, string::String};
+use core::fmt::Display;
+#[cfg(feature = "asyncx")]
+use core::future::Future;
 
 use bytes::{Bytes, BytesMut};
+#[cfg(feature = "asyncx")]
 use futures::AsyncWriteExt;
 
 use crate::v5::commons::error::MQTTError;
@@ -129,6 +131,7 @@ impl<'a> Property<'a> {
         func(buf);
     }
 
+    #[cfg(feature = "asyncx")]
     async fn write_to_stream<S, T>(&self, stream: &mut S, value: &T) -> Result<(), MQTTError>
     where
         S: AsyncWriteExt + Unpin,
@@ -139,6 +142,7 @@ impl<'a> Property<'a> {
         value.write(stream).await
     }
 
+    #[cfg(feature = "asyncx")]
     async fn write_async<'b, S, F, Fut>(&self, stream: &'b mut S, func: F) -> Result<(), MQTTError>
     where
         S: AsyncWriteExt + Unpin + 'b,
@@ -212,7 +216,7 @@ impl<'a> Property<'a> {
 
 /// this would eventually be changed to use derive_more lib
 impl<'a> Display for Property<'a> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         write!(
             f,
             "this would be changed eventually to use derive_more::Error"
@@ -221,8 +225,8 @@ impl<'a> Display for Property<'a> {
 }
 
 pub(crate) mod synx {
+    use alloc::{borrow::Cow, string::String};
     use bytes::{Bytes, BytesMut};
-    use std::borrow::Cow;
 
     use crate::v5::commons::error::MQTTError;
     use crate::v5::traits::bufferio::BufferIO;
@@ -248,7 +252,8 @@ pub(crate) mod synx {
                 Self::TopicAlias(Some(p)) => self.with_id(buf, |b| p.write(b)),
                 Self::RequestResponseInformation(Some(p)) => self.with_id(buf, |b| p.write(b)),
                 Self::RequestProblemInformation(Some(p)) => self.with_id(buf, |b| p.write(b)),
-                Self::UserProperty(Cow::Borrowed((ref k, ref v))) => self.with_id(buf, |b| {
+                Self::UserProperty(kv) => self.with_id(buf, |b| {
+                    let (k, v) = kv.as_ref();
                     k.write(b);
                     v.write(b);
                 }),
@@ -357,8 +362,9 @@ pub(crate) mod synx {
     }
 }
 
+#[cfg(feature = "asyncx")]
 pub(crate) mod asyncx {
-    use std::borrow::Cow;
+    use alloc::borrow::Cow;
 
     use crate::v5::commons::error::MQTTError;
     use crate::v5::traits::asyncx::read::Read;
@@ -390,7 +396,10 @@ pub(crate) mod asyncx {
                 3 => Ok(Property::ContentType(Some(Cow::Owned(
                     String::read(stream).await?,
                 )))),
-                8 => Ok(Property::CorrelationData(Some(Cow::Owned(
+                8 => Ok(Property::ResponseTopic(Some(Cow::Owned(
+                    String::read(stream).await?,
+                )))),
+                9 => Ok(Property::CorrelationData(Some(Cow::Owned(
                     Vec::read(stream).await?,
                 )))),
                 11 => Ok(Property::SubscriptionIdentifier(Cow::Owned(
@@ -462,7 +471,7 @@ pub(crate) mod asyncx {
                     self.write_to_stream(stream, p).await?
                 }
                 Self::RequestProblemInformation(Some(p)) => self.write_to_stream(stream, p).await?,
-                Self::UserProperty(Cow::Borrowed(p)) => self.write_to_stream(stream, *p).await?,
+                Self::UserProperty(p) => self.write_to_stream(stream, p.as_ref()).await?,
                 Self::AuthenticationMethod(Some(p)) => {
                     self.write_to_stream(stream, &p.to_string()).await?
                 }
@@ -508,3 +517,32 @@ pub(crate) mod asyncx {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bytes::BytesMut;
+    use futures::{executor::block_on, io::Cursor};
+    use proptest::prelude::*;
+
+    use super::*;
+    use crate::{
+        retest_utils::strategies,
+        v5::traits::{bufferio::BufferIO, streamio::StreamIO},
+    };
+
+    proptest! {
+        #[test]
+        fn properties_round_trip(property in strategies::property()) {
+            let mut buf = BytesMut::new();
+            BufferIO::write(&property, &mut buf).unwrap();
+
+            let mut stream = Cursor::new(Vec::new());
+            block_on(StreamIO::write(&property, &mut stream)).unwrap();
+            prop_assert_eq!(stream.into_inner(), buf.to_vec());
+
+            let mut buf = buf.freeze();
+            prop_assert_eq!(Property::read(&mut buf).unwrap(), property);
+            prop_assert!(buf.is_empty());
+        }
+    }
+}
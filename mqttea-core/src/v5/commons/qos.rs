@@ -1,6 +1,7 @@
 use mqttea_macros::FromU8;
 
-#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, FromU8)]
+#[cfg_attr(feature = "wasm", wasm_bindgen::prelude::wasm_bindgen)]
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, FromU8)]
 pub enum QoS {
     #[default]
     Zero = 0,
@@ -1,12 +1,17 @@
-#[allow(dead_code)]
-#[derive(derive_more::Display)]
-#[repr(u8)]
-pub(crate) enum ReasonCode {
+use super::packet_type::PacketType;
+
+/// Every Reason Code defined by the specification (2.4 Reason Code).
+///
+/// Packets that carry a Reason Code have their own enum holding only the Reason Codes they are allowed to
+/// carry (e.g. `ConnAckReasonCode`), these all convert into this type so that outcomes can be logged and
+/// branched on uniformly, regardless of the packet they were received on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, derive_more::Display)]
+pub enum ReasonCode {
     /// CONNACK, PUBACK. PUBREC, PUBREL, PUBCOMP, UNSUBACK, AUTH (0x00)
     #[display("Success")]
-    Success,  // 0x00
-    /// NormalDisconnection = 0x00
-    #[display("Normal Disconnection")]
+    Success,
+    /// DISCONNECT = 0x00
+    #[display("Normal disconnection")]
     NormalDisconnection,
     /// SUBACK  = 0x00
     #[display("Granted QoS 0")]
@@ -24,7 +29,7 @@ pub(crate) enum ReasonCode {
     #[display("No matching subscribers")]
     NoMatchingSubscribers,
     /// UNSUBACK = 0x11
-    #[display("No Subscription existed")]
+    #[display("No subscription existed")]
     NoSubscriptionExisted,
     /// AUTH = 0x18
     #[display("Continue authentication")]
@@ -80,11 +85,11 @@ pub(crate) enum ReasonCode {
     /// SUBACK, UNSUBACK, DISCONNECT = 0x8F
     #[display("Topic Filter invalid")]
     TopicFilterInvalid,
-    /// CONNACK, PUBACK, PUBREC, DISCONNECT = -0x90
-    #[display("Topic name invalid")]
+    /// CONNACK, PUBACK, PUBREC, DISCONNECT = 0x90
+    #[display("Topic Name invalid")]
     TopicNameInvalid,
     /// PUBACK, PUBREC, SUBACK, UNSUBACK = 0x91
-    #[display("Packet Identidier in use")]
+    #[display("Packet Identifier in use")]
     PacketIdentifierInUse,
     /// PUBREL, PUBCOMP = 0x92
     #[display("Packet Identifier not found")]
@@ -123,7 +128,7 @@ pub(crate) enum ReasonCode {
     #[display("Server moved")]
     ServerMoved,
     /// SUBACK, DISCONNECT = 0x9E
-    #[display("Shared Subcriptions not supported")]
+    #[display("Shared Subscriptions not supported")]
     SharedSubscriptionsNotSupported,
     /// CONNACK, DISCONNECT = 0x9F
     #[display("Connection rate exceeded")]
@@ -139,55 +144,310 @@ pub(crate) enum ReasonCode {
     WildcardSubscriptionsNotSupported,
 }
 
+impl ReasonCode {
+    pub const ALL: [ReasonCode; 45] = [
+        Self::Success,
+        Self::NormalDisconnection,
+        Self::GrantedQoS0,
+        Self::GrantedQoS1,
+        Self::GrantedQoS2,
+        Self::DisconnectWithWillMessage,
+        Self::NoMatchingSubscribers,
+        Self::NoSubscriptionExisted,
+        Self::ContinueAuthentication,
+        Self::ReAuthenticate,
+        Self::UnspecifiedError,
+        Self::MalformedPacket,
+        Self::ProtocolError,
+        Self::ImplementationSpecificError,
+        Self::UnsupportedProtocolVersion,
+        Self::ClientIdentifierNotValid,
+        Self::BadUserNameOrPassword,
+        Self::NotAuthorized,
+        Self::ServerUnavailable,
+        Self::ServerBusy,
+        Self::Banned,
+        Self::ServerShuttingDown,
+        Self::BadAuthenticationMethod,
+        Self::KeepAliveTimeout,
+        Self::SessionTakenOver,
+        Self::TopicFilterInvalid,
+        Self::TopicNameInvalid,
+        Self::PacketIdentifierInUse,
+        Self::PacketIdentifierNotFound,
+        Self::ReceiveMaximumExceeded,
+        Self::TopicAliasInvalid,
+        Self::PacketTooLarge,
+        Self::MessageRateTooHigh,
+        Self::QuotaExceeded,
+        Self::AdministrativeAction,
+        Self::PayloadFormatInvalid,
+        Self::RetainNotSupported,
+        Self::QoSNotSupported,
+        Self::UseAnotherServer,
+        Self::ServerMoved,
+        Self::SharedSubscriptionsNotSupported,
+        Self::ConnectionRateExceeded,
+        Self::MaximumConnectTime,
+        Self::SubscriptionIdentifiersNotSupported,
+        Self::WildcardSubscriptionsNotSupported,
+    ];
+
+    /// The Reason Code `code` as it is understood on `packet`, `None` if `packet` cannot carry it
+    pub fn new(packet: PacketType, code: u8) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|reason| reason.code() == code && reason.is_allowed_on(packet))
+    }
+
+    /// The value of this Reason Code on the wire
+    pub const fn code(&self) -> u8 {
+        match self {
+            Self::Success | Self::NormalDisconnection | Self::GrantedQoS0 => 0x00,
+            Self::GrantedQoS1 => 0x01,
+            Self::GrantedQoS2 => 0x02,
+            Self::DisconnectWithWillMessage => 0x04,
+            Self::NoMatchingSubscribers => 0x10,
+            Self::NoSubscriptionExisted => 0x11,
+            Self::ContinueAuthentication => 0x18,
+            Self::ReAuthenticate => 0x19,
+            Self::UnspecifiedError => 0x80,
+            Self::MalformedPacket => 0x81,
+            Self::ProtocolError => 0x82,
+            Self::ImplementationSpecificError => 0x83,
+            Self::UnsupportedProtocolVersion => 0x84,
+            Self::ClientIdentifierNotValid => 0x85,
+            Self::BadUserNameOrPassword => 0x86,
+            Self::NotAuthorized => 0x87,
+            Self::ServerUnavailable => 0x88,
+            Self::ServerBusy => 0x89,
+            Self::Banned => 0x8A,
+            Self::ServerShuttingDown => 0x8B,
+            Self::BadAuthenticationMethod => 0x8C,
+            Self::KeepAliveTimeout => 0x8D,
+            Self::SessionTakenOver => 0x8E,
+            Self::TopicFilterInvalid => 0x8F,
+            Self::TopicNameInvalid => 0x90,
+            Self::PacketIdentifierInUse => 0x91,
+            Self::PacketIdentifierNotFound => 0x92,
+            Self::ReceiveMaximumExceeded => 0x93,
+            Self::TopicAliasInvalid => 0x94,
+            Self::PacketTooLarge => 0x95,
+            Self::MessageRateTooHigh => 0x96,
+            Self::QuotaExceeded => 0x97,
+            Self::AdministrativeAction => 0x98,
+            Self::PayloadFormatInvalid => 0x99,
+            Self::RetainNotSupported => 0x9A,
+            Self::QoSNotSupported => 0x9B,
+            Self::UseAnotherServer => 0x9C,
+            Self::ServerMoved => 0x9D,
+            Self::SharedSubscriptionsNotSupported => 0x9E,
+            Self::ConnectionRateExceeded => 0x9F,
+            Self::MaximumConnectTime => 0xA0,
+            Self::SubscriptionIdentifiersNotSupported => 0xA1,
+            Self::WildcardSubscriptionsNotSupported => 0xA2,
+        }
+    }
+
+    /// Reason Codes less than 0x80 indicate successful completion of an operation, 0x80 or greater indicates failure
+    pub const fn is_error(&self) -> bool {
+        self.code() >= 0x80
+    }
+
+    /// The packets allowed to carry this Reason Code (Table 2-6 Reason Codes)
+    pub const fn packets(&self) -> &'static [PacketType] {
+        use PacketType::*;
+
+        match self {
+            Self::Success => &[ConnAck, PubAck, PubRec, PubRel, PubComp, UnSubAck, Auth],
+            Self::NormalDisconnection
+            | Self::DisconnectWithWillMessage
+            | Self::ServerShuttingDown
+            | Self::KeepAliveTimeout
+            | Self::SessionTakenOver
+            | Self::ReceiveMaximumExceeded
+            | Self::TopicAliasInvalid
+            | Self::MessageRateTooHigh
+            | Self::AdministrativeAction
+            | Self::MaximumConnectTime => &[Disconnect],
+            Self::GrantedQoS0 | Self::GrantedQoS1 | Self::GrantedQoS2 => &[SubAck],
+            Self::NoMatchingSubscribers => &[PubAck, PubRec],
+            Self::NoSubscriptionExisted => &[UnSubAck],
+            Self::ContinueAuthentication | Self::ReAuthenticate => &[Auth],
+            Self::UnspecifiedError | Self::ImplementationSpecificError | Self::NotAuthorized => {
+                &[ConnAck, PubAck, PubRec, SubAck, UnSubAck, Disconnect]
+            }
+            Self::MalformedPacket
+            | Self::ProtocolError
+            | Self::ServerBusy
+            | Self::BadAuthenticationMethod
+            | Self::PacketTooLarge
+            | Self::RetainNotSupported
+            | Self::QoSNotSupported
+            | Self::UseAnotherServer
+            | Self::ServerMoved
+            | Self::ConnectionRateExceeded => &[ConnAck, Disconnect],
+            Self::UnsupportedProtocolVersion
+            | Self::ClientIdentifierNotValid
+            | Self::BadUserNameOrPassword
+            | Self::ServerUnavailable
+            | Self::Banned => &[ConnAck],
+            Self::TopicFilterInvalid => &[SubAck, UnSubAck, Disconnect],
+            Self::TopicNameInvalid | Self::PayloadFormatInvalid => {
+                &[ConnAck, PubAck, PubRec, Disconnect]
+            }
+            Self::PacketIdentifierInUse => &[PubAck, PubRec, SubAck, UnSubAck],
+            Self::PacketIdentifierNotFound => &[PubRel, PubComp],
+            Self::QuotaExceeded => &[ConnAck, PubAck, PubRec, SubAck, Disconnect],
+            Self::SharedSubscriptionsNotSupported
+            | Self::SubscriptionIdentifiersNotSupported
+            | Self::WildcardSubscriptionsNotSupported => &[SubAck, Disconnect],
+        }
+    }
+
+    pub fn is_allowed_on(&self, packet: PacketType) -> bool {
+        self.packets().contains(&packet)
+    }
+}
 
 impl From<ReasonCode> for u8 {
     fn from(value: ReasonCode) -> Self {
-        match value {
-        
-            ReasonCode::Success | ReasonCode::NormalDisconnection | ReasonCode::GrantedQoS0 => 0x00,
-            ReasonCode::GrantedQoS1 => 0x01,
-            ReasonCode::GrantedQoS2 => 0x02,
-            ReasonCode::DisconnectWithWillMessage => 0x04,
-            ReasonCode::NoMatchingSubscribers => 0x10,
-            ReasonCode::NoSubscriptionExisted => 0x11,
-            ReasonCode::ContinueAuthentication => 0x18,
-            ReasonCode::ReAuthenticate => 0x19,
-            ReasonCode::UnspecifiedError => 0x80,
-            ReasonCode::MalformedPacket => 0x81,
-            ReasonCode::ProtocolError => 0x82,
-            ReasonCode::ImplementationSpecificError => 0x83,
-            ReasonCode::UnsupportedProtocolVersion => 0x84,
-            ReasonCode::ClientIdentifierNotValid => 0x85,
-            ReasonCode::BadUserNameOrPassword => 0x86,
-            ReasonCode::NotAuthorized => 0x87,
-            ReasonCode::ServerUnavailable => 0x88,
-            ReasonCode::ServerBusy => 0x89,
-            ReasonCode::Banned => 0x8A,
-            ReasonCode::ServerShuttingDown => 0x8B,
-            ReasonCode::BadAuthenticationMethod => 0x8C,
-            ReasonCode::KeepAliveTimeout => 0x8D,
-            ReasonCode::SessionTakenOver => 0x8E,
-            ReasonCode::TopicFilterInvalid => 0x8F,
-            ReasonCode::TopicNameInvalid => 0x90,
-            ReasonCode::PacketIdentifierInUse => 0x91,
-            ReasonCode::PacketIdentifierNotFound => 0x92,
-            ReasonCode::ReceiveMaximumExceeded => 0x93,
-            ReasonCode::TopicAliasInvalid => 0x94,
-            ReasonCode::PacketTooLarge => 0x95,
-            ReasonCode::MessageRateTooHigh => 0x96,
-            ReasonCode::QuotaExceeded => 0x97,
-            ReasonCode::AdministrativeAction => 0x98,
-            ReasonCode::PayloadFormatInvalid => 0x99,
-            ReasonCode::RetainNotSupported => 0x9A,
-            ReasonCode::QoSNotSupported => 0x9B,
-            ReasonCode::UseAnotherServer => 0x9C,
-            ReasonCode::ServerMoved => 0x9D,
-            ReasonCode::SharedSubscriptionsNotSupported => 0x9E,
-            ReasonCode::ConnectionRateExceeded => 0x9F,
-            ReasonCode::MaximumConnectTime => 0xA0,
-            ReasonCode::SubscriptionIdentifiersNotSupported => 0xA1,
-            ReasonCode::WildcardSubscriptionsNotSupported => 0xA2,
-            _ => 0x80
+        value.code()
+    }
+}
+
+/// Relates the Reason Codes of a packet (e.g. `ConnAckReasonCode`) to the shared `ReasonCode`:
+/// conversion into `ReasonCode` and `MQTTError`, `is_error()`, and the human readable text as `Display`
+macro_rules! packet_reason_code {
+    ($name:ident, $packet:ident) => {
+        impl From<$name> for $crate::v5::commons::reason_code::ReasonCode {
+            fn from(value: $name) -> Self {
+                Self::new(
+                    $crate::v5::commons::packet_type::PacketType::$packet,
+                    value as u8,
+                )
+                .expect(concat!(
+                    stringify!($name),
+                    " must only hold Reason Codes allowed on ",
+                    stringify!($packet)
+                ))
+            }
         }
+
+        impl From<$name> for $crate::v5::commons::error::MQTTError {
+            fn from(value: $name) -> Self {
+                $crate::v5::commons::error::Refusal::new(
+                    $crate::v5::commons::packet_type::PacketType::$packet,
+                    value.into(),
+                )
+                .into()
+            }
+        }
+
+        impl core::fmt::Display for $name {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                write!(
+                    f,
+                    "{}",
+                    $crate::v5::commons::reason_code::ReasonCode::from(*self)
+                )
+            }
+        }
+
+        impl $name {
+            /// Whether this Reason Code indicates the failure of the operation (0x80 or greater)
+            pub fn is_error(&self) -> bool {
+                (*self as u8) >= 0x80
+            }
+
+            pub(crate) fn decode(code: u8) -> Result<Self, $crate::v5::commons::error::MQTTError> {
+                Self::try_from(code).map_err(|_| {
+                    $crate::v5::commons::error::MQTTError::UnknownReasonCode(
+                        $crate::v5::commons::packet_type::PacketType::$packet,
+                        code,
+                    )
+                })
+            }
+        }
+    };
+}
+
+pub(crate) use packet_reason_code;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::v5::{
+        commons::error::{MQTTError, Refusal},
+        packet::{
+            auth::AuthReasonCode, connack::ConnAckReasonCode, disconnect::DisconnectReasonCode,
+            puback::PubAckReasonCode, pubcomp::PubCompReasonCode, pubrec::PubRecReasonCode,
+            pubrel::PubRelReasonCode, suback::SubAckReasonCode, unsuback::UnSubAckReasonCode,
+        },
+    };
+
+    /// Every value a packet's Reason Code enum accepts must map onto the `ReasonCode` with the same value,
+    /// that is allowed on the packet
+    fn assert_maps<T>(packet: PacketType)
+    where
+        T: TryFrom<u8> + Into<ReasonCode> + Into<u8> + Copy,
+    {
+        let mut count = 0;
+        for code in 0..=u8::MAX {
+            if let Ok(reason) = T::try_from(code) {
+                let shared: ReasonCode = reason.into();
+                assert_eq!(shared.code(), code);
+                assert!(shared.is_allowed_on(packet), "{shared:?} on {packet:?}");
+                count += 1;
+            }
+        }
+        assert!(count > 0);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn packet_reason_codes_map_onto_reason_code() {
+        assert_maps::<ConnAckReasonCode>(PacketType::ConnAck);
+        assert_maps::<PubAckReasonCode>(PacketType::PubAck);
+        assert_maps::<PubRecReasonCode>(PacketType::PubRec);
+        assert_maps::<PubRelReasonCode>(PacketType::PubRel);
+        assert_maps::<PubCompReasonCode>(PacketType::PubComp);
+        assert_maps::<SubAckReasonCode>(PacketType::SubAck);
+        assert_maps::<UnSubAckReasonCode>(PacketType::UnSubAck);
+        assert_maps::<DisconnectReasonCode>(PacketType::Disconnect);
+        assert_maps::<AuthReasonCode>(PacketType::Auth);
+    }
+
+    #[test]
+    fn zero_depends_on_the_packet() {
+        assert_eq!(
+            ReasonCode::new(PacketType::ConnAck, 0),
+            Some(ReasonCode::Success)
+        );
+        assert_eq!(
+            ReasonCode::new(PacketType::Disconnect, 0),
+            Some(ReasonCode::NormalDisconnection)
+        );
+        assert_eq!(
+            ReasonCode::new(PacketType::SubAck, 0),
+            Some(ReasonCode::GrantedQoS0)
+        );
+        assert_eq!(ReasonCode::new(PacketType::PubRel, 0x87), None);
+    }
+
+    #[test]
+    fn errors_display_and_conversion() {
+        assert!(!SubAckReasonCode::GrantedQoS2.is_error());
+        assert!(ConnAckReasonCode::NotAuthorized.is_error());
+        assert_eq!(
+            ConnAckReasonCode::BadUserNameOrPassword.to_string(),
+            "Bad User Name or Password"
+        );
+        assert_eq!(
+            MQTTError::from(DisconnectReasonCode::SessionTakenOver),
+            MQTTError::from(Refusal::new(
+                PacketType::Disconnect,
+                ReasonCode::SessionTakenOver
+            ))
+        );
+    }
+}
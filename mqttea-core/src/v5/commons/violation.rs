@@ -0,0 +1,286 @@
+use core::fmt::Display;
+
+use super::packet_type::PacketType;
+use crate::v5::packet::disconnect::DisconnectReasonCode;
+
+/// A breach of the MQTT 5 specification found on a (decoded) packet.
+///
+/// `spec` is the normative statement violated (e.g. `MQTT-3.3.1-4`), where the specification
+/// does not attach a normative statement to a rule, the section defining the rule is used instead (e.g. `3.3.2.3.4`).
+/// `reason` is the Reason Code to send on the DISCONNECT packet that closes the offending connection (4.13 Handling errors)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Violation {
+    pub spec: &'static str,
+    pub reason: DisconnectReasonCode,
+    pub description: &'static str,
+    /// The offending packet, once known
+    pub packet: Option<PacketType>,
+}
+
+impl Violation {
+    pub(crate) const fn malformed(spec: &'static str, description: &'static str) -> Self {
+        Self::new(spec, DisconnectReasonCode::MalformedPacket, description)
+    }
+
+    pub(crate) const fn protocol(spec: &'static str, description: &'static str) -> Self {
+        Self::new(spec, DisconnectReasonCode::ProtocolError, description)
+    }
+
+    pub(crate) const fn new(
+        spec: &'static str,
+        reason: DisconnectReasonCode,
+        description: &'static str,
+    ) -> Self {
+        Self {
+            spec,
+            reason,
+            description,
+            packet: None,
+        }
+    }
+
+    /// A property that the packet can't carry (2.2.2.2)
+    pub(crate) const fn unexpected_property(packet: PacketType) -> Self {
+        Self::protocol("2.2.2.2", "Property is not allowed on this packet").on(packet)
+    }
+
+    pub(crate) const fn on(mut self, packet: PacketType) -> Self {
+        self.packet = Some(packet);
+        self
+    }
+
+    pub fn is_malformed(&self) -> bool {
+        self.reason == DisconnectReasonCode::MalformedPacket
+    }
+}
+
+impl Display for Violation {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "[{}] {}", self.spec, self.description)
+    }
+}
+
+/// Confirms that the rules the specification places on the content of a packet are respected
+pub trait Validate {
+    fn validate(&self) -> Result<(), Violation>;
+}
+
+/// Fails with `violation` whenever `condition` does not hold
+pub(crate) fn ensure(condition: bool, violation: Violation) -> Result<(), Violation> {
+    if condition {
+        return Ok(());
+    }
+    Err(violation)
+}
+
+/// Payload Format Indicator (3.3.2.3.2 and 3.1.3.2.3): `0` for unspecified bytes, `1` for UTF-8 Encoded Character Data
+pub(crate) fn validate_payload_format(
+    indicator: Option<u8>,
+    payload: &[u8],
+    section: &'static str,
+) -> Result<(), Violation> {
+    match indicator {
+        None | Some(0) => Ok(()),
+        Some(1) => ensure(
+            core::str::from_utf8(payload).is_ok(),
+            Violation::new(
+                section,
+                DisconnectReasonCode::PayloadFormatInvalid,
+                "Payload must be UTF-8 Encoded Character Data when the Payload Format Indicator is 1",
+            ),
+        ),
+        Some(_) => Err(Violation::protocol(
+            section,
+            "Payload Format Indicator must be either 0 or 1",
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bytes::Bytes;
+
+    use super::*;
+    use crate::v5::{
+        commons::{
+            error::MQTTError, fixed_header::FixedHeader, packet::Packet, packet_type::PacketType,
+            qos::QoS,
+        },
+        packet::{
+            publish::{Publish, PublishProperties},
+            subscribe::{Subscribe, SubscriptionOptions},
+        },
+        traits::primitives::codec::BinaryCodec,
+    };
+
+    #[test]
+    fn fixed_header_reserved_flags() {
+        let header = FixedHeader::new(PacketType::Subscribe, 0b0010, 5);
+        assert_eq!(header.validate(), Ok(()));
+
+        let header = FixedHeader::new(PacketType::Subscribe, 0b0000, 5);
+        assert_eq!(header.validate().unwrap_err().spec, "MQTT-2.1.3-1");
+
+        let header = FixedHeader::new(PacketType::Publish, 0b0110, 5);
+        let violation = header.validate().unwrap_err();
+        assert_eq!(violation.spec, "MQTT-3.3.1-4");
+        assert_eq!(violation.reason, DisconnectReasonCode::MalformedPacket);
+    }
+
+    #[test]
+    fn publish_rules() {
+        let packet = Publish {
+            topic: String::from("sensors/temperature"),
+            ..Default::default()
+        };
+        assert_eq!(packet.validate(), Ok(()));
+
+        let dup_at_qos_0 = Publish {
+            dup: true,
+            ..packet.clone()
+        };
+        assert_eq!(dup_at_qos_0.validate().unwrap_err().spec, "MQTT-3.3.1-2");
+
+        let missing_pkid = Publish {
+            qos: QoS::One,
+            ..packet.clone()
+        };
+        assert_eq!(missing_pkid.validate().unwrap_err().spec, "MQTT-2.2.1-3");
+
+        let wildcard = Publish {
+            topic: String::from("sensors/#"),
+            ..packet.clone()
+        };
+        let violation = wildcard.validate().unwrap_err();
+        assert_eq!(violation.reason, DisconnectReasonCode::TopicNameInvalid);
+
+        let not_utf8 = Publish {
+            payload: Bytes::from_static(&[0xff, 0xfe]),
+            properties: PublishProperties {
+                payload_format_indicator: Some(1),
+                ..Default::default()
+            },
+            ..packet
+        };
+        let violation = not_utf8.validate().unwrap_err();
+        assert_eq!(violation.reason, DisconnectReasonCode::PayloadFormatInvalid);
+    }
+
+    #[test]
+    fn subscribe_rules() {
+        let packet = Subscribe {
+            pkid: 1,
+            ..Default::default()
+        };
+        assert_eq!(packet.validate().unwrap_err().spec, "MQTT-3.8.3-2");
+
+        let shared = Subscribe {
+            pkid: 1,
+            payload: vec![(
+                String::from("$share/group/sensors/+"),
+                SubscriptionOptions {
+                    no_local: true,
+                    ..Default::default()
+                },
+            )],
+            ..Default::default()
+        };
+        assert_eq!(shared.validate().unwrap_err().spec, "MQTT-3.8.3-4");
+    }
+
+    #[test]
+    fn subscription_options_reserved_bits() {
+        let err = SubscriptionOptions::try_from(0b0100_0001).unwrap_err();
+        assert!(matches!(err, MQTTError::ProtocolViolation(v) if v.spec == "MQTT-3.8.3-5"));
+        assert!(SubscriptionOptions::try_from(0b0011_0000).is_err());
+    }
+
+    /// Topic Alias, which only PUBLISH may carry
+    const TOPIC_ALIAS: [u8; 4] = [3, 0x23, 0, 1];
+    /// Server Keep Alive, which only CONNACK may carry
+    const SERVER_KEEP_ALIVE: [u8; 4] = [3, 0x13, 0, 1];
+
+    /// Decodes the packet made of `header`, the variable header before its properties, `properties` and what follows them
+    fn unexpected_property(
+        header: u8,
+        before: &[u8],
+        properties: [u8; 4],
+        after: &[u8],
+    ) -> Violation {
+        let body = [before, &properties, after].concat();
+        let encoded = [&[header, body.len() as u8], &body[..]].concat();
+        match Packet::read_from(&mut &encoded[..]) {
+            Err(MQTTError::ProtocolViolation(violation)) => violation,
+            result => panic!("expected a violation, got {result:?}"),
+        }
+    }
+
+    fn assert_unexpected(violation: Violation, packet: PacketType) {
+        assert_eq!(violation.spec, "2.2.2.2");
+        assert_eq!(violation.reason, DisconnectReasonCode::ProtocolError);
+        assert_eq!(violation.packet, Some(packet));
+    }
+
+    #[test]
+    fn connect_and_will_properties() {
+        let variable_header = [0, 4, b'M', b'Q', b'T', b'T', 5];
+        let connect = [&variable_header[..], &[0b0000_0010, 0, 60]].concat();
+        let violation = unexpected_property(0x10, &connect, TOPIC_ALIAS, &[0, 1, b'a']);
+        assert_unexpected(violation, PacketType::Connect);
+
+        // the Will Properties follow the Client Identifier
+        let with_will = [&variable_header[..], &[0b0000_0110, 0, 60, 0, 0, 1, b'a']].concat();
+        let violation =
+            unexpected_property(0x10, &with_will, SERVER_KEEP_ALIVE, &[0, 1, b't', 0, 0]);
+        assert_unexpected(violation, PacketType::Connect);
+    }
+
+    #[test]
+    fn connack_properties() {
+        let violation = unexpected_property(0x20, &[0, 0], TOPIC_ALIAS, &[]);
+        assert_unexpected(violation, PacketType::ConnAck);
+    }
+
+    #[test]
+    fn publish_and_acknowledgement_properties() {
+        let violation = unexpected_property(0x30, &[0, 1, b't'], SERVER_KEEP_ALIVE, &[]);
+        assert_unexpected(violation, PacketType::Publish);
+
+        for (header, packet) in [
+            (0x40, PacketType::PubAck),
+            (0x50, PacketType::PubRec),
+            (0x62, PacketType::PubRel),
+            (0x70, PacketType::PubComp),
+        ] {
+            let violation = unexpected_property(header, &[0, 1, 0], TOPIC_ALIAS, &[]);
+            assert_unexpected(violation, packet);
+        }
+    }
+
+    #[test]
+    fn subscribe_and_suback_properties() {
+        let violation = unexpected_property(0x82, &[0, 1], TOPIC_ALIAS, &[0, 1, b't', 0]);
+        assert_unexpected(violation, PacketType::Subscribe);
+
+        let violation = unexpected_property(0x90, &[0, 1], TOPIC_ALIAS, &[0]);
+        assert_unexpected(violation, PacketType::SubAck);
+    }
+
+    #[test]
+    fn unsubscribe_and_unsuback_properties() {
+        let violation = unexpected_property(0xA2, &[0, 1], TOPIC_ALIAS, &[0, 1, b't']);
+        assert_unexpected(violation, PacketType::UnSubscribe);
+
+        let violation = unexpected_property(0xB0, &[0, 1], TOPIC_ALIAS, &[0]);
+        assert_unexpected(violation, PacketType::UnSubAck);
+    }
+
+    #[test]
+    fn disconnect_and_auth_properties() {
+        let violation = unexpected_property(0xE0, &[0], TOPIC_ALIAS, &[]);
+        assert_unexpected(violation, PacketType::Disconnect);
+
+        let violation = unexpected_property(0xF0, &[0], TOPIC_ALIAS, &[]);
+        assert_unexpected(violation, PacketType::Auth);
+    }
+}
@@ -0,0 +1,37 @@
+use embedded_io_async::{ErrorType, Read, Write};
+use futures::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Runs the client over a `futures` stream (e.g. a tokio `TcpStream` through `tokio_util::compat`),
+/// so that firmware code can be exercised on the host
+#[derive(Debug)]
+pub struct FromFutures<S>(S);
+
+impl<S> FromFutures<S> {
+    pub fn new(stream: S) -> Self {
+        Self(stream)
+    }
+
+    pub fn into_inner(self) -> S {
+        self.0
+    }
+}
+
+impl<S> ErrorType for FromFutures<S> {
+    type Error = std::io::Error;
+}
+
+impl<S: AsyncRead + Unpin> Read for FromFutures<S> {
+    async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+        self.0.read(buf).await
+    }
+}
+
+impl<S: AsyncWrite + Unpin> Write for FromFutures<S> {
+    async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+        self.0.write(buf).await
+    }
+
+    async fn flush(&mut self) -> Result<(), Self::Error> {
+        self.0.flush().await
+    }
+}
@@ -0,0 +1,784 @@
+//! A client for firmware (e.g. Embassy), running over any `embedded_io_async::{Read, Write}` stream
+//! (e.g. an embassy-net `TcpSocket`).
+//!
+//! Packets are encoded and decoded with `v5::borrowed` into the two buffers the client is handed
+//! (which can be `static`), so nothing is allocated while the connection is up.
+//! The Keep Alive is timed with `embassy_time`: the target has to provide its time driver
+
+#[cfg(feature = "std")]
+mod adapter;
+mod packet_ids;
+
+#[cfg(feature = "std")]
+pub use adapter::FromFutures;
+
+use embassy_time::{with_deadline, Duration, Instant};
+use embedded_io_async::{Read, Write};
+
+use packet_ids::PacketIds;
+
+use crate::v5::{
+    borrowed::{
+        ConnAck, Connect, Disconnect, List, Packet, Property, PubAck, PubComp, PubRec, PubRel,
+        Publish, Subscribe, UnSubscribe,
+    },
+    commons::{
+        error::{MQTTError, Refusal},
+        packet_type::PacketType,
+        qos::QoS,
+        violation::{Validate, Violation},
+    },
+    packet::{
+        connack::ConnAckReasonCode, disconnect::DisconnectReasonCode, pubcomp::PubCompReasonCode,
+        subscribe::SubscriptionOptions,
+    },
+};
+
+/// How many exchanges of each kind `Client::new` keeps track of, see [`Client`]
+pub const IN_FLIGHT: usize = 16;
+
+/// The server has more QoS 2 deliveries under way than the Receive Maximum of the CONNECT allows
+const RECEIVE_MAXIMUM_EXCEEDED: Violation = Violation::new(
+    "MQTT-3.3.4-9",
+    DisconnectReasonCode::ReceiveMaximumExceeded,
+    "more QoS 2 messages unreleased than the Receive Maximum",
+)
+.on(PacketType::Publish);
+
+fn io<E: embedded_io::Error>(err: E) -> MQTTError {
+    MQTTError::EmbeddedIoError(err.kind())
+}
+
+/// The writing half of the client: packets are encoded into `buf` before they are written to the stream
+#[derive(Debug)]
+struct Outgoing<'b, S> {
+    stream: S,
+    buf: &'b mut [u8],
+    last_sent: Instant,
+}
+
+impl<S: Write> Outgoing<'_, S> {
+    async fn send(&mut self, packet: &Packet<'_>) -> Result<(), MQTTError> {
+        let len = packet.encode(self.buf)?;
+        self.stream.write_all(&self.buf[..len]).await.map_err(io)?;
+        self.stream.flush().await.map_err(io)?;
+        self.last_sent = Instant::now();
+        Ok(())
+    }
+}
+
+/// A client keeping track of up to `N` of each of the exchanges under way: the QoS 1 and QoS 2 messages it sent,
+/// the SUBSCRIBEs and UNSUBSCRIBEs it sent, and the QoS 2 messages it received and hasn't been released yet.
+///
+/// The Receive Maximum of the CONNECT must be at most `N`, and no more messages are published at a time than the
+/// Receive Maximum of the CONNACK
+#[derive(Debug)]
+pub struct Client<'b, S, const N: usize = IN_FLIGHT> {
+    outgoing: Outgoing<'b, S>,
+    incoming: &'b mut [u8],
+    /// The number of bytes read off the stream into `incoming`
+    filled: usize,
+    /// The number of bytes at the front of `incoming` holding the packet `poll` returned last
+    consumed: usize,
+    keep_alive: Duration,
+    /// When the PINGREQ awaiting its PINGRESP was sent
+    pingreq: Option<Instant>,
+    /// The Packet Identifier assigned last
+    pkid: u16,
+    /// The QoS 1 and QoS 2 messages sent, until their PUBACK, PUBCOMP or PUBREC with an error Reason Code
+    publishes: PacketIds<N>,
+    /// The SUBSCRIBEs and UNSUBSCRIBEs sent, until their SUBACK or UNSUBACK
+    requests: PacketIds<N>,
+    /// The Receive Maximum of the server, from its CONNACK
+    receive_maximum: u16,
+    /// The QoS 2 messages received, until their PUBREL
+    received: PacketIds<N>,
+}
+
+impl<'b, S> Client<'b, S>
+where
+    S: Read + Write,
+{
+    /// `incoming` must be able to hold the largest packet the server sends (its Maximum Packet Size can be set
+    /// on CONNECT), and `outgoing` the largest one the client sends
+    pub fn new(stream: S, incoming: &'b mut [u8], outgoing: &'b mut [u8]) -> Self {
+        Self::with_capacity(stream, incoming, outgoing)
+    }
+}
+
+impl<'b, S, const N: usize> Client<'b, S, N>
+where
+    S: Read + Write,
+{
+    /// A client keeping track of `N` exchanges of each kind rather than [`IN_FLIGHT`],
+    /// e.g. `Client::<_, 4>::with_capacity(stream, &mut incoming, &mut outgoing)`
+    pub fn with_capacity(stream: S, incoming: &'b mut [u8], outgoing: &'b mut [u8]) -> Self {
+        Self {
+            outgoing: Outgoing {
+                stream,
+                buf: outgoing,
+                last_sent: Instant::now(),
+            },
+            incoming,
+            filled: 0,
+            consumed: 0,
+            keep_alive: Duration::from_secs(0),
+            pingreq: None,
+            pkid: 0,
+            publishes: PacketIds::new(),
+            requests: PacketIds::new(),
+            receive_maximum: u16::MAX,
+            received: PacketIds::new(),
+        }
+    }
+
+    /// Sends the CONNECT and waits for the CONNACK.
+    /// The Keep Alive is the one on the CONNECT, unless the server replaces it with a Server Keep Alive (3.2.2.3.14).
+    ///
+    /// The CONNECT is refused when its Receive Maximum, which is 65,535 when it is left out, is larger than `N`:
+    /// the client couldn't keep track of as many QoS 2 messages as the server is allowed to send
+    pub async fn connect(&mut self, connect: &Connect<'_>) -> Result<ConnAck<'_>, MQTTError> {
+        let receive_maximum = connect
+            .properties
+            .iter()
+            .find_map(|property| match property {
+                Property::ReceiveMaximum(receive_maximum) => Some(receive_maximum),
+                _ => None,
+            })
+            .unwrap_or(u16::MAX);
+        if usize::from(receive_maximum) > N {
+            return Err(MQTTError::ProtocolError(
+                "the Receive Maximum of the CONNECT is larger than the client keeps track of",
+            ));
+        }
+
+        self.outgoing.send(&Packet::Connect(*connect)).await?;
+        self.keep_alive = Duration::from_secs(connect.keep_alive as u64);
+
+        self.next().await?;
+        let (server_keep_alive, receive_maximum) = match self.last()? {
+            Packet::ConnAck(connack) if connack.reason != ConnAckReasonCode::Success => {
+                return Err(Refusal::from(connack).into())
+            }
+            Packet::ConnAck(connack) => {
+                connack
+                    .properties
+                    .iter()
+                    .fold((None, None), |found, property| match property {
+                        Property::ServerKeepAlive(keep_alive) => (Some(keep_alive), found.1),
+                        Property::ReceiveMaximum(receive_maximum) => {
+                            (found.0, Some(receive_maximum))
+                        }
+                        _ => found,
+                    })
+            }
+            packet => return Err(MQTTError::UnexpectedPacket(packet.packet_type())),
+        };
+        if let Some(keep_alive) = server_keep_alive {
+            self.keep_alive = Duration::from_secs(keep_alive as u64);
+        }
+        self.receive_maximum = receive_maximum.unwrap_or(u16::MAX);
+
+        match self.last()? {
+            Packet::ConnAck(connack) => Ok(connack),
+            packet => Err(MQTTError::UnexpectedPacket(packet.packet_type())),
+        }
+    }
+
+    /// Assigns the PUBLISH a Packet Identifier when its QoS is 1 or 2, which is returned so that the
+    /// PUBACK or PUBCOMP `poll` returns can be matched with it.
+    ///
+    /// Fails with `PacketIdGenerationError` while as many QoS 1 and QoS 2 messages as the Receive Maximum of the server
+    /// (MQTT-3.3.4-7), or `N`, are waiting on their acknowledgement: `poll` frees them as their acknowledgements arrive
+    pub async fn publish(&mut self, mut publish: Publish<'_>) -> Result<Option<u16>, MQTTError> {
+        if publish.qos != QoS::Zero {
+            let limit = usize::from(self.receive_maximum).min(N);
+            if self.publishes.len() >= limit {
+                return Err(MQTTError::PacketIdGenerationError);
+            }
+            publish.pkid = Some(self.next_pkid());
+        }
+        publish.validate()?;
+
+        if let Some(pkid) = publish.pkid {
+            self.publishes.insert(pkid);
+        }
+        self.outgoing.send(&Packet::Publish(publish)).await?;
+        Ok(publish.pkid)
+    }
+
+    /// Returns the Packet Identifier the SUBACK will carry.
+    /// Fails with `PacketIdGenerationError` while `N` SUBSCRIBEs and UNSUBSCRIBEs are waiting on their acknowledgement
+    pub async fn subscribe(
+        &mut self,
+        filters: &[(&str, SubscriptionOptions)],
+    ) -> Result<u16, MQTTError> {
+        if self.requests.is_full() {
+            return Err(MQTTError::PacketIdGenerationError);
+        }
+        let subscribe = Subscribe {
+            pkid: self.next_pkid(),
+            payload: List::new(filters),
+            ..Default::default()
+        };
+        subscribe.validate()?;
+
+        self.requests.insert(subscribe.pkid);
+        self.outgoing.send(&Packet::Subscribe(subscribe)).await?;
+        Ok(subscribe.pkid)
+    }
+
+    /// Returns the Packet Identifier the UNSUBACK will carry, failing as `subscribe` does
+    pub async fn unsubscribe(&mut self, filters: &[&str]) -> Result<u16, MQTTError> {
+        if self.requests.is_full() {
+            return Err(MQTTError::PacketIdGenerationError);
+        }
+        let unsubscribe = UnSubscribe {
+            pkid: self.next_pkid(),
+            payload: List::new(filters),
+            ..Default::default()
+        };
+        unsubscribe.validate()?;
+
+        self.requests.insert(unsubscribe.pkid);
+        self.outgoing
+            .send(&Packet::UnSubscribe(unsubscribe))
+            .await?;
+        Ok(unsubscribe.pkid)
+    }
+
+    pub async fn disconnect(&mut self) -> Result<(), MQTTError> {
+        self.outgoing
+            .send(&Packet::Disconnect(Disconnect::default()))
+            .await
+    }
+
+    /// Sends any other packet as it is (e.g. an AUTH)
+    pub async fn send(&mut self, packet: &Packet<'_>) -> Result<(), MQTTError> {
+        self.outgoing.send(packet).await
+    }
+
+    /// Drives the connection until the next packet arrives from the server, which is returned.
+    ///
+    /// The PINGREQs are sent as the Keep Alive requires, and the acknowledgements QoS 1 and QoS 2 deliveries
+    /// call for (PUBACK, PUBREC, PUBREL and PUBCOMP) are sent before the packet is returned.
+    /// A QoS 2 message delivered again before its PUBREL is only acknowledged, it isn't returned twice.
+    /// The stream is only read while `poll` is awaited, so it should be called again as soon as the packet is handled
+    pub async fn poll(&mut self) -> Result<Packet<'_>, MQTTError> {
+        self.next().await?;
+        self.last()
+    }
+
+    /// The packet `next` read last, which is still at the front of `incoming`
+    fn last(&self) -> Result<Packet<'_>, MQTTError> {
+        Packet::decode(&self.incoming[..self.consumed]).map(|(packet, _)| packet)
+    }
+
+    /// The Packet Identifier after the one assigned last that no exchange under way uses (MQTT-2.2.1-3),
+    /// of which there is always one as at most `2 * N` are in use
+    fn next_pkid(&mut self) -> u16 {
+        loop {
+            self.pkid = self.pkid.checked_add(1).unwrap_or(1);
+            if !self.publishes.contains(self.pkid) && !self.requests.contains(self.pkid) {
+                return self.pkid;
+            }
+        }
+    }
+
+    /// Reads the next packet into the front of `incoming` and answers it when it calls for it,
+    /// skipping the QoS 2 messages delivered again
+    async fn next(&mut self) -> Result<(), MQTTError> {
+        loop {
+            self.read().await?;
+
+            let (packet, _) = Packet::decode(&self.incoming[..self.consumed])?;
+            if let Err(violation) = packet.validate() {
+                return Err(self.disconnect_with(violation).await);
+            }
+
+            let mut duplicate = false;
+            let response = match packet {
+                Packet::PingResp => {
+                    self.pingreq = None;
+                    None
+                }
+                Packet::Publish(Publish {
+                    qos: QoS::One,
+                    pkid: Some(pkid),
+                    ..
+                }) => Some(Packet::PubAck(PubAck {
+                    pkid,
+                    ..Default::default()
+                })),
+                // delivered again until the server has the PUBREC (4.3.3)
+                Packet::Publish(Publish {
+                    qos: QoS::Two,
+                    pkid: Some(pkid),
+                    ..
+                }) => {
+                    duplicate = self.received.contains(pkid);
+                    if !duplicate && !self.received.insert(pkid) {
+                        return Err(self.disconnect_with(RECEIVE_MAXIMUM_EXCEEDED).await);
+                    }
+                    Some(Packet::PubRec(PubRec {
+                        pkid,
+                        ..Default::default()
+                    }))
+                }
+                Packet::PubAck(puback) => {
+                    self.publishes.remove(puback.pkid);
+                    None
+                }
+                // a PUBREC with an error Reason Code ends the exchange (4.3.3)
+                Packet::PubRec(pubrec) if pubrec.reason_code.is_error() => {
+                    self.publishes.remove(pubrec.pkid);
+                    None
+                }
+                Packet::PubRec(pubrec) => Some(Packet::PubRel(PubRel {
+                    pkid: pubrec.pkid,
+                    ..Default::default()
+                })),
+                Packet::PubRel(pubrel) => {
+                    let reason_code = match self.received.remove(pubrel.pkid) {
+                        true => PubCompReasonCode::Success,
+                        false => PubCompReasonCode::PacketIdentifierNotFound,
+                    };
+                    Some(Packet::PubComp(PubComp {
+                        pkid: pubrel.pkid,
+                        reason_code,
+                        ..Default::default()
+                    }))
+                }
+                Packet::PubComp(pubcomp) => {
+                    self.publishes.remove(pubcomp.pkid);
+                    None
+                }
+                Packet::SubAck(suback) => {
+                    self.requests.remove(suback.pkid);
+                    None
+                }
+                Packet::UnSubAck(unsuback) => {
+                    self.requests.remove(unsuback.pkid);
+                    None
+                }
+                _ => None,
+            };
+
+            if let Some(response) = response {
+                self.outgoing.send(&response).await?;
+            }
+            if !duplicate {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Reads the next packet into the front of `incoming`, `consumed` being its size
+    async fn read(&mut self) -> Result<(), MQTTError> {
+        // the previous packet has been handled
+        self.incoming.copy_within(self.consumed..self.filled, 0);
+        self.filled -= self.consumed;
+        self.consumed = 0;
+
+        let size = loop {
+            let decoded = Packet::decode(&self.incoming[..self.filled]).map(|(_, size)| size);
+            match decoded {
+                Ok(size) => break size,
+                Err(MQTTError::IncompleteData(_, want, _)) if want > self.incoming.len() => {
+                    return Err(MQTTError::MaxPacketSizeExceed(want))
+                }
+                Err(MQTTError::IncompleteData(..)) => {}
+                Err(MQTTError::ProtocolViolation(violation)) => {
+                    return Err(self.disconnect_with(violation).await)
+                }
+                Err(err) => return Err(err),
+            }
+
+            let deadline = self.deadline();
+            let read = self.outgoing.stream.read(&mut self.incoming[self.filled..]);
+            let read = match deadline {
+                Some(deadline) => match with_deadline(deadline, read).await {
+                    Ok(read) => read,
+                    Err(_) => {
+                        self.keep_alive().await?;
+                        continue;
+                    }
+                },
+                None => read.await,
+            };
+
+            match read.map_err(io)? {
+                0 => return Err(MQTTError::IncompleteData("stream", 1, 0)),
+                read => self.filled += read,
+            }
+        };
+        self.consumed = size;
+        Ok(())
+    }
+
+    /// When the connection is due a PINGREQ, or its PINGRESP is overdue
+    fn deadline(&self) -> Option<Instant> {
+        if self.keep_alive.as_ticks() == 0 {
+            return None;
+        }
+
+        match self.pingreq {
+            // the PINGRESP is given one and a half times the Keep Alive to arrive (3.1.2.10)
+            Some(sent) => Some(sent + self.keep_alive * 3 / 2),
+            None => Some(self.outgoing.last_sent + self.keep_alive),
+        }
+    }
+
+    async fn keep_alive(&mut self) -> Result<(), MQTTError> {
+        if self.pingreq.is_some() {
+            return Err(MQTTError::TimeoutError);
+        }
+
+        self.outgoing.send(&Packet::PingReq).await?;
+        self.pingreq = Some(Instant::now());
+        Ok(())
+    }
+
+    /// Closes the connection with the DISCONNECT Reason Code the violation calls for,
+    /// returning the error the caller is given
+    async fn disconnect_with(&mut self, violation: Violation) -> MQTTError {
+        let reason_string = [Property::ReasonString(violation.description)];
+        let disconnect = Disconnect {
+            reason_code: violation.reason,
+            properties: List::new(&reason_string),
+        };
+
+        match self.outgoing.send(&Packet::Disconnect(disconnect)).await {
+            Ok(()) => MQTTError::ProtocolViolation(violation),
+            Err(err) => err,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::convert::Infallible;
+
+    use alloc::vec::Vec;
+    use embedded_io_async::ErrorType;
+    use futures::executor::block_on;
+
+    use crate::v5::packet::pubrec::PubRecReasonCode;
+
+    use super::*;
+
+    /// Plays back what the server sends, and records what the client sends
+    struct Script {
+        incoming: Vec<u8>,
+        outgoing: Vec<u8>,
+    }
+
+    impl Script {
+        fn new(packets: &[Packet]) -> Self {
+            let mut incoming = Vec::new();
+            for packet in packets {
+                let mut buf = [0u8; 64];
+                let len = packet.encode(&mut buf).unwrap();
+                incoming.extend_from_slice(&buf[..len]);
+            }
+            Self {
+                incoming,
+                outgoing: Vec::new(),
+            }
+        }
+    }
+
+    impl ErrorType for &mut Script {
+        type Error = Infallible;
+    }
+
+    impl Read for &mut Script {
+        async fn read(&mut self, buf: &mut [u8]) -> Result<usize, Self::Error> {
+            if self.incoming.is_empty() {
+                // the server has nothing more to say
+                core::future::pending::<()>().await;
+            }
+            let len = buf.len().min(self.incoming.len());
+            buf[..len].copy_from_slice(&self.incoming[..len]);
+            self.incoming.drain(..len);
+            Ok(len)
+        }
+    }
+
+    impl Write for &mut Script {
+        async fn write(&mut self, buf: &[u8]) -> Result<usize, Self::Error> {
+            self.outgoing.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+    }
+
+    fn sent(packets: &[Packet]) -> Vec<u8> {
+        Script::new(packets).incoming
+    }
+
+    /// The Receive Maximum `Client::new` takes
+    const RECEIVE_MAXIMUM: [Property; 1] = [Property::ReceiveMaximum(IN_FLIGHT as u16)];
+
+    fn connect(client_id: &str) -> Connect<'_> {
+        Connect {
+            client_id,
+            properties: List::new(&RECEIVE_MAXIMUM),
+            ..Default::default()
+        }
+    }
+
+    fn publish(qos: QoS, pkid: Option<u16>) -> Publish<'static> {
+        Publish {
+            qos,
+            pkid,
+            topic: "sensors/door",
+            payload: b"open",
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn acknowledges_deliveries_and_keeps_the_connection_alive() {
+        let server_keep_alive = [Property::ServerKeepAlive(1)];
+        let publish = Publish {
+            qos: QoS::One,
+            pkid: Some(5),
+            topic: "sensors/door",
+            payload: b"open",
+            ..Default::default()
+        };
+        let mut script = Script::new(&[
+            Packet::ConnAck(ConnAck {
+                properties: List::new(&server_keep_alive),
+                ..Default::default()
+            }),
+            Packet::Publish(publish),
+        ]);
+        let connect = Connect {
+            keep_alive: 60,
+            ..connect("door")
+        };
+
+        let (mut incoming, mut outgoing) = ([0u8; 64], [0u8; 64]);
+        let mut client = Client::new(&mut script, &mut incoming, &mut outgoing);
+        block_on(async {
+            client.connect(&connect).await.unwrap();
+            assert_eq!(client.keep_alive, Duration::from_secs(1));
+            assert_eq!(client.poll().await.unwrap(), Packet::Publish(publish));
+
+            // the server never answers the PINGREQ sent a Keep Alive after the PUBACK, which is shortened
+            // from the second of the Server Keep Alive so that the test doesn't wait for it
+            client.keep_alive = Duration::from_millis(20);
+            let started = Instant::now();
+            assert_eq!(client.poll().await, Err(MQTTError::TimeoutError));
+            assert!(started.elapsed() >= Duration::from_millis(50));
+        });
+
+        let expected = sent(&[
+            Packet::Connect(connect),
+            Packet::PubAck(PubAck {
+                pkid: 5,
+                ..Default::default()
+            }),
+            Packet::PingReq,
+        ]);
+        assert_eq!(script.outgoing, expected);
+    }
+
+    #[test]
+    fn refuses_packets_its_buffer_cannot_hold() {
+        let publish = Publish {
+            topic: "sensors/door",
+            payload: &[0; 40],
+            ..Default::default()
+        };
+        let mut script = Script::new(&[
+            Packet::ConnAck(ConnAck::default()),
+            Packet::Publish(publish),
+        ]);
+
+        let (mut incoming, mut outgoing) = ([0u8; 32], [0u8; 32]);
+        let mut client = Client::new(&mut script, &mut incoming, &mut outgoing);
+        block_on(async {
+            client.connect(&connect("")).await.unwrap();
+            assert_eq!(
+                client.poll().await,
+                Err(MQTTError::MaxPacketSizeExceed(
+                    Packet::Publish(publish).size()
+                ))
+            );
+        });
+    }
+
+    #[test]
+    fn refuses_to_connect_with_a_receive_maximum_it_cannot_keep_track_of() {
+        let mut script = Script::new(&[Packet::ConnAck(ConnAck::default())]);
+        let (mut incoming, mut outgoing) = ([0u8; 32], [0u8; 32]);
+        let mut client = Client::new(&mut script, &mut incoming, &mut outgoing);
+
+        let err = block_on(client.connect(&Connect::default())).unwrap_err();
+        assert!(matches!(err, MQTTError::ProtocolError(_)));
+        assert!(script.outgoing.is_empty());
+    }
+
+    #[test]
+    fn acknowledges_a_qos_2_message_delivered_again_without_returning_it() {
+        let publish = publish(QoS::Two, Some(7));
+        let pubrel = PubRel {
+            pkid: 7,
+            ..Default::default()
+        };
+        let mut script = Script::new(&[
+            Packet::ConnAck(ConnAck::default()),
+            Packet::Publish(publish),
+            // the server didn't get the PUBREC in time
+            Packet::Publish(Publish {
+                dup: true,
+                ..publish
+            }),
+            Packet::PubRel(pubrel),
+            // released, so the Packet Identifier is free to be used by another message
+            Packet::Publish(publish),
+        ]);
+
+        let (mut incoming, mut outgoing) = ([0u8; 64], [0u8; 64]);
+        let mut client = Client::new(&mut script, &mut incoming, &mut outgoing);
+        block_on(async {
+            client.connect(&connect("door")).await.unwrap();
+            assert_eq!(client.poll().await.unwrap(), Packet::Publish(publish));
+            assert_eq!(client.poll().await.unwrap(), Packet::PubRel(pubrel));
+            assert_eq!(client.poll().await.unwrap(), Packet::Publish(publish));
+        });
+
+        let pubrec = Packet::PubRec(PubRec {
+            pkid: 7,
+            ..Default::default()
+        });
+        let pubcomp = Packet::PubComp(PubComp {
+            pkid: 7,
+            ..Default::default()
+        });
+        let expected = sent(&[
+            Packet::Connect(connect("door")),
+            pubrec,
+            pubrec,
+            pubcomp,
+            pubrec,
+        ]);
+        assert_eq!(script.outgoing, expected);
+    }
+
+    #[test]
+    fn disconnects_a_server_exceeding_the_receive_maximum() {
+        let receive_maximum = [Property::ReceiveMaximum(2)];
+        let connect = Connect {
+            properties: List::new(&receive_maximum),
+            ..connect("door")
+        };
+        let mut script = Script::new(&[
+            Packet::ConnAck(ConnAck::default()),
+            Packet::Publish(publish(QoS::Two, Some(1))),
+            Packet::Publish(publish(QoS::Two, Some(2))),
+            Packet::Publish(publish(QoS::Two, Some(3))),
+        ]);
+
+        let (mut incoming, mut outgoing) = ([0u8; 128], [0u8; 128]);
+        let mut client = Client::<_, 2>::with_capacity(&mut script, &mut incoming, &mut outgoing);
+        block_on(async {
+            client.connect(&connect).await.unwrap();
+            client.poll().await.unwrap();
+            client.poll().await.unwrap();
+            assert_eq!(
+                client.poll().await,
+                Err(MQTTError::ProtocolViolation(RECEIVE_MAXIMUM_EXCEEDED))
+            );
+        });
+
+        let reason_string = [Property::ReasonString(RECEIVE_MAXIMUM_EXCEEDED.description)];
+        let disconnect = Disconnect {
+            reason_code: DisconnectReasonCode::ReceiveMaximumExceeded,
+            properties: List::new(&reason_string),
+        };
+        assert!(script
+            .outgoing
+            .ends_with(&sent(&[Packet::Disconnect(disconnect)])));
+    }
+
+    #[test]
+    fn publishes_no_more_than_the_receive_maximum_of_the_server() {
+        let receive_maximum = [Property::ReceiveMaximum(2)];
+        let mut script = Script::new(&[
+            Packet::ConnAck(ConnAck {
+                properties: List::new(&receive_maximum),
+                ..Default::default()
+            }),
+            Packet::PubAck(PubAck {
+                pkid: 1,
+                ..Default::default()
+            }),
+            Packet::PubRec(PubRec {
+                pkid: 2,
+                reason_code: PubRecReasonCode::QuotaExceeded,
+                ..Default::default()
+            }),
+        ]);
+
+        let (mut incoming, mut outgoing) = ([0u8; 64], [0u8; 64]);
+        let mut client = Client::new(&mut script, &mut incoming, &mut outgoing);
+        block_on(async {
+            client.connect(&connect("door")).await.unwrap();
+            assert_eq!(client.publish(publish(QoS::One, None)).await, Ok(Some(1)));
+            assert_eq!(client.publish(publish(QoS::Two, None)).await, Ok(Some(2)));
+            assert_eq!(
+                client.publish(publish(QoS::One, None)).await,
+                Err(MQTTError::PacketIdGenerationError)
+            );
+            // QoS 0 messages aren't acknowledged, so they aren't held back
+            assert_eq!(client.publish(publish(QoS::Zero, None)).await, Ok(None));
+
+            client.poll().await.unwrap();
+            assert_eq!(client.publish(publish(QoS::One, None)).await, Ok(Some(3)));
+            // a PUBREC with an error Reason Code frees the Packet Identifier as well
+            client.poll().await.unwrap();
+            assert_eq!(client.publish(publish(QoS::One, None)).await, Ok(Some(4)));
+        });
+    }
+
+    #[test]
+    fn skips_the_packet_identifiers_in_use() {
+        let mut script = Script::new(&[
+            Packet::ConnAck(ConnAck::default()),
+            Packet::PubRec(PubRec {
+                pkid: 1,
+                ..Default::default()
+            }),
+            Packet::PubComp(PubComp {
+                pkid: 1,
+                ..Default::default()
+            }),
+        ]);
+
+        let (mut incoming, mut outgoing) = ([0u8; 64], [0u8; 64]);
+        let mut client = Client::new(&mut script, &mut incoming, &mut outgoing);
+        block_on(async {
+            client.connect(&connect("door")).await.unwrap();
+            assert_eq!(client.publish(publish(QoS::Two, None)).await, Ok(Some(1)));
+            assert_eq!(
+                client.subscribe(&[("lights/#", Default::default())]).await,
+                Ok(2)
+            );
+
+            // once the Packet Identifiers wrap around, those still in use are skipped
+            client.pkid = u16::MAX;
+            assert_eq!(client.unsubscribe(&["lights/#"]).await, Ok(3));
+
+            // the PUBREC isn't the end of a QoS 2 exchange, its PUBCOMP is
+            client.poll().await.unwrap();
+            client.pkid = 0;
+            assert_eq!(client.publish(publish(QoS::One, None)).await, Ok(Some(4)));
+            client.poll().await.unwrap();
+            client.pkid = 0;
+            assert_eq!(client.publish(publish(QoS::One, None)).await, Ok(Some(1)));
+        });
+    }
+}
@@ -0,0 +1,57 @@
+/// The Packet Identifiers of the exchanges under way in one direction, at most `N` of them, kept without allocating
+#[derive(Debug)]
+pub(super) struct PacketIds<const N: usize> {
+    /// 0, which isn't a valid Packet Identifier (2.2.1), marks the free slots
+    slots: [u16; N],
+    len: usize,
+}
+
+impl<const N: usize> PacketIds<N> {
+    pub(super) const fn new() -> Self {
+        Self {
+            slots: [0; N],
+            len: 0,
+        }
+    }
+
+    pub(super) fn len(&self) -> usize {
+        self.len
+    }
+
+    pub(super) fn is_full(&self) -> bool {
+        self.len == N
+    }
+
+    pub(super) fn contains(&self, pkid: u16) -> bool {
+        pkid != 0 && self.slots.contains(&pkid)
+    }
+
+    /// `false` when there is no room left for `pkid`, which mustn't be in use already
+    pub(super) fn insert(&mut self, pkid: u16) -> bool {
+        debug_assert!(pkid != 0 && !self.contains(pkid));
+        match self.slots.iter_mut().find(|slot| **slot == 0) {
+            Some(slot) => {
+                *slot = pkid;
+                self.len += 1;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// `false` when `pkid` wasn't in use
+    pub(super) fn remove(&mut self, pkid: u16) -> bool {
+        match self
+            .slots
+            .iter_mut()
+            .find(|slot| pkid != 0 && **slot == pkid)
+        {
+            Some(slot) => {
+                *slot = 0;
+                self.len -= 1;
+                true
+            }
+            None => false,
+        }
+    }
+}
@@ -1,5 +1,9 @@
 pub mod packet;
 pub mod commons;
+#[cfg(feature = "asyncx")]
 pub mod client;
+pub mod borrowed;
+#[cfg(feature = "embedded")]
+pub mod embedded;
 pub mod traits;
 pub(crate) mod utils;
\ No newline at end of file
@@ -3,18 +3,41 @@ mod properties;
 pub use properties::{AuthProperties, AuthReasonCode};
 
 use crate::v5::{
-    commons::{fixed_header::FixedHeader, packet_type::PacketType},
+    commons::{
+        fixed_header::FixedHeader,
+        packet_type::PacketType,
+        violation::{ensure, Validate, Violation},
+    },
     traits::read_data::ReadData,
 };
 
 #[derive(Debug, Default, PartialEq, Eq)]
 pub struct Auth {
-    reason_code: AuthReasonCode,
-    properties: AuthProperties,
+    pub reason_code: AuthReasonCode,
+    pub properties: AuthProperties,
 }
 
 impl ReadData for Auth {}
 
+impl Validate for Auth {
+    fn validate(&self) -> Result<(), Violation> {
+        let properties = &self.properties;
+        let has_properties = properties.auth_data.is_some()
+            || properties.reason_string.is_some()
+            || !properties.user_property.is_empty();
+
+        // The Reason Code and Property Length can only be omitted together (3.15.2.1)
+        if self.reason_code == AuthReasonCode::Success && !has_properties {
+            return Ok(());
+        }
+
+        ensure(
+            properties.auth_method.is_some(),
+            Violation::protocol("3.15.2.2.2", "AUTH must contain an Authentication Method"),
+        )
+    }
+}
+
 mod synx {
     use bytes::Bytes;
 
@@ -49,8 +72,7 @@ mod synx {
                 return Ok(packet);
             }
 
-            packet.reason_code =
-                AuthReasonCode::try_from(u8::read(buf)?).map_err(MQTTError::UnknownData)?;
+            packet.reason_code = AuthReasonCode::decode(u8::read(buf)?)?;
             packet.properties = AuthProperties::read(buf)?;
 
             Ok(packet)
@@ -58,6 +80,7 @@ mod synx {
     }
 }
 
+#[cfg(feature = "asyncx")]
 mod asyncx {
     use crate::v5::commons::error::MQTTError;
     use crate::v5::packet::auth::{AuthProperties, AuthReasonCode, FixedHeader, PacketType};
@@ -80,21 +103,24 @@ mod asyncx {
             self.properties.write(stream).await
         }
 
-        async fn read<R>(stream: &mut R) -> Result<Self, crate::v5::commons::error::MQTTError>
+        async fn read_with_fixedheader<R>(
+            stream: &mut R,
+            header: &FixedHeader,
+        ) -> Result<Self, MQTTError>
         where
             R: futures::AsyncReadExt + Unpin,
         {
             let mut packet = Self::default();
 
-            let Ok(reason_code) = u8::read(stream).await else {
+            // reason code and property length can be omitted if reason_code is success and there are no properties
+            if header.remaining_length == 0 {
                 return Ok(packet);
-            };
+            }
 
-            packet.reason_code =
-                AuthReasonCode::try_from(reason_code).map_err(MQTTError::UnknownData)?;
+            packet.reason_code = AuthReasonCode::decode(u8::read(stream).await?)?;
             packet.properties = AuthProperties::read(stream).await?;
 
-            Ok(Self::default())
+            Ok(packet)
         }
     }
 }
@@ -1,8 +1,13 @@
+use alloc::{string::String, vec::Vec};
+
 use bytes::Bytes;
 use mqttea_macros::Length;
 
 use crate::v5::{
-    commons::{error::MQTTError, property::Property},
+    commons::{
+        error::MQTTError, packet_type::PacketType, property::Property,
+        reason_code::packet_reason_code, violation::Violation,
+    },
     traits::{read_data::ReadData, utils::Utils},
 };
 
@@ -36,7 +41,7 @@ impl ReadData for AuthProperties {
                     v.as_deref().map(String::from),
                 )(property)?,
                 Property::UserProperty(v) => props.user_property.push(v.into_owned()),
-                p => return Err(MQTTError::UnexpectedProperty(p.to_string(), "".to_string())),
+                _ => return Err(Violation::unexpected_property(PacketType::Auth).into()),
             }
             if data.is_empty() {
                 break;
@@ -47,6 +52,7 @@ impl ReadData for AuthProperties {
     }
 }
 
+#[cfg_attr(feature = "wasm", wasm_bindgen::prelude::wasm_bindgen)]
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, FromU8)]
 pub enum AuthReasonCode {
     #[default]
@@ -54,9 +60,10 @@ pub enum AuthReasonCode {
     ContinueAuthentication = 24,
     ReAuthenticate = 25,
 }
+packet_reason_code!(AuthReasonCode, Auth);
 
 mod syncx {
-    use std::borrow::Cow;
+    use alloc::borrow::Cow;
 
     use super::{AuthProperties, Property};
     use crate::v5::{commons::error::MQTTError, traits::bufferio::BufferIO};
@@ -82,8 +89,9 @@ mod syncx {
     }
 }
 
+#[cfg(feature = "asyncx")]
 mod asyncx {
-    use std::borrow::Cow;
+    use alloc::borrow::Cow;
 
     use crate::v5::commons::error::MQTTError;
     use crate::v5::packet::auth::AuthProperties;
@@ -1,11 +1,14 @@
 mod properties;
-pub(crate) mod reason_code;
+mod reason_code;
 
-use properties::ConnAckProperties;
-use reason_code::ConnAckReasonCode;
+pub use properties::ConnAckProperties;
+pub use reason_code::ConnAckReasonCode;
 
 use crate::v5::{
-    commons::{fixed_header::FixedHeader, packet_type::PacketType},
+    commons::{
+        packet_type::PacketType,
+        violation::{ensure, Validate, Violation},
+    },
     traits::read_data::ReadData,
 };
 
@@ -13,12 +16,46 @@ use crate::v5::{
 pub struct ConnAck {
     /// 3.2.2.1.1 Connect Acknowledge flag
     pub session_present: bool, // bit 0 of the COnnect Acknowledge flag
-    pub(crate) reason: ConnAckReasonCode,
+    pub reason: ConnAckReasonCode,
     pub properties: ConnAckProperties,
 }
 
 impl ReadData for ConnAck {}
 
+impl ConnAck {
+    /// 3.2.2.1 Connect Acknowledge Flags: bits 7-1 are reserved
+    pub(crate) fn session_present(flags: u8) -> Result<bool, Violation> {
+        ensure(
+            flags & 0b1111_1110 == 0,
+            Violation::malformed(
+                "MQTT-3.2.2-1",
+                "Reserved Connect Acknowledge Flags must be 0",
+            ),
+        )?;
+        Ok(flags == 1)
+    }
+}
+
+impl Validate for ConnAck {
+    fn validate(&self) -> Result<(), Violation> {
+        ensure(
+            !self.session_present || self.reason == ConnAckReasonCode::Success,
+            Violation::protocol(
+                "MQTT-3.2.2-6",
+                "Session Present must be 0 when the Reason Code is not 0",
+            ),
+        )?;
+        ensure(
+            self.properties.receive_maximum != Some(0),
+            Violation::protocol("3.2.2.3.3", "Receive Maximum must not be 0"),
+        )?;
+        ensure(
+            self.properties.maximum_packet_size != Some(0),
+            Violation::protocol("3.2.2.3.6", "Maximum Packet Size must not be 0"),
+        )
+    }
+}
+
 mod synx {
     use crate::v5::commons::{error::MQTTError, fixed_header::FixedHeader};
     use crate::v5::traits::{
@@ -51,11 +88,9 @@ mod synx {
         fn read(buf: &mut bytes::Bytes) -> Result<Self, MQTTError> {
             // Assumption is that the fixed header as been read already
             let mut packet = Self::default();
-            packet.session_present = u8::read(buf)? != 0;
+            packet.session_present = ConnAck::session_present(u8::read(buf)?)?;
             let reason = u8::read(buf)?;
-            packet.reason = ConnAckReasonCode::try_from(reason).map_err(|_| {
-                MQTTError::UnknownData(format!("Unrecognized reason code: {reason}"))
-            })?;
+            packet.reason = ConnAckReasonCode::decode(reason)?;
             packet.properties = ConnAckProperties::read(buf)?;
 
             Ok(packet)
@@ -63,18 +98,17 @@ mod synx {
     }
 }
 
+#[cfg(feature = "asyncx")]
 mod asyncx {
     use crate::v5::{
-        commons::error::MQTTError,
+        commons::{error::MQTTError, fixed_header::FixedHeader},
         traits::{
             asyncx::{read::Read, write::Write},
             streamio::StreamIO,
         },
     };
 
-    use super::{
-        properties::ConnAckProperties, ConnAck, ConnAckReasonCode, FixedHeader, PacketType,
-    };
+    use super::{properties::ConnAckProperties, ConnAck, ConnAckReasonCode, PacketType};
 
     impl StreamIO for ConnAck {
         /// This is the length of the Variable Header
@@ -90,11 +124,9 @@ mod asyncx {
             R: futures::AsyncReadExt + Unpin,
         {
             let mut packet = Self::default();
-            packet.session_present = u8::read(stream).await? != 0;
+            packet.session_present = ConnAck::session_present(u8::read(stream).await?)?;
             let reason = u8::read(stream).await?;
-            packet.reason = ConnAckReasonCode::try_from(reason).map_err(|_| {
-                MQTTError::UnknownData(format!("Unrecognized reason code: {reason}"))
-            })?;
+            packet.reason = ConnAckReasonCode::decode(reason)?;
             packet.properties = ConnAckProperties::read(stream).await?;
 
             Ok(packet)
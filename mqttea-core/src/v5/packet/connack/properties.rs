@@ -1,8 +1,12 @@
+use alloc::{borrow::ToOwned, string::String, vec::Vec};
+
 use bytes::Bytes;
 use mqttea_macros::Length;
 
 use crate::v5::commons::error::MQTTError;
+use crate::v5::commons::packet_type::PacketType;
 use crate::v5::commons::property::Property;
+use crate::v5::commons::violation::Violation;
 use crate::v5::traits::read_data::ReadData;
 use crate::v5::traits::utils::Utils;
 
@@ -94,7 +98,7 @@ impl ReadData for ConnAckProperties {
                     &mut properties.authentication_data,
                     v.to_owned().map(|x| Bytes::from_iter(x.into_owned())),
                 )(property)?,
-                p => return Err(MQTTError::UnexpectedProperty(p.to_string(), "".to_string())),
+                _ => return Err(Violation::unexpected_property(PacketType::ConnAck).into()),
             }
 
             if data.is_empty() {
@@ -107,7 +111,8 @@ impl ReadData for ConnAckProperties {
 }
 
 mod syncx {
-    use std::borrow::{Borrow, Cow};
+    use alloc::{borrow::Cow, string::String};
+    use core::borrow::Borrow;
 
     use crate::v5::{
         commons::{error::MQTTError, property::Property},
@@ -167,8 +172,10 @@ mod syncx {
     }
 }
 
+#[cfg(feature = "asyncx")]
 mod asyncx {
-    use std::borrow::{Borrow, Cow};
+    use alloc::borrow::Cow;
+    use core::borrow::Borrow;
 
     use crate::v5::{
         commons::property::Property, packet::connack::properties::ConnAckProperties,
@@ -1,17 +1,23 @@
 mod properties;
 pub mod will;
 
+use alloc::string::String;
+
 use mqttea_macros::Length;
-use properties::ConnectProperties;
+pub use properties::ConnectProperties;
 use will::Will;
 
 use crate::{
     constants::PROTOCOL_NAME,
     v5::{
-        client::ConnectOptions,
         commons::{
-            error::MQTTError, fixed_header::FixedHeader, packet_type::PacketType,
-            property::Property, qos::QoS, version::Version,
+            error::MQTTError,
+            fixed_header::FixedHeader,
+            packet_type::PacketType,
+            property::Property,
+            qos::QoS,
+            version::Version,
+            violation::{ensure, Validate, Violation},
         },
         traits::read_data::ReadData,
     },
@@ -20,21 +26,21 @@ use crate::{
 #[derive(Debug, Length, PartialEq, Eq)]
 pub struct Connect {
     #[bytes(no_id)]
-    pub(crate) client_id: String,
+    pub client_id: String,
     #[bytes(no_id)]
-    pub(crate) username: Option<String>,
+    pub username: Option<String>,
     #[bytes(no_id)]
-    pub(crate) password: Option<String>,
+    pub password: Option<String>,
     #[bytes(ignore)]
     pub(crate) version: Version,
     #[bytes(ignore)]
-    pub(crate) will: Option<Will>,
+    pub will: Option<Will>,
     #[bytes(ignore)]
-    pub(crate) clean_start: bool,
+    pub clean_start: bool,
     #[bytes(ignore)]
-    pub(crate) keep_alive: u16,
+    pub keep_alive: u16,
     #[bytes(ignore)] // Connection properties
-    pub(crate) properties: ConnectProperties,
+    pub properties: ConnectProperties,
 }
 
 #[cfg(feature = "asyncx")]
@@ -57,8 +63,9 @@ impl Default for Connect {
     }
 }
 
-impl From<&ConnectOptions> for Connect {
-    fn from(value: &ConnectOptions) -> Self {
+#[cfg(feature = "asyncx")]
+impl From<&crate::v5::client::ConnectOptions> for Connect {
+    fn from(value: &crate::v5::client::ConnectOptions) -> Self {
         Self {
             client_id: value.client_id.clone(),
             username: value.username.clone(),
@@ -84,7 +91,55 @@ impl From<&ConnectOptions> for Connect {
 
 impl ReadData for Connect {}
 
+impl Validate for Connect {
+    fn validate(&self) -> Result<(), Violation> {
+        let properties = &self.properties;
+
+        ensure(
+            properties.receive_maximum != Some(0),
+            Violation::protocol("3.1.2.11.3", "Receive Maximum must not be 0"),
+        )?;
+        ensure(
+            properties.maximum_packet_size != Some(0),
+            Violation::protocol("3.1.2.11.4", "Maximum Packet Size must not be 0"),
+        )?;
+        ensure(
+            properties
+                .request_response_information
+                .is_none_or(|v| v <= 1),
+            Violation::protocol(
+                "3.1.2.11.6",
+                "Request Response Information must be either 0 or 1",
+            ),
+        )?;
+        ensure(
+            properties
+                .request_problem_information
+                .is_none_or(|v| v <= 1),
+            Violation::protocol(
+                "3.1.2.11.7",
+                "Request Problem Information must be either 0 or 1",
+            ),
+        )?;
+        ensure(
+            properties.authentication_data.is_none() || properties.authentication_method.is_some(),
+            Violation::protocol(
+                "3.1.2.11.10",
+                "Authentication Data requires an Authentication Method",
+            ),
+        )?;
+
+        if let Some(will) = &self.will {
+            will.validate()?;
+        }
+
+        Ok(())
+    }
+}
+
 mod syncx {
+    use alloc::string::{String, ToString};
+
     use bytes::Bytes;
 
     use crate::v5::{
@@ -165,6 +220,7 @@ mod syncx {
             packet.version = Version::try_from(u8::read(buf)?)?;
 
             let flags = ConnectFlags::try_from(u8::read(buf)?)?;
+            packet.clean_start = flags.clean_start;
             packet.keep_alive = u16::read(buf)?;
             packet.properties = ConnectProperties::read(buf)?;
             packet.client_id = String::read(buf)?;
@@ -173,7 +229,6 @@ mod syncx {
                 let mut will = Will::read(buf)?;
                 will.retain = flags.will_retain;
                 will.qos = flags.will_qos;
-                packet.clean_start = flags.will_retain;
                 packet.will = Some(will);
             }
 
@@ -189,6 +244,7 @@ mod syncx {
     }
 }
 
+#[cfg(feature = "asyncx")]
 mod asyncx {
     use crate::v5::{
         commons::error::MQTTError,
@@ -276,6 +332,7 @@ mod asyncx {
             packet.version = Version::try_from(u8::read(stream).await?)?;
 
             let flags = ConnectFlags::try_from(u8::read(stream).await?)?;
+            packet.clean_start = flags.clean_start;
             packet.keep_alive = u16::read(stream).await?;
             packet.properties = ConnectProperties::read(stream).await?;
             packet.client_id = String::read(stream).await?;
@@ -284,7 +341,6 @@ mod asyncx {
                 let mut will = Will::read(stream).await?;
                 will.retain = flags.will_retain;
                 will.qos = flags.will_qos;
-                packet.clean_start = flags.will_retain;
                 packet.will = Some(will);
             }
 
@@ -325,14 +381,38 @@ impl From<ConnectFlags> for u8 {
 impl TryFrom<u8> for ConnectFlags {
     type Error = MQTTError;
     fn try_from(value: u8) -> Result<Self, Self::Error> {
+        ensure(
+            value & 1 == 0,
+            Violation::malformed("MQTT-3.1.2-3", "Reserved Connect Flag must be 0"),
+        )?;
+
         let username = (value & (1 << 7)) != 0;
         let password = (value & (1 << 6)) != 0;
         let will_retain = (value & (1 << 5)) != 0;
         let qos = (value & (0b11 << 3)) >> 3;
-        let will_qos = QoS::try_from(qos).map_err(|_| MQTTError::UnsupportedQoS(qos))?;
         let will_flag = (value & (1 << 2)) != 0;
         let clean_start = (value & (1 << 1)) != 0;
 
+        ensure(
+            qos != 3,
+            Violation::malformed("MQTT-3.1.2-12", "Will QoS must not be 3"),
+        )?;
+        ensure(
+            will_flag || qos == 0,
+            Violation::malformed(
+                "MQTT-3.1.2-11",
+                "Will QoS must be 0 when the Will Flag is 0",
+            ),
+        )?;
+        ensure(
+            will_flag || !will_retain,
+            Violation::malformed(
+                "MQTT-3.1.2-13",
+                "Will Retain must be 0 when the Will Flag is 0",
+            ),
+        )?;
+        let will_qos = QoS::try_from(qos).map_err(|_| MQTTError::UnsupportedQoS(qos))?;
+
         Ok(Self {
             username,
             password,
@@ -1,10 +1,14 @@
-use std::borrow::Cow;
+use alloc::{
+    borrow::{Cow, ToOwned},
+    string::String,
+    vec::Vec,
+};
 
 use bytes::Bytes;
 use mqttea_macros::Length;
 
 use crate::v5::{
-    commons::error::MQTTError,
+    commons::{error::MQTTError, packet_type::PacketType, violation::Violation},
     traits::{read_data::ReadData, utils::Utils},
 };
 
@@ -12,16 +16,16 @@ use super::Property;
 
 /// CONNECT Properties (3.1.2.11)
 #[derive(Debug, Clone, Length, Default, PartialEq, Eq)]
-pub(crate) struct ConnectProperties {
-    pub(crate) session_expiry_interval: Option<u32>,
-    pub(crate) receive_maximum: Option<u16>,
-    pub(crate) maximum_packet_size: Option<u32>,
-    pub(crate) topic_alias_maximum: Option<u16>,
-    pub(crate) request_response_information: Option<u8>,
-    pub(crate) request_problem_information: Option<u8>,
-    pub(crate) user_property: Vec<(String, String)>,
-    pub(crate) authentication_method: Option<String>,
-    pub(crate) authentication_data: Option<Bytes>,
+pub struct ConnectProperties {
+    pub session_expiry_interval: Option<u32>,
+    pub receive_maximum: Option<u16>,
+    pub maximum_packet_size: Option<u32>,
+    pub topic_alias_maximum: Option<u16>,
+    pub request_response_information: Option<u8>,
+    pub request_problem_information: Option<u8>,
+    pub user_property: Vec<(String, String)>,
+    pub authentication_method: Option<String>,
+    pub authentication_data: Option<Bytes>,
 }
 
 #[cfg(feature = "asyncx")]
@@ -65,7 +69,7 @@ impl ReadData for ConnectProperties {
                     &mut properties.authentication_data,
                     value.to_owned().map(|x| Bytes::from_iter(x.into_owned())),
                 )(property)?,
-                p => return Err(MQTTError::UnexpectedProperty(p.to_string(), "".to_string())),
+                _ => return Err(Violation::unexpected_property(PacketType::Connect).into()),
             }
             if data.is_empty() {
                 break;
@@ -77,7 +81,7 @@ impl ReadData for ConnectProperties {
 }
 
 mod syncx {
-    use std::borrow::Cow;
+    use alloc::borrow::Cow;
 
     use crate::v5::{
         commons::{error::MQTTError, property::Property},
@@ -115,9 +119,10 @@ mod syncx {
     }
 }
 
+#[cfg(feature = "asyncx")]
 mod asyncx {
 
-    use std::borrow::Cow;
+    use alloc::borrow::Cow;
 
     use crate::v5::{commons::property::Property, traits::streamio::StreamIO};
 
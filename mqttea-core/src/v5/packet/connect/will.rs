@@ -1,8 +1,17 @@
+use alloc::{string::String, vec::Vec};
+
 use bytes::Bytes;
 use mqttea_macros::Length;
 
-use crate::v5::commons::{error::MQTTError, qos::QoS};
+use crate::v5::commons::{
+    error::MQTTError,
+    packet_type::PacketType,
+    qos::QoS,
+    violation::{ensure, validate_payload_format, Validate, Violation},
+};
+use crate::v5::packet::disconnect::DisconnectReasonCode;
 use crate::v5::traits::utils::Utils;
+use crate::v5::utils::topic::validate_topic_name;
 
 use super::{Property, ReadData};
 
@@ -26,9 +35,9 @@ pub struct Will {
     #[bytes(no_id)]
     pub payload: Bytes,
     #[bytes(ignore)]
-    pub(super) qos: QoS,
+    pub qos: QoS,
     #[bytes(ignore)]
-    pub(super) retain: bool,
+    pub retain: bool,
 }
 
 impl ReadData for WillProperties {
@@ -62,7 +71,7 @@ impl ReadData for WillProperties {
                 Property::UserProperty(value) => {
                     properties.user_property.push(value.into_owned());
                 }
-                p => return Err(MQTTError::UnexpectedProperty(p.to_string(), "".to_string())),
+                _ => return Err(Violation::unexpected_property(PacketType::Connect).into()),
             }
 
             if data.is_empty() {
@@ -76,13 +85,37 @@ impl ReadData for WillProperties {
 
 impl ReadData for Will {}
 
+impl Validate for Will {
+    fn validate(&self) -> Result<(), Violation> {
+        ensure(
+            !self.topic.is_empty(),
+            Violation::new(
+                "MQTT-4.7.3-1",
+                DisconnectReasonCode::TopicNameInvalid,
+                "Will Topic must be at least one character long",
+            ),
+        )?;
+        validate_topic_name(&self.topic, "MQTT-4.7.0-1")?;
+
+        if let Some(topic) = &self.properties.response_topic {
+            validate_topic_name(topic, "3.1.3.2.6")?;
+        }
+
+        validate_payload_format(
+            self.properties.payload_format_indicator,
+            &self.payload,
+            "3.1.3.2.3",
+        )
+    }
+}
+
 #[cfg(feature = "asyncx")]
 pub(crate) use asyncx::*;
 #[cfg(feature = "syncx")]
 pub(crate) use syncx::*;
 
 mod syncx {
-    use std::borrow::Cow;
+    use alloc::{borrow::Cow, string::String};
 
     use bytes::Bytes;
 
@@ -163,8 +196,9 @@ mod syncx {
     }
 }
 
+#[cfg(feature = "asyncx")]
 mod asyncx {
-    use std::borrow::Cow;
+    use alloc::borrow::Cow;
 
     use bytes::Bytes;
 
@@ -173,7 +207,8 @@ mod asyncx {
         commons::error::MQTTError,
         traits::{
             asyncx::{read::Read, write::Write},
-            {read_data::ReadData, streamio::StreamIO},
+            read_data::ReadData,
+            streamio::{read_exactly, StreamIO},
         },
     };
 
@@ -192,10 +227,7 @@ mod asyncx {
                 return Ok(Self::default());
             };
 
-            let mut data = Vec::with_capacity(len);
-            stream.read_exact(&mut data).await?;
-
-            let mut data = Bytes::copy_from_slice(&data);
+            let mut data = Bytes::from(read_exactly(stream, len).await?);
 
             Self::read_data(&mut data)
         }
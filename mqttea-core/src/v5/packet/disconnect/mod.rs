@@ -5,7 +5,12 @@ pub use properties::DisconnectProperties;
 pub use reason_code::DisconnectReasonCode;
 
 use crate::v5::{
-    commons::{fixed_header::FixedHeader, packet_type::PacketType, property::Property},
+    commons::{
+        fixed_header::FixedHeader,
+        packet_type::PacketType,
+        property::Property,
+        violation::{Validate, Violation},
+    },
     traits::read_data::ReadData,
 };
 
@@ -22,6 +27,14 @@ pub(crate) use syncx::*;
 
 impl ReadData for Disconnect {}
 
+impl Validate for Disconnect {
+    /// Every rule on DISCONNECT depends on the direction or on the CONNECT that opened the session,
+    /// beyond what decoding already enforces
+    fn validate(&self) -> Result<(), Violation> {
+        Ok(())
+    }
+}
+
 mod syncx {
     use bytes::{Buf, Bytes, BytesMut};
 
@@ -51,22 +64,26 @@ mod syncx {
         fn write(&self, buf: &mut BytesMut) -> Result<(), MQTTError> {
             FixedHeader::new(PacketType::Disconnect, 0, self.length()).write(buf)?;
 
-            u8::from(self.reason_code).write(buf);
-
             if self.reason_code == DisconnectReasonCode::NormalDisconnection
                 && self.properties.length() == 0
             {
                 return Ok(());
             }
 
+            u8::from(self.reason_code).write(buf);
             self.properties.write(buf)?;
             Ok(())
         }
 
         fn read(buf: &mut Bytes) -> Result<Self, MQTTError> {
             let mut packet = Self::default();
-            packet.reason_code =
-                DisconnectReasonCode::try_from(u8::read(buf)?).map_err(MQTTError::UnknownData)?;
+
+            // a Remaining Length of 0 means a Normal disconnection without Properties (3.14.2.1)
+            if buf.is_empty() {
+                return Ok(packet);
+            }
+
+            packet.reason_code = DisconnectReasonCode::decode(u8::read(buf)?)?;
 
             if buf.has_remaining() {
                 packet.properties = DisconnectProperties::read(buf)?;
@@ -77,6 +94,7 @@ mod syncx {
     }
 }
 
+#[cfg(feature = "asyncx")]
 mod asyncx {
 
     use crate::v5::{
@@ -110,28 +128,35 @@ mod asyncx {
                 .write(stream)
                 .await?;
 
-            u8::from(self.reason_code).write(stream).await?;
-
             if self.reason_code == DisconnectReasonCode::NormalDisconnection
                 && self.properties.length() == 0
             {
                 return Ok(());
             }
 
+            u8::from(self.reason_code).write(stream).await?;
             self.properties.write(stream).await?;
             Ok(())
         }
 
-        async fn read<R>(stream: &mut R) -> Result<Self, crate::v5::commons::error::MQTTError>
+        async fn read_with_fixedheader<R>(
+            stream: &mut R,
+            header: &FixedHeader,
+        ) -> Result<Self, MQTTError>
         where
             R: futures::AsyncReadExt + Unpin,
         {
             let mut packet = Self::default();
 
-            packet.reason_code = DisconnectReasonCode::try_from(u8::read(stream).await?)
-                .map_err(MQTTError::UnknownData)?;
+            // a Remaining Length of 0 means a Normal disconnection without Properties (3.14.2.1)
+            if header.remaining_length == 0 {
+                return Ok(packet);
+            }
 
-            packet.properties = DisconnectProperties::read(stream).await?;
+            packet.reason_code = DisconnectReasonCode::decode(u8::read(stream).await?)?;
+            if header.remaining_length > 1 {
+                packet.properties = DisconnectProperties::read(stream).await?;
+            }
 
             Ok(packet)
         }
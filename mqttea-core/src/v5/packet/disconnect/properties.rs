@@ -1,7 +1,12 @@
+use alloc::{string::String, vec::Vec};
+
 use bytes::Bytes;
 use mqttea_macros::Length;
 
-use crate::v5::{commons::error::MQTTError, traits::utils::Utils};
+use crate::v5::{
+    commons::{error::MQTTError, packet_type::PacketType, violation::Violation},
+    traits::utils::Utils,
+};
 
 use super::{Property, ReadData};
 
@@ -37,7 +42,7 @@ impl ReadData for DisconnectProperties {
                     &mut props.server_reference,
                     v.as_deref().map(String::from),
                 )(property)?,
-                p => return Err(MQTTError::UnexpectedProperty(p.to_string(), "".to_string())),
+                _ => return Err(Violation::unexpected_property(PacketType::Disconnect).into()),
             }
 
             if data.is_empty() {
@@ -50,7 +55,7 @@ impl ReadData for DisconnectProperties {
 }
 
 mod syncx {
-    use std::borrow::Cow;
+    use alloc::borrow::Cow;
 
     use crate::v5::{
         commons::{error::MQTTError, property::Property},
@@ -80,8 +85,9 @@ mod syncx {
     }
 }
 
+#[cfg(feature = "asyncx")]
 mod asyncx {
-    use std::borrow::Cow;
+    use alloc::borrow::Cow;
 
     use crate::v5::{commons::property::Property, traits::streamio::StreamIO};
 
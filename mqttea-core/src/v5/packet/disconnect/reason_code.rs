@@ -1,5 +1,8 @@
 use mqttea_macros::FromU8;
 
+use crate::v5::commons::reason_code::packet_reason_code;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen::prelude::wasm_bindgen)]
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, FromU8)]
 pub enum DisconnectReasonCode {
@@ -23,7 +26,7 @@ pub enum DisconnectReasonCode {
     MessageRateTooHigh = 150,
     QuotaExceeded = 151,
     AdministrativeAction = 152,
-    PayloadFormatIndicator = 153,
+    PayloadFormatInvalid = 153,
     RetainNotSupported = 154,
     QoSNotSupported = 155,
     UseAnotherServer = 156,
@@ -31,6 +34,7 @@ pub enum DisconnectReasonCode {
     SharedSubscriptionsNotSupported = 158,
     ConnectionRateExceeded = 159,
     MaximumConnectTime = 160,
-    SubscriptionIdentifiers = 161,
+    SubscriptionIdentifiersNotSupported = 161,
     WildcardSubscriptionsNotSupported = 162,
-}
\ No newline at end of file
+}
+packet_reason_code!(DisconnectReasonCode, Disconnect);
@@ -1,5 +1,9 @@
 use crate::v5::{
-    commons::{fixed_header::FixedHeader, packet_type::PacketType},
+    commons::{
+        fixed_header::FixedHeader,
+        packet_type::PacketType,
+        violation::{Validate, Violation},
+    },
     traits::read_data::ReadData,
 };
 
@@ -17,6 +21,18 @@ pub(crate) use syncx::*;
 impl ReadData for PingReq {}
 impl ReadData for PingResp {}
 
+impl Validate for PingReq {
+    fn validate(&self) -> Result<(), Violation> {
+        Ok(())
+    }
+}
+
+impl Validate for PingResp {
+    fn validate(&self) -> Result<(), Violation> {
+        Ok(())
+    }
+}
+
 mod syncx {
     use crate::v5::{commons::error::MQTTError, traits::bufferio::BufferIO};
 
@@ -45,6 +61,7 @@ mod syncx {
     }
 }
 
+#[cfg(feature = "asyncx")]
 mod asyncx {
     use crate::v5::traits::streamio::StreamIO;
 
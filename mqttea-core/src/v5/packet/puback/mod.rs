@@ -2,15 +2,20 @@ mod properties;
 pub use properties::{PubAckProperties, PubAckReasonCode};
 
 use crate::v5::{
-    commons::{fixed_header::FixedHeader, packet_type::PacketType, property::Property},
+    commons::{
+        fixed_header::FixedHeader,
+        packet_type::PacketType,
+        property::Property,
+        violation::{ensure, Validate, Violation},
+    },
     traits::read_data::ReadData,
 };
 
 #[derive(Debug, Default, PartialEq, Eq)]
 pub struct PubAck {
-    pub(crate) pkid: u16,
-    pub(crate) reason_code: PubAckReasonCode,
-    pub(crate) properties: PubAckProperties,
+    pub pkid: u16,
+    pub reason_code: PubAckReasonCode,
+    pub properties: PubAckProperties,
 }
 
 #[cfg(feature = "asyncx")]
@@ -20,6 +25,18 @@ pub(crate) use syncx::*;
 
 impl ReadData for PubAck {}
 
+impl Validate for PubAck {
+    fn validate(&self) -> Result<(), Violation> {
+        ensure(
+            self.pkid != 0,
+            Violation::protocol(
+                "MQTT-2.2.1-5",
+                "PUBACK must contain the Packet Identifier of the PUBLISH packet",
+            ),
+        )
+    }
+}
+
 mod syncx {
     use crate::v5::{
         commons::error::MQTTError,
@@ -34,7 +51,7 @@ mod syncx {
     impl BufferIO for PubAck {
         /// Length of the Variable Header, encoded as Variable Byte Integer
         fn length(&self) -> usize {
-            let mut len = std::mem::size_of::<u16>(); // packet identifier
+            let mut len = core::mem::size_of::<u16>(); // packet identifier
 
             // only add reason code if there's no properties
             if self.reason_code == PubAckReasonCode::Success && self.properties.length() == 0 {
@@ -70,8 +87,7 @@ mod syncx {
                 return Ok(packet);
             }
 
-            packet.reason_code = PubAckReasonCode::try_from(u8::read(buf)?)
-                .map_err(|e| MQTTError::UnknownData(format!("Uknown reason code: {e}")))?;
+            packet.reason_code = PubAckReasonCode::decode(u8::read(buf)?)?;
             packet.properties = PubAckProperties::read(buf)?;
 
             Ok(packet)
@@ -79,6 +95,7 @@ mod syncx {
     }
 }
 
+#[cfg(feature = "asyncx")]
 mod asyncx {
     use crate::v5::{
         commons::error::MQTTError,
@@ -93,7 +110,7 @@ mod asyncx {
     impl StreamIO for PubAck {
         /// Length of the Variable Header, encoded as Variable Byte Integer
         fn length(&self) -> usize {
-            let mut len = std::mem::size_of::<u16>(); // packet identifier
+            let mut len = core::mem::size_of::<u16>(); // packet identifier
 
             // only add reason code if there's no properties
             if self.reason_code == PubAckReasonCode::Success && self.properties.length() == 0 {
@@ -139,8 +156,7 @@ mod asyncx {
                 return Ok(packet);
             }
 
-            packet.reason_code = PubAckReasonCode::try_from(u8::read(stream).await?)
-                .map_err(|e| MQTTError::UnknownData(format!("Uknown reason code: {e}")))?;
+            packet.reason_code = PubAckReasonCode::decode(u8::read(stream).await?)?;
             packet.properties = PubAckProperties::read(stream).await?;
 
             Ok(packet)
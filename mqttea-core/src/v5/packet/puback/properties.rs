@@ -1,13 +1,19 @@
+use alloc::{string::String, vec::Vec};
+
 use bytes::Bytes;
 use mqttea_macros::{FromU8, Length};
 
 use crate::v5::{
-    commons::error::MQTTError,
+    commons::{
+        error::MQTTError, packet_type::PacketType, reason_code::packet_reason_code,
+        violation::Violation,
+    },
     traits::{read_data::ReadData, utils::Utils},
 };
 
 use super::Property;
 
+#[cfg_attr(feature = "wasm", wasm_bindgen::prelude::wasm_bindgen)]
 #[repr(u8)]
 #[derive(Debug, PartialEq, Eq, Clone, Copy, FromU8, Default)]
 pub enum PubAckReasonCode {
@@ -22,6 +28,7 @@ pub enum PubAckReasonCode {
     QuotaExceeded = 151,
     PayloadFormatInvalid = 153,
 }
+packet_reason_code!(PubAckReasonCode, PubAck);
 
 #[cfg(feature = "asyncx")]
 pub(crate) use asyncx::*;
@@ -47,7 +54,7 @@ impl ReadData for PubAckProperties {
                     v.as_deref().map(String::from),
                 )(property)?,
                 Property::UserProperty(value) => props.user_property.push(value.into_owned()),
-                p => return Err(MQTTError::UnexpectedProperty(p.to_string(), "".to_string())),
+                _ => return Err(Violation::unexpected_property(PacketType::PubAck).into()),
             }
 
             if data.is_empty() {
@@ -60,7 +67,7 @@ impl ReadData for PubAckProperties {
 }
 
 mod syncx {
-    use std::borrow::Cow;
+    use alloc::borrow::Cow;
 
     use crate::v5::{
         commons::{error::MQTTError, property::Property},
@@ -86,8 +93,9 @@ mod syncx {
     }
 }
 
+#[cfg(feature = "asyncx")]
 mod asyncx {
-    use std::borrow::Cow;
+    use alloc::borrow::Cow;
 
     use crate::v5::{
         commons::{error::MQTTError, property::Property},
@@ -1,17 +1,22 @@
 mod properties;
 
-pub(crate) use properties::{PubCompProperties, PubCompReasonCode};
+pub use properties::{PubCompProperties, PubCompReasonCode};
 
 use crate::v5::{
-    commons::{fixed_header::FixedHeader, packet_type::PacketType, property::Property},
+    commons::{
+        fixed_header::FixedHeader,
+        packet_type::PacketType,
+        property::Property,
+        violation::{ensure, Validate, Violation},
+    },
     traits::read_data::ReadData,
 };
 
 #[derive(Debug, Default, PartialEq, Eq)]
 pub struct PubComp {
-    pub(crate) pkid: u16,
-    pub(crate) reason_code: PubCompReasonCode,
-    pub(crate) properties: PubCompProperties,
+    pub pkid: u16,
+    pub reason_code: PubCompReasonCode,
+    pub properties: PubCompProperties,
 }
 
 #[cfg(feature = "asyncx")]
@@ -21,6 +26,18 @@ pub(crate) use syncx::*;
 
 impl ReadData for PubComp {}
 
+impl Validate for PubComp {
+    fn validate(&self) -> Result<(), Violation> {
+        ensure(
+            self.pkid != 0,
+            Violation::protocol(
+                "MQTT-2.2.1-5",
+                "PUBCOMP must contain the Packet Identifier of the PUBLISH packet",
+            ),
+        )
+    }
+}
+
 mod syncx {
     use crate::v5::{
         commons::error::MQTTError,
@@ -37,7 +54,7 @@ mod syncx {
 
     impl BufferIO for PubComp {
         fn length(&self) -> usize {
-            let mut len = std::mem::size_of::<u16>(); // packet identifier
+            let mut len = core::mem::size_of::<u16>(); // packet identifier
             if self.reason_code == PubCompReasonCode::Success && self.properties.length() == 0 {
                 return len;
             }
@@ -70,8 +87,7 @@ mod syncx {
                 return Ok(packet);
             }
 
-            packet.reason_code = PubCompReasonCode::try_from(u8::read(buf)?)
-                .map_err(|e| MQTTError::UnknownData(e))?;
+            packet.reason_code = PubCompReasonCode::decode(u8::read(buf)?)?;
             packet.properties = PubCompProperties::read(buf)?;
 
             Ok(packet)
@@ -79,6 +95,7 @@ mod syncx {
     }
 }
 
+#[cfg(feature = "asyncx")]
 mod asyncx {
     use crate::v5::{
         commons::error::MQTTError,
@@ -95,7 +112,7 @@ mod asyncx {
 
     impl StreamIO for PubComp {
         fn length(&self) -> usize {
-            let mut len = std::mem::size_of::<u16>(); // packet identifier
+            let mut len = core::mem::size_of::<u16>(); // packet identifier
             if self.reason_code == PubCompReasonCode::Success && self.properties.length() == 0 {
                 return len;
             }
@@ -138,8 +155,7 @@ mod asyncx {
                 return Ok(packet);
             }
 
-            packet.reason_code = PubCompReasonCode::try_from(u8::read(stream).await?)
-                .map_err(|e| MQTTError::UnknownData(e))?;
+            packet.reason_code = PubCompReasonCode::decode(u8::read(stream).await?)?;
             packet.properties = PubCompProperties::read(stream).await?;
 
             Ok(packet)
@@ -1,16 +1,26 @@
+use alloc::{string::String, vec::Vec};
+
 use bytes::Bytes;
 use mqttea_macros::{FromU8, Length};
 
-use crate::v5::{commons::error::MQTTError, traits::utils::Utils};
+use crate::v5::{
+    commons::{
+        error::MQTTError, packet_type::PacketType, reason_code::packet_reason_code,
+        violation::Violation,
+    },
+    traits::utils::Utils,
+};
 
 use super::{Property, ReadData};
 
+#[cfg_attr(feature = "wasm", wasm_bindgen::prelude::wasm_bindgen)]
 #[derive(Debug, PartialEq, Eq, Default, FromU8, Clone, Copy)]
-pub(crate) enum PubCompReasonCode {
+pub enum PubCompReasonCode {
     #[default]
     Success = 0,
     PacketIdentifierNotFound = 146,
 }
+packet_reason_code!(PubCompReasonCode, PubComp);
 
 #[derive(Debug, Length, Default, PartialEq, Eq, Clone)]
 pub struct PubCompProperties {
@@ -31,7 +41,7 @@ impl ReadData for PubCompProperties {
                     v.as_deref().map(String::from),
                 )(property)?,
                 Property::UserProperty(v) => props.user_property.push(v.into_owned()),
-                p => return Err(MQTTError::UnexpectedProperty(p.to_string(), "".to_string())),
+                _ => return Err(Violation::unexpected_property(PacketType::PubComp).into()),
             };
 
             if data.is_empty() {
@@ -44,7 +54,7 @@ impl ReadData for PubCompProperties {
 }
 
 mod syncx {
-    use std::borrow::Cow;
+    use alloc::borrow::Cow;
 
     use crate::v5::{
         commons::{error::MQTTError, property::Property},
@@ -71,8 +81,9 @@ mod syncx {
     }
 }
 
+#[cfg(feature = "asyncx")]
 mod asyncx {
-    use std::borrow::Cow;
+    use alloc::borrow::Cow;
 
     use crate::v5::{
         commons::{error::MQTTError, property::Property},
@@ -1,11 +1,20 @@
 mod properties;
+use alloc::string::String;
+
 pub use properties::PublishProperties;
 
 use bytes::Bytes;
 
 use crate::v5::{
-    commons::{fixed_header::FixedHeader, packet_type::PacketType, qos::QoS},
+    commons::{
+        fixed_header::FixedHeader,
+        packet_type::PacketType,
+        qos::QoS,
+        violation::{ensure, validate_payload_format, Validate, Violation},
+    },
+    packet::disconnect::DisconnectReasonCode,
     traits::read_data::ReadData,
+    utils::topic::validate_topic_name,
 };
 
 #[derive(Debug, Default, PartialEq, Clone, Eq)]
@@ -26,7 +35,61 @@ pub(crate) use syncx::*;
 
 impl ReadData for Publish {}
 
+impl Validate for Publish {
+    fn validate(&self) -> Result<(), Violation> {
+        ensure(
+            self.qos != QoS::Zero || !self.dup,
+            Violation::protocol("MQTT-3.3.1-2", "DUP flag must be 0 for QoS 0 messages"),
+        )?;
+
+        match (self.qos, self.pkid) {
+            (QoS::Zero, Some(_)) => Err(Violation::protocol(
+                "MQTT-2.2.1-2",
+                "PUBLISH with QoS 0 must not contain a Packet Identifier",
+            )),
+            (QoS::One | QoS::Two, None | Some(0)) => Err(Violation::protocol(
+                "MQTT-2.2.1-3",
+                "PUBLISH with QoS > 0 must have a non-zero Packet Identifier",
+            )),
+            _ => Ok(()),
+        }?;
+
+        ensure(
+            !self.topic.is_empty() || self.properties.topic_alias.is_some(),
+            Violation::protocol(
+                "3.3.2.3.4",
+                "Topic Name must not be empty when there is no Topic Alias",
+            ),
+        )?;
+        validate_topic_name(&self.topic, "MQTT-3.3.2-2")?;
+        ensure(
+            self.properties.topic_alias != Some(0),
+            Violation::new(
+                "MQTT-3.3.2-8",
+                DisconnectReasonCode::TopicAliasInvalid,
+                "Topic Alias must not be 0",
+            ),
+        )?;
+
+        if let Some(topic) = &self.properties.response_topic {
+            validate_topic_name(topic, "MQTT-3.3.2-14")?;
+        }
+        ensure(
+            !self.properties.subscription_identifier.contains(&0),
+            Violation::protocol("3.3.2.3.8", "Subscription Identifier must not be 0"),
+        )?;
+
+        validate_payload_format(
+            self.properties.payload_format_indicator,
+            &self.payload,
+            "3.3.2.3.2",
+        )
+    }
+}
+
 mod syncx {
+    use alloc::string::String;
+
     use bytes::Bytes;
 
     use crate::v5::{
@@ -39,20 +102,10 @@ mod syncx {
 
     use super::{FixedHeader, PacketType, Publish, PublishProperties};
 
-    impl BufferIO for Publish {
-        /// variable header, length of the payload, encoded as Variable Byte Integer
-        fn length(&self) -> usize {
-            let mut len = if self.qos != QoS::Zero {
-                self.topic.len() + 2
-            } else {
-                0
-            };
-            len +=
-                self.properties.length() + self.properties.variable_length() + self.payload.len();
-            len
-        }
-
-        fn write(&self, buf: &mut bytes::BytesMut) -> Result<(), MQTTError> {
+    impl Publish {
+        /// Writes everything that precedes the payload (fixed header and variable header).
+        /// This allows the payload to be handed to the stream as-is, without copying it into the same buffer
+        pub(crate) fn write_head(&self, buf: &mut bytes::BytesMut) -> Result<(), MQTTError> {
             FixedHeader::new(
                 PacketType::Publish,
                 (self.dup as u8) << 3 | (self.qos as u8) << 1 | (self.retain as u8),
@@ -69,7 +122,25 @@ mod syncx {
             }
 
             self.properties.write(buf)?;
-            self.payload.write(buf);
+            Ok(())
+        }
+    }
+
+    impl BufferIO for Publish {
+        /// variable header, length of the payload, encoded as Variable Byte Integer
+        fn length(&self) -> usize {
+            let mut len = self.topic.len() + 2;
+            if self.qos != QoS::Zero {
+                len += 2; // packet identifier
+            }
+            len +=
+                self.properties.length() + self.properties.variable_length() + self.payload.len();
+            len
+        }
+
+        fn write(&self, buf: &mut bytes::BytesMut) -> Result<(), MQTTError> {
+            self.write_head(buf)?;
+            buf.extend_from_slice(&self.payload);
             Ok(())
         }
 
@@ -89,12 +160,14 @@ mod syncx {
             }
 
             packet.properties = PublishProperties::read(buf)?;
-            packet.payload = Bytes::read(buf)?;
+            // the payload isn't length prefixed, it is whatever remains of the packet (3.3.3)
+            packet.payload = buf.split_to(buf.len());
             Ok(packet)
         }
     }
 }
 
+#[cfg(feature = "asyncx")]
 mod asyncx {
     use bytes::Bytes;
 
@@ -102,7 +175,7 @@ mod asyncx {
         commons::{error::MQTTError, qos::QoS},
         traits::{
             asyncx::{read::Read, write::Write},
-            streamio::StreamIO,
+            streamio::{read_exactly, StreamIO},
         },
     };
 
@@ -110,11 +183,10 @@ mod asyncx {
     impl StreamIO for Publish {
         /// variable header, length of the payload, encoded as Variable Byte Integer
         fn length(&self) -> usize {
-            let mut len = if self.qos != QoS::Zero {
-                self.topic.len() + 2
-            } else {
-                0
-            };
+            let mut len = self.topic.len() + 2;
+            if self.qos != QoS::Zero {
+                len += 2; // packet identifier
+            }
             len +=
                 self.properties.length() + self.properties.variable_length() + self.payload.len();
             len
@@ -142,7 +214,7 @@ mod asyncx {
             }
 
             self.properties.write(stream).await?;
-            self.payload.to_vec().write(stream).await?;
+            stream.write_all(&self.payload).await?;
             Ok(())
         }
 
@@ -172,7 +244,13 @@ mod asyncx {
             }
 
             packet.properties = PublishProperties::read(stream).await?;
-            packet.payload = Bytes::from_iter(Vec::read(stream).await?);
+
+            // the payload isn't length prefixed, it is whatever remains of the packet (3.3.3)
+            let payload_len = header
+                .remaining_length
+                .checked_sub(packet.length())
+                .ok_or(MQTTError::MalformedPacket)?;
+            packet.payload = Bytes::from(read_exactly(stream, payload_len).await?);
 
             Ok(packet)
         }
@@ -182,13 +260,11 @@ mod asyncx {
 #[cfg(test)]
 mod syncx_tests {
     use super::*;
-    use crate::retest_utils::initialize_pid;
     use crate::v5::traits::bufferio::BufferIO;
     use bytes::BytesMut;
 
     #[test]
     fn read_write_publish() {
-        initialize_pid();
         let packet = Publish {
             dup: true,
             retain: true,
@@ -206,12 +282,11 @@ mod syncx_tests {
         let mut buf = BytesMut::new();
         packet.write(&mut buf).unwrap();
 
-        let expected =
-            b";)\0\x10packagin_plant/#\"\xe2\x05\x01\r#\0\x02\0\x11veryLarge payload".to_vec();
+        let expected = b";+\0\x10packagin_plant/#\"\xe2\x05\x01\r#\0\x02veryLarge payload".to_vec();
         assert_eq!(buf.to_vec(), expected);
 
         let mut expected = Bytes::from_iter(
-            b";)\0\x10packagin_plant/#\"\xe2\x05\x01\r#\0\x02\0\x11veryLarge payload".to_vec()[2..]
+            b";+\0\x10packagin_plant/#\"\xe2\x05\x01\r#\0\x02veryLarge payload".to_vec()[2..]
                 .to_vec(),
         );
         let created_packed = Publish::read_with_fixedheader(
@@ -1,8 +1,12 @@
+use alloc::{string::String, vec::Vec};
+
 use bytes::Bytes;
 use mqttea_macros::Length;
 
 use crate::v5::{
-    commons::{error::MQTTError, property::Property},
+    commons::{
+        error::MQTTError, packet_type::PacketType, property::Property, violation::Violation,
+    },
     traits::{read_data::ReadData, utils::Utils},
 };
 
@@ -58,7 +62,7 @@ impl ReadData for PublishProperties {
                     &mut props.content_type,
                     v.as_deref().map(String::from),
                 )(property)?,
-                p => return Err(MQTTError::UnexpectedProperty(p.to_string(), "".to_string())),
+                _ => return Err(Violation::unexpected_property(PacketType::Publish).into()),
             }
 
             if data.is_empty() {
@@ -71,7 +75,7 @@ impl ReadData for PublishProperties {
 }
 
 mod syncx {
-    use std::borrow::Cow;
+    use alloc::borrow::Cow;
 
     use crate::v5::{
         commons::{error::MQTTError, property::Property},
@@ -108,8 +112,9 @@ mod syncx {
     }
 }
 
+#[cfg(feature = "asyncx")]
 mod asyncx {
-    use std::borrow::Cow;
+    use alloc::borrow::Cow;
 
     use crate::v5::{commons::property::Property, traits::streamio::StreamIO};
 
@@ -1,16 +1,20 @@
 pub mod properties;
-use properties::{PubRecProperties, PubRecReasonCode};
+pub use properties::{PubRecProperties, PubRecReasonCode};
 
 use crate::v5::{
-    commons::{fixed_header::FixedHeader, packet_type::PacketType},
+    commons::{
+        fixed_header::FixedHeader,
+        packet_type::PacketType,
+        violation::{ensure, Validate, Violation},
+    },
     traits::read_data::ReadData,
 };
 
 #[derive(Debug, PartialEq, Eq, Default)]
 pub struct PubRec {
-    pub(crate) pkid: u16,
-    pub(crate) reason_code: PubRecReasonCode,
-    pub(crate) properties: PubRecProperties,
+    pub pkid: u16,
+    pub reason_code: PubRecReasonCode,
+    pub properties: PubRecProperties,
 }
 
 #[cfg(feature = "asyncx")]
@@ -20,6 +24,18 @@ pub(crate) use syncx::*;
 
 impl ReadData for PubRec {}
 
+impl Validate for PubRec {
+    fn validate(&self) -> Result<(), Violation> {
+        ensure(
+            self.pkid != 0,
+            Violation::protocol(
+                "MQTT-2.2.1-5",
+                "PUBREC must contain the Packet Identifier of the PUBLISH packet",
+            ),
+        )
+    }
+}
+
 mod syncx {
     use crate::v5::traits::syncx::{read::Read, write::Write};
     use crate::v5::{commons::error::MQTTError, traits::bufferio::BufferIO};
@@ -30,7 +46,7 @@ mod syncx {
     impl BufferIO for PubRec {
         // length of the variable header, encoded as a variable byte integer
         fn length(&self) -> usize {
-            let mut len = std::mem::size_of::<u16>(); // packet identifier
+            let mut len = core::mem::size_of::<u16>(); // packet identifier
 
             if self.reason_code == PubRecReasonCode::Success && self.properties.length() == 0 {
                 return len;
@@ -65,8 +81,7 @@ mod syncx {
                 return Ok(packet);
             }
 
-            packet.reason_code = PubRecReasonCode::try_from(u8::read(buf)?)
-                .map_err(|e| MQTTError::UnknownData(e))?;
+            packet.reason_code = PubRecReasonCode::decode(u8::read(buf)?)?;
             packet.properties = PubRecProperties::read(buf)?;
 
             Ok(packet)
@@ -74,6 +89,7 @@ mod syncx {
     }
 }
 
+#[cfg(feature = "asyncx")]
 mod asyncx {
     use crate::v5::{
         commons::error::MQTTError,
@@ -91,7 +107,7 @@ mod asyncx {
     impl StreamIO for PubRec {
         // length of the variable header, encoded as a variable byte integer
         fn length(&self) -> usize {
-            let mut len = std::mem::size_of::<u16>(); // packet identifier
+            let mut len = core::mem::size_of::<u16>(); // packet identifier
 
             if self.reason_code == PubRecReasonCode::Success && self.properties.length() == 0 {
                 return len;
@@ -136,8 +152,7 @@ mod asyncx {
                 return Ok(packet);
             }
 
-            packet.reason_code = PubRecReasonCode::try_from(u8::read(stream).await?)
-                .map_err(|e| MQTTError::UnknownData(e))?;
+            packet.reason_code = PubRecReasonCode::decode(u8::read(stream).await?)?;
             packet.properties = PubRecProperties::read(stream).await?;
 
             Ok(packet)
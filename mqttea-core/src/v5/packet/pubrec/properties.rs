@@ -1,13 +1,19 @@
+use alloc::{string::String, vec::Vec};
+
 use bytes::Bytes;
 use mqttea_macros::{FromU8, Length};
 
 use crate::v5::{
-    commons::{error::MQTTError, property::Property},
+    commons::{
+        error::MQTTError, packet_type::PacketType, property::Property,
+        reason_code::packet_reason_code, violation::Violation,
+    },
     traits::utils::Utils,
 };
 
 use super::ReadData;
 
+#[cfg_attr(feature = "wasm", wasm_bindgen::prelude::wasm_bindgen)]
 #[repr(u8)]
 #[derive(Debug, PartialEq, Eq, Clone, Copy, FromU8, Default)]
 pub enum PubRecReasonCode {
@@ -20,8 +26,9 @@ pub enum PubRecReasonCode {
     TopicNameInvalid = 144,
     PacketIdentifierInUse = 145,
     QuotaExceeded = 151,
-    PayloadFormatIndicator = 153,
+    PayloadFormatInvalid = 153,
 }
+packet_reason_code!(PubRecReasonCode, PubRec);
 
 #[derive(Debug, Length, Default, PartialEq, Eq)]
 pub struct PubRecProperties {
@@ -47,7 +54,7 @@ impl ReadData for PubRecProperties {
                     v.as_deref().map(String::from),
                 )(property)?,
                 Property::UserProperty(value) => props.user_property.push(value.into_owned()),
-                p => return Err(MQTTError::UnexpectedProperty(p.to_string(), "".to_string())),
+                _ => return Err(Violation::unexpected_property(PacketType::PubRec).into()),
             };
 
             if data.is_empty() {
@@ -60,7 +67,7 @@ impl ReadData for PubRecProperties {
 }
 
 mod syncx {
-    use std::borrow::Cow;
+    use alloc::borrow::Cow;
 
     use crate::v5::{
         commons::{error::MQTTError, property::Property},
@@ -87,8 +94,9 @@ mod syncx {
     }
 }
 
+#[cfg(feature = "asyncx")]
 mod asyncx {
-    use std::borrow::Cow;
+    use alloc::borrow::Cow;
 
     use crate::v5::{commons::property::Property, traits::streamio::StreamIO};
 
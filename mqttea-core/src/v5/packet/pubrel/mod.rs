@@ -2,15 +2,20 @@ mod properties;
 pub use properties::{PubRelProperties, PubRelReasonCode};
 
 use crate::v5::{
-    commons::{fixed_header::FixedHeader, packet_type::PacketType, property::Property},
+    commons::{
+        fixed_header::FixedHeader,
+        packet_type::PacketType,
+        property::Property,
+        violation::{ensure, Validate, Violation},
+    },
     traits::read_data::ReadData,
 };
 
 #[derive(Debug, Default, PartialEq, Eq)]
 pub struct PubRel {
-    pub(crate) pkid: u16,
-    pub(crate) reason_code: PubRelReasonCode,
-    pub(crate) properties: PubRelProperties,
+    pub pkid: u16,
+    pub reason_code: PubRelReasonCode,
+    pub properties: PubRelProperties,
 }
 
 #[cfg(feature = "asyncx")]
@@ -19,6 +24,18 @@ pub(crate) use asyncx::*;
 pub(crate) use syncx::*;
 
 impl ReadData for PubRel {}
+
+impl Validate for PubRel {
+    fn validate(&self) -> Result<(), Violation> {
+        ensure(
+            self.pkid != 0,
+            Violation::protocol(
+                "MQTT-2.2.1-5",
+                "PUBREL must contain the Packet Identifier of the PUBLISH packet",
+            ),
+        )
+    }
+}
 mod syncx {
     use crate::v5::{
         commons::error::MQTTError,
@@ -32,7 +49,7 @@ mod syncx {
 
     impl BufferIO for PubRel {
         fn length(&self) -> usize {
-            let mut len = std::mem::size_of::<u16>(); // packet identifier
+            let mut len = core::mem::size_of::<u16>(); // packet identifier
             if self.reason_code == PubRelReasonCode::Success && self.properties.length() == 0 {
                 return len;
             }
@@ -64,8 +81,7 @@ mod syncx {
                 return Ok(packet);
             }
 
-            packet.reason_code = PubRelReasonCode::try_from(u8::read(buf)?)
-                .map_err(|e| MQTTError::UnknownData(e))?;
+            packet.reason_code = PubRelReasonCode::decode(u8::read(buf)?)?;
             packet.properties = PubRelProperties::read(buf)?;
 
             Ok(packet)
@@ -73,6 +89,7 @@ mod syncx {
     }
 }
 
+#[cfg(feature = "asyncx")]
 mod asyncx {
     use crate::v5::{
         commons::error::MQTTError,
@@ -86,7 +103,7 @@ mod asyncx {
 
     impl StreamIO for PubRel {
         fn length(&self) -> usize {
-            let mut len = std::mem::size_of::<u16>(); // packet identifier
+            let mut len = core::mem::size_of::<u16>(); // packet identifier
             if self.reason_code == PubRelReasonCode::Success && self.properties.length() == 0 {
                 return len;
             }
@@ -128,8 +145,7 @@ mod asyncx {
                 return Ok(packet);
             }
 
-            packet.reason_code = PubRelReasonCode::try_from(u8::read(stream).await?)
-                .map_err(|e| MQTTError::UnknownData(e))?;
+            packet.reason_code = PubRelReasonCode::decode(u8::read(stream).await?)?;
             packet.properties = PubRelProperties::read(stream).await?;
 
             Ok(packet)
@@ -1,16 +1,26 @@
+use alloc::{string::String, vec::Vec};
+
 use bytes::Bytes;
 use mqttea_macros::{FromU8, Length};
 
-use crate::v5::{commons::error::MQTTError, traits::utils::Utils};
+use crate::v5::{
+    commons::{
+        error::MQTTError, packet_type::PacketType, reason_code::packet_reason_code,
+        violation::Violation,
+    },
+    traits::utils::Utils,
+};
 
 use super::{Property, ReadData};
 
+#[cfg_attr(feature = "wasm", wasm_bindgen::prelude::wasm_bindgen)]
 #[derive(Debug, PartialEq, Eq, Clone, Copy, FromU8, Default)]
 pub enum PubRelReasonCode {
     #[default]
     Success = 0,
     PacketIdentifierNotFound = 146,
 }
+packet_reason_code!(PubRelReasonCode, PubRel);
 
 #[cfg(feature = "asyncx")]
 pub(crate) use asyncx::*;
@@ -36,7 +46,7 @@ impl ReadData for PubRelProperties {
                     v.as_deref().map(String::from),
                 )(property)?,
                 Property::UserProperty(value) => props.user_property.push(value.into_owned()),
-                p => return Err(MQTTError::UnexpectedProperty(p.to_string(), "".to_string())),
+                _ => return Err(Violation::unexpected_property(PacketType::PubRel).into()),
             };
 
             if data.is_empty() {
@@ -49,7 +59,7 @@ impl ReadData for PubRelProperties {
 }
 
 mod syncx {
-    use std::borrow::Cow;
+    use alloc::borrow::Cow;
 
     use crate::v5::{
         commons::{error::MQTTError, property::Property},
@@ -75,8 +85,9 @@ mod syncx {
     }
 }
 
+#[cfg(feature = "asyncx")]
 mod asyncx {
-    use std::borrow::Cow;
+    use alloc::borrow::Cow;
 
     use crate::v5::{commons::property::Property, traits::streamio::StreamIO};
 
@@ -1,10 +1,16 @@
 mod properties;
 mod reason_code;
-use properties::SubAckProperties;
+use alloc::vec::Vec;
+
+pub use properties::SubAckProperties;
 pub use reason_code::SubAckReasonCode;
 
 use crate::v5::{
-    commons::{fixed_header::FixedHeader, packet_type::PacketType},
+    commons::{
+        fixed_header::FixedHeader,
+        packet_type::PacketType,
+        violation::{ensure, Validate, Violation},
+    },
     traits::read_data::ReadData,
 };
 
@@ -23,6 +29,25 @@ pub(crate) use syncx::*;
 
 impl ReadData for SubAck {}
 
+impl Validate for SubAck {
+    fn validate(&self) -> Result<(), Violation> {
+        ensure(
+            self.pkid != 0,
+            Violation::protocol(
+                "MQTT-2.2.1-6",
+                "SUBACK must contain the Packet Identifier of the SUBSCRIBE packet",
+            ),
+        )?;
+        ensure(
+            !self.payload.is_empty(),
+            Violation::protocol(
+                "MQTT-3.8.4-6",
+                "SUBACK must contain a Reason Code for each Topic Filter",
+            ),
+        )
+    }
+}
+
 mod syncx {
     use crate::v5::{
         commons::error::MQTTError,
@@ -62,9 +87,9 @@ mod syncx {
                 if buf.is_empty() {
                     break;
                 }
-                packet.payload.push(
-                    SubAckReasonCode::try_from(u8::read(buf)?).map_err(MQTTError::UnknownData)?,
-                );
+                packet
+                    .payload
+                    .push(SubAckReasonCode::decode(u8::read(buf)?)?);
             }
 
             Ok(packet)
@@ -72,6 +97,7 @@ mod syncx {
     }
 }
 
+#[cfg(feature = "asyncx")]
 mod asyncx {
     use crate::v5::{
         commons::error::MQTTError,
@@ -107,7 +133,10 @@ mod asyncx {
             Ok(())
         }
 
-        async fn read<R>(stream: &mut R) -> Result<Self, crate::v5::commons::error::MQTTError>
+        async fn read_with_fixedheader<R>(
+            stream: &mut R,
+            header: &FixedHeader,
+        ) -> Result<Self, crate::v5::commons::error::MQTTError>
         where
             R: futures::AsyncReadExt + Unpin,
             Self: Default,
@@ -116,8 +145,15 @@ mod asyncx {
             packet.pkid = u16::read(stream).await?;
             packet.properties = SubAckProperties::read(stream).await?;
 
-            while let Ok(value) = u8::read(stream).await {
-                SubAckReasonCode::try_from(value).map_err(MQTTError::UnknownData)?;
+            // one Reason Code per byte left in the packet
+            let payload_len = header
+                .remaining_length
+                .checked_sub(packet.length())
+                .ok_or(MQTTError::MalformedPacket)?;
+            for _ in 0..payload_len {
+                packet
+                    .payload
+                    .push(SubAckReasonCode::decode(u8::read(stream).await?)?);
             }
 
             Ok(packet)
@@ -154,7 +190,7 @@ mod asyncx {
 //             SubAckReasonCode::GrantedQoS2,
 //             SubAckReasonCode::QuotaExceeded,
 //             SubAckReasonCode::UnspecifiedError,
-//             SubAckReasonCode::NotAuhtorized,
+//             SubAckReasonCode::NotAuthorized,
 //         ];
 //         packet.properties = SubAckProperties {
 //             reason_string: Some("googoogReason".into()),
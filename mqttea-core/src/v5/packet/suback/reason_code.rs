@@ -1,10 +1,13 @@
 use mqttea_macros::FromU8;
 
+use crate::v5::commons::reason_code::packet_reason_code;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen::prelude::wasm_bindgen)]
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, FromU8, PartialEq, Eq)]
 pub enum SubAckReasonCode {
-    /// The subscription is accepted and the maximum Qos sent will be QoS1 (This might be lower than requested)
-    GrantedQos0 = 0,
+    /// The Subscription is accepted and the maximum QoS sent will be QoS 0 (This might be lower than requested)
+    GrantedQoS0 = 0,
     /// The Subscription is accepted and the maximum QoS sent will be QoS1 (This might be lower than requested)
     GrantedQoS1 = 1,
     /// The subscription is accepted and any received QoS will be sent to this subscription
@@ -12,11 +15,12 @@ pub enum SubAckReasonCode {
     UnspecifiedError = 128,
     /// Subscribe packet is valid, but the server does not accept it
     ImplementationSpecificError = 131,
-    NotAuhtorized = 135,
+    NotAuthorized = 135,
     TopicFilterInvalid = 143,
     PacketIdentifierInUse = 145,
     QuotaExceeded = 151,
-    SharedSubscriptionNotSupported = 158,
+    SharedSubscriptionsNotSupported = 158,
     SubscriptionIdentifiersNotSupported = 161,
-    WildCardSubscriptionNotSupported = 162,
+    WildcardSubscriptionsNotSupported = 162,
 }
+packet_reason_code!(SubAckReasonCode, SubAck);
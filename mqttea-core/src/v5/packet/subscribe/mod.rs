@@ -1,12 +1,20 @@
 mod options;
 mod properties;
 
-pub use options::SubscriptionOptions;
+use alloc::{string::String, vec::Vec};
+
+pub use options::{RetainHandling, SubscriptionOptions};
 pub use properties::SubscribeProperties;
 
 use crate::v5::{
-    commons::{fixed_header::FixedHeader, packet_type::PacketType, property::Property},
+    commons::{
+        fixed_header::FixedHeader,
+        packet_type::PacketType,
+        property::Property,
+        violation::{ensure, Validate, Violation},
+    },
     traits::read_data::ReadData,
+    utils::topic::{is_shared_subscription, validate_topic_filter},
 };
 
 #[cfg(feature = "asyncx")]
@@ -24,7 +32,45 @@ pub struct Subscribe {
 
 impl ReadData for Subscribe {}
 
+impl Validate for Subscribe {
+    fn validate(&self) -> Result<(), Violation> {
+        ensure(
+            self.pkid != 0,
+            Violation::protocol(
+                "MQTT-2.2.1-3",
+                "SUBSCRIBE must have a non-zero Packet Identifier",
+            ),
+        )?;
+        ensure(
+            self.properties.subscription_id != Some(0),
+            Violation::protocol("3.8.2.1.2", "Subscription Identifier must not be 0"),
+        )?;
+        ensure(
+            !self.payload.is_empty(),
+            Violation::protocol(
+                "MQTT-3.8.3-2",
+                "SUBSCRIBE must contain at least one Topic Filter",
+            ),
+        )?;
+
+        for (filter, options) in &self.payload {
+            validate_topic_filter(filter)?;
+            ensure(
+                !(options.no_local && is_shared_subscription(filter)),
+                Violation::protocol(
+                    "MQTT-3.8.3-4",
+                    "No Local must not be set on a Shared Subscription",
+                ),
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
 mod syncx {
+    use alloc::string::String;
+
     use bytes::Bytes;
 
     use crate::v5::{
@@ -92,6 +138,7 @@ mod syncx {
     }
 }
 
+#[cfg(feature = "asyncx")]
 mod asyncx {
     use crate::v5::{
         commons::error::MQTTError,
@@ -138,7 +185,10 @@ mod asyncx {
             Ok(())
         }
 
-        async fn read<R>(stream: &mut R) -> Result<Self, MQTTError>
+        async fn read_with_fixedheader<R>(
+            stream: &mut R,
+            header: &FixedHeader,
+        ) -> Result<Self, MQTTError>
         where
             R: futures::AsyncReadExt + Unpin,
             Self: Default,
@@ -148,10 +198,14 @@ mod asyncx {
 
             packet.properties = SubscribeProperties::read(stream).await?;
 
-            while let Ok(topic) = String::read(stream).await {
+            while packet.length() < header.remaining_length {
+                let topic = String::read(stream).await?;
                 let options = SubscriptionOptions::read(stream).await?;
                 packet.payload.push((topic, options));
             }
+            if packet.length() != header.remaining_length {
+                return Err(MQTTError::MalformedPacket);
+            }
 
             if packet.payload.len() == 0 {
                 return Err(MQTTError::ProtocolError(
@@ -1,5 +1,6 @@
 use crate::v5::commons::error::MQTTError;
 use crate::v5::commons::qos::QoS;
+use crate::v5::commons::violation::{ensure, Violation};
 
 use super::ReadData;
 
@@ -31,11 +32,29 @@ impl TryFrom<u8> for SubscriptionOptions {
     type Error = MQTTError;
 
     fn try_from(byte: u8) -> Result<Self, Self::Error> {
+        ensure(
+            byte & 0b1100_0000 == 0,
+            Violation::malformed(
+                "MQTT-3.8.3-5",
+                "Reserved Subscription Options bits must be 0",
+            ),
+        )?;
+
         let qos = byte & 0b0000_0011;
+        ensure(
+            qos != 3,
+            Violation::malformed("3.8.3.1", "Maximum QoS must not be 3"),
+        )?;
         let qos = QoS::try_from(qos).map_err(|_| MQTTError::UnsupportedQoS(qos))?;
         let no_local = (byte & 0b0000_0100) != 0;
         let retain_as_published = (byte & 0b0000_1000) != 0;
-        let retain_handling = RetainHandling::try_from(byte & 0b0011_0000)?;
+
+        let retain_handling = (byte & 0b0011_0000) >> 4;
+        ensure(
+            retain_handling != 3,
+            Violation::protocol("3.8.3.1", "Retain Handling must not be 3"),
+        )?;
+        let retain_handling = RetainHandling::try_from(retain_handling)?;
 
         Ok(Self {
             qos,
@@ -75,6 +94,7 @@ mod syncx {
     }
 }
 
+#[cfg(feature = "asyncx")]
 mod asyncx {
     use crate::v5::traits::{
         asyncx::{read::Read, write::Write},
@@ -106,6 +126,7 @@ mod asyncx {
     }
 }
 
+#[cfg_attr(feature = "wasm", wasm_bindgen::prelude::wasm_bindgen)]
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum RetainHandling {
@@ -1,9 +1,13 @@
-use std::ops::Deref;
+use alloc::{string::String, vec::Vec};
+use core::ops::Deref;
 
 use bytes::Bytes;
 use mqttea_macros::Length;
 
-use crate::v5::{commons::error::MQTTError, traits::utils::Utils};
+use crate::v5::{
+    commons::{error::MQTTError, packet_type::PacketType, violation::Violation},
+    traits::utils::Utils,
+};
 
 use super::{Property, ReadData};
 
@@ -30,7 +34,7 @@ impl ReadData for SubscribeProperties {
                     Self::try_update(&mut props.subscription_id, Some(*v.deref()))(property)?
                 }
                 Property::UserProperty(v) => props.user_property.push(v.into_owned()),
-                p => return Err(MQTTError::UnexpectedProperty(p.to_string(), "".to_string())),
+                _ => return Err(Violation::unexpected_property(PacketType::Subscribe).into()),
             }
 
             if data.is_empty() {
@@ -42,7 +46,7 @@ impl ReadData for SubscribeProperties {
 }
 
 mod syncx {
-    use std::borrow::Cow;
+    use alloc::borrow::Cow;
 
     use crate::v5::{
         commons::{error::MQTTError, property::Property},
@@ -71,8 +75,9 @@ mod syncx {
     }
 }
 
+#[cfg(feature = "asyncx")]
 mod asyncx {
-    use std::borrow::Cow;
+    use alloc::borrow::Cow;
 
     use crate::v5::{commons::property::Property, traits::streamio::StreamIO};
 
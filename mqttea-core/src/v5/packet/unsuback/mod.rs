@@ -1,11 +1,18 @@
 mod properties;
 mod reason_code;
 
-use properties::UnSubAckProperties;
+use alloc::vec::Vec;
+
+pub use properties::UnSubAckProperties;
 pub use reason_code::UnSubAckReasonCode;
 
 use crate::v5::{
-    commons::{fixed_header::FixedHeader, packet_type::PacketType, property::Property},
+    commons::{
+        fixed_header::FixedHeader,
+        packet_type::PacketType,
+        property::Property,
+        violation::{ensure, Validate, Violation},
+    },
     traits::read_data::ReadData,
 };
 
@@ -25,6 +32,25 @@ pub(crate) use syncx::*;
 
 impl ReadData for UnSubAck {}
 
+impl Validate for UnSubAck {
+    fn validate(&self) -> Result<(), Violation> {
+        ensure(
+            self.pkid != 0,
+            Violation::protocol(
+                "MQTT-2.2.1-6",
+                "UNSUBACK must contain the Packet Identifier of the UNSUBSCRIBE packet",
+            ),
+        )?;
+        ensure(
+            !self.payload.is_empty(),
+            Violation::protocol(
+                "MQTT-3.10.4-5",
+                "UNSUBACK must contain a Reason Code for each Topic Filter",
+            ),
+        )
+    }
+}
+
 mod syncx {
     use crate::v5::{
         commons::error::MQTTError,
@@ -66,9 +92,9 @@ mod syncx {
                 if buf.is_empty() {
                     break;
                 }
-                packet.payload.push(
-                    UnSubAckReasonCode::try_from(u8::read(buf)?).map_err(MQTTError::UnknownData)?,
-                );
+                packet
+                    .payload
+                    .push(UnSubAckReasonCode::decode(u8::read(buf)?)?);
             }
 
             Ok(packet)
@@ -76,6 +102,7 @@ mod syncx {
     }
 }
 
+#[cfg(feature = "asyncx")]
 mod asyncx {
     use crate::v5::{
         commons::error::MQTTError,
@@ -114,20 +141,27 @@ mod asyncx {
             Ok(())
         }
 
-        async fn read<R>(stream: &mut R) -> Result<Self, crate::v5::commons::error::MQTTError>
+        async fn read_with_fixedheader<R>(
+            stream: &mut R,
+            header: &FixedHeader,
+        ) -> Result<Self, crate::v5::commons::error::MQTTError>
         where
             R: futures::AsyncReadExt + Unpin,
             Self: Default,
         {
-            // the assumption here is that the provided buffer has already been advanced by the Fixed Header length
             let mut packet = Self::default();
             packet.pkid = u16::read(stream).await?;
             packet.properties = UnSubAckProperties::read(stream).await?;
 
-            while let Ok(value) = u8::read(stream).await {
+            // one Reason Code per byte left in the packet
+            let payload_len = header
+                .remaining_length
+                .checked_sub(packet.length())
+                .ok_or(MQTTError::MalformedPacket)?;
+            for _ in 0..payload_len {
                 packet
                     .payload
-                    .push(UnSubAckReasonCode::try_from(value).map_err(MQTTError::UnknownData)?);
+                    .push(UnSubAckReasonCode::decode(u8::read(stream).await?)?);
             }
 
             Ok(packet)
@@ -1,8 +1,10 @@
+use alloc::{string::String, vec::Vec};
+
 use bytes::Bytes;
 use mqttea_macros::Length;
 
 use crate::v5::{
-    commons::error::MQTTError,
+    commons::{error::MQTTError, packet_type::PacketType, violation::Violation},
     traits::{read_data::ReadData, utils::Utils},
 };
 
@@ -31,7 +33,7 @@ impl ReadData for UnSubAckProperties {
                     v.as_deref().map(String::from),
                 )(property)?,
                 Property::UserProperty(v) => props.user_property.push(v.into_owned()),
-                p => return Err(MQTTError::UnexpectedProperty(p.to_string(), "".to_string())),
+                _ => return Err(Violation::unexpected_property(PacketType::UnSubAck).into()),
             }
             if data.is_empty() {
                 break;
@@ -43,7 +45,7 @@ impl ReadData for UnSubAckProperties {
 }
 
 mod syncx {
-    use std::borrow::Cow;
+    use alloc::borrow::Cow;
 
     use crate::v5::{
         commons::{error::MQTTError, property::Property},
@@ -69,8 +71,9 @@ mod syncx {
     }
 }
 
+#[cfg(feature = "asyncx")]
 mod asyncx {
-    use std::borrow::Cow;
+    use alloc::borrow::Cow;
 
     use crate::v5::{commons::property::Property, traits::streamio::StreamIO};
 
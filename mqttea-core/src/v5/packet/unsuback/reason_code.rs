@@ -1,13 +1,17 @@
 use mqttea_macros::FromU8;
 
+use crate::v5::commons::reason_code::packet_reason_code;
+
+#[cfg_attr(feature = "wasm", wasm_bindgen::prelude::wasm_bindgen)]
 #[derive(Debug, Clone, Copy, FromU8, PartialEq, Eq)]
 #[repr(u8)]
 pub enum UnSubAckReasonCode {
     Success = 0,
-    NoSubscriptionExpired = 17,
+    NoSubscriptionExisted = 17,
     UnspecifiedError = 128,
-    ImplementationSpecificError =131,
+    ImplementationSpecificError = 131,
     NotAuthorized = 135,
     TopicFilterInvalid = 143,
     PacketIdentifierInUse = 145,
-}
\ No newline at end of file
+}
+packet_reason_code!(UnSubAckReasonCode, UnSubAck);
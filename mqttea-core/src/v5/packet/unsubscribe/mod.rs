@@ -1,7 +1,13 @@
 mod properties;
+use alloc::{string::String, vec::Vec};
+
 pub use properties::UnSubscribeProperties;
 
-use crate::v5::traits::read_data::ReadData;
+use crate::v5::{
+    commons::violation::{ensure, Validate, Violation},
+    traits::read_data::ReadData,
+    utils::topic::validate_topic_filter,
+};
 
 #[derive(Debug, PartialEq, Eq, Clone, Default)]
 pub struct UnSubscribe {
@@ -17,7 +23,32 @@ pub(crate) use syncx::*;
 
 impl ReadData for UnSubscribe {}
 
+impl Validate for UnSubscribe {
+    fn validate(&self) -> Result<(), Violation> {
+        ensure(
+            self.pkid != 0,
+            Violation::protocol(
+                "MQTT-2.2.1-3",
+                "UNSUBSCRIBE must have a non-zero Packet Identifier",
+            ),
+        )?;
+        ensure(
+            !self.payload.is_empty(),
+            Violation::protocol(
+                "MQTT-3.10.3-2",
+                "UNSUBSCRIBE must contain at least one Topic Filter",
+            ),
+        )?;
+
+        self.payload
+            .iter()
+            .try_for_each(|filter| validate_topic_filter(filter))
+    }
+}
+
 mod syncx {
+    use alloc::string::String;
+
     use crate::v5::{
         commons::{error::MQTTError, fixed_header::FixedHeader, packet_type::PacketType},
         traits::{
@@ -75,6 +106,7 @@ mod syncx {
     }
 }
 
+#[cfg(feature = "asyncx")]
 mod asyncx {
     use crate::v5::{
         commons::{error::MQTTError, fixed_header::FixedHeader, packet_type::PacketType},
@@ -117,19 +149,24 @@ mod asyncx {
             Ok(())
         }
 
-        async fn read<R>(stream: &mut R) -> Result<Self, MQTTError>
+        async fn read_with_fixedheader<R>(
+            stream: &mut R,
+            header: &FixedHeader,
+        ) -> Result<Self, MQTTError>
         where
             R: futures::AsyncReadExt + Unpin,
             Self: Default,
         {
-            // the assumption here is that the provided buffer has already been advanced by the Fixed Header length
             let mut packet = Self::default();
 
             packet.pkid = u16::read(stream).await?;
             packet.properties = UnSubscribeProperties::read(stream).await?;
 
-            while let Ok(value) = String::read(stream).await {
-                packet.payload.push(value);
+            while packet.length() < header.remaining_length {
+                packet.payload.push(String::read(stream).await?);
+            }
+            if packet.length() != header.remaining_length {
+                return Err(MQTTError::MalformedPacket);
             }
 
             if packet.payload.is_empty() {
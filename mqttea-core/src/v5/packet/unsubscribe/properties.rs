@@ -1,7 +1,11 @@
+use alloc::{string::String, vec::Vec};
+
 use bytes::Bytes;
 use mqttea_macros::Length;
 
-use crate::v5::commons::{error::MQTTError, property::Property};
+use crate::v5::commons::{
+    error::MQTTError, packet_type::PacketType, property::Property, violation::Violation,
+};
 
 use super::ReadData;
 
@@ -23,7 +27,7 @@ impl ReadData for UnSubscribeProperties {
             let property = Property::read(data)?;
             match property {
                 Property::UserProperty(v) => props.user_property.push(v.into_owned()),
-                p => return Err(MQTTError::UnexpectedProperty(p.to_string(), "".to_string())),
+                _ => return Err(Violation::unexpected_property(PacketType::UnSubscribe).into()),
             }
             if data.is_empty() {
                 break;
@@ -35,7 +39,7 @@ impl ReadData for UnSubscribeProperties {
 }
 
 mod syncx {
-    use std::borrow::Cow;
+    use alloc::borrow::Cow;
 
     use crate::v5::{
         commons::{error::MQTTError, property::Property},
@@ -60,8 +64,9 @@ mod syncx {
     }
 }
 
+#[cfg(feature = "asyncx")]
 mod asyncx {
-    use std::borrow::Cow;
+    use alloc::borrow::Cow;
 
     use crate::v5::{commons::property::Property, traits::streamio::StreamIO};
 
@@ -1,4 +1,4 @@
-use std::future::Future;
+use core::future::Future;
 
 use futures::AsyncReadExt;
 
@@ -13,7 +13,7 @@ where
     S: AsyncReadExt + Unpin,
 {
     async fn read(stream: &mut S) -> Result<u8, MQTTError> {
-        let mut buf = vec![0u8; std::mem::size_of::<u8>()];
+        let mut buf = vec![0u8; core::mem::size_of::<u8>()];
         stream.read_exact(&mut buf).await?;
 
         Ok(u8::from_be_bytes(buf.try_into().unwrap()))
@@ -25,7 +25,7 @@ where
     S: AsyncReadExt + Unpin,
 {
     async fn read(stream: &mut S) -> Result<Self, MQTTError> {
-        let mut buf = vec![0u8; std::mem::size_of::<u16>()];
+        let mut buf = vec![0u8; core::mem::size_of::<u16>()];
         stream.read_exact(&mut buf).await?;
 
         Ok(u16::from_be_bytes(buf.try_into().unwrap()))
@@ -37,7 +37,7 @@ where
     S: AsyncReadExt + Unpin,
 {
     async fn read(stream: &mut S) -> Result<Self, MQTTError> {
-        let mut buf = vec![0u8; std::mem::size_of::<u32>()];
+        let mut buf = vec![0u8; core::mem::size_of::<u32>()];
         stream.read_exact(&mut buf).await?;
 
         Ok(u32::from_be_bytes(buf.try_into().unwrap()))
@@ -49,7 +49,7 @@ where
     S: AsyncReadExt + Unpin,
 {
     async fn read(stream: &mut S) -> Result<Self, MQTTError> {
-        let len = u8::read(stream).await?;
+        let len = u16::read(stream).await?;
         let mut buf = vec![0u8; len as usize];
         stream.read_exact(&mut buf).await?;
 
@@ -63,7 +63,7 @@ where
     S: AsyncReadExt + Unpin,
 {
     async fn read(stream: &mut S) -> Result<Self, MQTTError> {
-        let len = u8::read(stream).await?;
+        let len = u16::read(stream).await?;
         let mut buf = vec![0u8; len as usize];
         stream.read_exact(&mut buf).await?;
 
@@ -1,5 +1,5 @@
 use crate::v5::{
-    commons::fixed_header::FixedHeader,
+    commons::{fixed_header::FixedHeader, violation::Violation},
     traits::syncx::{read::Read, write::Write},
 };
 use bytes::{Bytes, BytesMut};
@@ -60,6 +60,14 @@ pub(crate) trait BufferIO: Sized + ReadData {
             result += ((byte as usize) & 0x7F) << (7 * i);
 
             if (byte & 0x80) == 0 {
+                // a trailing zero byte would make the encoding longer than it needs to be
+                if i > 0 && byte == 0 {
+                    return Err(Violation::malformed(
+                        "MQTT-1.5.5-1",
+                        "Variable Byte Integer must use the minimum number of bytes",
+                    )
+                    .into());
+                }
                 return Ok((result, i + 1));
             }
         }
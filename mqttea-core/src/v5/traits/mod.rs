@@ -1,7 +1,9 @@
+#[cfg(feature = "asyncx")]
 pub(crate) mod asyncx;
 pub(crate) mod syncx; // sync traits // async traits
 
 pub(crate) mod bufferio;
+#[cfg(feature = "asyncx")]
 pub(crate) mod streamio;
 
 pub(crate) mod read_data;
@@ -9,3 +11,8 @@ pub(crate) mod utils;
 
 pub(crate) mod pkid_mgr;
 pub(crate) mod primitives;
+
+pub use primitives::{
+    codec::BinaryCodec,
+    io::{ByteRead, ByteWrite},
+};
@@ -1,3 +1,5 @@
+use core::future::Future;
+
 use crate::v5::commons::error::MQTTError;
 
 pub(crate) trait PacketIdRelease: Sized {
@@ -6,6 +8,16 @@ pub(crate) trait PacketIdRelease: Sized {
     fn is_occupied(&self, id: u16) -> bool;
 }
 
-pub(crate) trait PacketIdAlloc: Sized {
+/// Identifiers are released once the packets they were allocated for are acknowledged, or never sent
+pub(crate) trait PacketIdAlloc: PacketIdRelease {
     fn allocate(&self) -> Result<u16, MQTTError>;
+
+    /// Waits for a Packet Identifier to be released when none can be allocated
+    fn allocate_async(&self) -> impl Future<Output = u16> + Send;
+
+    /// Number of Packet Identifiers currently in use
+    fn in_flight(&self) -> u16;
+
+    /// Number of Packet Identifiers that can still be allocated
+    fn available(&self) -> u16;
 }
@@ -1,2 +1,3 @@
+#[cfg(feature = "asyncx")]
 pub(crate) mod async_io;
 pub(crate) mod sycn_io;
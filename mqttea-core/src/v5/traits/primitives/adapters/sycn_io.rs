@@ -21,3 +21,54 @@ impl ByteWrite for BytesMut {
         Ok(())
     }
 }
+
+/// Reads off the front of the slice, which is left with what is yet to be read
+impl ByteRead for &[u8] {
+    fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), MQTTError> {
+        if self.len() < buf.len() {
+            return Err(MQTTError::IncompleteData("buffer", buf.len(), self.len()));
+        }
+        let (read, rest) = self.split_at(buf.len());
+        buf.copy_from_slice(read);
+        *self = rest;
+        Ok(())
+    }
+}
+
+/// Writes to the front of the slice, which is left with the space that is still free
+impl ByteWrite for &mut [u8] {
+    fn write_all(&mut self, buf: &[u8]) -> Result<(), MQTTError> {
+        if self.len() < buf.len() {
+            return Err(MQTTError::IncompleteData("buffer", buf.len(), self.len()));
+        }
+        let (written, rest) = core::mem::take(self).split_at_mut(buf.len());
+        written.copy_from_slice(buf);
+        *self = rest;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::v5::traits::primitives::codec::BinaryCodec;
+
+    use super::*;
+
+    #[test]
+    fn slices_refuse_what_they_cant_hold() {
+        let mut storage = [0u8; 3];
+        let mut w = &mut storage[..];
+        assert_eq!(
+            0xdead_beefu32.write_to(&mut w),
+            Err(MQTTError::IncompleteData("buffer", 4, 3))
+        );
+        assert_eq!(w.len(), 3);
+
+        let mut r = &storage[..];
+        assert_eq!(
+            u32::read_from(&mut r),
+            Err(MQTTError::IncompleteData("buffer", 4, 3))
+        );
+        assert_eq!(r.len(), 3);
+    }
+}
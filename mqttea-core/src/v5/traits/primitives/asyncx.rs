@@ -24,7 +24,7 @@ where
 }
 
 pub trait WriteAsync<S> {
-    fn write(&self, stream: &mut S) -> impl std::future::Future<Output = Result<(), MQTTError>>;
+    fn write(&self, stream: &mut S) -> impl core::future::Future<Output = Result<(), MQTTError>>;
 }
 
 impl<T, S> WriteAsync<S> for T
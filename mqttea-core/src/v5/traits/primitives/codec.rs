@@ -1,3 +1,5 @@
+use alloc::{string::String, vec};
+
 use bytes::Bytes;
 
 use crate::v5::{
@@ -1,4 +1,5 @@
 pub(crate) mod adapters;
+#[cfg(feature = "asyncx")]
 pub(crate) mod asyncx;
 pub(crate) mod codec;
 pub(crate) mod io;
@@ -1,5 +1,5 @@
 use crate::v5::{
-    commons::error::MQTTError,
+    commons::{error::MQTTError, violation::Violation},
     traits::primitives::io::{ByteRead, ByteWrite},
 };
 
@@ -31,7 +31,7 @@ pub(crate) trait VarInt {
                 byte |= 0x80;
             }
 
-            w.write_all(&[byte]);
+            w.write_all(&[byte])?;
             written += 1;
 
             if len == 0 {
@@ -53,6 +53,14 @@ pub(crate) trait VarInt {
             value |= ((byte & 0x7F) as usize) << (7 * i);
 
             if (byte & 0x80) == 0 {
+                // a trailing zero byte would make the encoding longer than it needs to be
+                if i > 0 && byte == 0 {
+                    return Err(Violation::malformed(
+                        "MQTT-1.5.5-1",
+                        "Variable Byte Integer must use the minimum number of bytes",
+                    )
+                    .into());
+                }
                 return Ok((value, i + 1));
             }
         }
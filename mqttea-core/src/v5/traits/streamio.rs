@@ -1,5 +1,6 @@
 use crate::v5::commons::error::MQTTError;
 use crate::v5::commons::fixed_header::FixedHeader;
+use crate::v5::commons::violation::Violation;
 use crate::v5::traits::asyncx::read::Read;
 use crate::v5::traits::asyncx::write::Write;
 
@@ -64,6 +65,14 @@ pub(crate) trait StreamIO: Sized + ReadData {
             result += ((byte as usize) & 0x7F) << (7 * i);
 
             if (byte & 0x80) == 0 {
+                // a trailing zero byte would make the encoding longer than it needs to be
+                if i > 0 && byte == 0 {
+                    return Err(Violation::malformed(
+                        "MQTT-1.5.5-1",
+                        "Variable Byte Integer must use the minimum number of bytes",
+                    )
+                    .into());
+                }
                 return Ok((result, i + 1));
             }
         }
@@ -91,9 +100,8 @@ pub(crate) trait StreamIO: Sized + ReadData {
             return Ok(Self::default());
         };
 
-        let mut data = Vec::with_capacity(len);
-        stream.read_exact(&mut data).await?;
-        let mut data = Bytes::copy_from_slice(&data);
+        let data = read_exactly(stream, len).await?;
+        let mut data = Bytes::from(data);
 
         Self::read_data(&mut data)
     }
@@ -130,3 +138,18 @@ pub(crate) trait StreamIO: Sized + ReadData {
         Ok(())
     }
 }
+
+/// Reads `len` bytes off the stream.
+/// The buffer grows with what is actually received, so a peer can't make us allocate a large length it never sends
+pub(crate) async fn read_exactly<R>(stream: &mut R, len: usize) -> Result<Vec<u8>, MQTTError>
+where
+    R: AsyncReadExt + Unpin,
+{
+    let mut data = Vec::new();
+    stream.take(len as u64).read_to_end(&mut data).await?;
+
+    if data.len() != len {
+        return Err(MQTTError::IncompleteData("stream", len, data.len()));
+    }
+    Ok(data)
+}
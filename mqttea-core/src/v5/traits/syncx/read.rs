@@ -1,3 +1,5 @@
+use alloc::string::String;
+
 use bytes::{Buf, Bytes};
 
 use crate::v5::commons::error::MQTTError;
@@ -8,7 +10,7 @@ pub(crate) trait Read: Sized {
 
 impl Read for u8 {
     fn read(buf: &mut Bytes) -> Result<Self, MQTTError> {
-        let len = std::mem::size_of::<u8>();
+        let len = core::mem::size_of::<u8>();
         if buf.is_empty() {
             return Err(MQTTError::IncompleteData("u8", len, buf.len()));
         }
@@ -19,7 +21,7 @@ impl Read for u8 {
 
 impl Read for u16 {
     fn read(buf: &mut Bytes) -> Result<Self, MQTTError> {
-        let len = std::mem::size_of::<u16>();
+        let len = core::mem::size_of::<u16>();
 
         if buf.len() < len {
             return Err(MQTTError::IncompleteData("u16", len, buf.len()));
@@ -31,7 +33,7 @@ impl Read for u16 {
 
 impl Read for u32 {
     fn read(buf: &mut Bytes) -> Result<Self, MQTTError> {
-        let len = std::mem::size_of::<u32>();
+        let len = core::mem::size_of::<u32>();
         if buf.len() < len {
             return Err(MQTTError::IncompleteData("u32", len, buf.len()));
         }
@@ -53,7 +55,7 @@ impl Read for String {
 
 impl Read for Bytes {
     fn read(buf: &mut Bytes) -> Result<Self, MQTTError> {
-        let len = buf.get_u16() as usize;
+        let len = u16::read(buf)? as usize;
 
         if len > buf.len() {
             return Err(MQTTError::IncompleteData("Bytes", len, buf.len()));
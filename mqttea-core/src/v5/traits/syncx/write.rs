@@ -1,3 +1,5 @@
+use alloc::string::String;
+
 use bytes::{BufMut, Bytes, BytesMut};
 
 pub(crate) trait Write: Sized {
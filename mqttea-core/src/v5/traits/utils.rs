@@ -1,9 +1,12 @@
+use alloc::string::{String, ToString};
+
 use bytes::Bytes;
 
-use crate::v5::commons::{error::MQTTError, property::Property};
+use crate::v5::commons::{error::MQTTError, property::Property, violation::Violation};
 use crate::v5::traits::syncx::read::Read;
 
 use super::bufferio::BufferIO;
+#[cfg(feature = "asyncx")]
 use super::streamio::StreamIO;
 
 pub(crate) trait Utils: Sized {
@@ -35,6 +38,14 @@ pub(crate) trait Utils: Sized {
             result += ((byte as usize) & 0x7F) << (7 * i);
 
             if (byte & 0x80) == 0 {
+                // a trailing zero byte would make the encoding longer than it needs to be
+                if i > 0 && byte == 0 {
+                    return Err(Violation::malformed(
+                        "MQTT-1.5.5-1",
+                        "Variable Byte Integer must use the minimum number of bytes",
+                    )
+                    .into());
+                }
                 return Ok((result, i + 1));
             }
         }
@@ -51,4 +62,7 @@ pub(crate) trait Utils: Sized {
     }
 }
 
+#[cfg(feature = "asyncx")]
 impl<T: StreamIO + BufferIO> Utils for T {}
+#[cfg(not(feature = "asyncx"))]
+impl<T: BufferIO> Utils for T {}
@@ -1,4 +1,12 @@
-use crate::v5::commons::error::MQTTError;
+use alloc::format;
+
+use crate::v5::{
+    commons::{
+        error::MQTTError,
+        violation::{ensure, Violation},
+    },
+    packet::disconnect::DisconnectReasonCode,
+};
 
 pub(crate) fn parse_alias(alias: u16, alias_max: u16) -> Result<u16, MQTTError> {
     if alias == 0 || alias > alias_max {
@@ -8,3 +16,136 @@ pub(crate) fn parse_alias(alias: u16, alias_max: u16) -> Result<u16, MQTTError>
     }
     Ok(alias)
 }
+
+/// 4.7 Topic Names and Topic Filters
+pub(crate) fn validate_topic_name(topic: &str, spec: &'static str) -> Result<(), Violation> {
+    ensure(
+        !topic.contains(['+', '#']),
+        Violation::new(
+            spec,
+            DisconnectReasonCode::TopicNameInvalid,
+            "Topic Name must not contain wildcard characters",
+        ),
+    )?;
+    ensure(
+        !topic.contains('\0'),
+        Violation::new(
+            "MQTT-4.7.3-2",
+            DisconnectReasonCode::TopicNameInvalid,
+            "Topic Name must not include the null character",
+        ),
+    )
+}
+
+/// 4.7 Topic Names and Topic Filters, 4.8.2 Shared Subscriptions
+pub(crate) fn validate_topic_filter(filter: &str) -> Result<(), Violation> {
+    let invalid = |spec, description| {
+        Err(Violation::new(
+            spec,
+            DisconnectReasonCode::TopicFilterInvalid,
+            description,
+        ))
+    };
+
+    let filter = match filter.strip_prefix("$share/") {
+        Some(shared) => {
+            let Some((share_name, filter)) = shared.split_once('/') else {
+                return invalid(
+                    "MQTT-4.8.2-2",
+                    "Shared Subscription must be followed by a Topic Filter",
+                );
+            };
+            if share_name.is_empty() || share_name.contains(['+', '#']) {
+                return invalid(
+                    "MQTT-4.8.2-1",
+                    "ShareName must be at least one character long and not include wildcards",
+                );
+            }
+            filter
+        }
+        None => filter,
+    };
+
+    if filter.is_empty() {
+        return invalid(
+            "MQTT-4.7.3-1",
+            "Topic Filter must be at least one character long",
+        );
+    }
+    if filter.contains('\0') {
+        return invalid(
+            "MQTT-4.7.3-2",
+            "Topic Filter must not include the null character",
+        );
+    }
+
+    let mut levels = filter.split('/').peekable();
+    while let Some(level) = levels.next() {
+        if level.contains('#') && (level != "#" || levels.peek().is_some()) {
+            return invalid(
+                "MQTT-4.7.1-1",
+                "Multi-level wildcard must occupy an entire level and be the last character",
+            );
+        }
+        if level.contains('+') && level != "+" {
+            return invalid(
+                "MQTT-4.7.1-2",
+                "Single-level wildcard must occupy an entire level of the filter",
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Whether this is a `$share/{ShareName}/{filter}` topic filter
+pub(crate) fn is_shared_subscription(filter: &str) -> bool {
+    filter.starts_with("$share/")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_topic_filters() {
+        for filter in [
+            "#",
+            "+",
+            "a/b/c",
+            "a/+/c",
+            "a/#",
+            "+/+",
+            "/",
+            "$share/group/a/#",
+        ] {
+            assert_eq!(validate_topic_filter(filter), Ok(()), "{filter}");
+        }
+    }
+
+    #[test]
+    fn invalid_topic_filters() {
+        let cases = [
+            ("", "MQTT-4.7.3-1"),
+            ("a/\0", "MQTT-4.7.3-2"),
+            ("a/#/c", "MQTT-4.7.1-1"),
+            ("a/b#", "MQTT-4.7.1-1"),
+            ("a/b+/c", "MQTT-4.7.1-2"),
+            ("$share/group", "MQTT-4.8.2-2"),
+            ("$share/gr+up/a", "MQTT-4.8.2-1"),
+        ];
+
+        for (filter, spec) in cases {
+            let violation = validate_topic_filter(filter).unwrap_err();
+            assert_eq!(violation.spec, spec, "{filter}");
+            assert_eq!(violation.reason, DisconnectReasonCode::TopicFilterInvalid);
+        }
+    }
+
+    #[test]
+    fn topic_names_reject_wildcards() {
+        assert!(validate_topic_name("a/b", "MQTT-3.3.2-2").is_ok());
+        assert!(validate_topic_name("a/+", "MQTT-3.3.2-2").is_err());
+        assert!(validate_topic_name("a/#", "MQTT-3.3.2-2").is_err());
+    }
+}
@@ -0,0 +1,606 @@
+use std::{cell::RefCell, collections::HashMap, future::Future, rc::Rc};
+
+use futures::{
+    channel::mpsc::{self, UnboundedReceiver, UnboundedSender},
+    future, StreamExt,
+};
+use js_sys::{Function, Promise};
+use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
+use wasm_bindgen_futures::{future_to_promise, spawn_local, JsFuture};
+
+use crate::v5::{
+    client::{
+        client::MqttClient,
+        handler::AsyncHandler,
+        network::asyncx::{Network, NetworkStatus},
+        packet_id::PacketIdManager,
+    },
+    commons::{error::Refusal, packet::Packet, packet_type::PacketType, reason_code::ReasonCode},
+    packet::disconnect::DisconnectReasonCode,
+};
+
+use super::{
+    js::{self, JsConnectOptions, JsPublishOptions, JsSubscribeOptions, JsUnsubscribeOptions},
+    socket::Socket,
+};
+
+/// An MQTT client connected to a broker over a browser WebSocket.
+///
+/// Operations return promises settled by the acknowledgement the server sends back,
+/// with its Reason Code when the operation succeeded, and with an `MqttError` otherwise
+#[wasm_bindgen(js_name = MqttClient)]
+pub struct Client {
+    client: Rc<MqttClient<PacketIdManager>>,
+    shared: Rc<Shared>,
+}
+
+/// What the client and the tasks driving its connection share
+#[derive(Default)]
+struct Shared {
+    /// The promises waiting on an acknowledgement, by Packet Identifier
+    pending: RefCell<HashMap<u16, Pending>>,
+    on_message: RefCell<Option<Function>>,
+    on_close: RefCell<Option<Function>>,
+    /// Why the connection ended, once it has
+    close: RefCell<Option<JsValue>>,
+}
+
+struct Pending {
+    resolve: Function,
+    reject: Function,
+}
+
+/// Hands the packets received over to the task calling back into JavaScript, which can't be done from the network
+struct Forward(UnboundedSender<Packet>);
+
+impl AsyncHandler for Forward {
+    fn handle(&mut self, packet: Packet) -> impl Future<Output = ()> + Send + Sync {
+        let _ = self.0.unbounded_send(packet);
+        future::ready(())
+    }
+}
+
+#[wasm_bindgen(js_class = MqttClient)]
+impl Client {
+    /// Opens a WebSocket to `url` (`ws://` or `wss://`) and connects over it,
+    /// resolving once the server accepted the connection
+    #[wasm_bindgen(unchecked_return_type = "Promise<MqttClient>")]
+    pub fn connect(url: String, options: Option<JsConnectOptions>) -> Promise {
+        future_to_promise(async move {
+            let options = js::connect_options(&options.map_or(JsValue::UNDEFINED, Into::into))?;
+            let socket = Socket::open(&url).await?;
+            let (mut network, client) = Network::new(options, socket).await.map_err(js::error)?;
+
+            let shared = Rc::new(Shared::default());
+            let (tx, rx) = mpsc::unbounded();
+            spawn_local({
+                let shared = shared.clone();
+                async move {
+                    let mut forward = Forward(tx);
+                    let close = match network.run(&mut forward).await {
+                        // the DISCONNECT received says why
+                        Ok(NetworkStatus::IncomingDisconnect) => None,
+                        Ok(NetworkStatus::OutgoingDisconnect) => Some(js::close(
+                            Some(DisconnectReasonCode::NormalDisconnection as u8),
+                            None,
+                        )),
+                        Ok(NetworkStatus::Timeout) => {
+                            Some(js::close(None, Some(String::from("keep alive timeout"))))
+                        }
+                        Err(err) => Some(js::close(None, Some(err.to_string()))),
+                    };
+                    if let Some(close) = close {
+                        shared.close.replace(Some(close));
+                    }
+                    // ends the dispatch once the packets received before are handed over
+                    drop(forward);
+                }
+            });
+            spawn_local(dispatch(rx, shared.clone()));
+
+            Ok(Self {
+                client: Rc::new(client),
+                shared,
+            }
+            .into())
+        })
+    }
+
+    /// Publishes `payload`, a string being sent UTF-8 encoded.
+    /// Resolves with the Reason Code of the PUBACK or PUBCOMP, with `undefined` at QoS 0 once the message is queued
+    #[wasm_bindgen(unchecked_return_type = "Promise<number | undefined>")]
+    pub fn publish(
+        &self,
+        topic: String,
+        #[wasm_bindgen(unchecked_param_type = "Uint8Array | string")] payload: JsValue,
+        options: Option<JsPublishOptions>,
+    ) -> Promise {
+        let (client, shared) = (self.client.clone(), self.shared.clone());
+        future_to_promise(async move {
+            let payload = js::payload(&payload)?;
+            let (qos, retain, properties) =
+                js::publish_options(&options.map_or(JsValue::UNDEFINED, Into::into))?;
+
+            let pkid = client
+                .publish(topic, qos, retain, payload, Some(properties))
+                .await
+                .map_err(js::error)?;
+            match pkid {
+                Some(pkid) => JsFuture::from(shared.acknowledgement(pkid)).await,
+                None => Ok(JsValue::UNDEFINED),
+            }
+        })
+    }
+
+    /// Subscribes to `filter`, resolving with the Reason Code of the SUBACK, which is the QoS granted
+    #[wasm_bindgen(unchecked_return_type = "Promise<SubAckReasonCode>")]
+    pub fn subscribe(&self, filter: String, options: Option<JsSubscribeOptions>) -> Promise {
+        let (client, shared) = (self.client.clone(), self.shared.clone());
+        future_to_promise(async move {
+            let (subscription, properties) =
+                js::subscribe_options(&options.map_or(JsValue::UNDEFINED, Into::into))?;
+
+            let pkid = client
+                .subscribe(vec![(filter, subscription)], Some(properties))
+                .await
+                .map_err(js::error)?;
+            JsFuture::from(shared.acknowledgement(pkid)).await
+        })
+    }
+
+    /// Unsubscribes from `filter`, resolving with the Reason Code of the UNSUBACK
+    #[wasm_bindgen(unchecked_return_type = "Promise<UnSubAckReasonCode>")]
+    pub fn unsubscribe(&self, filter: String, options: Option<JsUnsubscribeOptions>) -> Promise {
+        let (client, shared) = (self.client.clone(), self.shared.clone());
+        future_to_promise(async move {
+            let properties =
+                js::unsubscribe_options(&options.map_or(JsValue::UNDEFINED, Into::into))?;
+
+            let pkid = client
+                .unsubscribe(vec![filter], Some(properties))
+                .await
+                .map_err(js::error)?;
+            JsFuture::from(shared.acknowledgement(pkid)).await
+        })
+    }
+
+    /// Sends a DISCONNECT, resolving once it is queued
+    #[wasm_bindgen(unchecked_return_type = "Promise<void>")]
+    pub fn disconnect(&self) -> Promise {
+        let client = self.client.clone();
+        future_to_promise(async move {
+            client.disconnect().await.map_err(js::error)?;
+            Ok(JsValue::UNDEFINED)
+        })
+    }
+
+    /// Sets the function called with every message the server delivers
+    #[wasm_bindgen(js_name = onMessage)]
+    pub fn on_message(
+        &self,
+        #[wasm_bindgen(unchecked_param_type = "(message: Message) => void")] callback: Function,
+    ) {
+        self.shared.on_message.replace(Some(callback));
+    }
+
+    /// Sets the function called once the connection ended, every pending promise being rejected by then
+    #[wasm_bindgen(js_name = onClose)]
+    pub fn on_close(
+        &self,
+        #[wasm_bindgen(unchecked_param_type = "(close: Close) => void")] callback: Function,
+    ) {
+        self.shared.on_close.replace(Some(callback));
+    }
+}
+
+impl Shared {
+    /// The promise the acknowledgement of `pkid` settles.
+    /// Registered as soon as the packet is queued, so that it is in place before the acknowledgement can be received
+    fn acknowledgement(&self, pkid: u16) -> Promise {
+        Promise::new(&mut |resolve, reject| {
+            self.pending
+                .borrow_mut()
+                .insert(pkid, Pending { resolve, reject });
+        })
+    }
+
+    fn settle(&self, pkid: u16, ack: Refusal) {
+        // the Packet Identifier may have been used by an operation the client isn't waiting on
+        let Some(pending) = self.pending.borrow_mut().remove(&pkid) else {
+            return;
+        };
+        let _ = match js::outcome(ack) {
+            Ok(code) => pending.resolve.call1(&JsValue::NULL, &code),
+            Err(err) => pending.reject.call1(&JsValue::NULL, &err),
+        };
+    }
+
+    /// Calls back into JavaScript, without holding on to the callback so that it may replace itself
+    fn call(callback: &RefCell<Option<Function>>, arg: &JsValue) {
+        let callback = callback.borrow().clone();
+        if let Some(callback) = callback {
+            // what the callback throws is the application's to handle
+            let _ = callback.call1(&JsValue::NULL, arg);
+        }
+    }
+}
+
+/// The acknowledgement `reason` received on `packet`
+fn ack(
+    packet: PacketType,
+    reason: impl Into<ReasonCode>,
+    reason_string: Option<String>,
+    user_property: Vec<(String, String)>,
+) -> Refusal {
+    Refusal {
+        packet,
+        reason: reason.into(),
+        reason_string,
+        user_property,
+    }
+}
+
+/// Settles promises and calls back into JavaScript as packets are received, until the connection ends
+async fn dispatch(mut packets: UnboundedReceiver<Packet>, shared: Rc<Shared>) {
+    while let Some(packet) = packets.next().await {
+        match packet {
+            Packet::Publish(publish) => Shared::call(&shared.on_message, &js::message(&publish)),
+            Packet::PubAck(puback) => {
+                let properties = puback.properties;
+                let ack = ack(
+                    PacketType::PubAck,
+                    puback.reason_code,
+                    properties.reason_string,
+                    properties.user_property,
+                );
+                shared.settle(puback.pkid, ack);
+            }
+            // a successful PUBREC is followed by the PUBCOMP the promise waits on
+            Packet::PubRec(pubrec) if pubrec.reason_code.is_error() => {
+                let properties = pubrec.properties;
+                let ack = ack(
+                    PacketType::PubRec,
+                    pubrec.reason_code,
+                    properties.reason_string,
+                    properties.user_property,
+                );
+                shared.settle(pubrec.pkid, ack);
+            }
+            Packet::PubComp(pubcomp) => {
+                let properties = pubcomp.properties;
+                let ack = ack(
+                    PacketType::PubComp,
+                    pubcomp.reason_code,
+                    properties.reason_string,
+                    properties.user_property,
+                );
+                shared.settle(pubcomp.pkid, ack);
+            }
+            // a single Topic Filter is subscribed to, or unsubscribed from, at a time
+            Packet::SubAck(suback) => {
+                let Some(&code) = suback.payload.first() else {
+                    continue;
+                };
+                let properties = suback.properties;
+                let ack = ack(
+                    PacketType::SubAck,
+                    code,
+                    properties.reason_string,
+                    properties.user_property,
+                );
+                shared.settle(suback.pkid, ack);
+            }
+            Packet::UnSubAck(unsuback) => {
+                let Some(&code) = unsuback.payload.first() else {
+                    continue;
+                };
+                let properties = unsuback.properties;
+                let ack = ack(
+                    PacketType::UnSubAck,
+                    code,
+                    properties.reason_string,
+                    properties.user_property,
+                );
+                shared.settle(unsuback.pkid, ack);
+            }
+            Packet::Disconnect(disconnect) => {
+                let close = js::close(
+                    Some(disconnect.reason_code as u8),
+                    disconnect.properties.reason_string,
+                );
+                shared.close.replace(Some(close));
+            }
+            _ => {}
+        }
+    }
+
+    let closed = js::closed();
+    for (_, pending) in shared.pending.take() {
+        let _ = pending.reject.call1(&JsValue::NULL, &closed);
+    }
+    let close = shared.close.take().unwrap_or_else(|| js::close(None, None));
+    Shared::call(&shared.on_close, &close);
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod tests {
+    use bytes::Bytes;
+    use js_sys::{Array, Reflect, Uint8Array};
+    use wasm_bindgen::{closure::Closure, JsCast};
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use crate::v5::{
+        commons::qos::QoS,
+        packet::{
+            disconnect::{Disconnect, DisconnectProperties},
+            puback::{PubAck, PubAckProperties, PubAckReasonCode},
+            pubcomp::{PubComp, PubCompReasonCode},
+            publish::{Publish, PublishProperties},
+            pubrec::{PubRec, PubRecReasonCode},
+            suback::{SubAck, SubAckReasonCode},
+            unsuback::{UnSubAck, UnSubAckReasonCode},
+        },
+    };
+
+    use super::*;
+
+    fn field(value: &JsValue, key: &str) -> JsValue {
+        Reflect::get(value, &JsValue::from_str(key)).unwrap()
+    }
+
+    /// A callback recording every value it is called with
+    fn recorder() -> (Function, Rc<RefCell<Vec<JsValue>>>) {
+        let calls = Rc::new(RefCell::new(Vec::new()));
+        let callback = Closure::<dyn FnMut(JsValue)>::new({
+            let calls = calls.clone();
+            move |value| calls.borrow_mut().push(value)
+        });
+        (callback.into_js_value().unchecked_into(), calls)
+    }
+
+    /// Dispatches `packets`, then ends the stream as the connection closing does
+    async fn dispatch_all(shared: &Rc<Shared>, packets: Vec<Packet>) {
+        let (tx, rx) = mpsc::unbounded();
+        for packet in packets {
+            tx.unbounded_send(packet).unwrap();
+        }
+        drop(tx);
+        dispatch(rx, shared.clone()).await;
+    }
+
+    fn puback(pkid: u16, reason_code: PubAckReasonCode) -> Packet {
+        Packet::PubAck(PubAck {
+            pkid,
+            reason_code,
+            ..Default::default()
+        })
+    }
+
+    fn pubrec(pkid: u16, reason_code: PubRecReasonCode) -> Packet {
+        Packet::PubRec(PubRec {
+            pkid,
+            reason_code,
+            ..Default::default()
+        })
+    }
+
+    fn pubcomp(pkid: u16, reason_code: PubCompReasonCode) -> Packet {
+        Packet::PubComp(PubComp {
+            pkid,
+            reason_code,
+            ..Default::default()
+        })
+    }
+
+    #[wasm_bindgen_test]
+    async fn settles_publishes_on_their_acknowledgement() {
+        let shared = Rc::new(Shared::default());
+        let delivered = shared.acknowledgement(1);
+        let unmatched = shared.acknowledgement(2);
+        let refused = shared.acknowledgement(3);
+        let completed = shared.acknowledgement(4);
+        let refused_on_pubrec = shared.acknowledgement(5);
+
+        let refusal = Packet::PubAck(PubAck {
+            pkid: 3,
+            reason_code: PubAckReasonCode::NotAuthorized,
+            properties: PubAckProperties {
+                reason_string: Some(String::from("read only")),
+                user_property: vec![(String::from("rule"), String::from("7"))],
+            },
+        });
+        dispatch_all(
+            &shared,
+            vec![
+                puback(1, PubAckReasonCode::Success),
+                puback(2, PubAckReasonCode::NoMatchingSubscribers),
+                refusal,
+                // a PUBACK for an operation nobody waits on is ignored
+                puback(9, PubAckReasonCode::Success),
+                pubrec(4, PubRecReasonCode::Success),
+                pubcomp(4, PubCompReasonCode::Success),
+                pubrec(5, PubRecReasonCode::QuotaExceeded),
+            ],
+        )
+        .await;
+
+        assert_eq!(JsFuture::from(delivered).await.unwrap(), 0);
+        assert_eq!(JsFuture::from(unmatched).await.unwrap(), 16);
+
+        let err = JsFuture::from(refused).await.unwrap_err();
+        assert_eq!(field(&err, "name"), "MqttError");
+        assert_eq!(field(&err, "kind"), "refused");
+        assert_eq!(field(&err, "packetType"), "PUBACK");
+        assert_eq!(field(&err, "reasonCode"), 135);
+        assert_eq!(field(&err, "reasonString"), "read only");
+        let pair = Array::from(&field(&err, "userProperties")).get(0);
+        assert_eq!(Array::from(&pair).to_vec(), vec!["rule", "7"]);
+
+        assert_eq!(JsFuture::from(completed).await.unwrap(), 0);
+
+        let err = JsFuture::from(refused_on_pubrec).await.unwrap_err();
+        assert_eq!(field(&err, "kind"), "refused");
+        assert_eq!(field(&err, "packetType"), "PUBREC");
+        assert_eq!(field(&err, "reasonCode"), 151);
+        assert!(shared.pending.borrow().is_empty());
+    }
+
+    #[wasm_bindgen_test]
+    async fn waits_on_the_pubcomp_after_a_successful_pubrec() {
+        let shared = Rc::new(Shared::default());
+        let (tx, rx) = mpsc::unbounded();
+        let publish = shared.acknowledgement(7);
+
+        let server = async {
+            tx.unbounded_send(pubrec(7, PubRecReasonCode::Success))
+                .unwrap();
+            // lets the PUBREC be dispatched, the send having woken the dispatching task up
+            futures::pending!();
+            assert!(shared.pending.borrow().contains_key(&7));
+
+            tx.unbounded_send(pubcomp(7, PubCompReasonCode::PacketIdentifierNotFound))
+                .unwrap();
+            tx.close_channel();
+        };
+        future::join(dispatch(rx, shared.clone()), server).await;
+
+        let err = JsFuture::from(publish).await.unwrap_err();
+        assert_eq!(field(&err, "packetType"), "PUBCOMP");
+        assert_eq!(field(&err, "reasonCode"), 146);
+    }
+
+    #[wasm_bindgen_test]
+    async fn settles_subscriptions_on_their_first_reason_code() {
+        let shared = Rc::new(Shared::default());
+        let granted = shared.acknowledgement(1);
+        let refused = shared.acknowledgement(2);
+        let unsubscribed = shared.acknowledgement(3);
+        let missing = shared.acknowledgement(4);
+
+        dispatch_all(
+            &shared,
+            vec![
+                Packet::SubAck(SubAck {
+                    pkid: 1,
+                    payload: vec![SubAckReasonCode::GrantedQoS1],
+                    ..Default::default()
+                }),
+                Packet::SubAck(SubAck {
+                    pkid: 2,
+                    payload: vec![SubAckReasonCode::NotAuthorized],
+                    ..Default::default()
+                }),
+                Packet::UnSubAck(UnSubAck {
+                    pkid: 3,
+                    payload: vec![UnSubAckReasonCode::Success],
+                    ..Default::default()
+                }),
+                Packet::UnSubAck(UnSubAck {
+                    pkid: 4,
+                    payload: vec![UnSubAckReasonCode::NoSubscriptionExisted],
+                    ..Default::default()
+                }),
+            ],
+        )
+        .await;
+
+        assert_eq!(JsFuture::from(granted).await.unwrap(), 1);
+        let err = JsFuture::from(refused).await.unwrap_err();
+        assert_eq!(field(&err, "packetType"), "SUBACK");
+        assert_eq!(field(&err, "reasonCode"), 135);
+        assert_eq!(JsFuture::from(unsubscribed).await.unwrap(), 0);
+        assert_eq!(JsFuture::from(missing).await.unwrap(), 17);
+    }
+
+    #[wasm_bindgen_test]
+    async fn hands_messages_over_to_on_message() {
+        let shared = Rc::new(Shared::default());
+        let (on_message, messages) = recorder();
+        shared.on_message.replace(Some(on_message));
+
+        let publish = Publish {
+            qos: QoS::One,
+            retain: true,
+            topic: String::from("sensors/7"),
+            pkid: Some(3),
+            payload: Bytes::from_static(&[0, 159, 255]),
+            properties: PublishProperties {
+                content_type: Some(String::from("application/octet-stream")),
+                user_property: vec![(String::from("unit"), String::from("K"))],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        dispatch_all(&shared, vec![Packet::Publish(publish)]).await;
+
+        let messages = messages.borrow();
+        assert_eq!(messages.len(), 1);
+        let message = &messages[0];
+        assert_eq!(field(message, "topic"), "sensors/7");
+        assert_eq!(
+            Uint8Array::from(field(message, "payload")).to_vec(),
+            [0, 159, 255]
+        );
+        assert_eq!(field(message, "qos"), 1);
+        assert_eq!(field(message, "retain"), true);
+        let properties = field(message, "properties");
+        assert_eq!(
+            field(&properties, "contentType"),
+            "application/octet-stream"
+        );
+        assert_eq!(
+            Array::from(&field(&properties, "userProperties")).length(),
+            1
+        );
+    }
+
+    #[wasm_bindgen_test]
+    async fn rejects_what_is_pending_once_the_connection_closes() {
+        let shared = Rc::new(Shared::default());
+        let (on_close, closes) = recorder();
+        shared.on_close.replace(Some(on_close));
+        let publish = shared.acknowledgement(1);
+        let subscribe = shared.acknowledgement(2);
+
+        let disconnect = Disconnect {
+            reason_code: DisconnectReasonCode::ServerShuttingDown,
+            properties: DisconnectProperties {
+                reason_string: Some(String::from("maintenance")),
+                ..Default::default()
+            },
+        };
+        dispatch_all(
+            &shared,
+            vec![
+                pubrec(1, PubRecReasonCode::Success),
+                Packet::Disconnect(disconnect),
+            ],
+        )
+        .await;
+
+        for promise in [publish, subscribe] {
+            let err = JsFuture::from(promise).await.unwrap_err();
+            assert_eq!(field(&err, "name"), "MqttError");
+            assert_eq!(field(&err, "kind"), "closed");
+        }
+        assert!(shared.pending.borrow().is_empty());
+
+        let closes = closes.borrow();
+        assert_eq!(closes.len(), 1);
+        assert_eq!(field(&closes[0], "reasonCode"), 139);
+        assert_eq!(field(&closes[0], "reason"), "maintenance");
+    }
+
+    #[wasm_bindgen_test]
+    async fn reports_a_close_without_disconnect() {
+        let shared = Rc::new(Shared::default());
+        let (on_close, closes) = recorder();
+        shared.on_close.replace(Some(on_close));
+
+        dispatch_all(&shared, Vec::new()).await;
+
+        let closes = closes.borrow();
+        assert_eq!(closes.len(), 1);
+        assert!(field(&closes[0], "reasonCode").is_undefined());
+    }
+}
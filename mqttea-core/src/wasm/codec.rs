@@ -0,0 +1,1121 @@
+use bytes::{Bytes, BytesMut};
+use js_sys::{Array, Object, Uint8Array};
+use wasm_bindgen::{prelude::wasm_bindgen, JsCast, JsValue};
+
+use crate::v5::{
+    commons::{error::MQTTError, packet::Packet, violation::Validate},
+    packet::{
+        auth::{Auth, AuthProperties},
+        connack::{ConnAck, ConnAckProperties},
+        connect::{Connect, ConnectProperties},
+        disconnect::{Disconnect, DisconnectProperties},
+        ping::{PingReq, PingResp},
+        puback::{PubAck, PubAckProperties},
+        pubcomp::{PubComp, PubCompProperties},
+        publish::Publish,
+        pubrec::{PubRec, PubRecProperties},
+        pubrel::{PubRel, PubRelProperties},
+        suback::{SubAck, SubAckProperties},
+        subscribe::{Subscribe, SubscribeProperties},
+        unsuback::{UnSubAck, UnSubAckProperties},
+        unsubscribe::{UnSubscribe, UnSubscribeProperties},
+    },
+    traits::bufferio::BufferIO,
+};
+
+use super::js::{
+    self, boolean, bytes, get, invalid, number, qos, set, set_some, string, user_properties,
+    user_properties_value,
+};
+
+#[wasm_bindgen(typescript_custom_section)]
+const TYPES: &'static str = r#"
+/** Any MQTT 5 Control Packet, told apart by its `type` */
+export type Packet =
+    | ConnectPacket | ConnAckPacket | PublishPacket | PubAckPacket | PubRecPacket | PubRelPacket | PubCompPacket
+    | SubscribePacket | SubAckPacket | UnsubscribePacket | UnsubAckPacket | PingReqPacket | PingRespPacket
+    | DisconnectPacket | AuthPacket;
+
+/** The properties of packets that acknowledge another one */
+export interface AckProperties {
+    reasonString?: string;
+    userProperties?: UserProperties;
+}
+
+export interface ConnectPacket {
+    type: "CONNECT";
+    clientId: string;
+    username?: string;
+    password?: string;
+    will?: Will;
+    cleanStart?: boolean;
+    keepAlive?: number;
+    properties?: {
+        sessionExpiryInterval?: number;
+        receiveMaximum?: number;
+        maximumPacketSize?: number;
+        topicAliasMaximum?: number;
+        requestResponseInformation?: number;
+        requestProblemInformation?: number;
+        userProperties?: UserProperties;
+        authenticationMethod?: string;
+        authenticationData?: Uint8Array;
+    };
+}
+
+export interface ConnAckPacket {
+    type: "CONNACK";
+    sessionPresent?: boolean;
+    reasonCode?: ConnAckReasonCode;
+    properties?: {
+        sessionExpiryInterval?: number;
+        receiveMaximum?: number;
+        maximumQoS?: 0 | 1;
+        retainAvailable?: boolean;
+        maximumPacketSize?: number;
+        assignedClientIdentifier?: string;
+        topicAliasMaximum?: number;
+        reasonString?: string;
+        userProperties?: UserProperties;
+        wildcardSubscriptionAvailable?: boolean;
+        subscriptionIdentifiersAvailable?: boolean;
+        sharedSubscriptionAvailable?: boolean;
+        serverKeepAlive?: number;
+        responseInformation?: string;
+        serverReference?: string;
+        authenticationMethod?: string;
+        authenticationData?: Uint8Array;
+    };
+}
+
+export interface PublishPacket {
+    type: "PUBLISH";
+    dup?: boolean;
+    retain?: boolean;
+    qos?: QoS;
+    topic: string;
+    /** Only carried at QoS 1 and QoS 2 */
+    packetId?: number;
+    payload?: Uint8Array | string;
+    properties?: PublishProperties;
+}
+
+export interface PubAckPacket {
+    type: "PUBACK";
+    packetId: number;
+    reasonCode?: PubAckReasonCode;
+    properties?: AckProperties;
+}
+
+export interface PubRecPacket {
+    type: "PUBREC";
+    packetId: number;
+    reasonCode?: PubRecReasonCode;
+    properties?: AckProperties;
+}
+
+export interface PubRelPacket {
+    type: "PUBREL";
+    packetId: number;
+    reasonCode?: PubRelReasonCode;
+    properties?: AckProperties;
+}
+
+export interface PubCompPacket {
+    type: "PUBCOMP";
+    packetId: number;
+    reasonCode?: PubCompReasonCode;
+    properties?: AckProperties;
+}
+
+export interface Subscription {
+    topicFilter: string;
+    qos?: QoS;
+    noLocal?: boolean;
+    retainAsPublished?: boolean;
+    retainHandling?: RetainHandling;
+}
+
+export interface SubscribePacket {
+    type: "SUBSCRIBE";
+    packetId: number;
+    subscriptions: Subscription[];
+    properties?: {
+        subscriptionIdentifier?: number;
+        userProperties?: UserProperties;
+    };
+}
+
+export interface SubAckPacket {
+    type: "SUBACK";
+    packetId: number;
+    /** One for each Topic Filter of the SUBSCRIBE, in order */
+    reasonCodes: SubAckReasonCode[];
+    properties?: AckProperties;
+}
+
+export interface UnsubscribePacket {
+    type: "UNSUBSCRIBE";
+    packetId: number;
+    topicFilters: string[];
+    properties?: {
+        userProperties?: UserProperties;
+    };
+}
+
+export interface UnsubAckPacket {
+    type: "UNSUBACK";
+    packetId: number;
+    /** One for each Topic Filter of the UNSUBSCRIBE, in order */
+    reasonCodes: UnSubAckReasonCode[];
+    properties?: AckProperties;
+}
+
+export interface PingReqPacket {
+    type: "PINGREQ";
+}
+
+export interface PingRespPacket {
+    type: "PINGRESP";
+}
+
+export interface DisconnectPacket {
+    type: "DISCONNECT";
+    reasonCode?: DisconnectReasonCode;
+    properties?: {
+        sessionExpiryInterval?: number;
+        reasonString?: string;
+        userProperties?: UserProperties;
+        serverReference?: string;
+    };
+}
+
+export interface AuthPacket {
+    type: "AUTH";
+    reasonCode?: AuthReasonCode;
+    properties?: {
+        authenticationMethod?: string;
+        authenticationData?: Uint8Array;
+        reasonString?: string;
+        userProperties?: UserProperties;
+    };
+}
+"#;
+
+/// Decodes a single packet, Fixed Header included, which must take up all of `bytes`.
+/// Throws an `MqttError` when the packet is malformed or breaks the rules of the protocol
+#[wasm_bindgen(js_name = decodePacket, unchecked_return_type = "Packet")]
+pub fn decode_packet(bytes: &[u8]) -> Result<JsValue, JsValue> {
+    let mut buf = Bytes::copy_from_slice(bytes);
+    let packet = <Packet as BufferIO>::read(&mut buf).map_err(js::error)?;
+    if !buf.is_empty() {
+        let consumed = bytes.len() - buf.len();
+        return Err(js::error(MQTTError::IncompleteData(
+            "Packet",
+            consumed,
+            bytes.len(),
+        )));
+    }
+    packet
+        .validate()
+        .map_err(|violation| js::error(violation.into()))?;
+
+    Ok(packet_value(&packet).into())
+}
+
+/// Encodes `packet` as it is sent on the wire, fields left out taking their default value.
+/// Throws a `TypeError` when a field doesn't have the expected type, and an `MqttError` when the packet breaks
+/// the rules of the protocol
+#[wasm_bindgen(js_name = encodePacket)]
+pub fn encode_packet(
+    #[wasm_bindgen(unchecked_param_type = "Packet")] packet: JsValue,
+) -> Result<Vec<u8>, JsValue> {
+    let packet = packet_from(&packet)?;
+    packet
+        .validate()
+        .map_err(|violation| js::error(violation.into()))?;
+
+    let mut buf = BytesMut::new();
+    BufferIO::write(&packet, &mut buf).map_err(js::error)?;
+    Ok(buf.to_vec())
+}
+
+/// `object.properties`, an empty object when it is left out
+fn properties(object: &JsValue) -> Result<JsValue, JsValue> {
+    Ok(get(object, "properties")?.unwrap_or_else(|| Object::new().into()))
+}
+
+fn packet_id(object: &JsValue) -> Result<u16, JsValue> {
+    number(object, "packetId")?.ok_or_else(|| invalid("packetId", "a whole number"))
+}
+
+/// The Reason Code of the packet, Success (or its equivalent) when it is left out
+fn reason_code<T: TryFrom<u8> + Default>(object: &JsValue) -> Result<T, JsValue> {
+    number::<u8>(object, "reasonCode")?
+        .map(|code| {
+            T::try_from(code)
+                .map_err(|_| invalid("reasonCode", "a Reason Code allowed on the packet"))
+        })
+        .transpose()
+        .map(Option::unwrap_or_default)
+}
+
+/// The Reason Codes of a SUBACK or UNSUBACK
+fn reason_codes<T: TryFrom<u8>>(object: &JsValue) -> Result<Vec<T>, JsValue> {
+    let expected = || {
+        invalid(
+            "reasonCodes",
+            "an array of Reason Codes allowed on the packet",
+        )
+    };
+    get(object, "reasonCodes")?
+        .ok_or_else(expected)?
+        .dyn_into::<Array>()
+        .map_err(|_| expected())?
+        .iter()
+        .map(|code| {
+            code.as_f64()
+                .filter(|code| code.fract() == 0.0 && (0.0..=255.0).contains(code))
+                .and_then(|code| T::try_from(code as u8).ok())
+                .ok_or_else(expected)
+        })
+        .collect()
+}
+
+/// The Reason String and User Properties acknowledgements carry
+type AckProperties = (Option<String>, Vec<(String, String)>);
+
+fn ack_properties(object: &JsValue) -> Result<AckProperties, JsValue> {
+    let properties = properties(object)?;
+    Ok((
+        string(&properties, "reasonString")?,
+        user_properties(&properties, "userProperties")?,
+    ))
+}
+
+fn packet_from(object: &JsValue) -> Result<Packet, JsValue> {
+    let kind = string(object, "type")?.ok_or_else(|| invalid("type", "a packet type"))?;
+    let packet = match kind.as_str() {
+        "CONNECT" => Packet::Connect(connect(object)?),
+        "CONNACK" => Packet::ConnAck(connack(object)?),
+        "PUBLISH" => Packet::Publish(publish(object)?),
+        "PUBACK" => {
+            let (reason_string, user_property) = ack_properties(object)?;
+            Packet::PubAck(PubAck {
+                pkid: packet_id(object)?,
+                reason_code: reason_code(object)?,
+                properties: PubAckProperties {
+                    reason_string,
+                    user_property,
+                },
+            })
+        }
+        "PUBREC" => {
+            let (reason_string, user_property) = ack_properties(object)?;
+            Packet::PubRec(PubRec {
+                pkid: packet_id(object)?,
+                reason_code: reason_code(object)?,
+                properties: PubRecProperties {
+                    reason_string,
+                    user_property,
+                },
+            })
+        }
+        "PUBREL" => {
+            let (reason_string, user_property) = ack_properties(object)?;
+            Packet::PubRel(PubRel {
+                pkid: packet_id(object)?,
+                reason_code: reason_code(object)?,
+                properties: PubRelProperties {
+                    reason_string,
+                    user_property,
+                },
+            })
+        }
+        "PUBCOMP" => {
+            let (reason_string, user_property) = ack_properties(object)?;
+            Packet::PubComp(PubComp {
+                pkid: packet_id(object)?,
+                reason_code: reason_code(object)?,
+                properties: PubCompProperties {
+                    reason_string,
+                    user_property,
+                },
+            })
+        }
+        "SUBSCRIBE" => Packet::Subscribe(subscribe(object)?),
+        "SUBACK" => {
+            let (reason_string, user_property) = ack_properties(object)?;
+            Packet::SubAck(SubAck {
+                pkid: packet_id(object)?,
+                payload: reason_codes(object)?,
+                properties: SubAckProperties {
+                    reason_string,
+                    user_property,
+                },
+            })
+        }
+        "UNSUBSCRIBE" => Packet::UnSubscribe(unsubscribe(object)?),
+        "UNSUBACK" => {
+            let (reason_string, user_property) = ack_properties(object)?;
+            Packet::UnSubAck(UnSubAck {
+                pkid: packet_id(object)?,
+                payload: reason_codes(object)?,
+                properties: UnSubAckProperties {
+                    reason_string,
+                    user_property,
+                },
+            })
+        }
+        "PINGREQ" => Packet::PingReq(PingReq {}),
+        "PINGRESP" => Packet::PingResp(PingResp),
+        "DISCONNECT" => Packet::Disconnect(disconnect(object)?),
+        "AUTH" => Packet::Auth(auth(object)?),
+        _ => return Err(invalid("type", "a packet type such as \"PUBLISH\"")),
+    };
+    Ok(packet)
+}
+
+fn connect(object: &JsValue) -> Result<Connect, JsValue> {
+    let properties = properties(object)?;
+    Ok(Connect {
+        client_id: string(object, "clientId")?.unwrap_or_default(),
+        username: string(object, "username")?,
+        password: string(object, "password")?,
+        will: get(object, "will")?
+            .map(|will| js::will(&will))
+            .transpose()?,
+        clean_start: boolean(object, "cleanStart")?.unwrap_or_default(),
+        keep_alive: number(object, "keepAlive")?.unwrap_or_default(),
+        properties: ConnectProperties {
+            session_expiry_interval: number(&properties, "sessionExpiryInterval")?,
+            receive_maximum: number(&properties, "receiveMaximum")?,
+            maximum_packet_size: number(&properties, "maximumPacketSize")?,
+            topic_alias_maximum: number(&properties, "topicAliasMaximum")?,
+            request_response_information: number(&properties, "requestResponseInformation")?,
+            request_problem_information: number(&properties, "requestProblemInformation")?,
+            user_property: user_properties(&properties, "userProperties")?,
+            authentication_method: string(&properties, "authenticationMethod")?,
+            authentication_data: bytes(&properties, "authenticationData")?,
+        },
+        ..Default::default()
+    })
+}
+
+fn connack(object: &JsValue) -> Result<ConnAck, JsValue> {
+    let properties = properties(object)?;
+    let maximum_qos = number::<u8>(&properties, "maximumQoS")?
+        .map(|qos| match qos {
+            0 | 1 => Ok(qos == 1),
+            _ => Err(invalid("maximumQoS", "0 or 1")),
+        })
+        .transpose()?;
+
+    Ok(ConnAck {
+        session_present: boolean(object, "sessionPresent")?.unwrap_or_default(),
+        reason: reason_code(object)?,
+        properties: ConnAckProperties {
+            session_expiry_interval: number(&properties, "sessionExpiryInterval")?,
+            receive_maximum: number(&properties, "receiveMaximum")?,
+            maximum_qos,
+            retain_available: boolean(&properties, "retainAvailable")?,
+            maximum_packet_size: number(&properties, "maximumPacketSize")?,
+            assigned_client_id: string(&properties, "assignedClientIdentifier")?,
+            topic_alias_maximum: number(&properties, "topicAliasMaximum")?,
+            reason_string: string(&properties, "reasonString")?,
+            user_property: user_properties(&properties, "userProperties")?,
+            wildcard_subscription_available: boolean(&properties, "wildcardSubscriptionAvailable")?,
+            subscription_identifiers_available: boolean(
+                &properties,
+                "subscriptionIdentifiersAvailable",
+            )?,
+            shared_subscription_available: boolean(&properties, "sharedSubscriptionAvailable")?,
+            server_keep_alive: number(&properties, "serverKeepAlive")?,
+            response_information: string(&properties, "responseInformation")?,
+            server_reference: string(&properties, "serverReference")?,
+            authentication_method: string(&properties, "authenticationMethod")?,
+            authentication_data: bytes(&properties, "authenticationData")?,
+        },
+    })
+}
+
+fn publish(object: &JsValue) -> Result<Publish, JsValue> {
+    Ok(Publish {
+        dup: boolean(object, "dup")?.unwrap_or_default(),
+        retain: boolean(object, "retain")?.unwrap_or_default(),
+        qos: qos(object, "qos")?,
+        topic: string(object, "topic")?.ok_or_else(|| invalid("topic", "a string"))?,
+        pkid: number(object, "packetId")?,
+        properties: js::publish_properties(&properties(object)?)?,
+        payload: bytes(object, "payload")?.unwrap_or_default(),
+    })
+}
+
+fn subscribe(object: &JsValue) -> Result<Subscribe, JsValue> {
+    let expected = || invalid("subscriptions", "an array of subscriptions");
+    let payload = get(object, "subscriptions")?
+        .ok_or_else(expected)?
+        .dyn_into::<Array>()
+        .map_err(|_| expected())?
+        .iter()
+        .map(|subscription| {
+            let filter = string(&subscription, "topicFilter")?
+                .ok_or_else(|| invalid("topicFilter", "a string"))?;
+            Ok((filter, js::subscription_options(&subscription)?))
+        })
+        .collect::<Result<_, JsValue>>()?;
+    let properties = properties(object)?;
+
+    Ok(Subscribe {
+        pkid: packet_id(object)?,
+        properties: SubscribeProperties {
+            subscription_id: number(&properties, "subscriptionIdentifier")?,
+            user_property: user_properties(&properties, "userProperties")?,
+        },
+        payload,
+    })
+}
+
+fn unsubscribe(object: &JsValue) -> Result<UnSubscribe, JsValue> {
+    let expected = || invalid("topicFilters", "an array of strings");
+    let payload = get(object, "topicFilters")?
+        .ok_or_else(expected)?
+        .dyn_into::<Array>()
+        .map_err(|_| expected())?
+        .iter()
+        .map(|filter| filter.as_string().ok_or_else(expected))
+        .collect::<Result<_, _>>()?;
+
+    Ok(UnSubscribe {
+        pkid: packet_id(object)?,
+        properties: UnSubscribeProperties {
+            user_property: user_properties(&properties(object)?, "userProperties")?,
+        },
+        payload,
+    })
+}
+
+fn disconnect(object: &JsValue) -> Result<Disconnect, JsValue> {
+    let properties = properties(object)?;
+    Ok(Disconnect {
+        reason_code: reason_code(object)?,
+        properties: DisconnectProperties {
+            session_expiry_interval: number(&properties, "sessionExpiryInterval")?,
+            reason_string: string(&properties, "reasonString")?,
+            user_property: user_properties(&properties, "userProperties")?,
+            server_reference: string(&properties, "serverReference")?,
+        },
+    })
+}
+
+fn auth(object: &JsValue) -> Result<Auth, JsValue> {
+    let properties = properties(object)?;
+    Ok(Auth {
+        reason_code: reason_code(object)?,
+        properties: AuthProperties {
+            auth_method: string(&properties, "authenticationMethod")?,
+            auth_data: bytes(&properties, "authenticationData")?,
+            reason_string: string(&properties, "reasonString")?,
+            user_property: user_properties(&properties, "userProperties")?,
+        },
+    })
+}
+
+fn bytes_value(bytes: Option<&Bytes>) -> Option<Uint8Array> {
+    bytes.map(|bytes| Uint8Array::from(&bytes[..]))
+}
+
+fn ack_properties_value(reason_string: Option<&str>, user_property: &[(String, String)]) -> Object {
+    let object = Object::new();
+    set_some(&object, "reasonString", reason_string);
+    set(
+        &object,
+        "userProperties",
+        user_properties_value(user_property),
+    );
+    object
+}
+
+fn reason_codes_value<T: Copy + Into<u8>>(codes: &[T]) -> Array {
+    codes
+        .iter()
+        .map(|code| JsValue::from((*code).into()))
+        .collect()
+}
+
+/// The JavaScript object `decodePacket` returns for `packet`, every property it carries being set
+fn packet_value(packet: &Packet) -> Object {
+    let object = Object::new();
+    set(&object, "type", js::packet_name(packet.packet_type()));
+
+    let properties = match packet {
+        Packet::Connect(connect) => {
+            set(&object, "clientId", connect.client_id.as_str());
+            set_some(&object, "username", connect.username.as_deref());
+            set_some(&object, "password", connect.password.as_deref());
+            set_some(&object, "will", connect.will.as_ref().map(js::will_value));
+            set(&object, "cleanStart", connect.clean_start);
+            set(&object, "keepAlive", connect.keep_alive);
+
+            let properties = &connect.properties;
+            let value = Object::new();
+            set_some(
+                &value,
+                "sessionExpiryInterval",
+                properties.session_expiry_interval,
+            );
+            set_some(&value, "receiveMaximum", properties.receive_maximum);
+            set_some(&value, "maximumPacketSize", properties.maximum_packet_size);
+            set_some(&value, "topicAliasMaximum", properties.topic_alias_maximum);
+            set_some(
+                &value,
+                "requestResponseInformation",
+                properties.request_response_information,
+            );
+            set_some(
+                &value,
+                "requestProblemInformation",
+                properties.request_problem_information,
+            );
+            set(
+                &value,
+                "userProperties",
+                user_properties_value(&properties.user_property),
+            );
+            set_some(
+                &value,
+                "authenticationMethod",
+                properties.authentication_method.as_deref(),
+            );
+            set_some(
+                &value,
+                "authenticationData",
+                bytes_value(properties.authentication_data.as_ref()),
+            );
+            Some(value)
+        }
+        Packet::ConnAck(connack) => {
+            set(&object, "sessionPresent", connack.session_present);
+            set(&object, "reasonCode", connack.reason as u8);
+
+            let properties = &connack.properties;
+            let value = Object::new();
+            set_some(
+                &value,
+                "sessionExpiryInterval",
+                properties.session_expiry_interval,
+            );
+            set_some(&value, "receiveMaximum", properties.receive_maximum);
+            set_some(&value, "maximumQoS", properties.maximum_qos.map(u8::from));
+            set_some(&value, "retainAvailable", properties.retain_available);
+            set_some(&value, "maximumPacketSize", properties.maximum_packet_size);
+            set_some(
+                &value,
+                "assignedClientIdentifier",
+                properties.assigned_client_id.as_deref(),
+            );
+            set_some(&value, "topicAliasMaximum", properties.topic_alias_maximum);
+            set_some(&value, "reasonString", properties.reason_string.as_deref());
+            set(
+                &value,
+                "userProperties",
+                user_properties_value(&properties.user_property),
+            );
+            set_some(
+                &value,
+                "wildcardSubscriptionAvailable",
+                properties.wildcard_subscription_available,
+            );
+            set_some(
+                &value,
+                "subscriptionIdentifiersAvailable",
+                properties.subscription_identifiers_available,
+            );
+            set_some(
+                &value,
+                "sharedSubscriptionAvailable",
+                properties.shared_subscription_available,
+            );
+            set_some(&value, "serverKeepAlive", properties.server_keep_alive);
+            set_some(
+                &value,
+                "responseInformation",
+                properties.response_information.as_deref(),
+            );
+            set_some(
+                &value,
+                "serverReference",
+                properties.server_reference.as_deref(),
+            );
+            set_some(
+                &value,
+                "authenticationMethod",
+                properties.authentication_method.as_deref(),
+            );
+            set_some(
+                &value,
+                "authenticationData",
+                bytes_value(properties.authentication_data.as_ref()),
+            );
+            Some(value)
+        }
+        Packet::Publish(publish) => {
+            set(&object, "dup", publish.dup);
+            set(&object, "retain", publish.retain);
+            set(&object, "qos", publish.qos);
+            set(&object, "topic", publish.topic.as_str());
+            set_some(&object, "packetId", publish.pkid);
+            set(&object, "payload", Uint8Array::from(&publish.payload[..]));
+            Some(js::publish_properties_value(&publish.properties))
+        }
+        Packet::PubAck(puback) => {
+            set(&object, "packetId", puback.pkid);
+            set(&object, "reasonCode", puback.reason_code as u8);
+            let properties = &puback.properties;
+            Some(ack_properties_value(
+                properties.reason_string.as_deref(),
+                &properties.user_property,
+            ))
+        }
+        Packet::PubRec(pubrec) => {
+            set(&object, "packetId", pubrec.pkid);
+            set(&object, "reasonCode", pubrec.reason_code as u8);
+            let properties = &pubrec.properties;
+            Some(ack_properties_value(
+                properties.reason_string.as_deref(),
+                &properties.user_property,
+            ))
+        }
+        Packet::PubRel(pubrel) => {
+            set(&object, "packetId", pubrel.pkid);
+            set(&object, "reasonCode", pubrel.reason_code as u8);
+            let properties = &pubrel.properties;
+            Some(ack_properties_value(
+                properties.reason_string.as_deref(),
+                &properties.user_property,
+            ))
+        }
+        Packet::PubComp(pubcomp) => {
+            set(&object, "packetId", pubcomp.pkid);
+            set(&object, "reasonCode", pubcomp.reason_code as u8);
+            let properties = &pubcomp.properties;
+            Some(ack_properties_value(
+                properties.reason_string.as_deref(),
+                &properties.user_property,
+            ))
+        }
+        Packet::Subscribe(subscribe) => {
+            set(&object, "packetId", subscribe.pkid);
+            let subscriptions = subscribe
+                .payload
+                .iter()
+                .map(|(filter, options)| {
+                    let subscription = Object::new();
+                    set(&subscription, "topicFilter", filter.as_str());
+                    set(&subscription, "qos", options.qos);
+                    set(&subscription, "noLocal", options.no_local);
+                    set(
+                        &subscription,
+                        "retainAsPublished",
+                        options.retain_as_published,
+                    );
+                    set(&subscription, "retainHandling", options.retain_handling);
+                    JsValue::from(subscription)
+                })
+                .collect::<Array>();
+            set(&object, "subscriptions", subscriptions);
+
+            let value = Object::new();
+            set_some(
+                &value,
+                "subscriptionIdentifier",
+                subscribe.properties.subscription_id.map(|id| id as f64),
+            );
+            set(
+                &value,
+                "userProperties",
+                user_properties_value(&subscribe.properties.user_property),
+            );
+            Some(value)
+        }
+        Packet::SubAck(suback) => {
+            set(&object, "packetId", suback.pkid);
+            set(&object, "reasonCodes", reason_codes_value(&suback.payload));
+            let properties = &suback.properties;
+            Some(ack_properties_value(
+                properties.reason_string.as_deref(),
+                &properties.user_property,
+            ))
+        }
+        Packet::UnSubscribe(unsubscribe) => {
+            set(&object, "packetId", unsubscribe.pkid);
+            let filters = unsubscribe
+                .payload
+                .iter()
+                .map(|filter| JsValue::from(filter.as_str()))
+                .collect::<Array>();
+            set(&object, "topicFilters", filters);
+
+            let value = Object::new();
+            set(
+                &value,
+                "userProperties",
+                user_properties_value(&unsubscribe.properties.user_property),
+            );
+            Some(value)
+        }
+        Packet::UnSubAck(unsuback) => {
+            set(&object, "packetId", unsuback.pkid);
+            set(
+                &object,
+                "reasonCodes",
+                reason_codes_value(&unsuback.payload),
+            );
+            let properties = &unsuback.properties;
+            Some(ack_properties_value(
+                properties.reason_string.as_deref(),
+                &properties.user_property,
+            ))
+        }
+        Packet::PingReq(_) | Packet::PingResp(_) => None,
+        Packet::Disconnect(disconnect) => {
+            set(&object, "reasonCode", disconnect.reason_code as u8);
+
+            let properties = &disconnect.properties;
+            let value = Object::new();
+            set_some(
+                &value,
+                "sessionExpiryInterval",
+                properties.session_expiry_interval,
+            );
+            set_some(&value, "reasonString", properties.reason_string.as_deref());
+            set(
+                &value,
+                "userProperties",
+                user_properties_value(&properties.user_property),
+            );
+            set_some(
+                &value,
+                "serverReference",
+                properties.server_reference.as_deref(),
+            );
+            Some(value)
+        }
+        Packet::Auth(auth) => {
+            set(&object, "reasonCode", auth.reason_code as u8);
+
+            let properties = &auth.properties;
+            let value = Object::new();
+            set_some(
+                &value,
+                "authenticationMethod",
+                properties.auth_method.as_deref(),
+            );
+            set_some(
+                &value,
+                "authenticationData",
+                bytes_value(properties.auth_data.as_ref()),
+            );
+            set_some(&value, "reasonString", properties.reason_string.as_deref());
+            set(
+                &value,
+                "userProperties",
+                user_properties_value(&properties.user_property),
+            );
+            Some(value)
+        }
+    };
+    set_some(&object, "properties", properties);
+    object
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod tests {
+    use std::cell::RefCell;
+
+    use js_sys::{Reflect, JSON};
+    use proptest::test_runner::{Config, TestRunner};
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use crate::{
+        retest_utils::strategies,
+        v5::{
+            commons::qos::QoS,
+            packet::{
+                connect::will::{Will, WillProperties},
+                publish::PublishProperties,
+                subscribe::{RetainHandling, SubscriptionOptions},
+            },
+        },
+    };
+
+    use super::*;
+
+    fn field(value: &JsValue, key: &str) -> JsValue {
+        Reflect::get(value, &JsValue::from_str(key)).unwrap()
+    }
+
+    fn encode(packet: &Packet) -> Vec<u8> {
+        let mut buf = BytesMut::new();
+        BufferIO::write(packet, &mut buf).unwrap();
+        buf.to_vec()
+    }
+
+    /// The `[name, value]` pairs of a `userProperties` array
+    fn pairs(value: &JsValue) -> Vec<Vec<String>> {
+        Array::from(value)
+            .iter()
+            .map(|pair| {
+                Array::from(&pair)
+                    .iter()
+                    .map(|part| part.as_string().unwrap())
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn user_property(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs
+            .iter()
+            .map(|&(name, value)| (name.into(), value.into()))
+            .collect()
+    }
+
+    #[wasm_bindgen_test]
+    fn every_packet_round_trips_through_javascript() {
+        let mut runner = TestRunner::new(Config {
+            // there is no file system to record failures in
+            failure_persistence: None,
+            ..Config::default()
+        });
+        let seen = RefCell::new(Vec::new());
+        runner
+            .run(&strategies::packet(), |packet| {
+                let bytes = encode(&packet);
+                let value = decode_packet(&bytes).unwrap();
+                let name = js::packet_name(packet.packet_type());
+                assert_eq!(field(&value, "type"), name);
+                if !seen.borrow().contains(&name) {
+                    seen.borrow_mut().push(name);
+                }
+
+                assert_eq!(encode_packet(value).unwrap(), bytes);
+                Ok(())
+            })
+            .unwrap();
+        assert_eq!(seen.borrow().len(), 15, "packet types generated: {seen:?}");
+    }
+
+    #[wasm_bindgen_test]
+    fn decodes_binary_data_as_bytes() {
+        let publish = Publish {
+            qos: QoS::Two,
+            pkid: Some(9),
+            topic: String::from("sensors/7"),
+            payload: Bytes::from_static(&[0, 159, 146, 150, 255]),
+            properties: PublishProperties {
+                correlation_data: Some(Bytes::from_static(&[0xC3, 0x28])),
+                user_property: user_property(&[("unit", "K"), ("unit", "°C"), ("", "")]),
+                subscription_identifier: vec![1, 268_435_455],
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let bytes = encode(&Packet::Publish(publish));
+        let value = decode_packet(&bytes).unwrap();
+
+        assert_eq!(field(&value, "qos"), 2);
+        assert_eq!(field(&value, "packetId"), 9);
+        let payload = field(&value, "payload").dyn_into::<Uint8Array>().unwrap();
+        assert_eq!(payload.to_vec(), [0, 159, 146, 150, 255]);
+
+        let properties = field(&value, "properties");
+        let correlation = field(&properties, "correlationData")
+            .dyn_into::<Uint8Array>()
+            .unwrap();
+        assert_eq!(correlation.to_vec(), [0xC3, 0x28]);
+        // repeated names are kept, in order
+        assert_eq!(
+            pairs(&field(&properties, "userProperties")),
+            [vec!["unit", "K"], vec!["unit", "°C"], vec!["", ""]]
+        );
+        let identifiers = Array::from(&field(&properties, "subscriptionIdentifiers"));
+        assert_eq!(identifiers.to_vec(), [1, 268_435_455]);
+        // absent properties are left out rather than set to `undefined` or `null`
+        assert!(!Reflect::has(&properties, &"contentType".into()).unwrap());
+
+        assert_eq!(encode_packet(value).unwrap(), bytes);
+    }
+
+    #[wasm_bindgen_test]
+    fn decodes_the_will_and_subscriptions() {
+        let connect = Connect {
+            client_id: String::from("sensor-7"),
+            password: Some(String::from("secret")),
+            keep_alive: 30,
+            will: Some(Will {
+                topic: String::from("sensors/7/status"),
+                payload: Bytes::from_static(b"offline"),
+                qos: QoS::One,
+                retain: true,
+                properties: WillProperties {
+                    delay_interval: Some(5),
+                    correlation_data: Some(Bytes::from_static(&[7])),
+                    user_property: user_property(&[("site", "north")]),
+                    ..Default::default()
+                },
+            }),
+            properties: ConnectProperties {
+                authentication_method: Some(String::from("SCRAM-SHA-256")),
+                authentication_data: Some(Bytes::from_static(&[1, 2, 3])),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let bytes = encode(&Packet::Connect(connect));
+        let value = decode_packet(&bytes).unwrap();
+
+        assert_eq!(field(&value, "clientId"), "sensor-7");
+        assert_eq!(field(&value, "password"), "secret");
+        assert!(field(&value, "username").is_undefined());
+        let will = field(&value, "will");
+        assert_eq!(field(&will, "topic"), "sensors/7/status");
+        assert_eq!(
+            Uint8Array::from(field(&will, "payload")).to_vec(),
+            b"offline"
+        );
+        assert_eq!(field(&will, "retain"), true);
+        let will_properties = field(&will, "properties");
+        assert_eq!(field(&will_properties, "delayInterval"), 5);
+        assert_eq!(
+            pairs(&field(&will_properties, "userProperties")),
+            [vec!["site", "north"]]
+        );
+        let properties = field(&value, "properties");
+        assert_eq!(
+            Uint8Array::from(field(&properties, "authenticationData")).to_vec(),
+            [1, 2, 3]
+        );
+        assert_eq!(encode_packet(value).unwrap(), bytes);
+
+        let subscribe = Subscribe {
+            pkid: 3,
+            payload: vec![(
+                String::from("sensors/+/status"),
+                SubscriptionOptions {
+                    qos: QoS::One,
+                    no_local: true,
+                    retain_as_published: true,
+                    retain_handling: RetainHandling::Two,
+                },
+            )],
+            properties: SubscribeProperties {
+                subscription_id: Some(268_435_455),
+                user_property: Vec::new(),
+            },
+        };
+        let bytes = encode(&Packet::Subscribe(subscribe));
+        let value = decode_packet(&bytes).unwrap();
+
+        let subscription = Array::from(&field(&value, "subscriptions")).get(0);
+        assert_eq!(field(&subscription, "topicFilter"), "sensors/+/status");
+        assert_eq!(field(&subscription, "qos"), 1);
+        assert_eq!(field(&subscription, "noLocal"), true);
+        assert_eq!(field(&subscription, "retainAsPublished"), true);
+        assert_eq!(field(&subscription, "retainHandling"), 2);
+        let properties = field(&value, "properties");
+        assert_eq!(field(&properties, "subscriptionIdentifier"), 268_435_455);
+        assert_eq!(encode_packet(value).unwrap(), bytes);
+    }
+
+    #[wasm_bindgen_test]
+    fn encodes_left_out_fields_with_their_default() {
+        let packet = JSON::parse(r#"{ "type": "PUBACK", "packetId": 4 }"#).unwrap();
+        let bytes = encode_packet(packet).unwrap();
+        assert_eq!(bytes, [0x40, 0x02, 0x00, 0x04]);
+
+        let packet =
+            JSON::parse(r#"{ "type": "PUBLISH", "topic": "a", "payload": "°C" }"#).unwrap();
+        let value = decode_packet(&encode_packet(packet).unwrap()).unwrap();
+        assert_eq!(field(&value, "qos"), 0);
+        assert!(field(&value, "packetId").is_undefined());
+        assert_eq!(
+            Uint8Array::from(field(&value, "payload")).to_vec(),
+            "°C".as_bytes()
+        );
+    }
+
+    #[wasm_bindgen_test]
+    fn throws_malformed_packets_as_mqtt_errors() {
+        // SUBSCRIBE with the reserved flags cleared
+        let mut bytes = encode(&Packet::Subscribe(Subscribe {
+            pkid: 1,
+            payload: vec![(String::from("a"), SubscriptionOptions::default())],
+            ..Default::default()
+        }));
+        bytes[0] = 0x80;
+        let err = decode_packet(&bytes).unwrap_err();
+        assert!(err.is_instance_of::<js_sys::Error>());
+        assert_eq!(field(&err, "name"), "MqttError");
+        assert_eq!(field(&err, "kind"), "malformed");
+        assert_eq!(field(&err, "packetType"), "SUBSCRIBE");
+        assert_eq!(field(&err, "spec"), "MQTT-2.1.3-1");
+        assert_eq!(field(&err, "reasonCode"), 0x81);
+
+        // a packet cut short, then one followed by another
+        let bytes = encode(&Packet::PingReq(PingReq {}));
+        let err = decode_packet(&bytes[..1]).unwrap_err();
+        assert_eq!(field(&err, "kind"), "malformed");
+        let err = decode_packet(&[bytes.clone(), bytes].concat()).unwrap_err();
+        assert_eq!(field(&err, "kind"), "malformed");
+        assert!(field(&err, "spec").is_undefined());
+        assert!(field(&err, "reasonCode").is_undefined());
+    }
+
+    #[wasm_bindgen_test]
+    fn throws_protocol_violations_as_mqtt_errors() {
+        let publish = Publish {
+            qos: QoS::One,
+            pkid: Some(1),
+            topic: String::from("sensors/+"),
+            ..Default::default()
+        };
+        let err = decode_packet(&encode(&Packet::Publish(publish))).unwrap_err();
+        assert_eq!(field(&err, "name"), "MqttError");
+        assert_eq!(field(&err, "kind"), "protocol");
+        assert_eq!(field(&err, "packetType"), "PUBLISH");
+        assert_eq!(field(&err, "spec"), "MQTT-3.3.2-2");
+        assert_eq!(field(&err, "reasonCode"), 0x90);
+
+        let packet =
+            JSON::parse(r#"{ "type": "PUBLISH", "topic": "a", "qos": 0, "packetId": 1 }"#).unwrap();
+        let err = encode_packet(packet).unwrap_err();
+        assert_eq!(field(&err, "kind"), "protocol");
+        assert_eq!(field(&err, "spec"), "MQTT-2.2.1-2");
+        assert_eq!(field(&err, "reasonCode"), 0x82);
+    }
+
+    #[wasm_bindgen_test]
+    fn throws_type_errors_on_fields_of_the_wrong_type() {
+        let message = |json: &str| {
+            let err = encode_packet(JSON::parse(json).unwrap()).unwrap_err();
+            String::from(err.dyn_into::<js_sys::TypeError>().unwrap().message())
+        };
+        assert_eq!(
+            message(r#"{ "type": "PUBLISHED" }"#),
+            "type: expected a packet type such as \"PUBLISH\""
+        );
+        assert_eq!(
+            message(r#"{ "type": "PUBACK" }"#),
+            "packetId: expected a whole number"
+        );
+        assert_eq!(
+            message(r#"{ "type": "SUBACK", "packetId": 1, "reasonCodes": [3] }"#),
+            "reasonCodes: expected an array of Reason Codes allowed on the packet"
+        );
+        assert_eq!(
+            message(r#"{ "type": "CONNACK", "properties": { "maximumQoS": 2 } }"#),
+            "maximumQoS: expected 0 or 1"
+        );
+    }
+}
@@ -0,0 +1,707 @@
+use std::num::NonZero;
+
+use bytes::Bytes;
+use js_sys::{Array, Object, Reflect, TypeError, Uint8Array};
+use wasm_bindgen::{prelude::wasm_bindgen, JsCast, JsValue};
+
+use crate::v5::{
+    client::ConnectOptions,
+    commons::{
+        error::{ErrorKind, MQTTError, Refusal},
+        packet_type::PacketType,
+        qos::QoS,
+    },
+    packet::{
+        connect::will::{Will, WillProperties},
+        publish::{Publish, PublishProperties},
+        subscribe::{RetainHandling, SubscribeProperties, SubscriptionOptions},
+        unsubscribe::UnSubscribeProperties,
+    },
+};
+
+#[wasm_bindgen(typescript_custom_section)]
+const TYPES: &'static str = r#"
+/** Pairs of names and values, in order, a name may appear more than once */
+export type UserProperties = [string, string][];
+
+/** What the CONNECT is made of (3.1) */
+export interface ConnectOptions {
+    /** Left out for the server to assign one */
+    clientId?: string;
+    cleanStart?: boolean;
+    /** Seconds between control packets, 0 turns the Keep Alive off */
+    keepAlive?: number;
+    /** Seconds the session outlives the connection */
+    sessionExpiryInterval?: number;
+    username?: string;
+    password?: string;
+    /** QoS 1 and QoS 2 messages the client is willing to process at once */
+    receiveMaximum?: number;
+    /** Largest packet the client accepts, in bytes */
+    maximumPacketSize?: number;
+    userProperties?: UserProperties;
+    will?: Will;
+}
+
+/** The message the server publishes when the connection is lost (3.1.2.5) */
+export interface Will {
+    topic: string;
+    payload?: Uint8Array | string;
+    qos?: QoS;
+    retain?: boolean;
+    properties?: WillProperties;
+}
+
+export interface WillProperties {
+    /** Seconds the server waits before publishing the will */
+    delayInterval?: number;
+    payloadFormatIndicator?: number;
+    messageExpiryInterval?: number;
+    contentType?: string;
+    responseTopic?: string;
+    correlationData?: Uint8Array;
+    userProperties?: UserProperties;
+}
+
+export interface PublishProperties {
+    payloadFormatIndicator?: number;
+    messageExpiryInterval?: number;
+    topicAlias?: number;
+    responseTopic?: string;
+    correlationData?: Uint8Array;
+    userProperties?: UserProperties;
+    /** Identifiers of the subscriptions a message was delivered for */
+    subscriptionIdentifiers?: number[];
+    contentType?: string;
+}
+
+export interface PublishOptions {
+    qos?: QoS;
+    retain?: boolean;
+    properties?: PublishProperties;
+}
+
+export interface SubscribeOptions {
+    qos?: QoS;
+    noLocal?: boolean;
+    retainAsPublished?: boolean;
+    retainHandling?: RetainHandling;
+    subscriptionIdentifier?: number;
+    userProperties?: UserProperties;
+}
+
+export interface UnsubscribeOptions {
+    userProperties?: UserProperties;
+}
+
+/** A message the server delivered */
+export interface Message {
+    topic: string;
+    payload: Uint8Array;
+    qos: QoS;
+    retain: boolean;
+    properties: PublishProperties;
+}
+
+/** Why the connection ended */
+export interface Close {
+    /** Set when the connection ended with a DISCONNECT */
+    reasonCode?: DisconnectReasonCode;
+    reason?: string;
+}
+
+export type PacketType =
+    | "CONNECT" | "CONNACK" | "PUBLISH" | "PUBACK" | "PUBREC" | "PUBREL" | "PUBCOMP"
+    | "SUBSCRIBE" | "SUBACK" | "UNSUBSCRIBE" | "UNSUBACK" | "PINGREQ" | "PINGRESP" | "DISCONNECT" | "AUTH";
+
+/** What promises are rejected with, and what packets that can't be decoded or encoded are thrown as */
+export interface MqttError extends Error {
+    name: "MqttError";
+    /** How to react to the failure: retry on `io`, alert on `refused`, give up on `protocol` or `malformed` */
+    kind: "io" | "protocol" | "malformed" | "refused" | "limits" | "closed";
+    /** The packet responsible, when it is known */
+    packetType?: PacketType;
+    /** The normative statement (or section) of the specification breached */
+    spec?: string;
+    /** The Reason Code the server refused the operation with, or the one the breach is disconnected with */
+    reasonCode?: number;
+    reasonString?: string;
+    userProperties?: UserProperties;
+}
+"#;
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(typescript_type = "ConnectOptions")]
+    pub type JsConnectOptions;
+
+    #[wasm_bindgen(typescript_type = "PublishOptions")]
+    pub type JsPublishOptions;
+
+    #[wasm_bindgen(typescript_type = "SubscribeOptions")]
+    pub type JsSubscribeOptions;
+
+    #[wasm_bindgen(typescript_type = "UnsubscribeOptions")]
+    pub type JsUnsubscribeOptions;
+}
+
+/// `object[key]`, `None` when it is `undefined` or `null`
+pub(crate) fn get(object: &JsValue, key: &str) -> Result<Option<JsValue>, JsValue> {
+    let value = Reflect::get(object, &JsValue::from_str(key))?;
+    Ok((!value.is_undefined() && !value.is_null()).then_some(value))
+}
+
+pub(crate) fn invalid(key: &str, expected: &str) -> JsValue {
+    TypeError::new(&format!("{key}: expected {expected}")).into()
+}
+
+pub(crate) fn string(object: &JsValue, key: &str) -> Result<Option<String>, JsValue> {
+    get(object, key)?
+        .map(|value| value.as_string().ok_or_else(|| invalid(key, "a string")))
+        .transpose()
+}
+
+pub(crate) fn boolean(object: &JsValue, key: &str) -> Result<Option<bool>, JsValue> {
+    get(object, key)?
+        .map(|value| value.as_bool().ok_or_else(|| invalid(key, "a boolean")))
+        .transpose()
+}
+
+/// A whole number that fits in `T`
+pub(crate) fn number<T: TryFrom<u64>>(object: &JsValue, key: &str) -> Result<Option<T>, JsValue> {
+    get(object, key)?
+        .map(|value| {
+            value
+                .as_f64()
+                .filter(|n| n.fract() == 0.0 && *n >= 0.0)
+                .and_then(|n| T::try_from(n as u64).ok())
+                .ok_or_else(|| invalid(key, "a whole number in range"))
+        })
+        .transpose()
+}
+
+pub(crate) fn bytes(object: &JsValue, key: &str) -> Result<Option<Bytes>, JsValue> {
+    get(object, key)?
+        .map(|value| payload(&value).map_err(|_| invalid(key, "a Uint8Array or a string")))
+        .transpose()
+}
+
+pub(crate) fn qos(object: &JsValue, key: &str) -> Result<QoS, JsValue> {
+    number::<u8>(object, key)?
+        .map(|qos| QoS::try_from(qos).map_err(|_| invalid(key, "0, 1 or 2")))
+        .transpose()
+        .map(Option::unwrap_or_default)
+}
+
+/// A payload given as bytes, or as a string sent UTF-8 encoded
+pub(crate) fn payload(value: &JsValue) -> Result<Bytes, JsValue> {
+    if let Some(text) = value.as_string() {
+        return Ok(Bytes::from(text));
+    }
+    value
+        .dyn_ref::<Uint8Array>()
+        .map(|array| Bytes::from(array.to_vec()))
+        .ok_or_else(|| invalid("payload", "a Uint8Array or a string"))
+}
+
+pub(crate) fn user_properties(
+    object: &JsValue,
+    key: &str,
+) -> Result<Vec<(String, String)>, JsValue> {
+    let Some(pairs) = get(object, key)? else {
+        return Ok(Vec::new());
+    };
+    let expected = || invalid(key, "an array of [name, value] pairs");
+    pairs
+        .dyn_into::<Array>()
+        .map_err(|_| expected())?
+        .iter()
+        .map(|pair| {
+            let pair = pair.dyn_into::<Array>().map_err(|_| expected())?;
+            match (pair.get(0).as_string(), pair.get(1).as_string()) {
+                (Some(name), Some(value)) if pair.length() == 2 => Ok((name, value)),
+                _ => Err(expected()),
+            }
+        })
+        .collect()
+}
+
+pub(crate) fn connect_options(options: &JsValue) -> Result<ConnectOptions, JsValue> {
+    let mut connect = ConnectOptions {
+        client_id: String::new(),
+        ..Default::default()
+    };
+    if options.is_undefined() || options.is_null() {
+        return Ok(connect);
+    }
+
+    if let Some(client_id) = string(options, "clientId")? {
+        connect.client_id = client_id;
+    }
+    if let Some(clean_start) = boolean(options, "cleanStart")? {
+        connect.clean_start = clean_start;
+    }
+    if let Some(keep_alive) = number(options, "keepAlive")? {
+        connect.keep_alive = keep_alive;
+    }
+    if let Some(interval) = number(options, "sessionExpiryInterval")? {
+        connect.session_expiry_interval = Some(interval);
+    }
+    connect.username = string(options, "username")?;
+    connect.password = string(options, "password")?;
+    if let Some(receive_max) = number(options, "receiveMaximum")? {
+        connect.client_receive_max =
+            NonZero::new(receive_max).ok_or_else(|| invalid("receiveMaximum", "at least 1"))?;
+    }
+    if let Some(max_size) = number(options, "maximumPacketSize")? {
+        connect.client_max_size =
+            NonZero::new(max_size).ok_or_else(|| invalid("maximumPacketSize", "at least 1"))?;
+    }
+    connect.user_property = user_properties(options, "userProperties")?;
+    connect.will = get(options, "will")?
+        .map(|will| self::will(&will))
+        .transpose()?;
+
+    Ok(connect)
+}
+
+pub(crate) fn will(will: &JsValue) -> Result<Will, JsValue> {
+    let properties = get(will, "properties")?.unwrap_or_else(|| Object::new().into());
+    Ok(Will {
+        topic: string(will, "topic")?.ok_or_else(|| invalid("will.topic", "a string"))?,
+        payload: bytes(will, "payload")?.unwrap_or_default(),
+        qos: qos(will, "qos")?,
+        retain: boolean(will, "retain")?.unwrap_or_default(),
+        properties: WillProperties {
+            delay_interval: number(&properties, "delayInterval")?,
+            payload_format_indicator: number(&properties, "payloadFormatIndicator")?,
+            message_expiry_interval: number(&properties, "messageExpiryInterval")?,
+            content_type: string(&properties, "contentType")?,
+            response_topic: string(&properties, "responseTopic")?,
+            correlation_data: bytes(&properties, "correlationData")?,
+            user_property: user_properties(&properties, "userProperties")?,
+        },
+    })
+}
+
+/// The QoS, retain flag and properties a message is published with
+pub(crate) fn publish_options(
+    options: &JsValue,
+) -> Result<(QoS, bool, PublishProperties), JsValue> {
+    if options.is_undefined() || options.is_null() {
+        return Ok((QoS::Zero, false, PublishProperties::default()));
+    }
+
+    let properties = match get(options, "properties")? {
+        Some(properties) => publish_properties(&properties)?,
+        None => PublishProperties::default(),
+    };
+    Ok((
+        qos(options, "qos")?,
+        boolean(options, "retain")?.unwrap_or_default(),
+        properties,
+    ))
+}
+
+pub(crate) fn publish_properties(properties: &JsValue) -> Result<PublishProperties, JsValue> {
+    let subscription_identifier = match get(properties, "subscriptionIdentifiers")? {
+        Some(ids) => ids
+            .dyn_into::<Array>()
+            .map_err(|_| invalid("subscriptionIdentifiers", "an array"))?
+            .iter()
+            .map(|id| {
+                id.as_f64()
+                    .filter(|id| id.fract() == 0.0 && *id >= 1.0)
+                    .map(|id| id as usize)
+                    .ok_or_else(|| invalid("subscriptionIdentifiers", "whole numbers"))
+            })
+            .collect::<Result<_, _>>()?,
+        None => Vec::new(),
+    };
+
+    Ok(PublishProperties {
+        payload_format_indicator: number(properties, "payloadFormatIndicator")?,
+        message_expiry_internal: number(properties, "messageExpiryInterval")?,
+        topic_alias: number(properties, "topicAlias")?,
+        response_topic: string(properties, "responseTopic")?,
+        correlation_data: bytes(properties, "correlationData")?,
+        user_property: user_properties(properties, "userProperties")?,
+        subscription_identifier,
+        content_type: string(properties, "contentType")?,
+    })
+}
+
+/// The options of a single subscription, read off `options`
+pub(crate) fn subscription_options(options: &JsValue) -> Result<SubscriptionOptions, JsValue> {
+    let retain_handling = number::<u8>(options, "retainHandling")?
+        .map(|handling| {
+            RetainHandling::try_from(handling).map_err(|_| invalid("retainHandling", "0, 1 or 2"))
+        })
+        .transpose()?
+        .unwrap_or_default();
+    Ok(SubscriptionOptions {
+        qos: qos(options, "qos")?,
+        no_local: boolean(options, "noLocal")?.unwrap_or_default(),
+        retain_as_published: boolean(options, "retainAsPublished")?.unwrap_or_default(),
+        retain_handling,
+    })
+}
+
+pub(crate) fn subscribe_options(
+    options: &JsValue,
+) -> Result<(SubscriptionOptions, SubscribeProperties), JsValue> {
+    if options.is_undefined() || options.is_null() {
+        return Ok(Default::default());
+    }
+
+    let subscription = subscription_options(options)?;
+    let properties = SubscribeProperties {
+        subscription_id: number(options, "subscriptionIdentifier")?,
+        user_property: user_properties(options, "userProperties")?,
+    };
+    Ok((subscription, properties))
+}
+
+pub(crate) fn unsubscribe_options(options: &JsValue) -> Result<UnSubscribeProperties, JsValue> {
+    if options.is_undefined() || options.is_null() {
+        return Ok(Default::default());
+    }
+
+    Ok(UnSubscribeProperties {
+        user_property: user_properties(options, "userProperties")?,
+    })
+}
+
+pub(crate) fn set(object: &Object, key: &str, value: impl Into<JsValue>) {
+    // only fails on frozen objects, and these were just made
+    let _ = Reflect::set(object, &JsValue::from_str(key), &value.into());
+}
+
+pub(crate) fn set_some(object: &Object, key: &str, value: Option<impl Into<JsValue>>) {
+    if let Some(value) = value {
+        set(object, key, value);
+    }
+}
+
+pub(crate) fn user_properties_value(user_property: &[(String, String)]) -> Array {
+    user_property
+        .iter()
+        .map(|(name, value)| Array::of2(&name.into(), &value.into()))
+        .collect()
+}
+
+pub(crate) fn publish_properties_value(properties: &PublishProperties) -> Object {
+    let object = Object::new();
+    set_some(
+        &object,
+        "payloadFormatIndicator",
+        properties.payload_format_indicator,
+    );
+    set_some(
+        &object,
+        "messageExpiryInterval",
+        properties.message_expiry_internal,
+    );
+    set_some(&object, "topicAlias", properties.topic_alias);
+    set_some(
+        &object,
+        "responseTopic",
+        properties.response_topic.as_deref(),
+    );
+    set_some(
+        &object,
+        "correlationData",
+        properties.correlation_data.as_deref().map(Uint8Array::from),
+    );
+    set(
+        &object,
+        "userProperties",
+        user_properties_value(&properties.user_property),
+    );
+    let identifiers = properties
+        .subscription_identifier
+        .iter()
+        .map(|id| JsValue::from(*id as f64))
+        .collect::<Array>();
+    set(&object, "subscriptionIdentifiers", identifiers);
+    set_some(&object, "contentType", properties.content_type.as_deref());
+    object
+}
+
+pub(crate) fn will_value(will: &Will) -> Object {
+    let properties = Object::new();
+    let will_properties = &will.properties;
+    set_some(&properties, "delayInterval", will_properties.delay_interval);
+    set_some(
+        &properties,
+        "payloadFormatIndicator",
+        will_properties.payload_format_indicator,
+    );
+    set_some(
+        &properties,
+        "messageExpiryInterval",
+        will_properties.message_expiry_interval,
+    );
+    set_some(
+        &properties,
+        "contentType",
+        will_properties.content_type.as_deref(),
+    );
+    set_some(
+        &properties,
+        "responseTopic",
+        will_properties.response_topic.as_deref(),
+    );
+    set_some(
+        &properties,
+        "correlationData",
+        will_properties
+            .correlation_data
+            .as_deref()
+            .map(Uint8Array::from),
+    );
+    set(
+        &properties,
+        "userProperties",
+        user_properties_value(&will_properties.user_property),
+    );
+
+    let object = Object::new();
+    set(&object, "topic", will.topic.as_str());
+    set(&object, "payload", Uint8Array::from(&will.payload[..]));
+    set(&object, "qos", will.qos);
+    set(&object, "retain", will.retain);
+    set(&object, "properties", properties);
+    object
+}
+
+/// The `Message` handed to `onMessage`
+pub(crate) fn message(publish: &Publish) -> JsValue {
+    let object = Object::new();
+    set(&object, "topic", publish.topic.as_str());
+    set(&object, "payload", Uint8Array::from(&publish.payload[..]));
+    set(&object, "qos", publish.qos);
+    set(&object, "retain", publish.retain);
+    set(
+        &object,
+        "properties",
+        publish_properties_value(&publish.properties),
+    );
+    object.into()
+}
+
+/// The `Close` handed to `onClose`
+pub(crate) fn close(reason_code: Option<u8>, reason: Option<String>) -> JsValue {
+    let object = Object::new();
+    set_some(&object, "reasonCode", reason_code);
+    set_some(&object, "reason", reason);
+    object.into()
+}
+
+fn error_of(kind: ErrorKind, message: &str) -> js_sys::Error {
+    let error = js_sys::Error::new(message);
+    error.set_name("MqttError");
+    set(&error, "kind", error_kind(kind));
+    error
+}
+
+/// The `MqttError` promises still pending are rejected with once the connection ended
+pub(crate) fn closed() -> JsValue {
+    error_of(ErrorKind::Closed, "the connection was closed").into()
+}
+
+/// An `MqttError` describing `err`: what kind of failure it is, the packet and the statement of the specification
+/// involved when known, and the Reason Code the server refused the operation with, or the one the breach warrants
+pub(crate) fn error(err: MQTTError) -> JsValue {
+    let error = error_of(err.kind(), &err.to_string());
+    set_some(&error, "packetType", err.packet_type().map(packet_name));
+    set_some(&error, "spec", err.spec());
+    match &err {
+        MQTTError::Refused(refusal) => {
+            set(&error, "reasonCode", refusal.reason.code());
+            set_some(&error, "reasonString", refusal.reason_string.as_deref());
+            set(
+                &error,
+                "userProperties",
+                user_properties_value(&refusal.user_property),
+            );
+        }
+        MQTTError::ProtocolViolation(violation) => {
+            set(&error, "reasonCode", violation.reason as u8);
+        }
+        _ => {}
+    }
+    error.into()
+}
+
+fn error_kind(kind: ErrorKind) -> &'static str {
+    match kind {
+        ErrorKind::Io => "io",
+        ErrorKind::Protocol => "protocol",
+        ErrorKind::Malformed => "malformed",
+        ErrorKind::Refused => "refused",
+        ErrorKind::Limits => "limits",
+        ErrorKind::Closed => "closed",
+    }
+}
+
+/// The name the specification gives the packet
+pub(crate) fn packet_name(packet: PacketType) -> &'static str {
+    match packet {
+        PacketType::Connect => "CONNECT",
+        PacketType::ConnAck => "CONNACK",
+        PacketType::Publish => "PUBLISH",
+        PacketType::PubAck => "PUBACK",
+        PacketType::PubRec => "PUBREC",
+        PacketType::PubRel => "PUBREL",
+        PacketType::PubComp => "PUBCOMP",
+        PacketType::Subscribe => "SUBSCRIBE",
+        PacketType::SubAck => "SUBACK",
+        PacketType::UnSubscribe => "UNSUBSCRIBE",
+        PacketType::UnSubAck => "UNSUBACK",
+        PacketType::PingReq => "PINGREQ",
+        PacketType::PingResp => "PINGRESP",
+        PacketType::Disconnect => "DISCONNECT",
+        PacketType::Auth => "AUTH",
+    }
+}
+
+/// What an acknowledgement settles its promise with: its Reason Code, or the error when it is one
+pub(crate) fn outcome(ack: Refusal) -> Result<JsValue, JsValue> {
+    if ack.reason.is_error() {
+        return Err(error(ack.into()));
+    }
+    Ok(JsValue::from(ack.reason.code()))
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+mod tests {
+    use js_sys::JSON;
+    use wasm_bindgen_test::wasm_bindgen_test;
+
+    use crate::v5::commons::reason_code::ReasonCode;
+
+    use super::*;
+
+    fn object(json: &str) -> JsValue {
+        JSON::parse(json).unwrap()
+    }
+
+    fn message(err: JsValue) -> String {
+        err.dyn_into::<TypeError>().unwrap().message().into()
+    }
+
+    #[wasm_bindgen_test]
+    fn reads_the_connect_options() {
+        let options = object(
+            r#"{
+                "clientId": "sensor-7",
+                "cleanStart": true,
+                "keepAlive": 30,
+                "sessionExpiryInterval": 3600,
+                "username": "sensor",
+                "receiveMaximum": 20,
+                "userProperties": [["site", "north"]],
+                "will": { "topic": "sensors/7/status", "payload": "offline", "qos": 1, "properties": { "delayInterval": 5 } }
+            }"#,
+        );
+        let connect = connect_options(&options).unwrap();
+        assert_eq!(connect.client_id, "sensor-7");
+        assert!(connect.clean_start);
+        assert_eq!(connect.keep_alive, 30);
+        assert_eq!(connect.session_expiry_interval, Some(3600));
+        assert_eq!(connect.username.as_deref(), Some("sensor"));
+        assert_eq!(connect.password, None);
+        assert_eq!(connect.client_receive_max.get(), 20);
+        assert_eq!(
+            connect.user_property,
+            [(String::from("site"), String::from("north"))]
+        );
+
+        let will = connect.will.unwrap();
+        assert_eq!(will.topic, "sensors/7/status");
+        assert_eq!(will.payload, "offline");
+        assert_eq!(will.qos, QoS::One);
+        assert_eq!(will.properties.delay_interval, Some(5));
+
+        let connect = connect_options(&JsValue::UNDEFINED).unwrap();
+        assert_eq!(connect.client_id, "");
+    }
+
+    #[wasm_bindgen_test]
+    fn throws_type_errors_naming_the_field() {
+        let err = connect_options(&object(r#"{ "keepAlive": 1.5 }"#)).unwrap_err();
+        assert_eq!(message(err), "keepAlive: expected a whole number in range");
+
+        let err = connect_options(&object(r#"{ "receiveMaximum": 0 }"#)).unwrap_err();
+        assert_eq!(message(err), "receiveMaximum: expected at least 1");
+
+        let err = connect_options(&object(r#"{ "will": { "qos": 1 } }"#)).unwrap_err();
+        assert_eq!(message(err), "will.topic: expected a string");
+
+        let err = publish_options(&object(r#"{ "qos": 3 }"#)).unwrap_err();
+        assert_eq!(message(err), "qos: expected 0, 1 or 2");
+
+        for pairs in [r#""site""#, r#"[["site"]]"#, r#"[["site", 7]]"#] {
+            let options = object(&format!(r#"{{ "userProperties": {pairs} }}"#));
+            let err = unsubscribe_options(&options).unwrap_err();
+            assert_eq!(
+                message(err),
+                "userProperties: expected an array of [name, value] pairs"
+            );
+        }
+    }
+
+    #[wasm_bindgen_test]
+    fn reads_back_the_publish_properties_it_writes() {
+        let properties = PublishProperties {
+            payload_format_indicator: Some(1),
+            message_expiry_internal: Some(60),
+            topic_alias: Some(4),
+            response_topic: Some(String::from("replies/7")),
+            correlation_data: Some(Bytes::from_static(&[0, 1, 254, 255])),
+            user_property: vec![
+                (String::from("unit"), String::from("K")),
+                (String::from("unit"), String::from("°C")),
+            ],
+            subscription_identifier: vec![1, 268_435_455],
+            content_type: Some(String::from("text/plain")),
+        };
+        let value = publish_properties_value(&properties);
+        assert_eq!(publish_properties(&value).unwrap(), properties);
+
+        let options = object(r#"{ "qos": 2, "retain": true }"#);
+        Reflect::set(&options, &JsValue::from_str("properties"), &value).unwrap();
+        let (qos, retain, read) = publish_options(&options).unwrap();
+        assert_eq!((qos, retain), (QoS::Two, true));
+        assert_eq!(read, properties);
+    }
+
+    #[wasm_bindgen_test]
+    fn reads_payloads_as_bytes_or_text() {
+        let bytes = Uint8Array::from(&[0, 159, 255][..]);
+        assert_eq!(payload(&bytes.into()).unwrap(), &[0, 159, 255][..]);
+        assert_eq!(payload(&JsValue::from_str("°C")).unwrap(), "°C".as_bytes());
+
+        let err = payload(&JsValue::from(7)).unwrap_err();
+        assert_eq!(message(err), "payload: expected a Uint8Array or a string");
+    }
+
+    #[wasm_bindgen_test]
+    fn resolves_successes_and_rejects_refusals() {
+        let ack = Refusal::new(PacketType::SubAck, ReasonCode::GrantedQoS2);
+        assert_eq!(outcome(ack).unwrap(), 2);
+
+        let ack = Refusal::new(PacketType::SubAck, ReasonCode::QuotaExceeded);
+        let err = outcome(ack).unwrap_err();
+        let field = |key: &str| Reflect::get(&err, &JsValue::from_str(key)).unwrap();
+        assert!(err.is_instance_of::<js_sys::Error>());
+        assert_eq!(field("name"), "MqttError");
+        assert_eq!(field("kind"), "refused");
+        assert_eq!(field("packetType"), "SUBACK");
+        assert_eq!(field("reasonCode"), 151);
+
+        let err = closed();
+        assert_eq!(Reflect::get(&err, &"kind".into()).unwrap(), "closed");
+    }
+}
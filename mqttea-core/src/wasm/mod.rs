@@ -0,0 +1,8 @@
+//! The browser client, built into the `pkg/` package with `wasm-pack build --target web --out-dir ./../pkg -- --features wasm`.
+//!
+//! JavaScript connects with `MqttClient.connect(url, options)`, the packets being carried by the browser's `WebSocket`.
+//! Tooling that handles raw packets decodes and encodes them with `decodePacket` and `encodePacket`
+mod client;
+mod codec;
+mod js;
+mod socket;
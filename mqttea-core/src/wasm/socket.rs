@@ -0,0 +1,177 @@
+use std::{
+    cell::RefCell,
+    io,
+    pin::Pin,
+    rc::Rc,
+    task::{ready, Context, Poll},
+};
+
+use bytes::{Buf, Bytes};
+use futures::{
+    channel::{mpsc, oneshot},
+    AsyncRead, AsyncWrite, Stream,
+};
+use js_sys::{ArrayBuffer, Uint8Array};
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+use web_sys::{BinaryType, CloseEvent, Event, MessageEvent, WebSocket};
+
+/// The WebSocket subprotocol MQTT is carried in (MQTT-6.0.0-3, MQTT-6.0.0-4)
+const SUBPROTOCOL: &str = "mqtt";
+
+/// A browser `WebSocket`, read and written as the byte stream MQTT packets are carried in.
+///
+/// Frames are queued as the browser receives them, a packet may span several frames and a frame may hold several packets (MQTT-6.0.0-2).
+/// Each write is sent as a frame of its own
+pub(crate) struct Socket {
+    ws: WebSocket,
+    /// Frames received, `Err` for the text frames MQTT doesn't allow
+    frames: mpsc::UnboundedReceiver<Result<Bytes, ()>>,
+    /// What is left of the last frame received
+    frame: Bytes,
+    // the browser calls these for as long as the socket is open
+    _onmessage: Closure<dyn FnMut(MessageEvent)>,
+    _onclose: Closure<dyn FnMut(CloseEvent)>,
+}
+
+impl Socket {
+    /// Opens a WebSocket to `url`, offering the `mqtt` subprotocol
+    pub(crate) async fn open(url: &str) -> Result<Self, JsValue> {
+        let ws = WebSocket::new_with_str(url, SUBPROTOCOL)?;
+        ws.set_binary_type(BinaryType::Arraybuffer);
+
+        let (tx, frames) = mpsc::unbounded();
+        let (opened_tx, opened) = oneshot::channel();
+        let opened_tx = Rc::new(RefCell::new(Some(opened_tx)));
+        let settle = move |opened: bool| {
+            if let Some(tx) = opened_tx.borrow_mut().take() {
+                let _ = tx.send(opened);
+            }
+        };
+
+        let onmessage = {
+            let tx = tx.clone();
+            Closure::<dyn FnMut(MessageEvent)>::new(move |event: MessageEvent| {
+                // MQTT-6.0.0-1 only binary frames may carry MQTT Control Packets
+                let frame = event
+                    .data()
+                    .dyn_into::<ArrayBuffer>()
+                    .map(|data| Bytes::from(Uint8Array::new(&data).to_vec()))
+                    .map_err(|_| ());
+                let _ = tx.unbounded_send(frame);
+            })
+        };
+        let onclose = {
+            let settle = settle.clone();
+            Closure::<dyn FnMut(CloseEvent)>::new(move |_: CloseEvent| {
+                settle(false);
+                tx.close_channel();
+            })
+        };
+        let onopen = {
+            let settle = settle.clone();
+            Closure::<dyn FnMut(Event)>::new(move |_: Event| settle(true))
+        };
+        let onerror = Closure::<dyn FnMut(Event)>::new(move |_: Event| settle(false));
+
+        ws.set_onmessage(Some(onmessage.as_ref().unchecked_ref()));
+        ws.set_onclose(Some(onclose.as_ref().unchecked_ref()));
+        ws.set_onopen(Some(onopen.as_ref().unchecked_ref()));
+        ws.set_onerror(Some(onerror.as_ref().unchecked_ref()));
+
+        let opened = opened.await.unwrap_or(false);
+        ws.set_onopen(None);
+        ws.set_onerror(None);
+
+        let socket = Self {
+            ws,
+            frames,
+            frame: Bytes::new(),
+            _onmessage: onmessage,
+            _onclose: onclose,
+        };
+        if !opened {
+            return Err(js_sys::Error::new(&format!("could not open a WebSocket to {url}")).into());
+        }
+        if socket.ws.protocol() != SUBPROTOCOL {
+            return Err(
+                js_sys::Error::new("the server did not agree to the mqtt subprotocol").into(),
+            );
+        }
+        Ok(socket)
+    }
+}
+
+impl Drop for Socket {
+    fn drop(&mut self) {
+        // the callbacks are about to be freed, the browser must not call them anymore
+        self.ws.set_onmessage(None);
+        self.ws.set_onclose(None);
+        let _ = self.ws.close();
+    }
+}
+
+impl AsyncRead for Socket {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut [u8],
+    ) -> Poll<io::Result<usize>> {
+        while self.frame.is_empty() {
+            match ready!(Pin::new(&mut self.frames).poll_next(cx)) {
+                Some(Ok(frame)) => self.frame = frame,
+                Some(Err(())) => {
+                    return Poll::Ready(Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "received a text frame",
+                    )))
+                }
+                None => return Poll::Ready(Ok(0)),
+            }
+        }
+
+        let n = buf.len().min(self.frame.len());
+        buf[..n].copy_from_slice(&self.frame[..n]);
+        self.frame.advance(n);
+        Poll::Ready(Ok(n))
+    }
+}
+
+impl AsyncWrite for Socket {
+    fn poll_write(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        self.poll_write_vectored(cx, &[io::IoSlice::new(buf)])
+    }
+
+    /// Sends every slice in a single frame, rather than one frame per slice
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        _: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        if self.ws.ready_state() != WebSocket::OPEN {
+            return Poll::Ready(Err(io::ErrorKind::BrokenPipe.into()));
+        }
+
+        let frame = bufs
+            .iter()
+            .flat_map(|buf| buf.iter().copied())
+            .collect::<Vec<_>>();
+        // the browser buffers what it can't send right away
+        self.ws
+            .send_with_u8_array(&frame)
+            .map_err(|_| io::Error::from(io::ErrorKind::BrokenPipe))?;
+        Poll::Ready(Ok(frame.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_close(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let _ = self.ws.close();
+        Poll::Ready(Ok(()))
+    }
+}
@@ -95,9 +95,9 @@ fn get_size(f_name: &Ident, type_name: &Ident, segment: &PathSegment, is_optiona
         // this should be updated, if there's an unincluded type
         "u8" | "u16" | "u32" | "u64" | "u128" | "bool" => {
             if is_optional {
-                return quote! { if self.#f_name.is_some() { size += std::mem::size_of::<#type_name>() + usize::from(#include_id) }; }
+                return quote! { if self.#f_name.is_some() { size += ::core::mem::size_of::<#type_name>() + usize::from(#include_id) }; }
             } else {
-                return quote! { size += std::mem::size_of::<#type_name>() + usize::from(#include_id); }
+                return quote! { size += ::core::mem::size_of::<#type_name>() + usize::from(#include_id); }
             }
         }
         "usize" => {
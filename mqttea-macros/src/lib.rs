@@ -79,13 +79,14 @@ pub fn derive_u8(input: TokenStream) -> TokenStream {
     let (variant, discriminant): (Vec<_>, Vec<_>) = variant_pair.into_iter().filter_map(|d| d.ok()).unzip();
     
     let try_from_u8 = quote! {
+        // the value refused is handed back, which keeps the conversion free of allocations (`no_std`)
         impl TryFrom<u8> for #struct_name {
-            type Error = String;
+            type Error = u8;
             
             fn try_from(value: u8) -> Result<Self, Self::Error> {
                 match value {
                     #( #discriminant => Ok(#struct_name::#variant), )*
-                    v => Err((format!("{}", v)))
+                    v => Err(v)
                 }
             }
         }
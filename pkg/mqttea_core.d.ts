@@ -1,31 +1,548 @@
 /* tslint:disable */
 /* eslint-disable */
 
+/** Any MQTT 5 Control Packet, told apart by its `type` */
+export type Packet =
+| ConnectPacket | ConnAckPacket | PublishPacket | PubAckPacket | PubRecPacket | PubRelPacket | PubCompPacket
+| SubscribePacket | SubAckPacket | UnsubscribePacket | UnsubAckPacket | PingReqPacket | PingRespPacket
+| DisconnectPacket | AuthPacket;
+
+/** The properties of packets that acknowledge another one */
+export interface AckProperties {
+    reasonString?: string;
+    userProperties?: UserProperties;
+}
+
+export interface ConnectPacket {
+    type: "CONNECT";
+    clientId: string;
+    username?: string;
+    password?: string;
+    will?: Will;
+    cleanStart?: boolean;
+    keepAlive?: number;
+    properties?: {
+        sessionExpiryInterval?: number;
+        receiveMaximum?: number;
+        maximumPacketSize?: number;
+        topicAliasMaximum?: number;
+        requestResponseInformation?: number;
+        requestProblemInformation?: number;
+        userProperties?: UserProperties;
+        authenticationMethod?: string;
+        authenticationData?: Uint8Array;
+    };
+}
+
+export interface ConnAckPacket {
+    type: "CONNACK";
+    sessionPresent?: boolean;
+    reasonCode?: ConnAckReasonCode;
+    properties?: {
+        sessionExpiryInterval?: number;
+        receiveMaximum?: number;
+        maximumQoS?: 0 | 1;
+        retainAvailable?: boolean;
+        maximumPacketSize?: number;
+        assignedClientIdentifier?: string;
+        topicAliasMaximum?: number;
+        reasonString?: string;
+        userProperties?: UserProperties;
+        wildcardSubscriptionAvailable?: boolean;
+        subscriptionIdentifiersAvailable?: boolean;
+        sharedSubscriptionAvailable?: boolean;
+        serverKeepAlive?: number;
+        responseInformation?: string;
+        serverReference?: string;
+        authenticationMethod?: string;
+        authenticationData?: Uint8Array;
+    };
+}
+
+export interface PublishPacket {
+    type: "PUBLISH";
+    dup?: boolean;
+    retain?: boolean;
+    qos?: QoS;
+    topic: string;
+    /** Only carried at QoS 1 and QoS 2 */
+    packetId?: number;
+    payload?: Uint8Array | string;
+    properties?: PublishProperties;
+}
+
+export interface PubAckPacket {
+    type: "PUBACK";
+    packetId: number;
+    reasonCode?: PubAckReasonCode;
+    properties?: AckProperties;
+}
+
+export interface PubRecPacket {
+    type: "PUBREC";
+    packetId: number;
+    reasonCode?: PubRecReasonCode;
+    properties?: AckProperties;
+}
+
+export interface PubRelPacket {
+    type: "PUBREL";
+    packetId: number;
+    reasonCode?: PubRelReasonCode;
+    properties?: AckProperties;
+}
+
+export interface PubCompPacket {
+    type: "PUBCOMP";
+    packetId: number;
+    reasonCode?: PubCompReasonCode;
+    properties?: AckProperties;
+}
+
+export interface Subscription {
+    topicFilter: string;
+    qos?: QoS;
+    noLocal?: boolean;
+    retainAsPublished?: boolean;
+    retainHandling?: RetainHandling;
+}
+
+export interface SubscribePacket {
+    type: "SUBSCRIBE";
+    packetId: number;
+    subscriptions: Subscription[];
+    properties?: {
+        subscriptionIdentifier?: number;
+        userProperties?: UserProperties;
+    };
+}
+
+export interface SubAckPacket {
+    type: "SUBACK";
+    packetId: number;
+    /** One for each Topic Filter of the SUBSCRIBE, in order */
+    reasonCodes: SubAckReasonCode[];
+    properties?: AckProperties;
+}
+
+export interface UnsubscribePacket {
+    type: "UNSUBSCRIBE";
+    packetId: number;
+    topicFilters: string[];
+    properties?: {
+        userProperties?: UserProperties;
+    };
+}
+
+export interface UnsubAckPacket {
+    type: "UNSUBACK";
+    packetId: number;
+    /** One for each Topic Filter of the UNSUBSCRIBE, in order */
+    reasonCodes: UnSubAckReasonCode[];
+    properties?: AckProperties;
+}
+
+export interface PingReqPacket {
+    type: "PINGREQ";
+}
+
+export interface PingRespPacket {
+    type: "PINGRESP";
+}
+
+export interface DisconnectPacket {
+    type: "DISCONNECT";
+    reasonCode?: DisconnectReasonCode;
+    properties?: {
+        sessionExpiryInterval?: number;
+        reasonString?: string;
+        userProperties?: UserProperties;
+        serverReference?: string;
+    };
+}
+
+export interface AuthPacket {
+    type: "AUTH";
+    reasonCode?: AuthReasonCode;
+    properties?: {
+        authenticationMethod?: string;
+        authenticationData?: Uint8Array;
+        reasonString?: string;
+        userProperties?: UserProperties;
+    };
+}
+
+
+
+/** Pairs of names and values, in order, a name may appear more than once */
+export type UserProperties = [string, string][];
+
+/** What the CONNECT is made of (3.1) */
+export interface ConnectOptions {
+    /** Left out for the server to assign one */
+    clientId?: string;
+    cleanStart?: boolean;
+    /** Seconds between control packets, 0 turns the Keep Alive off */
+    keepAlive?: number;
+    /** Seconds the session outlives the connection */
+    sessionExpiryInterval?: number;
+    username?: string;
+    password?: string;
+    /** QoS 1 and QoS 2 messages the client is willing to process at once */
+    receiveMaximum?: number;
+    /** Largest packet the client accepts, in bytes */
+    maximumPacketSize?: number;
+    userProperties?: UserProperties;
+    will?: Will;
+}
+
+/** The message the server publishes when the connection is lost (3.1.2.5) */
+export interface Will {
+    topic: string;
+    payload?: Uint8Array | string;
+    qos?: QoS;
+    retain?: boolean;
+    properties?: WillProperties;
+}
+
+export interface WillProperties {
+    /** Seconds the server waits before publishing the will */
+    delayInterval?: number;
+    payloadFormatIndicator?: number;
+    messageExpiryInterval?: number;
+    contentType?: string;
+    responseTopic?: string;
+    correlationData?: Uint8Array;
+    userProperties?: UserProperties;
+}
+
+export interface PublishProperties {
+    payloadFormatIndicator?: number;
+    messageExpiryInterval?: number;
+    topicAlias?: number;
+    responseTopic?: string;
+    correlationData?: Uint8Array;
+    userProperties?: UserProperties;
+    /** Identifiers of the subscriptions a message was delivered for */
+    subscriptionIdentifiers?: number[];
+    contentType?: string;
+}
+
+export interface PublishOptions {
+    qos?: QoS;
+    retain?: boolean;
+    properties?: PublishProperties;
+}
+
+export interface SubscribeOptions {
+    qos?: QoS;
+    noLocal?: boolean;
+    retainAsPublished?: boolean;
+    retainHandling?: RetainHandling;
+    subscriptionIdentifier?: number;
+    userProperties?: UserProperties;
+}
+
+export interface UnsubscribeOptions {
+    userProperties?: UserProperties;
+}
+
+/** A message the server delivered */
+export interface Message {
+    topic: string;
+    payload: Uint8Array;
+    qos: QoS;
+    retain: boolean;
+    properties: PublishProperties;
+}
+
+/** Why the connection ended */
+export interface Close {
+    /** Set when the connection ended with a DISCONNECT */
+    reasonCode?: DisconnectReasonCode;
+    reason?: string;
+}
+
+export type PacketType =
+| "CONNECT" | "CONNACK" | "PUBLISH" | "PUBACK" | "PUBREC" | "PUBREL" | "PUBCOMP"
+| "SUBSCRIBE" | "SUBACK" | "UNSUBSCRIBE" | "UNSUBACK" | "PINGREQ" | "PINGRESP" | "DISCONNECT" | "AUTH";
+
+/** What promises are rejected with, and what packets that can't be decoded or encoded are thrown as */
+export interface MqttError extends Error {
+    name: "MqttError";
+    /** How to react to the failure: retry on `io`, alert on `refused`, give up on `protocol` or `malformed` */
+    kind: "io" | "protocol" | "malformed" | "refused" | "limits" | "closed";
+    /** The packet responsible, when it is known */
+    packetType?: PacketType;
+    /** The normative statement (or section) of the specification breached */
+    spec?: string;
+    /** The Reason Code the server refused the operation with, or the one the breach is disconnected with */
+    reasonCode?: number;
+    reasonString?: string;
+    userProperties?: UserProperties;
+}
+
+
+
+export enum AuthReasonCode {
+    Success = 0,
+    ContinueAuthentication = 24,
+    ReAuthenticate = 25,
+}
+
+export enum ConnAckReasonCode {
+    Success = 0,
+    UnspecifiedError = 128,
+    MalformedPacket = 129,
+    ProtocolError = 130,
+    ImplementationSpecificError = 131,
+    UnsupportedProtocolVersion = 132,
+    ClientIdentifierNotValid = 133,
+    BadUserNameOrPassword = 134,
+    NotAuthorized = 135,
+    ServerUnavailable = 136,
+    ServerBusy = 137,
+    Banned = 138,
+    BadAuthenticationMethod = 140,
+    TopicNameInvalid = 144,
+    PacketTooLarge = 149,
+    QuotaExceeded = 151,
+    PayloadFormatInvalid = 153,
+    RetainNotSupported = 154,
+    QoSNotSupported = 155,
+    UseAnotherServer = 156,
+    ServerMoved = 157,
+    ConnectionRateExceeded = 159,
+}
+
+export enum DisconnectReasonCode {
+    NormalDisconnection = 0,
+    DisconnectWithWillMessage = 4,
+    UnspecifiedError = 128,
+    MalformedPacket = 129,
+    ProtocolError = 130,
+    ImplementationSpecificError = 131,
+    NotAuthorized = 135,
+    ServerBusy = 137,
+    ServerShuttingDown = 139,
+    KeepAliveTimeout = 141,
+    SessionTakenOver = 142,
+    TopicFilterInvalid = 143,
+    TopicNameInvalid = 144,
+    ReceiveMaximumExceeded = 147,
+    TopicAliasInvalid = 148,
+    PacketTooLarge = 149,
+    MessageRateTooHigh = 150,
+    QuotaExceeded = 151,
+    AdministrativeAction = 152,
+    PayloadFormatInvalid = 153,
+    RetainNotSupported = 154,
+    QoSNotSupported = 155,
+    UseAnotherServer = 156,
+    ServerMoved = 157,
+    SharedSubscriptionsNotSupported = 158,
+    ConnectionRateExceeded = 159,
+    MaximumConnectTime = 160,
+    SubscriptionIdentifiersNotSupported = 161,
+    WildcardSubscriptionsNotSupported = 162,
+}
+
+/**
+ * An MQTT client connected to a broker over a browser WebSocket.
+ *
+ * Operations return promises settled by the acknowledgement the server sends back,
+ * with its Reason Code when the operation succeeded, and with an `MqttError` otherwise
+ */
+export class MqttClient {
+    private constructor();
+    free(): void;
+    [Symbol.dispose](): void;
+    /**
+     * Opens a WebSocket to `url` (`ws://` or `wss://`) and connects over it,
+     * resolving once the server accepted the connection
+     */
+    static connect(url: string, options?: ConnectOptions | null): Promise<MqttClient>;
+    /**
+     * Sends a DISCONNECT, resolving once it is queued
+     */
+    disconnect(): Promise<void>;
+    /**
+     * Sets the function called once the connection ended, every pending promise being rejected by then
+     */
+    onClose(callback: (close: Close) => void): void;
+    /**
+     * Sets the function called with every message the server delivers
+     */
+    onMessage(callback: (message: Message) => void): void;
+    /**
+     * Publishes `payload`, a string being sent UTF-8 encoded.
+     * Resolves with the Reason Code of the PUBACK or PUBCOMP, with `undefined` at QoS 0 once the message is queued
+     */
+    publish(topic: string, payload: Uint8Array | string, options?: PublishOptions | null): Promise<number | undefined>;
+    /**
+     * Subscribes to `filter`, resolving with the Reason Code of the SUBACK, which is the QoS granted
+     */
+    subscribe(filter: string, options?: SubscribeOptions | null): Promise<SubAckReasonCode>;
+    /**
+     * Unsubscribes from `filter`, resolving with the Reason Code of the UNSUBACK
+     */
+    unsubscribe(filter: string, options?: UnsubscribeOptions | null): Promise<UnSubAckReasonCode>;
+}
+
+export enum PubAckReasonCode {
+    Success = 0,
+    NoMatchingSubscribers = 16,
+    UnspecifiedError = 128,
+    ImplementationSpecificError = 131,
+    NotAuthorized = 135,
+    TopicNameInvalid = 144,
+    PacketIdentifierInUse = 145,
+    QuotaExceeded = 151,
+    PayloadFormatInvalid = 153,
+}
+
+export enum PubCompReasonCode {
+    Success = 0,
+    PacketIdentifierNotFound = 146,
+}
+
+export enum PubRecReasonCode {
+    Success = 0,
+    NoMatchingSubscribers = 16,
+    UnspecifiedError = 128,
+    ImplementationSpecificError = 131,
+    NotAuthorized = 135,
+    TopicNameInvalid = 144,
+    PacketIdentifierInUse = 145,
+    QuotaExceeded = 151,
+    PayloadFormatInvalid = 153,
+}
+
+export enum PubRelReasonCode {
+    Success = 0,
+    PacketIdentifierNotFound = 146,
+}
+
+export enum QoS {
+    Zero = 0,
+    One = 1,
+    Two = 2,
+}
+
+export enum RetainHandling {
+    /**
+     * Send the retained messages at the time of the subscribe
+     */
+    Zero = 0,
+    /**
+     * Send retained messages at subscribe only if subscription does not currently exist
+     */
+    One = 1,
+    /**
+     * Do not send retained messages at the time of the subscription
+     */
+    Two = 2,
+}
+
+export enum SubAckReasonCode {
+    /**
+     * The Subscription is accepted and the maximum QoS sent will be QoS 0 (This might be lower than requested)
+     */
+    GrantedQoS0 = 0,
+    /**
+     * The Subscription is accepted and the maximum QoS sent will be QoS1 (This might be lower than requested)
+     */
+    GrantedQoS1 = 1,
+    /**
+     * The subscription is accepted and any received QoS will be sent to this subscription
+     */
+    GrantedQoS2 = 2,
+    UnspecifiedError = 128,
+    /**
+     * Subscribe packet is valid, but the server does not accept it
+     */
+    ImplementationSpecificError = 131,
+    NotAuthorized = 135,
+    TopicFilterInvalid = 143,
+    PacketIdentifierInUse = 145,
+    QuotaExceeded = 151,
+    SharedSubscriptionsNotSupported = 158,
+    SubscriptionIdentifiersNotSupported = 161,
+    WildcardSubscriptionsNotSupported = 162,
+}
+
+export enum UnSubAckReasonCode {
+    Success = 0,
+    NoSubscriptionExisted = 17,
+    UnspecifiedError = 128,
+    ImplementationSpecificError = 131,
+    NotAuthorized = 135,
+    TopicFilterInvalid = 143,
+    PacketIdentifierInUse = 145,
+}
+
+/**
+ * Decodes a single packet, Fixed Header included, which must take up all of `bytes`.
+ * Throws an `MqttError` when the packet is malformed or breaks the rules of the protocol
+ */
+export function decodePacket(bytes: Uint8Array): Packet;
+
+/**
+ * Encodes `packet` as it is sent on the wire, fields left out taking their default value.
+ * Throws a `TypeError` when a field doesn't have the expected type, and an `MqttError` when the packet breaks
+ * the rules of the protocol
+ */
+export function encodePacket(packet: Packet): Uint8Array;
+
 export type InitInput = RequestInfo | URL | Response | BufferSource | WebAssembly.Module;
 
 export interface InitOutput {
-  readonly memory: WebAssembly.Memory;
-  readonly __wbindgen_export_0: WebAssembly.Table;
-  readonly __wbindgen_start: () => void;
+    readonly memory: WebAssembly.Memory;
+    readonly __wbg_mqttclient_free: (a: number, b: number) => void;
+    readonly decodePacket: (a: number, b: number) => [number, number, number];
+    readonly encodePacket: (a: any) => [number, number, number, number];
+    readonly mqttclient_connect: (a: number, b: number, c: number) => any;
+    readonly mqttclient_disconnect: (a: number) => any;
+    readonly mqttclient_onClose: (a: number, b: any) => void;
+    readonly mqttclient_onMessage: (a: number, b: any) => void;
+    readonly mqttclient_publish: (a: number, b: number, c: number, d: any, e: number) => any;
+    readonly mqttclient_subscribe: (a: number, b: number, c: number, d: number) => any;
+    readonly mqttclient_unsubscribe: (a: number, b: number, c: number, d: number) => any;
+    readonly wasm_bindgen__convert__closures_____invoke__h2d5142923d938445: (a: number, b: number, c: any, d: any) => void;
+    readonly wasm_bindgen__convert__closures_____invoke__hd5224973baf38e05: (a: number, b: number, c: any) => [number, number];
+    readonly wasm_bindgen__convert__closures_____invoke__h5777668fe466b876: (a: number, b: number, c: any) => void;
+    readonly wasm_bindgen__convert__closures_____invoke__h5777668fe466b876_12: (a: number, b: number, c: any) => void;
+    readonly wasm_bindgen__convert__closures_____invoke__h5777668fe466b876_13: (a: number, b: number, c: any) => void;
+    readonly wasm_bindgen__convert__closures_____invoke__h3b77f23eff1f1e9a: (a: number, b: number) => void;
+    readonly __wbindgen_malloc: (a: number, b: number) => number;
+    readonly __wbindgen_realloc: (a: number, b: number, c: number, d: number) => number;
+    readonly __wbindgen_exn_store: (a: number) => void;
+    readonly __externref_table_alloc: () => number;
+    readonly __wbindgen_externrefs: WebAssembly.Table;
+    readonly __wbindgen_destroy_closure: (a: number, b: number) => void;
+    readonly __externref_table_dealloc: (a: number) => void;
+    readonly __wbindgen_free: (a: number, b: number, c: number) => void;
+    readonly __wbindgen_start: () => void;
 }
 
 export type SyncInitInput = BufferSource | WebAssembly.Module;
+
 /**
-* Instantiates the given `module`, which can either be bytes or
-* a precompiled `WebAssembly.Module`.
-*
-* @param {{ module: SyncInitInput }} module - Passing `SyncInitInput` directly is deprecated.
-*
-* @returns {InitOutput}
-*/
+ * Instantiates the given `module`, which can either be bytes or
+ * a precompiled `WebAssembly.Module`.
+ *
+ * @param {{ module: SyncInitInput }} module - Passing `SyncInitInput` directly is deprecated.
+ *
+ * @returns {InitOutput}
+ */
 export function initSync(module: { module: SyncInitInput } | SyncInitInput): InitOutput;
 
 /**
-* If `module_or_path` is {RequestInfo} or {URL}, makes a request and
-* for everything else, calls `WebAssembly.instantiate` directly.
-*
-* @param {{ module_or_path: InitInput | Promise<InitInput> }} module_or_path - Passing `InitInput` directly is deprecated.
-*
-* @returns {Promise<InitOutput>}
-*/
+ * If `module_or_path` is {RequestInfo} or {URL}, makes a request and
+ * for everything else, calls `WebAssembly.instantiate` directly.
+ *
+ * @param {{ module_or_path: InitInput | Promise<InitInput> }} module_or_path - Passing `InitInput` directly is deprecated.
+ *
+ * @returns {Promise<InitOutput>}
+ */
 export default function __wbg_init (module_or_path?: { module_or_path: InitInput | Promise<InitInput> } | InitInput | Promise<InitInput>): Promise<InitOutput>;
@@ -1,71 +1,879 @@
-let wasm;
+/* @ts-self-types="./mqttea_core.d.ts" */
+
+/**
+ * @enum {0 | 24 | 25}
+ */
+export const AuthReasonCode = Object.freeze({
+    Success: 0, "0": "Success",
+    ContinueAuthentication: 24, "24": "ContinueAuthentication",
+    ReAuthenticate: 25, "25": "ReAuthenticate",
+});
+
+/**
+ * @enum {0 | 128 | 129 | 130 | 131 | 132 | 133 | 134 | 135 | 136 | 137 | 138 | 140 | 144 | 149 | 151 | 153 | 154 | 155 | 156 | 157 | 159}
+ */
+export const ConnAckReasonCode = Object.freeze({
+    Success: 0, "0": "Success",
+    UnspecifiedError: 128, "128": "UnspecifiedError",
+    MalformedPacket: 129, "129": "MalformedPacket",
+    ProtocolError: 130, "130": "ProtocolError",
+    ImplementationSpecificError: 131, "131": "ImplementationSpecificError",
+    UnsupportedProtocolVersion: 132, "132": "UnsupportedProtocolVersion",
+    ClientIdentifierNotValid: 133, "133": "ClientIdentifierNotValid",
+    BadUserNameOrPassword: 134, "134": "BadUserNameOrPassword",
+    NotAuthorized: 135, "135": "NotAuthorized",
+    ServerUnavailable: 136, "136": "ServerUnavailable",
+    ServerBusy: 137, "137": "ServerBusy",
+    Banned: 138, "138": "Banned",
+    BadAuthenticationMethod: 140, "140": "BadAuthenticationMethod",
+    TopicNameInvalid: 144, "144": "TopicNameInvalid",
+    PacketTooLarge: 149, "149": "PacketTooLarge",
+    QuotaExceeded: 151, "151": "QuotaExceeded",
+    PayloadFormatInvalid: 153, "153": "PayloadFormatInvalid",
+    RetainNotSupported: 154, "154": "RetainNotSupported",
+    QoSNotSupported: 155, "155": "QoSNotSupported",
+    UseAnotherServer: 156, "156": "UseAnotherServer",
+    ServerMoved: 157, "157": "ServerMoved",
+    ConnectionRateExceeded: 159, "159": "ConnectionRateExceeded",
+});
+
+/**
+ * @enum {0 | 4 | 128 | 129 | 130 | 131 | 135 | 137 | 139 | 141 | 142 | 143 | 144 | 147 | 148 | 149 | 150 | 151 | 152 | 153 | 154 | 155 | 156 | 157 | 158 | 159 | 160 | 161 | 162}
+ */
+export const DisconnectReasonCode = Object.freeze({
+    NormalDisconnection: 0, "0": "NormalDisconnection",
+    DisconnectWithWillMessage: 4, "4": "DisconnectWithWillMessage",
+    UnspecifiedError: 128, "128": "UnspecifiedError",
+    MalformedPacket: 129, "129": "MalformedPacket",
+    ProtocolError: 130, "130": "ProtocolError",
+    ImplementationSpecificError: 131, "131": "ImplementationSpecificError",
+    NotAuthorized: 135, "135": "NotAuthorized",
+    ServerBusy: 137, "137": "ServerBusy",
+    ServerShuttingDown: 139, "139": "ServerShuttingDown",
+    KeepAliveTimeout: 141, "141": "KeepAliveTimeout",
+    SessionTakenOver: 142, "142": "SessionTakenOver",
+    TopicFilterInvalid: 143, "143": "TopicFilterInvalid",
+    TopicNameInvalid: 144, "144": "TopicNameInvalid",
+    ReceiveMaximumExceeded: 147, "147": "ReceiveMaximumExceeded",
+    TopicAliasInvalid: 148, "148": "TopicAliasInvalid",
+    PacketTooLarge: 149, "149": "PacketTooLarge",
+    MessageRateTooHigh: 150, "150": "MessageRateTooHigh",
+    QuotaExceeded: 151, "151": "QuotaExceeded",
+    AdministrativeAction: 152, "152": "AdministrativeAction",
+    PayloadFormatInvalid: 153, "153": "PayloadFormatInvalid",
+    RetainNotSupported: 154, "154": "RetainNotSupported",
+    QoSNotSupported: 155, "155": "QoSNotSupported",
+    UseAnotherServer: 156, "156": "UseAnotherServer",
+    ServerMoved: 157, "157": "ServerMoved",
+    SharedSubscriptionsNotSupported: 158, "158": "SharedSubscriptionsNotSupported",
+    ConnectionRateExceeded: 159, "159": "ConnectionRateExceeded",
+    MaximumConnectTime: 160, "160": "MaximumConnectTime",
+    SubscriptionIdentifiersNotSupported: 161, "161": "SubscriptionIdentifiersNotSupported",
+    WildcardSubscriptionsNotSupported: 162, "162": "WildcardSubscriptionsNotSupported",
+});
+
+/**
+ * An MQTT client connected to a broker over a browser WebSocket.
+ *
+ * Operations return promises settled by the acknowledgement the server sends back,
+ * with its Reason Code when the operation succeeded, and with an `MqttError` otherwise
+ */
+export class MqttClient {
+    static __wrap(ptr) {
+        const obj = Object.create(MqttClient.prototype);
+        obj.__wbg_ptr = ptr;
+        MqttClientFinalization.register(obj, obj.__wbg_ptr, obj);
+        return obj;
+    }
+    __destroy_into_raw() {
+        const ptr = this.__wbg_ptr;
+        this.__wbg_ptr = 0;
+        MqttClientFinalization.unregister(this);
+        return ptr;
+    }
+    free() {
+        const ptr = this.__destroy_into_raw();
+        wasm.__wbg_mqttclient_free(ptr, 0);
+    }
+    /**
+     * Opens a WebSocket to `url` (`ws://` or `wss://`) and connects over it,
+     * resolving once the server accepted the connection
+     * @param {string} url
+     * @param {ConnectOptions | null} [options]
+     * @returns {Promise<MqttClient>}
+     */
+    static connect(url, options) {
+        const ptr0 = passStringToWasm0(url, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+        const len0 = WASM_VECTOR_LEN;
+        const ret = wasm.mqttclient_connect(ptr0, len0, isLikeNone(options) ? 0 : addToExternrefTable0(options));
+        return ret;
+    }
+    /**
+     * Sends a DISCONNECT, resolving once it is queued
+     * @returns {Promise<void>}
+     */
+    disconnect() {
+        const ret = wasm.mqttclient_disconnect(this.__wbg_ptr);
+        return ret;
+    }
+    /**
+     * Sets the function called once the connection ended, every pending promise being rejected by then
+     * @param {(close: Close) => void} callback
+     */
+    onClose(callback) {
+        wasm.mqttclient_onClose(this.__wbg_ptr, callback);
+    }
+    /**
+     * Sets the function called with every message the server delivers
+     * @param {(message: Message) => void} callback
+     */
+    onMessage(callback) {
+        wasm.mqttclient_onMessage(this.__wbg_ptr, callback);
+    }
+    /**
+     * Publishes `payload`, a string being sent UTF-8 encoded.
+     * Resolves with the Reason Code of the PUBACK or PUBCOMP, with `undefined` at QoS 0 once the message is queued
+     * @param {string} topic
+     * @param {Uint8Array | string} payload
+     * @param {PublishOptions | null} [options]
+     * @returns {Promise<number | undefined>}
+     */
+    publish(topic, payload, options) {
+        const ptr0 = passStringToWasm0(topic, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+        const len0 = WASM_VECTOR_LEN;
+        const ret = wasm.mqttclient_publish(this.__wbg_ptr, ptr0, len0, payload, isLikeNone(options) ? 0 : addToExternrefTable0(options));
+        return ret;
+    }
+    /**
+     * Subscribes to `filter`, resolving with the Reason Code of the SUBACK, which is the QoS granted
+     * @param {string} filter
+     * @param {SubscribeOptions | null} [options]
+     * @returns {Promise<SubAckReasonCode>}
+     */
+    subscribe(filter, options) {
+        const ptr0 = passStringToWasm0(filter, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+        const len0 = WASM_VECTOR_LEN;
+        const ret = wasm.mqttclient_subscribe(this.__wbg_ptr, ptr0, len0, isLikeNone(options) ? 0 : addToExternrefTable0(options));
+        return ret;
+    }
+    /**
+     * Unsubscribes from `filter`, resolving with the Reason Code of the UNSUBACK
+     * @param {string} filter
+     * @param {UnsubscribeOptions | null} [options]
+     * @returns {Promise<UnSubAckReasonCode>}
+     */
+    unsubscribe(filter, options) {
+        const ptr0 = passStringToWasm0(filter, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+        const len0 = WASM_VECTOR_LEN;
+        const ret = wasm.mqttclient_unsubscribe(this.__wbg_ptr, ptr0, len0, isLikeNone(options) ? 0 : addToExternrefTable0(options));
+        return ret;
+    }
+}
+if (Symbol.dispose) MqttClient.prototype[Symbol.dispose] = MqttClient.prototype.free;
+
+/**
+ * @enum {0 | 16 | 128 | 131 | 135 | 144 | 145 | 151 | 153}
+ */
+export const PubAckReasonCode = Object.freeze({
+    Success: 0, "0": "Success",
+    NoMatchingSubscribers: 16, "16": "NoMatchingSubscribers",
+    UnspecifiedError: 128, "128": "UnspecifiedError",
+    ImplementationSpecificError: 131, "131": "ImplementationSpecificError",
+    NotAuthorized: 135, "135": "NotAuthorized",
+    TopicNameInvalid: 144, "144": "TopicNameInvalid",
+    PacketIdentifierInUse: 145, "145": "PacketIdentifierInUse",
+    QuotaExceeded: 151, "151": "QuotaExceeded",
+    PayloadFormatInvalid: 153, "153": "PayloadFormatInvalid",
+});
+
+/**
+ * @enum {0 | 146}
+ */
+export const PubCompReasonCode = Object.freeze({
+    Success: 0, "0": "Success",
+    PacketIdentifierNotFound: 146, "146": "PacketIdentifierNotFound",
+});
+
+/**
+ * @enum {0 | 16 | 128 | 131 | 135 | 144 | 145 | 151 | 153}
+ */
+export const PubRecReasonCode = Object.freeze({
+    Success: 0, "0": "Success",
+    NoMatchingSubscribers: 16, "16": "NoMatchingSubscribers",
+    UnspecifiedError: 128, "128": "UnspecifiedError",
+    ImplementationSpecificError: 131, "131": "ImplementationSpecificError",
+    NotAuthorized: 135, "135": "NotAuthorized",
+    TopicNameInvalid: 144, "144": "TopicNameInvalid",
+    PacketIdentifierInUse: 145, "145": "PacketIdentifierInUse",
+    QuotaExceeded: 151, "151": "QuotaExceeded",
+    PayloadFormatInvalid: 153, "153": "PayloadFormatInvalid",
+});
+
+/**
+ * @enum {0 | 146}
+ */
+export const PubRelReasonCode = Object.freeze({
+    Success: 0, "0": "Success",
+    PacketIdentifierNotFound: 146, "146": "PacketIdentifierNotFound",
+});
+
+/**
+ * @enum {0 | 1 | 2}
+ */
+export const QoS = Object.freeze({
+    Zero: 0, "0": "Zero",
+    One: 1, "1": "One",
+    Two: 2, "2": "Two",
+});
+
+/**
+ * @enum {0 | 1 | 2}
+ */
+export const RetainHandling = Object.freeze({
+    /**
+     * Send the retained messages at the time of the subscribe
+     */
+    Zero: 0, "0": "Zero",
+    /**
+     * Send retained messages at subscribe only if subscription does not currently exist
+     */
+    One: 1, "1": "One",
+    /**
+     * Do not send retained messages at the time of the subscription
+     */
+    Two: 2, "2": "Two",
+});
+
+/**
+ * @enum {0 | 1 | 2 | 128 | 131 | 135 | 143 | 145 | 151 | 158 | 161 | 162}
+ */
+export const SubAckReasonCode = Object.freeze({
+    /**
+     * The Subscription is accepted and the maximum QoS sent will be QoS 0 (This might be lower than requested)
+     */
+    GrantedQoS0: 0, "0": "GrantedQoS0",
+    /**
+     * The Subscription is accepted and the maximum QoS sent will be QoS1 (This might be lower than requested)
+     */
+    GrantedQoS1: 1, "1": "GrantedQoS1",
+    /**
+     * The subscription is accepted and any received QoS will be sent to this subscription
+     */
+    GrantedQoS2: 2, "2": "GrantedQoS2",
+    UnspecifiedError: 128, "128": "UnspecifiedError",
+    /**
+     * Subscribe packet is valid, but the server does not accept it
+     */
+    ImplementationSpecificError: 131, "131": "ImplementationSpecificError",
+    NotAuthorized: 135, "135": "NotAuthorized",
+    TopicFilterInvalid: 143, "143": "TopicFilterInvalid",
+    PacketIdentifierInUse: 145, "145": "PacketIdentifierInUse",
+    QuotaExceeded: 151, "151": "QuotaExceeded",
+    SharedSubscriptionsNotSupported: 158, "158": "SharedSubscriptionsNotSupported",
+    SubscriptionIdentifiersNotSupported: 161, "161": "SubscriptionIdentifiersNotSupported",
+    WildcardSubscriptionsNotSupported: 162, "162": "WildcardSubscriptionsNotSupported",
+});
+
+/**
+ * @enum {0 | 17 | 128 | 131 | 135 | 143 | 145}
+ */
+export const UnSubAckReasonCode = Object.freeze({
+    Success: 0, "0": "Success",
+    NoSubscriptionExisted: 17, "17": "NoSubscriptionExisted",
+    UnspecifiedError: 128, "128": "UnspecifiedError",
+    ImplementationSpecificError: 131, "131": "ImplementationSpecificError",
+    NotAuthorized: 135, "135": "NotAuthorized",
+    TopicFilterInvalid: 143, "143": "TopicFilterInvalid",
+    PacketIdentifierInUse: 145, "145": "PacketIdentifierInUse",
+});
+
+/**
+ * Decodes a single packet, Fixed Header included, which must take up all of `bytes`.
+ * Throws an `MqttError` when the packet is malformed or breaks the rules of the protocol
+ * @param {Uint8Array} bytes
+ * @returns {Packet}
+ */
+export function decodePacket(bytes) {
+    const ptr0 = passArray8ToWasm0(bytes, wasm.__wbindgen_malloc);
+    const len0 = WASM_VECTOR_LEN;
+    const ret = wasm.decodePacket(ptr0, len0);
+    if (ret[2]) {
+        throw takeFromExternrefTable0(ret[1]);
+    }
+    return takeFromExternrefTable0(ret[0]);
+}
+
+/**
+ * Encodes `packet` as it is sent on the wire, fields left out taking their default value.
+ * Throws a `TypeError` when a field doesn't have the expected type, and an `MqttError` when the packet breaks
+ * the rules of the protocol
+ * @param {Packet} packet
+ * @returns {Uint8Array}
+ */
+export function encodePacket(packet) {
+    const ret = wasm.encodePacket(packet);
+    if (ret[3]) {
+        throw takeFromExternrefTable0(ret[2]);
+    }
+    var v1 = getArrayU8FromWasm0(ret[0], ret[1]).slice();
+    wasm.__wbindgen_free(ret[0], ret[1] * 1, 1);
+    return v1;
+}
+function __wbg_get_imports() {
+    const import0 = {
+        __proto__: null,
+        __wbg___wbindgen_boolean_get_5b446f51afd21013: function(arg0) {
+            const v = arg0;
+            const ret = typeof(v) === 'boolean' ? v : undefined;
+            return isLikeNone(ret) ? 0xFFFFFF : ret ? 1 : 0;
+        },
+        __wbg___wbindgen_is_function_1f9d30630b8b1d3d: function(arg0) {
+            const ret = typeof(arg0) === 'function';
+            return ret;
+        },
+        __wbg___wbindgen_is_null_e343b7d08827ba72: function(arg0) {
+            const ret = arg0 === null;
+            return ret;
+        },
+        __wbg___wbindgen_is_undefined_8865fb403f8fe9d8: function(arg0) {
+            const ret = arg0 === undefined;
+            return ret;
+        },
+        __wbg___wbindgen_number_get_2e0e7dee9f701a71: function(arg0, arg1) {
+            const obj = arg1;
+            const ret = typeof(obj) === 'number' ? obj : undefined;
+            getDataViewMemory0().setFloat64(arg0 + 8 * 1, isLikeNone(ret) ? 0 : ret, true);
+            getDataViewMemory0().setInt32(arg0 + 4 * 0, !isLikeNone(ret), true);
+        },
+        __wbg___wbindgen_string_get_0380ccaa2f57f0d9: function(arg0, arg1) {
+            const obj = arg1;
+            const ret = typeof(obj) === 'string' ? obj : undefined;
+            var ptr1 = isLikeNone(ret) ? 0 : passStringToWasm0(ret, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+            var len1 = WASM_VECTOR_LEN;
+            getDataViewMemory0().setInt32(arg0 + 4 * 1, len1, true);
+            getDataViewMemory0().setInt32(arg0 + 4 * 0, ptr1, true);
+        },
+        __wbg___wbindgen_throw_41e9ee4f547fc59a: function(arg0, arg1) {
+            throw new Error(getStringFromWasm0(arg0, arg1));
+        },
+        __wbg__wbg_cb_unref_dcc1a90847f04c41: function(arg0) {
+            arg0._wbg_cb_unref();
+        },
+        __wbg_call_187d372bd5fdd4aa: function() { return handleError(function (arg0, arg1, arg2) {
+            const ret = arg0.call(arg1, arg2);
+            return ret;
+        }, arguments); },
+        __wbg_clearTimeout_3629d6209dfcc46e: function(arg0) {
+            const ret = clearTimeout(arg0);
+            return ret;
+        },
+        __wbg_close_d3ed56b5763be5ae: function() { return handleError(function (arg0) {
+            arg0.close();
+        }, arguments); },
+        __wbg_data_522f7abc70721269: function(arg0) {
+            const ret = arg0.data;
+            return ret;
+        },
+        __wbg_get_31af05bd4842a84f: function() { return handleError(function (arg0, arg1) {
+            const ret = Reflect.get(arg0, arg1);
+            return ret;
+        }, arguments); },
+        __wbg_get_6c896e0571ddae51: function(arg0, arg1) {
+            const ret = arg0[arg1 >>> 0];
+            return ret;
+        },
+        __wbg_get_unchecked_288889d017702237: function(arg0, arg1) {
+            const ret = arg0[arg1 >>> 0];
+            return ret;
+        },
+        __wbg_instanceof_ArrayBuffer_a99f175873e5d9b8: function(arg0) {
+            let result;
+            try {
+                result = arg0 instanceof ArrayBuffer;
+            } catch (_) {
+                result = false;
+            }
+            const ret = result;
+            return ret;
+        },
+        __wbg_instanceof_Uint8Array_828cef2aaacafc31: function(arg0) {
+            let result;
+            try {
+                result = arg0 instanceof Uint8Array;
+            } catch (_) {
+                result = false;
+            }
+            const ret = result;
+            return ret;
+        },
+        __wbg_isArray_e15a2ff68ffdbef2: function(arg0) {
+            const ret = Array.isArray(arg0);
+            return ret;
+        },
+        __wbg_length_7f3c00c40364105e: function(arg0) {
+            const ret = arg0.length;
+            return ret;
+        },
+        __wbg_length_d4bdea10311bd9cf: function(arg0) {
+            const ret = arg0.length;
+            return ret;
+        },
+        __wbg_mqttclient_new: function(arg0) {
+            const ret = MqttClient.__wrap(arg0);
+            return ret;
+        },
+        __wbg_new_1dbf7428bba60a42: function(arg0) {
+            const ret = new Uint8Array(arg0);
+            return ret;
+        },
+        __wbg_new_2bdcdfcebdfc34d2: function(arg0, arg1) {
+            const ret = new TypeError(getStringFromWasm0(arg0, arg1));
+            return ret;
+        },
+        __wbg_new_343a093a3c2ffb4e: function(arg0, arg1) {
+            const ret = new Error(getStringFromWasm0(arg0, arg1));
+            return ret;
+        },
+        __wbg_new_5502aad30c185fc8: function(arg0, arg1) {
+            try {
+                var state0 = {a: arg0, b: arg1};
+                var cb0 = (arg0, arg1) => {
+                    const a = state0.a;
+                    state0.a = 0;
+                    try {
+                        return wasm_bindgen__convert__closures_____invoke__h2d5142923d938445(a, state0.b, arg0, arg1);
+                    } finally {
+                        state0.a = a;
+                    }
+                };
+                const ret = new Promise(cb0);
+                return ret;
+            } finally {
+                state0.a = 0;
+            }
+        },
+        __wbg_new_617a8cdb8bb1130e: function() {
+            const ret = new Object();
+            return ret;
+        },
+        __wbg_new_ee2291f50781bf1d: function() {
+            const ret = new Array();
+            return ret;
+        },
+        __wbg_new_from_slice_9a868026ffa4208a: function(arg0, arg1) {
+            const ret = new Uint8Array(getArrayU8FromWasm0(arg0, arg1));
+            return ret;
+        },
+        __wbg_new_typed_b01cb72a8af741a3: function(arg0, arg1) {
+            try {
+                var state0 = {a: arg0, b: arg1};
+                var cb0 = (arg0, arg1) => {
+                    const a = state0.a;
+                    state0.a = 0;
+                    try {
+                        return wasm_bindgen__convert__closures_____invoke__h2d5142923d938445(a, state0.b, arg0, arg1);
+                    } finally {
+                        state0.a = a;
+                    }
+                };
+                const ret = new Promise(cb0);
+                return ret;
+            } finally {
+                state0.a = 0;
+            }
+        },
+        __wbg_new_with_str_d969ab40df6f3773: function() { return handleError(function (arg0, arg1, arg2, arg3) {
+            const ret = new WebSocket(getStringFromWasm0(arg0, arg1), getStringFromWasm0(arg2, arg3));
+            return ret;
+        }, arguments); },
+        __wbg_now_e7c6795a7f81e10f: function(arg0) {
+            const ret = arg0.now();
+            return ret;
+        },
+        __wbg_of_20798cb14708764f: function(arg0, arg1) {
+            const ret = Array.of(arg0, arg1);
+            return ret;
+        },
+        __wbg_performance_3fcf6e32a7e1ed0a: function(arg0) {
+            const ret = arg0.performance;
+            return ret;
+        },
+        __wbg_protocol_f3c53ad42fd438f8: function(arg0, arg1) {
+            const ret = arg1.protocol;
+            const ptr1 = passStringToWasm0(ret, wasm.__wbindgen_malloc, wasm.__wbindgen_realloc);
+            const len1 = WASM_VECTOR_LEN;
+            getDataViewMemory0().setInt32(arg0 + 4 * 1, len1, true);
+            getDataViewMemory0().setInt32(arg0 + 4 * 0, ptr1, true);
+        },
+        __wbg_prototypesetcall_bc27214492979395: function(arg0, arg1, arg2) {
+            Uint8Array.prototype.set.call(getArrayU8FromWasm0(arg0, arg1), arg2);
+        },
+        __wbg_push_2baf45db356cf468: function(arg0, arg1) {
+            const ret = arg0.push(arg1);
+            return ret;
+        },
+        __wbg_queueMicrotask_9833f9a49df95a49: function(arg0) {
+            const ret = arg0.queueMicrotask;
+            return ret;
+        },
+        __wbg_queueMicrotask_a72f977e97f23c5f: function(arg0) {
+            queueMicrotask(arg0);
+        },
+        __wbg_readyState_d8514376867e415b: function(arg0) {
+            const ret = arg0.readyState;
+            return ret;
+        },
+        __wbg_resolve_0076e10020304ede: function(arg0) {
+            const ret = Promise.resolve(arg0);
+            return ret;
+        },
+        __wbg_send_c1dad0ec2e52defb: function() { return handleError(function (arg0, arg1, arg2) {
+            arg0.send(getArrayU8FromWasm0(arg1, arg2));
+        }, arguments); },
+        __wbg_setTimeout_56bcdccbad22fd44: function() { return handleError(function (arg0, arg1) {
+            const ret = setTimeout(arg0, arg1);
+            return ret;
+        }, arguments); },
+        __wbg_set_145a351398b48c65: function() { return handleError(function (arg0, arg1, arg2) {
+            const ret = Reflect.set(arg0, arg1, arg2);
+            return ret;
+        }, arguments); },
+        __wbg_set_binaryType_21835bf0df8f70aa: function(arg0, arg1) {
+            arg0.binaryType = __wbindgen_enum_BinaryType[arg1];
+        },
+        __wbg_set_name_2c630595dc90a7aa: function(arg0, arg1, arg2) {
+            arg0.name = getStringFromWasm0(arg1, arg2);
+        },
+        __wbg_set_onclose_a84370531f3d2948: function(arg0, arg1) {
+            arg0.onclose = arg1;
+        },
+        __wbg_set_onerror_94ee307653399172: function(arg0, arg1) {
+            arg0.onerror = arg1;
+        },
+        __wbg_set_onmessage_cb6f77d2d8e0402a: function(arg0, arg1) {
+            arg0.onmessage = arg1;
+        },
+        __wbg_set_onopen_c914147e8a2db4b0: function(arg0, arg1) {
+            arg0.onopen = arg1;
+        },
+        __wbg_static_accessor_GLOBAL_266715b9d96ba635: function() {
+            const ret = typeof global === 'undefined' ? null : global;
+            return isLikeNone(ret) ? 0 : addToExternrefTable0(ret);
+        },
+        __wbg_static_accessor_GLOBAL_THIS_10fb7dc1ae063179: function() {
+            const ret = typeof globalThis === 'undefined' ? null : globalThis;
+            return isLikeNone(ret) ? 0 : addToExternrefTable0(ret);
+        },
+        __wbg_static_accessor_SELF_0b583911f537483a: function() {
+            const ret = typeof self === 'undefined' ? null : self;
+            return isLikeNone(ret) ? 0 : addToExternrefTable0(ret);
+        },
+        __wbg_static_accessor_WINDOW_d7f903d1508cbdc4: function() {
+            const ret = typeof window === 'undefined' ? null : window;
+            return isLikeNone(ret) ? 0 : addToExternrefTable0(ret);
+        },
+        __wbg_then_c949d5a25a4e78f8: function(arg0, arg1, arg2) {
+            const ret = arg0.then(arg1, arg2);
+            return ret;
+        },
+        __wbg_then_e71170d78fcf8954: function(arg0, arg1) {
+            const ret = arg0.then(arg1);
+            return ret;
+        },
+        __wbindgen_generic_0000000000000001: function(arg0, arg1) {
+            // Cast intrinsic for `Closure(Closure { owned: true, function: Function { arguments: [Externref], shim_idx: 190, ret: Result(Unit), inner_ret: Some(Result(Unit)) }, mutable: true }) -> Externref`.
+            const ret = makeMutClosure(arg0, arg1, wasm_bindgen__convert__closures_____invoke__hd5224973baf38e05);
+            return ret;
+        },
+        __wbindgen_generic_0000000000000002: function(arg0, arg1) {
+            // Cast intrinsic for `Closure(Closure { owned: true, function: Function { arguments: [NamedExternref("CloseEvent")], shim_idx: 110, ret: Unit, inner_ret: Some(Unit) }, mutable: true }) -> Externref`.
+            const ret = makeMutClosure(arg0, arg1, wasm_bindgen__convert__closures_____invoke__h5777668fe466b876);
+            return ret;
+        },
+        __wbindgen_generic_0000000000000003: function(arg0, arg1) {
+            // Cast intrinsic for `Closure(Closure { owned: true, function: Function { arguments: [NamedExternref("Event")], shim_idx: 110, ret: Unit, inner_ret: Some(Unit) }, mutable: true }) -> Externref`.
+            const ret = makeMutClosure(arg0, arg1, wasm_bindgen__convert__closures_____invoke__h5777668fe466b876_12);
+            return ret;
+        },
+        __wbindgen_generic_0000000000000004: function(arg0, arg1) {
+            // Cast intrinsic for `Closure(Closure { owned: true, function: Function { arguments: [NamedExternref("MessageEvent")], shim_idx: 110, ret: Unit, inner_ret: Some(Unit) }, mutable: true }) -> Externref`.
+            const ret = makeMutClosure(arg0, arg1, wasm_bindgen__convert__closures_____invoke__h5777668fe466b876_13);
+            return ret;
+        },
+        __wbindgen_generic_0000000000000005: function(arg0, arg1) {
+            // Cast intrinsic for `Closure(Closure { owned: true, function: Function { arguments: [], shim_idx: 169, ret: Unit, inner_ret: Some(Unit) }, mutable: true }) -> Externref`.
+            const ret = makeMutClosure(arg0, arg1, wasm_bindgen__convert__closures_____invoke__h3b77f23eff1f1e9a);
+            return ret;
+        },
+        __wbindgen_generic_0000000000000006: function(arg0) {
+            // Cast intrinsic for `F64 -> Externref`.
+            const ret = arg0;
+            return ret;
+        },
+        __wbindgen_generic_0000000000000007: function(arg0, arg1) {
+            // Cast intrinsic for `Ref(String) -> Externref`.
+            const ret = getStringFromWasm0(arg0, arg1);
+            return ret;
+        },
+        __wbindgen_init_externref_table: function() {
+            const table = wasm.__wbindgen_externrefs;
+            const offset = table.grow(4);
+            table.set(0, undefined);
+            table.set(offset + 0, undefined);
+            table.set(offset + 1, null);
+            table.set(offset + 2, true);
+            table.set(offset + 3, false);
+        },
+    };
+    return {
+        __proto__: null,
+        "./mqttea_core_bg.js": import0,
+    };
+}
+
+function wasm_bindgen__convert__closures_____invoke__h3b77f23eff1f1e9a(arg0, arg1) {
+    wasm.wasm_bindgen__convert__closures_____invoke__h3b77f23eff1f1e9a(arg0, arg1);
+}
+
+function wasm_bindgen__convert__closures_____invoke__h5777668fe466b876(arg0, arg1, arg2) {
+    wasm.wasm_bindgen__convert__closures_____invoke__h5777668fe466b876(arg0, arg1, arg2);
+}
+
+function wasm_bindgen__convert__closures_____invoke__h5777668fe466b876_12(arg0, arg1, arg2) {
+    wasm.wasm_bindgen__convert__closures_____invoke__h5777668fe466b876_12(arg0, arg1, arg2);
+}
+
+function wasm_bindgen__convert__closures_____invoke__h5777668fe466b876_13(arg0, arg1, arg2) {
+    wasm.wasm_bindgen__convert__closures_____invoke__h5777668fe466b876_13(arg0, arg1, arg2);
+}
+
+function wasm_bindgen__convert__closures_____invoke__hd5224973baf38e05(arg0, arg1, arg2) {
+    const ret = wasm.wasm_bindgen__convert__closures_____invoke__hd5224973baf38e05(arg0, arg1, arg2);
+    if (ret[1]) {
+        throw takeFromExternrefTable0(ret[0]);
+    }
+}
+
+function wasm_bindgen__convert__closures_____invoke__h2d5142923d938445(arg0, arg1, arg2, arg3) {
+    wasm.wasm_bindgen__convert__closures_____invoke__h2d5142923d938445(arg0, arg1, arg2, arg3);
+}
+
+
+const __wbindgen_enum_BinaryType = ["blob", "arraybuffer"];
+const MqttClientFinalization = (typeof FinalizationRegistry === 'undefined')
+    ? { register: () => {}, unregister: () => {} }
+    : new FinalizationRegistry(ptr => wasm.__wbg_mqttclient_free(ptr, 1));
+
+function addToExternrefTable0(obj) {
+    const idx = wasm.__externref_table_alloc();
+    wasm.__wbindgen_externrefs.set(idx, obj);
+    return idx;
+}
+
+const CLOSURE_DTORS = (typeof FinalizationRegistry === 'undefined')
+    ? { register: () => {}, unregister: () => {} }
+    : new FinalizationRegistry(state => wasm.__wbindgen_destroy_closure(state.a, state.b));
+
+function getArrayU8FromWasm0(ptr, len) {
+    ptr = ptr >>> 0;
+    return getUint8ArrayMemory0().subarray(ptr / 1, ptr / 1 + len);
+}
+
+let cachedDataViewMemory0 = null;
+function getDataViewMemory0() {
+    if (cachedDataViewMemory0 === null || cachedDataViewMemory0.buffer.detached === true || (cachedDataViewMemory0.buffer.detached === undefined && cachedDataViewMemory0.buffer !== wasm.memory.buffer)) {
+        cachedDataViewMemory0 = new DataView(wasm.memory.buffer);
+    }
+    return cachedDataViewMemory0;
+}
+
+function getStringFromWasm0(ptr, len) {
+    return decodeText(ptr >>> 0, len);
+}
+
+let cachedUint8ArrayMemory0 = null;
+function getUint8ArrayMemory0() {
+    if (cachedUint8ArrayMemory0 === null || cachedUint8ArrayMemory0.byteLength === 0) {
+        cachedUint8ArrayMemory0 = new Uint8Array(wasm.memory.buffer);
+    }
+    return cachedUint8ArrayMemory0;
+}
+
+function handleError(f, args) {
+    try {
+        return f.apply(this, args);
+    } catch (e) {
+        const idx = addToExternrefTable0(e);
+        wasm.__wbindgen_exn_store(idx);
+    }
+}
+
+function isLikeNone(x) {
+    return x === undefined || x === null;
+}
+
+function makeMutClosure(arg0, arg1, f) {
+    const state = { a: arg0, b: arg1, cnt: 1 };
+    const real = (...args) => {
+
+        // First up with a closure we increment the internal reference
+        // count. This ensures that the Rust closure environment won't
+        // be deallocated while we're invoking it.
+        state.cnt++;
+        const a = state.a;
+        state.a = 0;
+        try {
+            return f(a, state.b, ...args);
+        } finally {
+            state.a = a;
+            real._wbg_cb_unref();
+        }
+    };
+    real._wbg_cb_unref = () => {
+        if (--state.cnt === 0) {
+            wasm.__wbindgen_destroy_closure(state.a, state.b);
+            state.a = 0;
+            CLOSURE_DTORS.unregister(state);
+        }
+    };
+    CLOSURE_DTORS.register(real, state, state);
+    return real;
+}
+
+function passArray8ToWasm0(arg, malloc) {
+    const ptr = malloc(arg.length * 1, 1) >>> 0;
+    getUint8ArrayMemory0().set(arg, ptr / 1);
+    WASM_VECTOR_LEN = arg.length;
+    return ptr;
+}
+
+function passStringToWasm0(arg, malloc, realloc) {
+    if (realloc === undefined) {
+        const buf = cachedTextEncoder.encode(arg);
+        const ptr = malloc(buf.length, 1) >>> 0;
+        getUint8ArrayMemory0().subarray(ptr, ptr + buf.length).set(buf);
+        WASM_VECTOR_LEN = buf.length;
+        return ptr;
+    }
+
+    let len = arg.length;
+    let ptr = malloc(len, 1) >>> 0;
+
+    const mem = getUint8ArrayMemory0();
+
+    let offset = 0;
+
+    for (; offset < len; offset++) {
+        const code = arg.charCodeAt(offset);
+        if (code > 0x7F) break;
+        mem[ptr + offset] = code;
+    }
+    if (offset !== len) {
+        if (offset !== 0) {
+            arg = arg.slice(offset);
+        }
+        ptr = realloc(ptr, len, len = offset + arg.length * 3, 1) >>> 0;
+        const view = getUint8ArrayMemory0().subarray(ptr + offset, ptr + len);
+        const ret = cachedTextEncoder.encodeInto(arg, view);
+
+        offset += ret.written;
+        ptr = realloc(ptr, len, offset, 1) >>> 0;
+    }
+
+    WASM_VECTOR_LEN = offset;
+    return ptr;
+}
+
+function takeFromExternrefTable0(idx) {
+    const value = wasm.__wbindgen_externrefs.get(idx);
+    wasm.__externref_table_dealloc(idx);
+    return value;
+}
+
+let cachedTextDecoder = new TextDecoder('utf-8', { ignoreBOM: true, fatal: true });
+cachedTextDecoder.decode();
+const MAX_SAFARI_DECODE_BYTES = 2146435072;
+let numBytesDecoded = 0;
+function decodeText(ptr, len) {
+    numBytesDecoded += len;
+    if (numBytesDecoded >= MAX_SAFARI_DECODE_BYTES) {
+        cachedTextDecoder = new TextDecoder('utf-8', { ignoreBOM: true, fatal: true });
+        cachedTextDecoder.decode();
+        numBytesDecoded = len;
+    }
+    return cachedTextDecoder.decode(getUint8ArrayMemory0().subarray(ptr, ptr + len));
+}
+
+const cachedTextEncoder = new TextEncoder();
+
+if (!('encodeInto' in cachedTextEncoder)) {
+    cachedTextEncoder.encodeInto = function (arg, view) {
+        const buf = cachedTextEncoder.encode(arg);
+        view.set(buf);
+        return {
+            read: arg.length,
+            written: buf.length
+        };
+    };
+}
+
+let WASM_VECTOR_LEN = 0;
+
+let wasmModule, wasmInstance, wasm;
+function __wbg_finalize_init(instance, module) {
+    wasmInstance = instance;
+    wasm = instance.exports;
+    wasmModule = module;
+    cachedDataViewMemory0 = null;
+    cachedUint8ArrayMemory0 = null;
+    wasm.__wbindgen_start();
+    return wasm;
+}
 
 async function __wbg_load(module, imports) {
     if (typeof Response === 'function' && module instanceof Response) {
+        if (!module.ok) {
+            throw new Error(`failed to fetch Wasm: ${module.status} ${module.statusText} fetching '${module.url}'`);
+        }
+
         if (typeof WebAssembly.instantiateStreaming === 'function') {
             try {
                 return await WebAssembly.instantiateStreaming(module, imports);
-
             } catch (e) {
-                if (module.headers.get('Content-Type') != 'application/wasm') {
+                const validResponse = expectedResponseType(module.type);
+
+                if (validResponse && module.headers.get('Content-Type') !== 'application/wasm') {
                     console.warn("`WebAssembly.instantiateStreaming` failed because your server does not serve Wasm with `application/wasm` MIME type. Falling back to `WebAssembly.instantiate` which is slower. Original error:\n", e);
 
-                } else {
-                    throw e;
-                }
+                } else { throw e; }
             }
         }
 
         const bytes = await module.arrayBuffer();
         return await WebAssembly.instantiate(bytes, imports);
-
     } else {
         const instance = await WebAssembly.instantiate(module, imports);
 
         if (instance instanceof WebAssembly.Instance) {
             return { instance, module };
-
         } else {
             return instance;
         }
     }
-}
-
-function __wbg_get_imports() {
-    const imports = {};
-    imports.wbg = {};
-    imports.wbg.__wbindgen_init_externref_table = function() {
-        const table = wasm.__wbindgen_export_0;
-        const offset = table.grow(4);
-        table.set(0, undefined);
-        table.set(offset + 0, undefined);
-        table.set(offset + 1, null);
-        table.set(offset + 2, true);
-        table.set(offset + 3, false);
-        ;
-    };
-
-    return imports;
-}
-
-function __wbg_init_memory(imports, memory) {
 
-}
-
-function __wbg_finalize_init(instance, module) {
-    wasm = instance.exports;
-    __wbg_init.__wbindgen_wasm_module = module;
-
-
-    wasm.__wbindgen_start();
-    return wasm;
+    function expectedResponseType(type) {
+        switch (type) {
+            case 'basic': case 'cors': case 'default': return true;
+        }
+        return false;
+    }
 }
 
 function initSync(module) {
     if (wasm !== undefined) return wasm;
 
 
-    if (typeof module !== 'undefined') {
+    if (module !== undefined) {
         if (Object.getPrototypeOf(module) === Object.prototype) {
             ({module} = module)
         } else {
@@ -74,15 +882,10 @@ function initSync(module) {
     }
 
     const imports = __wbg_get_imports();
-
-    __wbg_init_memory(imports);
-
     if (!(module instanceof WebAssembly.Module)) {
         module = new WebAssembly.Module(module);
     }
-
     const instance = new WebAssembly.Instance(module, imports);
-
     return __wbg_finalize_init(instance, module);
 }
 
@@ -90,7 +893,7 @@ async function __wbg_init(module_or_path) {
     if (wasm !== undefined) return wasm;
 
 
-    if (typeof module_or_path !== 'undefined') {
+    if (module_or_path !== undefined) {
         if (Object.getPrototypeOf(module_or_path) === Object.prototype) {
             ({module_or_path} = module_or_path)
         } else {
@@ -98,7 +901,7 @@ async function __wbg_init(module_or_path) {
         }
     }
 
-    if (typeof module_or_path === 'undefined') {
+    if (module_or_path === undefined) {
         module_or_path = new URL('mqttea_core_bg.wasm', import.meta.url);
     }
     const imports = __wbg_get_imports();
@@ -107,12 +910,9 @@ async function __wbg_init(module_or_path) {
         module_or_path = fetch(module_or_path);
     }
 
-    __wbg_init_memory(imports);
-
     const { instance, module } = await __wbg_load(await module_or_path, imports);
 
     return __wbg_finalize_init(instance, module);
 }
 
-export { initSync };
-export default __wbg_init;
+export { initSync, __wbg_init as default };
@@ -1,5 +1,28 @@
 /* tslint:disable */
 /* eslint-disable */
 export const memory: WebAssembly.Memory;
-export const __wbindgen_export_0: WebAssembly.Table;
+export const __wbg_mqttclient_free: (a: number, b: number) => void;
+export const decodePacket: (a: number, b: number) => [number, number, number];
+export const encodePacket: (a: any) => [number, number, number, number];
+export const mqttclient_connect: (a: number, b: number, c: number) => any;
+export const mqttclient_disconnect: (a: number) => any;
+export const mqttclient_onClose: (a: number, b: any) => void;
+export const mqttclient_onMessage: (a: number, b: any) => void;
+export const mqttclient_publish: (a: number, b: number, c: number, d: any, e: number) => any;
+export const mqttclient_subscribe: (a: number, b: number, c: number, d: number) => any;
+export const mqttclient_unsubscribe: (a: number, b: number, c: number, d: number) => any;
+export const wasm_bindgen__convert__closures_____invoke__h2d5142923d938445: (a: number, b: number, c: any, d: any) => void;
+export const wasm_bindgen__convert__closures_____invoke__hd5224973baf38e05: (a: number, b: number, c: any) => [number, number];
+export const wasm_bindgen__convert__closures_____invoke__h5777668fe466b876: (a: number, b: number, c: any) => void;
+export const wasm_bindgen__convert__closures_____invoke__h5777668fe466b876_12: (a: number, b: number, c: any) => void;
+export const wasm_bindgen__convert__closures_____invoke__h5777668fe466b876_13: (a: number, b: number, c: any) => void;
+export const wasm_bindgen__convert__closures_____invoke__h3b77f23eff1f1e9a: (a: number, b: number) => void;
+export const __wbindgen_malloc: (a: number, b: number) => number;
+export const __wbindgen_realloc: (a: number, b: number, c: number, d: number) => number;
+export const __wbindgen_exn_store: (a: number) => void;
+export const __externref_table_alloc: () => number;
+export const __wbindgen_externrefs: WebAssembly.Table;
+export const __wbindgen_destroy_closure: (a: number, b: number) => void;
+export const __externref_table_dealloc: (a: number) => void;
+export const __wbindgen_free: (a: number, b: number, c: number) => void;
 export const __wbindgen_start: () => void;
@@ -0,0 +1,132 @@
+use std::{
+    sync::Barrier,
+    thread,
+    time::{Duration, Instant},
+};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use mqttea::bench::Subscriptions;
+use mqttea_core::v5::commons::qos::QoS;
+
+const SIZES: [usize; 3] = [10_000, 100_000, 1_000_000];
+
+/// Devices spread over a thousand sites, one subscription each, shared between ten thousand clients.
+/// One in ten subscriptions has a `+` in place of the site, another one in ten a trailing `#`
+fn filter(i: usize) -> String {
+    match i % 10 {
+        0 => format!("site/+/device/{i}/temp"),
+        1 => format!("site/{}/device/{i}/#", i % 1000),
+        _ => format!("site/{}/device/{i}/temp", i % 1000),
+    }
+}
+
+fn topic(i: usize) -> String {
+    format!("site/{}/device/{i}/temp", i % 1000)
+}
+
+fn client_id(i: usize) -> String {
+    format!("client-{}", i % 10_000)
+}
+
+fn index(size: usize) -> Subscriptions {
+    let subscriptions = Subscriptions::default();
+    for i in 0..size {
+        subscriptions.subscribe(&client_id(i), &filter(i), QoS::One, Some(i));
+    }
+    subscriptions
+}
+
+/// Routes a PUBLISH to topics matched by exact, `+` and `#` subscriptions in turn
+fn route(c: &mut Criterion) {
+    let mut group = c.benchmark_group("router/route");
+    group.throughput(Throughput::Elements(1));
+
+    for size in SIZES {
+        let subscriptions = index(size);
+        let topics: Vec<String> = (0..1024).map(|i| topic(i * (size / 1024))).collect();
+
+        group.bench_with_input(BenchmarkId::from_parameter(size), &topics, |b, topics| {
+            let mut topics = topics.iter().cycle();
+            b.iter(|| subscriptions.route(topics.next().unwrap(), "publisher"))
+        });
+
+        // nothing is subscribed to `$SYS`, and the wildcards at the first level are not even looked at
+        group.bench_with_input(BenchmarkId::new("unmatched", size), &size, |b, _| {
+            b.iter(|| subscriptions.route("$SYS/broker/clients/connected", "publisher"))
+        });
+    }
+
+    group.finish();
+}
+
+/// Subscribes a client and unsubscribes it right away, next to a million other subscriptions
+fn churn(c: &mut Criterion) {
+    let mut group = c.benchmark_group("router/churn");
+    group.throughput(Throughput::Elements(1));
+
+    let size = SIZES[SIZES.len() - 1];
+    let subscriptions = index(size);
+    let filters: Vec<String> = (size..size + 1024).map(filter).collect();
+
+    group.bench_with_input(BenchmarkId::from_parameter(size), &filters, |b, filters| {
+        let mut filters = filters.iter().cycle();
+        b.iter(|| {
+            let filter = filters.next().unwrap();
+            subscriptions.subscribe("churn", filter, QoS::Zero, None);
+            subscriptions.unsubscribe("churn", filter)
+        })
+    });
+
+    group.finish();
+}
+
+/// `threads` connections routing `iters` PUBLISH each through the same index
+fn contended(
+    subscriptions: &Subscriptions,
+    topics: &[String],
+    threads: usize,
+    iters: u64,
+) -> Duration {
+    let barrier = Barrier::new(threads + 1);
+
+    thread::scope(|scope| {
+        for thread in 0..threads {
+            let barrier = &barrier;
+            scope.spawn(move || {
+                let mut topics = topics.iter().cycle().skip(thread * 128);
+                barrier.wait();
+                for _ in 0..iters {
+                    subscriptions.route(topics.next().unwrap(), "publisher");
+                }
+            });
+        }
+
+        barrier.wait();
+        let start = Instant::now();
+        // the scope joins the threads before returning
+        start
+    })
+    .elapsed()
+}
+
+fn contention(c: &mut Criterion) {
+    let mut group = c.benchmark_group("router/contention");
+
+    let size = SIZES[SIZES.len() - 1];
+    let subscriptions = index(size);
+    let topics: Vec<String> = (0..1024).map(|i| topic(i * (size / 1024))).collect();
+
+    for threads in [1, 2, 4, 8] {
+        group.throughput(Throughput::Elements(threads as u64));
+        group.bench_with_input(
+            BenchmarkId::new(format!("filters={size}"), threads),
+            &threads,
+            |b, &threads| b.iter_custom(|iters| contended(&subscriptions, &topics, threads, iters)),
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, route, churn, contention);
+criterion_main!(benches);
@@ -0,0 +1,382 @@
+use std::{collections::HashMap, fs, io, path::Path};
+
+use super::{Authorizer, Identity};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Access {
+    /// Subscribing
+    Read,
+    /// Publishing
+    Write,
+    ReadWrite,
+    /// Neither, whatever other rules allow
+    Deny,
+}
+
+impl Access {
+    fn parse(word: &str) -> Option<Self> {
+        match word {
+            "read" => Some(Self::Read),
+            "write" => Some(Self::Write),
+            "readwrite" => Some(Self::ReadWrite),
+            "deny" => Some(Self::Deny),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Rule {
+    access: Access,
+    filter: String,
+}
+
+impl Rule {
+    /// `Some(false)` when the rule denies `filter`, `Some(true)` when it grants the access asked for.
+    /// A grant has to cover every topic `filter` matches, while a deny refuses it as soon as they have a topic in common,
+    /// so that subscribing to `sensors/#` doesn't deliver what `deny sensors/private/#` keeps away
+    fn check(&self, filter: &str, write: bool) -> Option<bool> {
+        match (self.access, write) {
+            (Access::Deny, _) => overlaps(&self.filter, filter).then_some(false),
+            (Access::ReadWrite, _) | (Access::Read, false) | (Access::Write, true) => {
+                covers(&self.filter, filter).then_some(true)
+            }
+            _ => None,
+        }
+    }
+
+    /// The rule with `%c` replaced by the Client Identifier and `%u` by the username.
+    /// `None` when the client has no username to substitute, or when either would add levels or wildcards to the filter
+    fn substitute(&self, identity: &Identity) -> Option<Self> {
+        let safe = |value: &str| !value.contains(['/', '+', '#']);
+
+        let mut filter = self.filter.clone();
+        if filter.contains("%c") {
+            safe(identity.client_id).then_some(())?;
+            filter = filter.replace("%c", identity.client_id);
+        }
+        if filter.contains("%u") {
+            let username = identity.username.filter(|username| safe(username))?;
+            filter = filter.replace("%u", username);
+        }
+
+        Some(Self {
+            access: self.access,
+            filter,
+        })
+    }
+}
+
+/// Whether every topic matched by `filter` is matched by `rule`.
+/// A topic is a filter without wildcards, so this also tells whether `rule` matches a topic (4.7)
+fn covers(rule: &str, filter: &str) -> bool {
+    let mut rules = rule.split('/');
+    let mut levels = filter.split('/');
+    let mut first = true;
+
+    loop {
+        match (rules.next(), levels.next()) {
+            // MQTT-4.7.2-1 wildcards at the first level don't match topics starting with `$`
+            (Some("+" | "#"), Some(level)) if first && level.starts_with('$') => return false,
+            // `#` also matches the parent level, "sport/#" matches "sport" (4.7.1.2)
+            (Some("#"), _) => return true,
+            (Some("+"), Some(level)) if level != "#" => {}
+            (Some(rule), Some(level)) if rule == level => {}
+            (None, None) => return true,
+            _ => return false,
+        }
+        first = false;
+    }
+}
+
+/// Whether some topic is matched by both `rule` and `filter` (4.7)
+fn overlaps(rule: &str, filter: &str) -> bool {
+    let mut rules = rule.split('/');
+    let mut levels = filter.split('/');
+    let mut first = true;
+
+    loop {
+        match (rules.next(), levels.next()) {
+            // MQTT-4.7.2-1 wildcards at the first level don't match topics starting with `$`
+            (Some("+" | "#"), Some(level)) | (Some(level), Some("+" | "#"))
+                if first && level.starts_with('$') =>
+            {
+                return false
+            }
+            // `#` also matches the parent level, "sport/#" matches "sport" (4.7.1.2)
+            (Some("#"), _) | (_, Some("#")) => return true,
+            (Some("+"), Some(_)) | (Some(_), Some("+")) => {}
+            (Some(rule), Some(level)) if rule == level => {}
+            (None, None) => return true,
+            _ => return false,
+        }
+        first = false;
+    }
+}
+
+/// Topics each client may publish and subscribe to, in the format of mosquitto's ACL files:
+///
+/// ```text
+/// # clients without a username may only read the weather
+/// topic read weather/#
+///
+/// user alice
+/// topic readwrite sensors/#
+/// topic deny sensors/private/#
+///
+/// # every client may publish its own status
+/// pattern write clients/%c/status
+/// pattern readwrite users/%u/#
+/// ```
+///
+/// `topic` rules apply to the `user` above them, or to clients without a username before any `user` line.
+/// `pattern` rules apply to every client, with `%c` replaced by its Client Identifier and `%u` by its username.
+/// The access is one of `read` (subscribe), `write` (publish), `readwrite` (the default) and `deny`.
+/// A `deny` rule refuses every topic it matches, whatever the other rules grant,
+/// and every filter matching any of those topics: with `deny sensors/private/#`, `sensors/#` can't be subscribed to either
+#[derive(Debug, Clone, Default)]
+pub struct AclFile {
+    anonymous: Vec<Rule>,
+    users: HashMap<String, Vec<Rule>>,
+    patterns: Vec<Rule>,
+}
+
+impl AclFile {
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(contents: &str) -> io::Result<Self> {
+        let mut acl = Self::default();
+        let mut user: Option<String> = None;
+
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let invalid = |reason: &str| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line {}: {reason}", number + 1),
+                )
+            };
+            let (keyword, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let rest = rest.trim();
+            if rest.is_empty() {
+                return Err(invalid(&format!("`{keyword}` expects an argument")));
+            }
+
+            let rule = || {
+                let (access, filter) = match rest.split_once(char::is_whitespace) {
+                    Some((access, filter)) if Access::parse(access).is_some() => {
+                        (Access::parse(access).unwrap(), filter.trim())
+                    }
+                    _ => (Access::ReadWrite, rest),
+                };
+                Rule {
+                    access,
+                    filter: filter.to_string(),
+                }
+            };
+
+            match keyword {
+                "user" => user = Some(rest.to_string()),
+                "topic" => match &user {
+                    Some(user) => acl.users.entry(user.clone()).or_default().push(rule()),
+                    None => acl.anonymous.push(rule()),
+                },
+                "pattern" => acl.patterns.push(rule()),
+                keyword => return Err(invalid(&format!("unknown keyword `{keyword}`"))),
+            }
+        }
+
+        Ok(acl)
+    }
+
+    fn allows(&self, identity: &Identity, filter: &str, write: bool) -> bool {
+        let rules = match identity.username {
+            Some(username) => self.users.get(username).map(Vec::as_slice),
+            None => Some(self.anonymous.as_slice()),
+        };
+        let patterns = self
+            .patterns
+            .iter()
+            .filter_map(|pattern| pattern.substitute(identity));
+
+        let mut allowed = false;
+        for check in rules
+            .into_iter()
+            .flatten()
+            .map(|rule| rule.check(filter, write))
+            .chain(patterns.map(|rule| rule.check(filter, write)))
+        {
+            match check {
+                Some(false) => return false,
+                Some(true) => allowed = true,
+                None => {}
+            }
+        }
+        allowed
+    }
+}
+
+impl Authorizer for AclFile {
+    fn authorize_publish(&self, identity: &Identity, topic: &str) -> bool {
+        self.allows(identity, topic, true)
+    }
+
+    fn authorize_subscribe(&self, identity: &Identity, filter: &str) -> bool {
+        self.allows(identity, filter, false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ACL: &str = "
+        # clients without a username may only read the weather
+        topic read weather/#
+
+        user alice
+        topic readwrite sensors/#
+        topic deny sensors/private/#
+        topic $SYS/#
+
+        user bob
+        topic write sensors/+/temperature
+
+        pattern write clients/%c/status
+        pattern users/%u/#
+    ";
+
+    fn identity<'a>(client_id: &'a str, username: Option<&'a str>) -> Identity<'a> {
+        Identity {
+            client_id,
+            username,
+        }
+    }
+
+    #[test]
+    fn covers_topics_and_filters() {
+        let cases = [
+            ("sport/#", "sport", true),
+            ("sport/#", "sport/tennis/player1", true),
+            ("sport/#", "sport/+/player1", true),
+            ("sport/#", "sport/#", true),
+            ("sport/+", "sport/tennis", true),
+            ("sport/+", "sport/+", true),
+            ("sport/+", "sport/#", false),
+            ("sport/+", "sport/tennis/player1", false),
+            ("sport/tennis", "sport/+", false),
+            ("sport/tennis", "sport/tennis", true),
+            ("sport/tennis", "sport", false),
+            ("#", "$SYS/uptime", false),
+            ("+/uptime", "$SYS/uptime", false),
+            ("$SYS/#", "$SYS/uptime", true),
+        ];
+
+        for (rule, filter, expected) in cases {
+            assert_eq!(covers(rule, filter), expected, "{rule} covering {filter}");
+        }
+    }
+
+    #[test]
+    fn applies_topic_rules_to_their_user() {
+        let acl = AclFile::parse(ACL).unwrap();
+        let alice = identity("a", Some("alice"));
+        let bob = identity("b", Some("bob"));
+        let anonymous = identity("c", None);
+
+        assert!(acl.authorize_publish(&alice, "sensors/kitchen/temperature"));
+        assert!(acl.authorize_subscribe(&alice, "sensors/kitchen/#"));
+        assert!(acl.authorize_subscribe(&alice, "$SYS/broker/uptime"));
+        assert!(!acl.authorize_subscribe(&alice, "weather/today"));
+
+        assert!(acl.authorize_publish(&bob, "sensors/kitchen/temperature"));
+        assert!(!acl.authorize_subscribe(&bob, "sensors/kitchen/temperature"));
+        assert!(!acl.authorize_publish(&bob, "sensors/kitchen/humidity"));
+
+        assert!(acl.authorize_subscribe(&anonymous, "weather/#"));
+        assert!(!acl.authorize_publish(&anonymous, "weather/today"));
+        assert!(!acl.authorize_subscribe(&identity("d", Some("carol")), "weather/#"));
+    }
+
+    #[test]
+    fn deny_wins_over_what_other_rules_grant() {
+        let acl = AclFile::parse(ACL).unwrap();
+        let alice = identity("a", Some("alice"));
+
+        assert!(!acl.authorize_publish(&alice, "sensors/private/key"));
+        assert!(!acl.authorize_subscribe(&alice, "sensors/private/#"));
+        assert!(!acl.authorize_subscribe(&alice, "sensors/private/key"));
+    }
+
+    #[test]
+    fn refuses_filters_reaching_into_what_is_denied() {
+        let acl = AclFile::parse(ACL).unwrap();
+        let alice = identity("a", Some("alice"));
+
+        // readwrite sensors/# covers them, but they would deliver sensors/private/key
+        assert!(!acl.authorize_subscribe(&alice, "sensors/#"));
+        assert!(!acl.authorize_subscribe(&alice, "sensors/+/key"));
+        // `#` also matches its parent level, which is sensors/private itself
+        assert!(!acl.authorize_subscribe(&alice, "sensors/+"));
+        assert!(acl.authorize_subscribe(&alice, "sensors/kitchen/+"));
+    }
+
+    #[test]
+    fn overlaps_topics_and_filters() {
+        let cases = [
+            ("sensors/private/#", "sensors/#", true),
+            ("sensors/private/#", "sensors/+/key", true),
+            ("sensors/private/#", "sensors/private", true),
+            ("sensors/private/#", "sensors/+", true),
+            ("sensors/private/#", "sensors/kitchen/#", false),
+            ("sensors/+/key", "sensors/private/+", true),
+            ("sensors/+/key", "sensors/+/lock", false),
+            ("a/b", "a/b/c", false),
+            ("#", "$SYS/uptime", false),
+            ("$SYS/#", "+/uptime", false),
+            ("+/uptime", "#", true),
+        ];
+
+        for (rule, filter, expected) in cases {
+            assert_eq!(
+                overlaps(rule, filter),
+                expected,
+                "{rule} overlapping {filter}"
+            );
+        }
+    }
+
+    #[test]
+    fn substitutes_client_identifier_and_username_in_patterns() {
+        let acl = AclFile::parse(ACL).unwrap();
+        let sensor = identity("sensor-1", Some("bob"));
+
+        assert!(acl.authorize_publish(&sensor, "clients/sensor-1/status"));
+        assert!(!acl.authorize_publish(&sensor, "clients/sensor-2/status"));
+        assert!(!acl.authorize_subscribe(&sensor, "clients/sensor-1/status"));
+        assert!(acl.authorize_subscribe(&sensor, "users/bob/inbox"));
+        assert!(!acl.authorize_subscribe(&sensor, "users/alice/inbox"));
+
+        // no username to substitute
+        assert!(!acl.authorize_subscribe(&identity("sensor-1", None), "users//inbox"));
+        // substitutions can't widen a rule
+        assert!(!acl.authorize_publish(&identity("+", None), "clients/sensor-2/status"));
+        assert!(!acl.authorize_subscribe(&identity("c", Some("#")), "users/alice/inbox"));
+    }
+
+    #[test]
+    fn reports_the_line_that_is_invalid() {
+        let err = AclFile::parse("user alice\ntopic read a/b\ntopics a/b").unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().starts_with("line 3:"));
+
+        let err = AclFile::parse("user").unwrap_err();
+        assert!(err.to_string().starts_with("line 1:"));
+    }
+}
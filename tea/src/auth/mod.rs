@@ -0,0 +1,58 @@
+//! Access control: who may connect to the broker, and which topics it may publish and subscribe to.
+
+use std::fmt::Debug;
+
+use mqttea_core::v5::packet::{connack::ConnAckReasonCode, connect::Connect};
+
+mod acl_file;
+mod password_file;
+
+pub use acl_file::AclFile;
+pub use password_file::PasswordFile;
+
+/// What a client presents in its CONNECT to be let in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Credentials<'a> {
+    pub client_id: &'a str,
+    pub username: Option<&'a str>,
+    pub password: Option<&'a str>,
+    /// 3.1.2.11.9 set when the client asks for enhanced authentication
+    pub authentication_method: Option<&'a str>,
+    pub authentication_data: Option<&'a [u8]>,
+}
+
+impl<'a> From<&'a Connect> for Credentials<'a> {
+    fn from(connect: &'a Connect) -> Self {
+        Self {
+            client_id: &connect.client_id,
+            username: connect.username.as_deref(),
+            password: connect.password.as_deref(),
+            authentication_method: connect.properties.authentication_method.as_deref(),
+            authentication_data: connect.properties.authentication_data.as_deref(),
+        }
+    }
+}
+
+/// Who a connected client is, as far as authorization goes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Identity<'a> {
+    pub client_id: &'a str,
+    pub username: Option<&'a str>,
+}
+
+/// Decides whether a CONNECT is let in
+pub trait Authenticator: Debug + Send + Sync {
+    /// Returns the Reason Code the CONNACK refuses the client with, if it is refused.
+    /// An Authentication Method is accepted in this single step, there is no AUTH exchange (4.12)
+    fn authenticate(&self, credentials: &Credentials) -> Result<(), ConnAckReasonCode>;
+}
+
+/// Decides which topics a client may publish and subscribe to.
+/// Refusals are sent back as Not Authorized (0x87) in the PUBACK, PUBREC or SUBACK
+pub trait Authorizer: Debug + Send + Sync {
+    /// Whether the client may publish to `topic`, will messages included
+    fn authorize_publish(&self, identity: &Identity, topic: &str) -> bool;
+
+    /// Whether the client may subscribe to `filter`
+    fn authorize_subscribe(&self, identity: &Identity, filter: &str) -> bool;
+}
@@ -0,0 +1,150 @@
+use std::{collections::HashMap, fs, io, path::Path};
+
+use argon2::{
+    password_hash::{PasswordHash, PasswordVerifier},
+    Argon2,
+};
+use mqttea_core::v5::packet::connack::ConnAckReasonCode;
+
+use super::{Authenticator, Credentials};
+
+/// Usernames along with the argon2 hash of their password, one `username:hash` per line.
+///
+/// Hashes are in the PHC string format, e.g. `$argon2id$v=19$m=19456,t=2,p=1$<salt>$<hash>`.
+/// Blank lines and lines starting with `#` are ignored.
+/// Clients without a username, or asking for enhanced authentication, are refused
+#[derive(Debug, Clone, Default)]
+pub struct PasswordFile {
+    users: HashMap<String, String>,
+}
+
+impl PasswordFile {
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(contents: &str) -> io::Result<Self> {
+        let mut users = HashMap::new();
+
+        for (number, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let invalid = |reason: &str| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line {}: {reason}", number + 1),
+                )
+            };
+            let (username, hash) = line
+                .split_once(':')
+                .ok_or_else(|| invalid("expected `username:hash`"))?;
+            PasswordHash::new(hash).map_err(|err| invalid(&err.to_string()))?;
+            users.insert(username.to_string(), hash.to_string());
+        }
+
+        Ok(Self { users })
+    }
+}
+
+impl Authenticator for PasswordFile {
+    fn authenticate(&self, credentials: &Credentials) -> Result<(), ConnAckReasonCode> {
+        if credentials.authentication_method.is_some() {
+            return Err(ConnAckReasonCode::BadAuthenticationMethod);
+        }
+
+        let (Some(username), Some(password)) = (credentials.username, credentials.password) else {
+            return Err(ConnAckReasonCode::BadUserNameOrPassword);
+        };
+        let hash = self
+            .users
+            .get(username)
+            .and_then(|hash| PasswordHash::new(hash).ok())
+            .ok_or(ConnAckReasonCode::BadUserNameOrPassword)?;
+
+        // the parameters the password was hashed with are read from the hash itself
+        Argon2::default()
+            .verify_password(password.as_bytes(), &hash)
+            .map_err(|_| ConnAckReasonCode::BadUserNameOrPassword)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use argon2::{
+        password_hash::{PasswordHasher, SaltString},
+        Algorithm, Params, Version,
+    };
+
+    use super::*;
+
+    /// A hash cheap enough to compute in tests
+    fn hash(password: &str) -> String {
+        let argon2 = Argon2::new(
+            Algorithm::Argon2id,
+            Version::V0x13,
+            Params::new(8, 1, 1, None).unwrap(),
+        );
+        let salt = SaltString::from_b64("c2FsdHNhbHRzYWx0").unwrap();
+        argon2
+            .hash_password(password.as_bytes(), &salt)
+            .unwrap()
+            .to_string()
+    }
+
+    fn credentials<'a>(username: Option<&'a str>, password: Option<&'a str>) -> Credentials<'a> {
+        Credentials {
+            client_id: "client",
+            username,
+            password,
+            authentication_method: None,
+            authentication_data: None,
+        }
+    }
+
+    #[test]
+    fn verifies_passwords() {
+        let file = PasswordFile::parse(&format!(
+            "# sensors\n\nalice:{}\nbob:{}\n",
+            hash("wonderland"),
+            hash("builder")
+        ))
+        .unwrap();
+
+        let authenticate = |username, password| file.authenticate(&credentials(username, password));
+        assert_eq!(authenticate(Some("alice"), Some("wonderland")), Ok(()));
+        assert_eq!(authenticate(Some("bob"), Some("builder")), Ok(()));
+
+        let refused = Err(ConnAckReasonCode::BadUserNameOrPassword);
+        assert_eq!(authenticate(Some("alice"), Some("builder")), refused);
+        assert_eq!(authenticate(Some("carol"), Some("wonderland")), refused);
+        assert_eq!(authenticate(Some("alice"), None), refused);
+        assert_eq!(authenticate(None, None), refused);
+    }
+
+    #[test]
+    fn refuses_enhanced_authentication() {
+        let file = PasswordFile::parse(&format!("alice:{}", hash("wonderland"))).unwrap();
+        let credentials = Credentials {
+            authentication_method: Some("SCRAM-SHA-256"),
+            ..credentials(Some("alice"), Some("wonderland"))
+        };
+
+        assert_eq!(
+            file.authenticate(&credentials),
+            Err(ConnAckReasonCode::BadAuthenticationMethod)
+        );
+    }
+
+    #[test]
+    fn reports_the_line_that_is_invalid() {
+        let err = PasswordFile::parse(&format!("alice:{}\nbob", hash("wonderland"))).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(err.to_string().starts_with("line 2:"));
+
+        let err = PasswordFile::parse("alice:wonderland").unwrap_err();
+        assert!(err.to_string().starts_with("line 1:"));
+    }
+}
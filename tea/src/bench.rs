@@ -0,0 +1,34 @@
+//! Entry points into the broker's internals for the criterion benches under `benches/`.
+//! Nothing here is part of the public API.
+
+use mqttea_core::v5::{commons::qos::QoS, packet::subscribe::SubscriptionOptions};
+
+use crate::router::{Router, Subscription};
+
+/// The broker's subscription index
+#[derive(Debug, Default)]
+pub struct Subscriptions(Router);
+
+impl Subscriptions {
+    pub fn subscribe(&self, client_id: &str, filter: &str, qos: QoS, id: Option<usize>) -> bool {
+        let options = SubscriptionOptions {
+            qos,
+            ..Default::default()
+        };
+        self.0
+            .subscribe(client_id, filter, Subscription { options, id })
+    }
+
+    pub fn unsubscribe(&self, client_id: &str, filter: &str) -> bool {
+        self.0.unsubscribe(client_id, filter)
+    }
+
+    pub fn remove(&self, client_id: &str) {
+        self.0.remove(client_id)
+    }
+
+    /// Number of clients a PUBLISH to `topic` is forwarded to
+    pub fn route(&self, topic: &str, publisher: &str) -> usize {
+        self.0.route(topic, publisher).len()
+    }
+}
@@ -0,0 +1,146 @@
+use std::{
+    io,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+#[cfg(unix)]
+use std::path::Path;
+
+#[cfg(unix)]
+use tokio::net::UnixListener;
+use tokio::net::{TcpListener, ToSocketAddrs};
+
+#[cfg(feature = "tls")]
+use crate::tls::{TlsConfig, TlsReloader};
+use crate::{
+    config::Config,
+    listener::{DuplexConnector, Listener},
+    retain::Retained,
+    router::Router,
+    session::Sessions,
+    transport::Transport,
+};
+
+/// State shared by every connection to the broker
+#[derive(Debug)]
+pub(crate) struct Shared {
+    pub(crate) config: Config,
+    pub(crate) sessions: Mutex<Sessions>,
+    pub(crate) router: Router,
+    pub(crate) retained: Retained,
+    connections: AtomicU64,
+}
+
+impl Shared {
+    /// Identifies a connection, so that it can tell whether it still owns its session
+    pub(crate) fn next_connection(&self) -> u64 {
+        self.connections.fetch_add(1, Ordering::Relaxed)
+    }
+}
+
+/// A single node MQTT 5 broker, serving every connection on its own task
+#[derive(Debug)]
+pub struct Broker {
+    listener: Listener,
+    transport: Transport,
+    shared: Arc<Shared>,
+}
+
+impl Broker {
+    pub async fn bind<A: ToSocketAddrs>(addr: A, config: Config) -> io::Result<Self> {
+        let listener = TcpListener::bind(addr).await?;
+        Self::new(Listener::Tcp(listener), config)
+    }
+
+    /// Binds a listener on the Unix domain socket at `path`, for clients on the same host (e.g. sidecars).
+    /// Binding fails when a file already exists at `path`, which is left behind once the broker is dropped
+    #[cfg(unix)]
+    pub async fn bind_unix<P: AsRef<Path>>(path: P, config: Config) -> io::Result<Self> {
+        let listener = UnixListener::bind(path)?;
+        Self::new(Listener::Unix(listener), config)
+    }
+
+    /// A broker without any socket, which its clients connect to through the in-memory streams of the connector,
+    /// e.g. to run a client and the broker in the same process with no network at all
+    pub fn in_memory(config: Config) -> io::Result<(Self, DuplexConnector)> {
+        let (connector, listener) = DuplexConnector::new();
+        Ok((Self::new(listener, config)?, connector))
+    }
+
+    fn new(listener: Listener, config: Config) -> io::Result<Self> {
+        let retained = Retained::load(config.retained_store.clone())?;
+        let shared = Arc::new(Shared {
+            config,
+            sessions: Mutex::new(Sessions::default()),
+            router: Router::default(),
+            retained,
+            connections: AtomicU64::new(1),
+        });
+
+        Ok(Self {
+            listener,
+            transport: Transport::default(),
+            shared,
+        })
+    }
+
+    /// Binds a listener on which every connection is made over TLS 1.3, presenting the certificate of `tls`
+    #[cfg(feature = "tls")]
+    pub async fn bind_tls<A: ToSocketAddrs>(
+        addr: A,
+        config: Config,
+        tls: TlsConfig,
+    ) -> io::Result<Self> {
+        let acceptor = tls.acceptor()?;
+        let mut broker = Self::bind(addr, config).await?;
+        broker.transport.tls = Some(TlsReloader::new(acceptor));
+        Ok(broker)
+    }
+
+    /// Binds a listener on which every connection is a WebSocket carrying MQTT in binary frames (`ws://`)
+    #[cfg(feature = "websocket")]
+    pub async fn bind_websocket<A: ToSocketAddrs>(addr: A, config: Config) -> io::Result<Self> {
+        let mut broker = Self::bind(addr, config).await?;
+        broker.transport.websocket = true;
+        Ok(broker)
+    }
+
+    /// Binds a listener on which every connection is a WebSocket over TLS 1.3 (`wss://`), presenting the certificate of `tls`
+    #[cfg(all(feature = "websocket", feature = "tls"))]
+    pub async fn bind_websocket_tls<A: ToSocketAddrs>(
+        addr: A,
+        config: Config,
+        tls: TlsConfig,
+    ) -> io::Result<Self> {
+        let mut broker = Self::bind_tls(addr, config, tls).await?;
+        broker.transport.websocket = true;
+        Ok(broker)
+    }
+
+    /// Rotates the certificates of a TLS listener while it runs, `None` for a listener without TLS
+    #[cfg(feature = "tls")]
+    pub fn tls_reloader(&self) -> Option<TlsReloader> {
+        self.transport.tls.clone()
+    }
+
+    /// The address of a TCP listener, `Unsupported` for the others
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        match &self.listener {
+            Listener::Tcp(listener) => listener.local_addr(),
+            _ => Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                "the broker isn't listening on TCP",
+            )),
+        }
+    }
+
+    /// Accepts connections until the listener fails, or until every connector of an in-memory broker is dropped
+    pub async fn run(self) -> io::Result<()> {
+        self.listener.run(self.transport, self.shared).await
+    }
+}
+
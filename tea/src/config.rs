@@ -0,0 +1,66 @@
+use std::{num::NonZero, sync::Arc, time::Duration};
+
+use mqttea_core::v5::commons::qos::QoS;
+
+use crate::{
+    auth::{Authenticator, Authorizer},
+    retain::RetainedStore,
+};
+
+/// Limits the broker enforces, most of which are announced to every client in its CONNACK
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// 3.2.2.3.3 Number of QoS 1 and QoS 2 publications the broker processes concurrently for each client
+    pub receive_maximum: NonZero<u16>,
+    /// 3.2.2.3.6 Largest packet the broker accepts, a client sending anything bigger is disconnected
+    pub maximum_packet_size: NonZero<u32>,
+    /// 3.2.2.3.4 Highest QoS the broker accepts on PUBLISH and grants on SUBSCRIBE
+    pub maximum_qos: QoS,
+    /// 3.2.2.3.5 Whether PUBLISH packets with their RETAIN flag set are accepted and retained
+    pub retain_available: bool,
+    /// Where retained messages are persisted, they are only kept in memory when there is none
+    pub retained_store: Option<Arc<dyn RetainedStore>>,
+    /// 3.2.2.3.14 Keep Alive imposed on every client in place of the one it asked for
+    pub server_keep_alive: Option<u16>,
+    /// How long a new connection has to send its CONNECT before it is closed
+    pub connect_timeout: Duration,
+    /// 3.2.2.3.2 Longest Session Expiry Interval granted, in seconds. Clients asking for more are told in their CONNACK
+    pub maximum_session_expiry_interval: u32,
+    /// Number of messages queued for each session, while its client is offline or has no room for them
+    pub maximum_queued_messages: usize,
+    /// Which message is dropped when a session's queue is full
+    pub queue_drop_policy: DropPolicy,
+    /// Decides which clients are let in, every CONNECT is accepted when there is none
+    pub authenticator: Option<Arc<dyn Authenticator>>,
+    /// Decides which topics clients may publish and subscribe to, everything is allowed when there is none
+    pub authorizer: Option<Arc<dyn Authorizer>>,
+}
+
+/// Which message is dropped when a message is routed to a session whose queue is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DropPolicy {
+    /// The oldest message queued makes room for the new one
+    Oldest,
+    /// The new message is dropped
+    #[default]
+    Newest,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            receive_maximum: NonZero::new(1024).unwrap(),
+            maximum_packet_size: NonZero::new(1024 * 1024).unwrap(),
+            maximum_qos: QoS::Two,
+            retain_available: true,
+            retained_store: None,
+            server_keep_alive: None,
+            connect_timeout: Duration::from_secs(10),
+            maximum_session_expiry_interval: u32::MAX,
+            maximum_queued_messages: 1000,
+            queue_drop_policy: DropPolicy::default(),
+            authenticator: None,
+            authorizer: None,
+        }
+    }
+}
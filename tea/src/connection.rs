@@ -0,0 +1,828 @@
+use std::{sync::Arc, time::Duration};
+
+use mqttea_core::v5::{
+    client::network::outgoing::OutgoingBuffer,
+    commons::{
+        error::{ErrorKind, MQTTError},
+        packet::Packet,
+        qos::QoS,
+        violation::{Validate, Violation},
+    },
+    packet::{
+        connack::{ConnAck, ConnAckProperties, ConnAckReasonCode},
+        connect::{will::Will, Connect},
+        disconnect::{Disconnect, DisconnectProperties, DisconnectReasonCode},
+        ping::PingResp,
+        puback::{PubAck, PubAckReasonCode},
+        pubcomp::{PubComp, PubCompReasonCode},
+        publish::{Publish, PublishProperties},
+        pubrec::{PubRec, PubRecReasonCode},
+        pubrel::{PubRel, PubRelReasonCode},
+        suback::{SubAck, SubAckReasonCode},
+        subscribe::{RetainHandling, Subscribe, SubscriptionOptions},
+        unsuback::{UnSubAck, UnSubAckReasonCode},
+        unsubscribe::UnSubscribe,
+    },
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, BufReader},
+    sync::{mpsc, Notify},
+    time::{self, Instant},
+};
+use tokio_util::compat::{Compat, TokioAsyncReadCompatExt, TokioAsyncWriteCompatExt};
+
+use crate::{
+    auth::{Credentials, Identity},
+    broker::Shared,
+    router::{Route, Subscription},
+    session::{Detached, Session},
+    will,
+};
+
+/// Packets decoded off the connection, buffered ahead of the task serving it
+const READ_AHEAD: usize = 16;
+
+type Reader = Compat<BufReader<Box<dyn AsyncRead + Send + Unpin>>>;
+type Writer = Compat<Box<dyn AsyncWrite + Send + Unpin>>;
+
+/// Why a connection is being closed
+#[derive(Debug)]
+enum Close {
+    /// The client sent a DISCONNECT
+    Disconnected,
+    /// The broker closes the connection, sending a DISCONNECT with this Reason Code first
+    With(DisconnectReasonCode, Option<String>),
+    /// The network connection is gone, there is nobody left to tell
+    Gone,
+}
+
+impl From<Violation> for Close {
+    fn from(violation: Violation) -> Self {
+        Self::With(violation.reason, Some(violation.to_string()))
+    }
+}
+
+impl From<MQTTError> for Close {
+    fn from(err: MQTTError) -> Self {
+        match err {
+            MQTTError::ProtocolViolation(violation) => violation.into(),
+            MQTTError::MaxPacketSizeExceed(_) => {
+                Self::With(DisconnectReasonCode::PacketTooLarge, None)
+            }
+            err => match err.kind() {
+                ErrorKind::Malformed => {
+                    Self::With(DisconnectReasonCode::MalformedPacket, Some(err.to_string()))
+                }
+                ErrorKind::Protocol => {
+                    Self::With(DisconnectReasonCode::ProtocolError, Some(err.to_string()))
+                }
+                _ => Self::Gone,
+            },
+        }
+    }
+}
+
+/// Who the client proved to be before sending its CONNECT, through the certificate it presented (mutual TLS).
+/// Nobody in particular on transports that don't authenticate the client
+#[derive(Debug, Default)]
+pub(crate) struct Peer {
+    /// The Client Identifier the CONNECT must carry
+    pub(crate) client_id: Option<String>,
+    /// The username the client is known by, whatever its CONNECT says
+    pub(crate) username: Option<String>,
+}
+
+/// Serves a client from its CONNECT until the connection closes, whichever transport it came through.
+/// The connection is closed if its CONNECT hasn't arrived by `deadline`
+pub(crate) async fn serve<R, W>(
+    reader: R,
+    writer: W,
+    shared: Arc<Shared>,
+    peer: Peer,
+    deadline: Instant,
+) where
+    R: AsyncRead + Send + Unpin + 'static,
+    W: AsyncWrite + Send + Unpin + 'static,
+{
+    let reader: Box<dyn AsyncRead + Send + Unpin> = Box::new(reader);
+    let writer: Box<dyn AsyncWrite + Send + Unpin> = Box::new(writer);
+    let mut reader = BufReader::new(reader).compat();
+    let mut writer = writer.compat_write();
+    let mut buffer = OutgoingBuffer::with_capacity(8 * 1024);
+    let max_size = shared.config.maximum_packet_size.get() as usize;
+
+    // MQTT-3.1.0-1 the first packet sent by the client must be a CONNECT
+    let connect = match time::timeout_at(deadline, Packet::read_async(&mut reader, max_size)).await
+    {
+        Ok(Ok(Packet::Connect(connect))) => connect,
+        Ok(Err(err)) => {
+            if let Some(reason) = refusal(&err) {
+                let _ = refuse(&mut buffer, &mut writer, reason).await;
+            }
+            return;
+        }
+        _ => return,
+    };
+
+    if let Err(reason) = accept(&connect, &shared, &peer) {
+        let _ = refuse(&mut buffer, &mut writer, reason).await;
+        return;
+    }
+
+    let (tx, packets) = mpsc::channel(READ_AHEAD);
+    let read = tokio::spawn(read(reader, tx, max_size));
+
+    let mut connection = Connection::new(connect, peer, &shared, writer, buffer);
+    let close = match connection.connack().await {
+        Ok(()) => connection.run(packets).await,
+        Err(close) => close,
+    };
+    read.abort();
+
+    if let Close::With(reason, reason_string) = close {
+        let _ = connection.disconnect(reason, reason_string).await;
+    }
+    let detached = connection.detach();
+    // MQTT-3.1.2-8 the will is published once the connection is gone, unless the client disconnected normally
+    if let Some(will) = connection.will.take() {
+        let delay = will::delay(&will, &detached);
+        let client_id = connection.client_id.clone();
+        if delay.is_zero() {
+            route(&shared, &will::publish_of(will), &client_id);
+        } else {
+            let session = connection.session.clone();
+            let publish = will::publish(
+                shared.clone(),
+                session,
+                connection.id,
+                client_id,
+                will,
+                delay,
+            );
+            tokio::spawn(publish);
+        }
+    }
+    if let Detached::Expires(Some(after)) = detached {
+        let client_id = connection.client_id.clone();
+        tokio::spawn(expire(shared.clone(), client_id, connection.id, after));
+    }
+}
+
+/// Ends the session the connection left behind once its Session Expiry Interval has elapsed (MQTT-4.1.0-2)
+async fn expire(shared: Arc<Shared>, client_id: String, connection: u64, after: Duration) {
+    time::sleep(after).await;
+
+    let mut sessions = shared.sessions.lock().unwrap();
+    if sessions.expire(&client_id, connection) {
+        shared.router.remove(&client_id);
+    }
+}
+
+/// Decodes packets off the connection until it fails or the task serving the connection is done with it
+async fn read(mut reader: Reader, tx: mpsc::Sender<Result<Packet, MQTTError>>, max_size: usize) {
+    loop {
+        let packet = Packet::read_async(&mut reader, max_size).await;
+        let failed = packet.is_err();
+        if tx.send(packet).await.is_err() || failed {
+            return;
+        }
+    }
+}
+
+/// The CONNACK Reason Code sent when the CONNECT can't be read, if any is (MQTT-3.1.4-1)
+fn refusal(err: &MQTTError) -> Option<ConnAckReasonCode> {
+    match err {
+        MQTTError::VersionNotSupported(_) => Some(ConnAckReasonCode::UnsupportedProtocolVersion),
+        MQTTError::MaxPacketSizeExceed(_) => Some(ConnAckReasonCode::PacketTooLarge),
+        MQTTError::ProtocolViolation(violation) => Some(connack_reason(violation.reason)),
+        err => match err.kind() {
+            ErrorKind::Malformed => Some(ConnAckReasonCode::MalformedPacket),
+            ErrorKind::Protocol => Some(ConnAckReasonCode::ProtocolError),
+            _ => None,
+        },
+    }
+}
+
+fn connack_reason(reason: DisconnectReasonCode) -> ConnAckReasonCode {
+    match reason {
+        DisconnectReasonCode::MalformedPacket => ConnAckReasonCode::MalformedPacket,
+        DisconnectReasonCode::TopicNameInvalid => ConnAckReasonCode::TopicNameInvalid,
+        DisconnectReasonCode::PayloadFormatInvalid => ConnAckReasonCode::PayloadFormatInvalid,
+        _ => ConnAckReasonCode::ProtocolError,
+    }
+}
+
+/// Confirms the broker can take the connection on, the CONNACK Reason Code it is refused with otherwise
+fn accept(connect: &Connect, shared: &Shared, peer: &Peer) -> Result<(), ConnAckReasonCode> {
+    connect
+        .validate()
+        .map_err(|violation| connack_reason(violation.reason))?;
+
+    // the client can't claim to be anyone else than who its certificate says it is
+    let client_id = match &peer.client_id {
+        Some(client_id) if connect.client_id.is_empty() => client_id,
+        Some(client_id) if *client_id != connect.client_id => {
+            return Err(ConnAckReasonCode::ClientIdentifierNotValid)
+        }
+        _ => &connect.client_id,
+    };
+    let username = match (&peer.username, &connect.username) {
+        (Some(certified), Some(username)) if certified != username => {
+            return Err(ConnAckReasonCode::BadUserNameOrPassword)
+        }
+        (Some(username), _) | (None, Some(username)) => Some(username.as_str()),
+        (None, None) => None,
+    };
+
+    let config = &shared.config;
+    match &config.authenticator {
+        Some(authenticator) => {
+            let credentials = Credentials {
+                client_id,
+                username,
+                ..Credentials::from(connect)
+            };
+            authenticator.authenticate(&credentials)?
+        }
+        // 4.12 there is nothing to carry out enhanced authentication without an authenticator
+        None if connect.properties.authentication_method.is_some() => {
+            return Err(ConnAckReasonCode::BadAuthenticationMethod)
+        }
+        None => {}
+    }
+
+    if let Some(will) = &connect.will {
+        let identity = Identity {
+            client_id,
+            username,
+        };
+        let authorized = config
+            .authorizer
+            .as_ref()
+            .is_none_or(|authorizer| authorizer.authorize_publish(&identity, &will.topic));
+        if !authorized {
+            return Err(ConnAckReasonCode::NotAuthorized);
+        }
+        // MQTT-3.2.2-12
+        if will.qos > config.maximum_qos {
+            return Err(ConnAckReasonCode::QoSNotSupported);
+        }
+        // MQTT-3.2.2-13
+        if will.retain && !config.retain_available {
+            return Err(ConnAckReasonCode::RetainNotSupported);
+        }
+    }
+
+    Ok(())
+}
+
+async fn refuse(
+    buffer: &mut OutgoingBuffer,
+    writer: &mut Writer,
+    reason: ConnAckReasonCode,
+) -> Result<(), MQTTError> {
+    buffer.push(&Packet::ConnAck(ConnAck {
+        reason,
+        ..Default::default()
+    }))?;
+    buffer.flush(writer).await
+}
+
+/// A client's connection, once its CONNECT has been accepted
+struct Connection<'a> {
+    shared: &'a Shared,
+    writer: Writer,
+    buffer: OutgoingBuffer,
+    id: u64,
+    client_id: String,
+    /// 3.2.2.3.7 set when the broker had to make up the Client Identifier
+    assigned_client_id: Option<String>,
+    username: Option<String>,
+    /// 3.2.2.3.17 the Authentication Method the client was let in with
+    authentication_method: Option<String>,
+    keep_alive: u16,
+    /// 3.1.2.11.3 the number of QoS 1 and QoS 2 PUBLISH the client processes concurrently
+    client_receive_maximum: u16,
+    /// 3.1.2.11.4 packets larger than this are never sent to the client
+    client_max_size: usize,
+    /// 3.1.2.11.2 the Session Expiry Interval the client asked for, in seconds
+    expiry_interval: u32,
+    session: Arc<Session>,
+    /// 3.2.2.1.1 whether the session was resumed from a previous connection
+    session_present: bool,
+    /// Woken when messages are queued for the client, or when another connection takes the session over
+    wake: Arc<Notify>,
+    will: Option<Will>,
+}
+
+impl<'a> Connection<'a> {
+    fn new(
+        connect: Connect,
+        peer: Peer,
+        shared: &'a Shared,
+        writer: Writer,
+        buffer: OutgoingBuffer,
+    ) -> Self {
+        let id = shared.next_connection();
+        let config = &shared.config;
+
+        // MQTT-3.1.3-6 a zero length Client Identifier is replaced with one unique to the broker,
+        // or with the one the client's certificate gives it
+        let (client_id, assigned_client_id) = match connect.client_id.is_empty() {
+            true => {
+                let client_id = peer.client_id.unwrap_or_else(|| format!("tea-{id}"));
+                (client_id.clone(), Some(client_id))
+            }
+            false => (connect.client_id, None),
+        };
+
+        let expiry_interval = connect.properties.session_expiry_interval.unwrap_or(0);
+        let wake = Arc::new(Notify::new());
+        // the connection that held the session until now is woken up to find it lost it (MQTT-3.1.4-3)
+        let (session, session_present) = {
+            let mut sessions = shared.sessions.lock().unwrap();
+            let (session, present) = sessions.attach(
+                &client_id,
+                id,
+                wake.clone(),
+                connect.clean_start,
+                expiry_interval.min(config.maximum_session_expiry_interval),
+            );
+            // a new session doesn't inherit the subscriptions of the one it replaces
+            if !present {
+                shared.router.remove(&client_id);
+            }
+            (session, present)
+        };
+
+        Self {
+            shared,
+            writer,
+            buffer,
+            id,
+            client_id,
+            assigned_client_id,
+            username: peer.username.or(connect.username),
+            authentication_method: connect.properties.authentication_method,
+            keep_alive: config.server_keep_alive.unwrap_or(connect.keep_alive),
+            client_receive_maximum: connect.properties.receive_maximum.unwrap_or(u16::MAX),
+            client_max_size: connect.properties.maximum_packet_size.unwrap_or(u32::MAX) as usize,
+            expiry_interval,
+            session,
+            session_present,
+            wake,
+            will: connect.will,
+        }
+    }
+
+    fn identity(&self) -> Identity<'_> {
+        Identity {
+            client_id: &self.client_id,
+            username: self.username.as_deref(),
+        }
+    }
+
+    /// Detaches the connection from the client's session, which ends here unless it has a Session Expiry Interval
+    fn detach(&self) -> Detached {
+        let mut sessions = self.shared.sessions.lock().unwrap();
+        let detached = sessions.detach(&self.client_id, self.id);
+        if detached == Detached::Ended {
+            self.shared.router.remove(&self.client_id);
+        }
+        detached
+    }
+
+    async fn connack(&mut self) -> Result<(), Close> {
+        let config = &self.shared.config;
+
+        let maximum_expiry_interval = config.maximum_session_expiry_interval;
+        let connack = ConnAck {
+            session_present: self.session_present,
+            reason: ConnAckReasonCode::Success,
+            properties: ConnAckProperties {
+                receive_maximum: Some(config.receive_maximum.get()),
+                // absent when QoS 2 is supported, `true` for QoS 1 and `false` for QoS 0 otherwise (3.2.2.3.4)
+                maximum_qos: (config.maximum_qos != QoS::Two)
+                    .then_some(config.maximum_qos == QoS::One),
+                // absent when retained messages are supported (3.2.2.3.5)
+                retain_available: (!config.retain_available).then_some(false),
+                maximum_packet_size: Some(config.maximum_packet_size.get()),
+                assigned_client_id: self.assigned_client_id.take(),
+                authentication_method: self.authentication_method.take(),
+                shared_subscription_available: Some(false),
+                server_keep_alive: config.server_keep_alive,
+                // MQTT-3.2.2-19 the client is told when it is granted less than it asked for
+                session_expiry_interval: (self.expiry_interval > maximum_expiry_interval)
+                    .then_some(maximum_expiry_interval),
+                ..Default::default()
+            },
+        };
+        self.buffer.push(&Packet::ConnAck(connack))?;
+
+        // MQTT-4.4.0-1 what was in flight on the previous connection is sent again before anything else
+        let resent = self
+            .session
+            .state()
+            .outgoing
+            .resume(self.client_receive_maximum, self.client_max_size);
+        for packet in resent {
+            self.buffer.push(&packet)?;
+        }
+        self.next()?;
+
+        self.flush().await
+    }
+
+    async fn run(&mut self, mut packets: mpsc::Receiver<Result<Packet, MQTTError>>) -> Close {
+        // MQTT-3.1.2-22 the client is given one and a half times its Keep Alive to send a packet
+        let idle = Duration::from_millis(self.keep_alive as u64 * 1500);
+        let mut deadline = Instant::now() + idle;
+        let wake = self.wake.clone();
+
+        loop {
+            let result = tokio::select! {
+                packet = packets.recv() => match packet {
+                    Some(Ok(packet)) => {
+                        deadline = Instant::now() + idle;
+                        self.handle(packet)
+                    }
+                    Some(Err(err)) => Err(err.into()),
+                    None => Err(Close::Gone),
+                },
+                _ = wake.notified() => self.next(),
+                _ = time::sleep_until(deadline), if self.keep_alive > 0 => {
+                    Err(Close::With(DisconnectReasonCode::KeepAliveTimeout, None))
+                }
+            };
+
+            if let Err(close) = result {
+                return close;
+            }
+            if let Err(close) = self.flush().await {
+                return close;
+            }
+        }
+    }
+
+    /// Queues the responses to a packet received from the client, they are sent on the next flush
+    fn handle(&mut self, packet: Packet) -> Result<(), Close> {
+        packet.validate()?;
+
+        match packet {
+            Packet::Publish(publish) => self.publish(publish),
+            Packet::PubAck(puback) => {
+                self.session.state().outgoing.puback(puback.pkid);
+                self.next()
+            }
+            Packet::PubRec(pubrec) => self.pubrec(pubrec),
+            Packet::PubRel(pubrel) => self.pubrel(pubrel),
+            Packet::PubComp(pubcomp) => {
+                self.session.state().outgoing.pubcomp(pubcomp.pkid);
+                self.next()
+            }
+            Packet::Subscribe(subscribe) => self.subscribe(subscribe),
+            Packet::UnSubscribe(unsubscribe) => self.unsubscribe(unsubscribe),
+            Packet::PingReq(_) => Ok(self.buffer.push(&Packet::PingResp(PingResp))?),
+            Packet::Disconnect(disconnect) => self.disconnected(disconnect),
+            // MQTT-3.1.0-2
+            Packet::Connect(_) => Err(Close::With(
+                DisconnectReasonCode::ProtocolError,
+                Some(String::from(
+                    "[MQTT-3.1.0-2] CONNECT must only be sent once",
+                )),
+            )),
+            // 4.12 AUTH is only allowed after a CONNECT with an Authentication Method, which is refused
+            Packet::Auth(_) => Err(Close::With(DisconnectReasonCode::ProtocolError, None)),
+            packet => Err(MQTTError::UnexpectedPacket(packet.packet_type()).into()),
+        }
+    }
+
+    fn publish(&mut self, publish: Publish) -> Result<(), Close> {
+        let config = &self.shared.config;
+
+        // MQTT-3.2.2-11
+        if publish.qos > config.maximum_qos {
+            return Err(Close::With(DisconnectReasonCode::QoSNotSupported, None));
+        }
+        // MQTT-3.2.2-14
+        if publish.retain && !config.retain_available {
+            return Err(Close::With(DisconnectReasonCode::RetainNotSupported, None));
+        }
+        // 3.3.2.3.4 the Topic Alias Maximum announced in CONNACK is 0
+        if publish.properties.topic_alias.is_some() {
+            return Err(Close::With(DisconnectReasonCode::TopicAliasInvalid, None));
+        }
+        // MQTT-3.3.4-6
+        if !publish.properties.subscription_identifier.is_empty() {
+            return Err(Close::With(
+                DisconnectReasonCode::ProtocolError,
+                Some(String::from("[MQTT-3.3.4-6] PUBLISH sent to the Server must not contain a Subscription Identifier")),
+            ));
+        }
+
+        let pkid = publish.pkid.unwrap_or_default();
+        let authorized = config.authorizer.as_ref().is_none_or(|authorizer| {
+            authorizer.authorize_publish(&self.identity(), &publish.topic)
+        });
+        // a PUBLISH the client may not send is acknowledged with Not Authorized, or dropped at QoS 0
+        if !authorized {
+            match publish.qos {
+                QoS::Zero => {}
+                QoS::One => self.buffer.push(&Packet::PubAck(PubAck {
+                    pkid,
+                    reason_code: PubAckReasonCode::NotAuthorized,
+                    ..Default::default()
+                }))?,
+                QoS::Two => self.buffer.push(&Packet::PubRec(PubRec {
+                    pkid,
+                    reason_code: PubRecReasonCode::NotAuthorized,
+                    ..Default::default()
+                }))?,
+            }
+            return Ok(());
+        }
+
+        if publish.qos == QoS::Two {
+            let (duplicate, incoming) = {
+                let state = self.session.state();
+                (state.incoming.contains(&pkid), state.incoming.len())
+            };
+            // MQTT-4.3.3-10 a retransmission of a PUBLISH that is already being processed is not delivered again
+            if duplicate {
+                self.buffer.push(&Packet::PubRec(PubRec {
+                    pkid,
+                    ..Default::default()
+                }))?;
+                return Ok(());
+            }
+            // MQTT-3.3.4-8
+            if incoming >= config.receive_maximum.get() as usize {
+                return Err(Close::With(
+                    DisconnectReasonCode::ReceiveMaximumExceeded,
+                    None,
+                ));
+            }
+        }
+
+        let delivered = route(self.shared, &publish, &self.client_id);
+
+        match publish.qos {
+            QoS::Zero => {}
+            QoS::One => self.buffer.push(&Packet::PubAck(PubAck {
+                pkid,
+                reason_code: match delivered {
+                    true => PubAckReasonCode::Success,
+                    false => PubAckReasonCode::NoMatchingSubscribers,
+                },
+                ..Default::default()
+            }))?,
+            QoS::Two => {
+                self.session.state().incoming.insert(pkid);
+                self.buffer.push(&Packet::PubRec(PubRec {
+                    pkid,
+                    reason_code: match delivered {
+                        true => PubRecReasonCode::Success,
+                        false => PubRecReasonCode::NoMatchingSubscribers,
+                    },
+                    ..Default::default()
+                }))?
+            }
+        }
+
+        Ok(())
+    }
+
+    fn pubrec(&mut self, pubrec: PubRec) -> Result<(), Close> {
+        let success = (pubrec.reason_code as u8) < 0x80;
+        let found = self.session.state().outgoing.pubrec(pubrec.pkid, success);
+
+        if !success {
+            // the exchange is over, the slot it freed may go to a queued message
+            return self.next();
+        }
+
+        self.buffer.push(&Packet::PubRel(PubRel {
+            pkid: pubrec.pkid,
+            reason_code: match found {
+                true => PubRelReasonCode::Success,
+                false => PubRelReasonCode::PacketIdentifierNotFound,
+            },
+            ..Default::default()
+        }))?;
+        Ok(())
+    }
+
+    fn pubrel(&mut self, pubrel: PubRel) -> Result<(), Close> {
+        let found = self.session.state().incoming.remove(&pubrel.pkid);
+
+        self.buffer.push(&Packet::PubComp(PubComp {
+            pkid: pubrel.pkid,
+            reason_code: match found {
+                true => PubCompReasonCode::Success,
+                false => PubCompReasonCode::PacketIdentifierNotFound,
+            },
+            ..Default::default()
+        }))?;
+        Ok(())
+    }
+
+    fn subscribe(&mut self, subscribe: Subscribe) -> Result<(), Close> {
+        let config = &self.shared.config;
+        let maximum_qos = config.maximum_qos;
+        let id = subscribe.properties.subscription_id;
+
+        let router = &self.shared.router;
+        let identity = self.identity();
+        let mut retained = Vec::new();
+        let payload = subscribe
+            .payload
+            .into_iter()
+            .map(|(filter, options)| {
+                if filter.starts_with("$share/") {
+                    return SubAckReasonCode::SharedSubscriptionsNotSupported;
+                }
+                let authorized = config
+                    .authorizer
+                    .as_ref()
+                    .is_none_or(|authorizer| authorizer.authorize_subscribe(&identity, &filter));
+                if !authorized {
+                    return SubAckReasonCode::NotAuthorized;
+                }
+
+                // MQTT-3.2.2-9 the QoS granted is downgraded to the maximum the broker supports
+                let qos = options.qos.min(maximum_qos);
+                let options = SubscriptionOptions { qos, ..options };
+                let existed =
+                    router.subscribe(&self.client_id, &filter, Subscription { options, id });
+
+                // MQTT-3.3.1-9 to MQTT-3.3.1-11
+                let replay = match options.retain_handling {
+                    RetainHandling::Zero => true,
+                    RetainHandling::One => !existed,
+                    RetainHandling::Two => false,
+                };
+                if replay {
+                    retained.extend(
+                        self.shared
+                            .retained
+                            .matching(&filter)
+                            .into_iter()
+                            .map(|publish| replay_retained(publish, qos, id)),
+                    );
+                }
+
+                match qos {
+                    QoS::Zero => SubAckReasonCode::GrantedQoS0,
+                    QoS::One => SubAckReasonCode::GrantedQoS1,
+                    QoS::Two => SubAckReasonCode::GrantedQoS2,
+                }
+            })
+            .collect();
+
+        self.buffer.push(&Packet::SubAck(SubAck {
+            pkid: subscribe.pkid,
+            payload,
+            ..Default::default()
+        }))?;
+        for publish in retained {
+            self.session.deliver(
+                publish,
+                config.maximum_queued_messages,
+                config.queue_drop_policy,
+            );
+        }
+        self.next()
+    }
+
+    fn unsubscribe(&mut self, unsubscribe: UnSubscribe) -> Result<(), Close> {
+        let payload = unsubscribe
+            .payload
+            .iter()
+            .map(
+                |filter| match self.shared.router.unsubscribe(&self.client_id, filter) {
+                    true => UnSubAckReasonCode::Success,
+                    false => UnSubAckReasonCode::NoSubscriptionExisted,
+                },
+            )
+            .collect();
+
+        self.buffer.push(&Packet::UnSubAck(UnSubAck {
+            pkid: unsubscribe.pkid,
+            payload,
+            ..Default::default()
+        }))?;
+        Ok(())
+    }
+
+    /// The client's DISCONNECT may change how long its session outlives the connection, and whether its will is published
+    fn disconnected(&mut self, disconnect: Disconnect) -> Result<(), Close> {
+        if let Some(interval) = disconnect.properties.session_expiry_interval {
+            // MQTT-3.14.2-2
+            if self.expiry_interval == 0 && interval != 0 {
+                return Err(Close::With(
+                    DisconnectReasonCode::ProtocolError,
+                    Some(String::from("[MQTT-3.14.2-2] Session Expiry Interval must not be set on DISCONNECT when it was 0 on CONNECT")),
+                ));
+            }
+            let maximum = self.shared.config.maximum_session_expiry_interval;
+            self.session.state().expiry_interval = interval.min(maximum);
+        }
+        // MQTT-3.14.4-3 the will is discarded, unless the client asks for it to be published
+        if disconnect.reason_code != DisconnectReasonCode::DisconnectWithWillMessage {
+            self.will = None;
+        }
+
+        Err(Close::Disconnected)
+    }
+
+    /// Sends the messages queued for the client, as many as it has room for
+    fn next(&mut self) -> Result<(), Close> {
+        let mut state = self.session.state();
+        if !state.is_attached(self.id) {
+            return Err(Close::With(DisconnectReasonCode::SessionTakenOver, None));
+        }
+
+        while let Some(publish) = state.outgoing.next() {
+            self.buffer.push(&Packet::Publish(publish))?;
+        }
+        Ok(())
+    }
+
+    async fn flush(&mut self) -> Result<(), Close> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        Ok(self.buffer.flush(&mut self.writer).await?)
+    }
+
+    async fn disconnect(
+        &mut self,
+        reason_code: DisconnectReasonCode,
+        reason_string: Option<String>,
+    ) -> Result<(), Close> {
+        self.buffer.push(&Packet::Disconnect(Disconnect {
+            reason_code,
+            properties: DisconnectProperties {
+                reason_string,
+                ..Default::default()
+            },
+        }))?;
+        self.flush().await
+    }
+}
+
+/// Retains the PUBLISH if it asks to be, and forwards it to every session subscribed to its topic.
+/// Returns whether any was
+pub(crate) fn route(shared: &Shared, publish: &Publish, publisher: &str) -> bool {
+    let config = &shared.config;
+    if publish.retain {
+        shared.retained.retain(publish);
+    }
+
+    let routes = shared.router.route(&publish.topic, publisher);
+    let delivered = !routes.is_empty();
+    let sessions = shared.sessions.lock().unwrap();
+    for route in routes {
+        if let Some(session) = sessions.get(&route.client_id) {
+            session.deliver(
+                forward(publish, route),
+                config.maximum_queued_messages,
+                config.queue_drop_policy,
+            );
+        }
+    }
+
+    delivered
+}
+
+/// The copy of a PUBLISH sent to a subscriber
+fn forward(publish: &Publish, route: Route) -> Publish {
+    Publish {
+        dup: false,
+        // MQTT-3.3.1-12 and MQTT-3.3.1-13
+        retain: publish.retain && route.retain_as_published,
+        // MQTT-3.8.4-8 delivered at the lower of the QoS it was published with and the one granted
+        qos: publish.qos.min(route.qos),
+        topic: publish.topic.clone(),
+        pkid: None,
+        payload: publish.payload.clone(),
+        properties: PublishProperties {
+            topic_alias: None,
+            subscription_identifier: route.subscription_ids,
+            ..publish.properties.clone()
+        },
+    }
+}
+
+/// The copy of a retained message sent to a new subscriber, which keeps its RETAIN flag
+/// whatever the subscription's Retain As Published (MQTT-3.3.1-9)
+fn replay_retained(publish: Publish, qos: QoS, id: Option<usize>) -> Publish {
+    Publish {
+        qos: publish.qos.min(qos),
+        properties: PublishProperties {
+            subscription_identifier: id.into_iter().collect(),
+            ..publish.properties
+        },
+        ..publish
+    }
+}
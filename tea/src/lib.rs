@@ -1,14 +1,28 @@
-pub fn add(left: u64, right: u64) -> u64 {
-    left + right
-}
+//! A single node MQTT 5 broker.
+//!
+//! Every connection is served on its own task, routing the PUBLISH packets it receives
+//! to the sessions subscribed to their topic.
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+mod auth;
+#[cfg(feature = "bench")]
+#[doc(hidden)]
+pub mod bench;
+mod broker;
+mod config;
+mod connection;
+mod listener;
+mod retain;
+mod router;
+mod session;
+#[cfg(feature = "tls")]
+mod tls;
+mod transport;
+mod will;
 
-    #[test]
-    fn it_works() {
-        let result = add(2, 2);
-        assert_eq!(result, 4);
-    }
-}
+pub use auth::{AclFile, Authenticator, Authorizer, Credentials, Identity, PasswordFile};
+pub use broker::Broker;
+pub use config::{Config, DropPolicy};
+pub use listener::DuplexConnector;
+pub use retain::{RetainedMessage, RetainedStore};
+#[cfg(feature = "tls")]
+pub use tls::{ClientIdentity, TlsConfig, TlsFiles, TlsReloader};
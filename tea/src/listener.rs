@@ -0,0 +1,160 @@
+use std::{io, sync::Arc, time::Duration};
+
+use mqttea_core::v5::client::network::duplex::{duplex, DuplexStream};
+#[cfg(unix)]
+use tokio::net::UnixListener;
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    net::TcpListener,
+    sync::mpsc,
+    time,
+};
+use tokio_util::compat::FuturesAsyncReadCompatExt;
+
+use crate::{broker::Shared, transport::Transport};
+
+/// How long accepting pauses once the system is out of file descriptors, buffers or memory,
+/// giving the connections being served a chance to close and free some
+const ACCEPT_BACKOFF: Duration = Duration::from_millis(100);
+
+/// How much each direction of an in-memory connection holds before its writer waits
+const DUPLEX_CAPACITY: usize = 64 * 1024;
+
+/// Where a broker's connections come from
+#[derive(Debug)]
+pub(crate) enum Listener {
+    Tcp(TcpListener),
+    #[cfg(unix)]
+    Unix(UnixListener),
+    /// The broker's ends of the connections made through a `DuplexConnector`
+    Memory(mpsc::UnboundedReceiver<DuplexStream>),
+}
+
+impl Listener {
+    /// Serves every connection on its own task, until the listener itself fails or, in memory, every connector is dropped.
+    /// Accepting carries on when a peer goes away before its connection is accepted, and after a pause when resources run out
+    pub(crate) async fn run(mut self, transport: Transport, shared: Arc<Shared>) -> io::Result<()> {
+        loop {
+            match &mut self {
+                Self::Tcp(listener) => {
+                    let stream = match listener.accept().await {
+                        Ok((stream, _)) => stream,
+                        Err(err) => {
+                            recover(err).await?;
+                            continue;
+                        }
+                    };
+                    // fails only if the connection is already gone
+                    if stream.set_nodelay(true).is_err() {
+                        continue;
+                    }
+                    spawn(&transport, &shared, stream);
+                }
+                #[cfg(unix)]
+                Self::Unix(listener) => match listener.accept().await {
+                    Ok((stream, _)) => spawn(&transport, &shared, stream),
+                    Err(err) => recover(err).await?,
+                },
+                Self::Memory(streams) => match streams.recv().await {
+                    Some(stream) => spawn(&transport, &shared, stream.compat()),
+                    None => return Ok(()),
+                },
+            }
+        }
+    }
+}
+
+fn spawn<S>(transport: &Transport, shared: &Arc<Shared>, stream: S)
+where
+    S: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+{
+    tokio::spawn(transport.clone().serve(stream, shared.clone()));
+}
+
+/// `Ok` when accepting can go on after `err`, once the resources it lacked had time to be freed
+async fn recover(err: io::Error) -> io::Result<()> {
+    if went_away(&err) {
+        return Ok(());
+    }
+    if exhausted(&err) {
+        time::sleep(ACCEPT_BACKOFF).await;
+        return Ok(());
+    }
+    Err(err)
+}
+
+/// Whether the peer went away before its connection was accepted
+fn went_away(err: &io::Error) -> bool {
+    matches!(
+        err.kind(),
+        io::ErrorKind::ConnectionAborted | io::ErrorKind::ConnectionReset
+    )
+}
+
+/// Whether the process or the system ran out of file descriptors, buffers or memory, which is transient under load
+fn exhausted(err: &io::Error) -> bool {
+    #[cfg(unix)]
+    if let Some(code) = err.raw_os_error() {
+        return matches!(
+            code,
+            libc::EMFILE | libc::ENFILE | libc::ENOBUFS | libc::ENOMEM
+        );
+    }
+    err.kind() == io::ErrorKind::OutOfMemory
+}
+
+/// Opens connections to a broker made with `Broker::in_memory`, without any socket.
+/// The broker stops accepting once every clone of its connector is dropped
+#[derive(Debug, Clone)]
+pub struct DuplexConnector {
+    streams: mpsc::UnboundedSender<DuplexStream>,
+}
+
+impl DuplexConnector {
+    pub(crate) fn new() -> (Self, Listener) {
+        let (streams, accepted) = mpsc::unbounded_channel();
+        (Self { streams }, Listener::Memory(accepted))
+    }
+
+    /// A new connection to the broker, which the client's `Network::new` is made over.
+    /// Fails with `ConnectionRefused` once the broker is gone
+    pub fn connect(&self) -> io::Result<DuplexStream> {
+        let (client, broker) = duplex(DUPLEX_CAPACITY);
+        self.streams
+            .send(broker)
+            .map_err(|_| io::Error::new(io::ErrorKind::ConnectionRefused, "the broker is gone"))?;
+        Ok(client)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::time::Instant;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn keeps_accepting_once_resources_are_freed() {
+        #[cfg(unix)]
+        for code in [libc::EMFILE, libc::ENFILE, libc::ENOBUFS, libc::ENOMEM] {
+            let started = Instant::now();
+            recover(io::Error::from_raw_os_error(code)).await.unwrap();
+            assert!(started.elapsed() >= ACCEPT_BACKOFF);
+        }
+        recover(io::ErrorKind::OutOfMemory.into()).await.unwrap();
+
+        let started = Instant::now();
+        recover(io::ErrorKind::ConnectionReset.into())
+            .await
+            .unwrap();
+        assert!(started.elapsed() < ACCEPT_BACKOFF);
+    }
+
+    #[tokio::test]
+    async fn stops_when_the_listener_fails() {
+        let err = recover(io::ErrorKind::InvalidInput.into())
+            .await
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+}
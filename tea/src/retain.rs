@@ -0,0 +1,380 @@
+use std::{
+    collections::HashMap,
+    fmt::Debug,
+    io,
+    sync::{Arc, RwLock},
+    time::{Duration, SystemTime},
+};
+
+use mqttea_core::v5::packet::publish::Publish;
+
+/// A PUBLISH retained on its topic, along with when the broker received it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetainedMessage {
+    pub publish: Publish,
+    pub received_at: SystemTime,
+}
+
+impl RetainedMessage {
+    /// 3.3.2.3.3 how long the message has left before it expires, `None` when it never does
+    fn remaining(&self) -> Option<Duration> {
+        let interval = self.publish.properties.message_expiry_internal?;
+        let elapsed = self.received_at.elapsed().unwrap_or_default();
+        Some(Duration::from_secs(interval as u64).saturating_sub(elapsed))
+    }
+
+    fn is_expired(&self) -> bool {
+        self.remaining()
+            .is_some_and(|remaining| remaining.is_zero())
+    }
+}
+
+/// Where retained messages are persisted, so that they outlive the broker.
+///
+/// The broker reads every message back when it starts and writes each change through as it happens.
+/// A change that fails to be persisted is still applied to the messages the broker holds in memory
+pub trait RetainedStore: Debug + Send + Sync {
+    fn load(&self) -> io::Result<Vec<RetainedMessage>>;
+
+    /// Replaces the message retained on the same topic, if any
+    fn store(&self, message: &RetainedMessage) -> io::Result<()>;
+
+    fn remove(&self, topic: &str) -> io::Result<()>;
+}
+
+/// Messages retained by the broker, indexed by the levels of their topic
+#[derive(Debug, Default)]
+pub(crate) struct Retained {
+    root: RwLock<Node>,
+    store: Option<Arc<dyn RetainedStore>>,
+}
+
+#[derive(Debug, Default)]
+struct Node {
+    children: HashMap<Box<str>, Node>,
+    message: Option<RetainedMessage>,
+}
+
+impl Retained {
+    /// Starts from the messages already persisted in `store`, expired ones excepted
+    pub(crate) fn load(store: Option<Arc<dyn RetainedStore>>) -> io::Result<Self> {
+        let mut root = Node::default();
+        if let Some(store) = &store {
+            for message in store.load()? {
+                match message.is_expired() {
+                    true => store.remove(&message.publish.topic)?,
+                    false => root.insert(message),
+                }
+            }
+        }
+
+        Ok(Self {
+            root: RwLock::new(root),
+            store,
+        })
+    }
+
+    /// Retains a PUBLISH sent with its RETAIN flag set.
+    /// MQTT-3.3.1-6 and MQTT-3.3.1-7 one with an empty payload clears the topic instead of being retained
+    pub(crate) fn retain(&self, publish: &Publish) {
+        let topic = &publish.topic;
+        let mut root = self.root.write().unwrap();
+
+        if publish.payload.is_empty() {
+            root.remove(&mut topic.split('/'));
+            if let Some(store) = &self.store {
+                let _ = store.remove(topic);
+            }
+            return;
+        }
+
+        let message = RetainedMessage {
+            publish: Publish {
+                dup: false,
+                retain: true,
+                pkid: None,
+                ..publish.clone()
+            },
+            received_at: SystemTime::now(),
+        };
+        if let Some(store) = &self.store {
+            let _ = store.store(&message);
+        }
+        root.insert(message);
+    }
+
+    /// The messages retained on topics matching `filter`, with the time they have left before they expire.
+    /// Expired messages are dropped along the way (MQTT-3.3.2-5)
+    pub(crate) fn matching(&self, filter: &str) -> Vec<Publish> {
+        let levels: Vec<&str> = filter.split('/').collect();
+        let mut publishes = Vec::new();
+        let mut expired = Vec::new();
+
+        {
+            let root = self.root.read().unwrap();
+            root.matches(&levels, true, &mut |message| {
+                match message.remaining() {
+                    Some(remaining) if remaining.is_zero() => {
+                        expired.push(message.publish.topic.clone())
+                    }
+                    // MQTT-3.3.2-6 the interval is reduced by how long the message has been waiting
+                    remaining => {
+                        let mut publish = message.publish.clone();
+                        publish.properties.message_expiry_internal =
+                            remaining.map(|remaining| remaining.as_secs().max(1) as u32);
+                        publishes.push(publish);
+                    }
+                }
+            });
+        }
+
+        if !expired.is_empty() {
+            let mut root = self.root.write().unwrap();
+            for topic in expired {
+                // the message may have been replaced since
+                if root.get(&topic).is_some_and(RetainedMessage::is_expired) {
+                    root.remove(&mut topic.split('/'));
+                    if let Some(store) = &self.store {
+                        let _ = store.remove(&topic);
+                    }
+                }
+            }
+        }
+
+        publishes
+    }
+}
+
+impl Node {
+    fn insert(&mut self, message: RetainedMessage) {
+        let node = message.publish.topic.split('/').fold(self, |node, level| {
+            node.children.entry(level.into()).or_default()
+        });
+        node.message = Some(message);
+    }
+
+    fn get(&self, topic: &str) -> Option<&RetainedMessage> {
+        topic
+            .split('/')
+            .try_fold(self, |node, level| node.children.get(level))?
+            .message
+            .as_ref()
+    }
+
+    /// Removes the message retained on the topic made of `levels`, pruning the levels left unused
+    fn remove<'a>(&mut self, levels: &mut impl Iterator<Item = &'a str>) {
+        let Some(level) = levels.next() else {
+            self.message = None;
+            return;
+        };
+        let Some(node) = self.children.get_mut(level) else {
+            return;
+        };
+
+        node.remove(levels);
+        if node.message.is_none() && node.children.is_empty() {
+            self.children.remove(level);
+        }
+    }
+
+    /// Visits every message retained on a topic matching the remaining `levels` of the Topic Filter.
+    /// `root` is true at the first level, where wildcards don't match topics starting with `$` (MQTT-4.7.2-1)
+    fn matches<'a, F>(&'a self, levels: &[&str], root: bool, visit: &mut F)
+    where
+        F: FnMut(&'a RetainedMessage),
+    {
+        let Some((level, rest)) = levels.split_first() else {
+            self.message.iter().for_each(&mut *visit);
+            return;
+        };
+
+        let children = self
+            .children
+            .iter()
+            .filter(|(name, _)| !(root && name.starts_with('$')));
+        match *level {
+            // `#` also matches the parent level, "sport/#" matches "sport" (4.7.1.2)
+            "#" => {
+                self.message.iter().for_each(&mut *visit);
+                children.for_each(|(_, node)| node.visit(visit));
+            }
+            "+" => children.for_each(|(_, node)| node.matches(rest, false, visit)),
+            level => {
+                if let Some(node) = self.children.get(level) {
+                    node.matches(rest, false, visit);
+                }
+            }
+        }
+    }
+
+    /// Visits every message retained at or under this level
+    fn visit<'a, F>(&'a self, visit: &mut F)
+    where
+        F: FnMut(&'a RetainedMessage),
+    {
+        self.message.iter().for_each(&mut *visit);
+        for node in self.children.values() {
+            node.visit(visit);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use mqttea_core::v5::{commons::qos::QoS, packet::publish::PublishProperties};
+
+    use super::*;
+
+    fn publish(topic: &str, payload: &'static str) -> Publish {
+        Publish {
+            retain: true,
+            qos: QoS::One,
+            topic: topic.to_string(),
+            pkid: Some(7),
+            payload: payload.into(),
+            ..Default::default()
+        }
+    }
+
+    fn topics(retained: &Retained, filter: &str) -> Vec<String> {
+        let mut topics: Vec<String> = retained
+            .matching(filter)
+            .into_iter()
+            .map(|publish| publish.topic)
+            .collect();
+        topics.sort();
+        topics
+    }
+
+    #[derive(Debug, Default)]
+    struct Memory(Mutex<HashMap<String, RetainedMessage>>);
+
+    impl RetainedStore for Memory {
+        fn load(&self) -> io::Result<Vec<RetainedMessage>> {
+            Ok(self.0.lock().unwrap().values().cloned().collect())
+        }
+
+        fn store(&self, message: &RetainedMessage) -> io::Result<()> {
+            let topic = message.publish.topic.clone();
+            self.0.lock().unwrap().insert(topic, message.clone());
+            Ok(())
+        }
+
+        fn remove(&self, topic: &str) -> io::Result<()> {
+            self.0.lock().unwrap().remove(topic);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn replays_messages_matching_the_filter() {
+        let retained = Retained::default();
+        for topic in [
+            "sport",
+            "sport/tennis",
+            "sport/tennis/player1",
+            "sport/golf",
+            "finance",
+            "$SYS/uptime",
+        ] {
+            retained.retain(&publish(topic, "x"));
+        }
+
+        assert_eq!(topics(&retained, "sport/tennis"), ["sport/tennis"]);
+        assert_eq!(topics(&retained, "sport/+"), ["sport/golf", "sport/tennis"]);
+        assert_eq!(
+            topics(&retained, "sport/#"),
+            [
+                "sport",
+                "sport/golf",
+                "sport/tennis",
+                "sport/tennis/player1"
+            ]
+        );
+        assert_eq!(
+            topics(&retained, "+/tennis/#"),
+            ["sport/tennis", "sport/tennis/player1"]
+        );
+        assert_eq!(topics(&retained, "#").len(), 5);
+        assert_eq!(topics(&retained, "+").len(), 2);
+        assert_eq!(topics(&retained, "$SYS/#"), ["$SYS/uptime"]);
+    }
+
+    #[test]
+    fn keeps_the_last_message_of_each_topic() {
+        let retained = Retained::default();
+        retained.retain(&publish("sport/tennis", "first"));
+        retained.retain(&publish("sport/tennis", "second"));
+
+        let publishes = retained.matching("sport/tennis");
+        assert_eq!(publishes.len(), 1);
+        assert_eq!(&publishes[0].payload[..], b"second");
+        assert_eq!(publishes[0].pkid, None);
+    }
+
+    #[test]
+    fn empty_payload_clears_the_topic() {
+        let retained = Retained::default();
+        retained.retain(&publish("sport/tennis/player1", "x"));
+        retained.retain(&publish("sport/tennis/player1", ""));
+
+        assert!(retained.matching("#").is_empty());
+        assert!(retained.root.read().unwrap().children.is_empty());
+    }
+
+    #[test]
+    fn message_expiry_is_counted_down() {
+        let retained = Retained::default();
+        let expiring = |interval| Publish {
+            properties: PublishProperties {
+                message_expiry_internal: Some(interval),
+                ..Default::default()
+            },
+            ..publish("sensors/temperature", "21.5")
+        };
+
+        retained.retain(&expiring(60));
+        let publishes = retained.matching("sensors/temperature");
+        assert!(publishes[0]
+            .properties
+            .message_expiry_internal
+            .is_some_and(|remaining| (59..=60).contains(&remaining)));
+
+        retained.retain(&expiring(0));
+        assert!(retained.matching("sensors/temperature").is_empty());
+        assert!(retained.root.read().unwrap().children.is_empty());
+    }
+
+    #[test]
+    fn changes_are_written_through_to_the_store() {
+        let store = Arc::new(Memory::default());
+        let retained = Retained::load(Some(store.clone())).unwrap();
+        retained.retain(&publish("sport/tennis", "x"));
+        retained.retain(&publish("sport/golf", "x"));
+        retained.retain(&publish("sport/golf", ""));
+
+        let mut stored: Vec<String> = store.0.lock().unwrap().keys().cloned().collect();
+        stored.sort();
+        assert_eq!(stored, ["sport/tennis"]);
+
+        let reloaded = Retained::load(Some(store)).unwrap();
+        assert_eq!(topics(&reloaded, "#"), ["sport/tennis"]);
+    }
+
+    #[test]
+    fn expired_messages_are_not_loaded() {
+        let store = Arc::new(Memory::default());
+        let mut message = RetainedMessage {
+            publish: publish("sport/tennis", "x"),
+            received_at: SystemTime::now() - Duration::from_secs(120),
+        };
+        message.publish.properties.message_expiry_internal = Some(60);
+        store.store(&message).unwrap();
+
+        let retained = Retained::load(Some(store.clone())).unwrap();
+        assert!(retained.matching("#").is_empty());
+        assert!(store.0.lock().unwrap().is_empty());
+    }
+}
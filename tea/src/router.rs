@@ -0,0 +1,342 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, RwLock},
+};
+
+use mqttea_core::v5::{commons::qos::QoS, packet::subscribe::SubscriptionOptions};
+
+/// A client's subscription to a Topic Filter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Subscription {
+    pub(crate) options: SubscriptionOptions,
+    /// 3.8.2.1.2 Subscription Identifier, sent back on every PUBLISH matching this subscription
+    pub(crate) id: Option<usize>,
+}
+
+/// Where a PUBLISH has to be forwarded to, once every subscription of the client it matches is accounted for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Route {
+    pub(crate) client_id: Arc<str>,
+    pub(crate) qos: QoS,
+    pub(crate) retain_as_published: bool,
+    pub(crate) subscription_ids: Vec<usize>,
+}
+
+/// Subscriptions of every connected client, indexed by the levels of their Topic Filter.
+/// PUBLISH packets are routed concurrently, while subscribing and unsubscribing take turns
+#[derive(Debug, Default)]
+pub(crate) struct Router {
+    index: RwLock<Index>,
+}
+
+#[derive(Debug, Default)]
+struct Index {
+    root: Node,
+    /// Topic Filters of each client, so that its subscriptions can be found when it goes away
+    clients: HashMap<Arc<str>, HashSet<Box<str>>>,
+}
+
+/// A level of a Topic Filter, `+` and `#` included
+#[derive(Debug, Default)]
+struct Node {
+    children: HashMap<Box<str>, Node>,
+    /// Subscriptions to the Topic Filter ending at this level, by Client Identifier
+    subscribers: HashMap<Arc<str>, Subscription>,
+}
+
+impl Router {
+    /// Subscribes the client to `filter`, returns whether this replaced an existing subscription (MQTT-3.8.4-3)
+    pub(crate) fn subscribe(
+        &self,
+        client_id: &str,
+        filter: &str,
+        subscription: Subscription,
+    ) -> bool {
+        let mut index = self.index.write().unwrap();
+        let Index { root, clients } = &mut *index;
+
+        let client_id = match clients.get_key_value(client_id) {
+            Some((client_id, _)) => client_id.clone(),
+            None => Arc::from(client_id),
+        };
+        clients
+            .entry(client_id.clone())
+            .or_default()
+            .insert(filter.into());
+
+        let node = filter.split('/').fold(root, |node, level| {
+            node.children.entry(level.into()).or_default()
+        });
+        node.subscribers.insert(client_id, subscription).is_some()
+    }
+
+    /// Returns whether the client was subscribed to `filter`
+    pub(crate) fn unsubscribe(&self, client_id: &str, filter: &str) -> bool {
+        let mut index = self.index.write().unwrap();
+
+        let Some(filters) = index.clients.get_mut(client_id) else {
+            return false;
+        };
+        if !filters.remove(filter) {
+            return false;
+        }
+        if filters.is_empty() {
+            index.clients.remove(client_id);
+        }
+
+        index.root.remove(&mut filter.split('/'), client_id)
+    }
+
+    /// Drops every subscription the client has
+    pub(crate) fn remove(&self, client_id: &str) {
+        let mut index = self.index.write().unwrap();
+
+        let Some(filters) = index.clients.remove(client_id) else {
+            return;
+        };
+        for filter in filters.iter() {
+            index.root.remove(&mut filter.split('/'), client_id);
+        }
+    }
+
+    /// The clients a PUBLISH to `topic` sent by `publisher` is forwarded to.
+    /// A client with several matching subscriptions receives the message once, at the highest QoS granted
+    /// and with every Subscription Identifier (MQTT-3.3.4-3 to MQTT-3.3.4-5)
+    pub(crate) fn route(&self, topic: &str, publisher: &str) -> Vec<Route> {
+        let levels: Vec<&str> = topic.split('/').collect();
+        let mut routes: HashMap<&Arc<str>, Route> = HashMap::new();
+
+        let index = self.index.read().unwrap();
+        // MQTT-4.7.2-1 wildcards at the first level don't match Topic Names starting with `$`
+        let wildcards = !topic.starts_with('$');
+
+        index
+            .root
+            .matches(&levels, wildcards, &mut |client_id, subscription| {
+                let options = subscription.options;
+                // MQTT-3.8.3-3 No Local: the message is not sent back to the client that published it
+                if options.no_local && &**client_id == publisher {
+                    return;
+                }
+
+                let route = routes.entry(client_id).or_insert_with(|| Route {
+                    client_id: client_id.clone(),
+                    qos: QoS::Zero,
+                    retain_as_published: false,
+                    subscription_ids: Vec::new(),
+                });
+                route.qos = route.qos.max(options.qos);
+                route.retain_as_published |= options.retain_as_published;
+                route.subscription_ids.extend(subscription.id);
+            });
+
+        routes.into_values().collect()
+    }
+}
+
+impl Node {
+    /// Visits every subscription whose Topic Filter matches the remaining `levels` of the Topic Name.
+    /// `wildcards` is false when `+` and `#` may not match the next level
+    fn matches<'a, F>(&'a self, levels: &[&str], wildcards: bool, visit: &mut F)
+    where
+        F: FnMut(&'a Arc<str>, &'a Subscription),
+    {
+        // `#` also matches the parent level, "sport/#" matches "sport" (4.7.1.2)
+        if let Some(node) = self.children.get("#").filter(|_| wildcards) {
+            node.subscribers
+                .iter()
+                .for_each(|(client_id, s)| visit(client_id, s));
+        }
+
+        let Some((level, rest)) = levels.split_first() else {
+            self.subscribers
+                .iter()
+                .for_each(|(client_id, s)| visit(client_id, s));
+            return;
+        };
+
+        if let Some(node) = self.children.get(*level) {
+            node.matches(rest, true, visit);
+        }
+        if let Some(node) = self.children.get("+").filter(|_| wildcards) {
+            node.matches(rest, true, visit);
+        }
+    }
+
+    /// Removes the client's subscription to the Topic Filter made of `levels`, pruning the levels left unused
+    fn remove<'a>(&mut self, levels: &mut impl Iterator<Item = &'a str>, client_id: &str) -> bool {
+        let Some(level) = levels.next() else {
+            return self.subscribers.remove(client_id).is_some();
+        };
+        let Some(node) = self.children.get_mut(level) else {
+            return false;
+        };
+
+        let removed = node.remove(levels, client_id);
+        if node.subscribers.is_empty() && node.children.is_empty() {
+            self.children.remove(level);
+        }
+        removed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn subscription(qos: QoS, id: Option<usize>) -> Subscription {
+        Subscription {
+            options: SubscriptionOptions {
+                qos,
+                ..Default::default()
+            },
+            id,
+        }
+    }
+
+    /// Whether a client subscribed to `filter` alone receives a PUBLISH to `topic`
+    fn matches(filter: &str, topic: &str) -> bool {
+        let router = Router::default();
+        router.subscribe("a", filter, subscription(QoS::Zero, None));
+        !router.route(topic, "b").is_empty()
+    }
+
+    #[test]
+    fn matches_topic_filters() {
+        let cases = [
+            ("sport/tennis/player1", "sport/tennis/player1", true),
+            ("sport/tennis/player1/#", "sport/tennis/player1", true),
+            (
+                "sport/tennis/player1/#",
+                "sport/tennis/player1/ranking",
+                true,
+            ),
+            (
+                "sport/tennis/player1/#",
+                "sport/tennis/player1/score/wimbledon",
+                true,
+            ),
+            ("sport/#", "sport", true),
+            ("#", "sport/tennis", true),
+            ("sport/tennis/+", "sport/tennis/player1", true),
+            ("sport/tennis/+", "sport/tennis/player1/ranking", false),
+            ("sport/tennis/+", "sport/tennis", false),
+            ("sport/+", "sport/", true),
+            ("+/+", "/finance", true),
+            ("/+", "/finance", true),
+            ("+", "/finance", false),
+            ("+/tennis/#", "sport/tennis", true),
+            ("sport/+/#", "sport/tennis/player1", true),
+            ("sport/tennis", "sport/Tennis", false),
+            ("sport", "sport/", false),
+        ];
+
+        for (filter, topic, expected) in cases {
+            assert_eq!(matches(filter, topic), expected, "{filter} against {topic}");
+        }
+    }
+
+    #[test]
+    fn wildcards_do_not_match_dollar_topics_at_the_first_level() {
+        assert!(!matches("#", "$SYS/broker/uptime"));
+        assert!(!matches("+/broker/uptime", "$SYS/broker/uptime"));
+        assert!(matches("$SYS/#", "$SYS/broker/uptime"));
+        assert!(matches("$SYS/+/uptime", "$SYS/broker/uptime"));
+        assert!(matches("$SYS/broker/+", "$SYS/broker/uptime"));
+    }
+
+    #[test]
+    fn overlapping_subscriptions_are_delivered_once() {
+        let router = Router::default();
+        router.subscribe("a", "sport/#", subscription(QoS::Zero, Some(1)));
+        router.subscribe("a", "sport/+/player1", subscription(QoS::Two, Some(2)));
+        router.subscribe("a", "finance/#", subscription(QoS::One, Some(3)));
+        router.subscribe("b", "sport/tennis/player1", subscription(QoS::One, None));
+
+        let mut routes = router.route("sport/tennis/player1", "c");
+        routes.sort_by(|a, b| a.client_id.cmp(&b.client_id));
+        assert_eq!(routes.len(), 2);
+
+        assert_eq!(routes[0].qos, QoS::Two);
+        routes[0].subscription_ids.sort();
+        assert_eq!(routes[0].subscription_ids, vec![1, 2]);
+
+        assert_eq!(routes[1].qos, QoS::One);
+        assert!(routes[1].subscription_ids.is_empty());
+    }
+
+    #[test]
+    fn retain_as_published_is_kept_if_any_subscription_asks_for_it() {
+        let router = Router::default();
+        let mut retained = subscription(QoS::Zero, None);
+        retained.options.retain_as_published = true;
+        router.subscribe("a", "sport/#", retained);
+        router.subscribe("a", "sport/tennis", subscription(QoS::Zero, None));
+
+        assert!(router.route("sport/tennis", "b")[0].retain_as_published);
+        assert!(router.route("sport/golf", "b")[0].retain_as_published);
+    }
+
+    #[test]
+    fn no_local_skips_the_publisher() {
+        let router = Router::default();
+        let mut no_local = subscription(QoS::One, None);
+        no_local.options.no_local = true;
+        router.subscribe("a", "chat/#", no_local);
+        router.subscribe("b", "chat/#", subscription(QoS::One, None));
+
+        let routes = router.route("chat/general", "a");
+        assert_eq!(routes.len(), 1);
+        assert_eq!(&*routes[0].client_id, "b");
+    }
+
+    #[test]
+    fn resubscribing_replaces_the_subscription() {
+        let router = Router::default();
+        assert!(!router.subscribe("a", "t", subscription(QoS::Zero, Some(1))));
+        assert!(router.subscribe("a", "t", subscription(QoS::One, Some(2))));
+
+        let routes = router.route("t", "b");
+        assert_eq!(routes[0].qos, QoS::One);
+        assert_eq!(routes[0].subscription_ids, vec![2]);
+
+        assert!(router.unsubscribe("a", "t"));
+        assert!(!router.unsubscribe("a", "t"));
+        assert!(router.route("t", "b").is_empty());
+    }
+
+    #[test]
+    fn unused_levels_are_pruned() {
+        let router = Router::default();
+        router.subscribe("a", "sport/tennis/+", subscription(QoS::Zero, None));
+        router.subscribe("b", "sport/#", subscription(QoS::Zero, None));
+
+        assert!(router.unsubscribe("a", "sport/tennis/+"));
+        assert!(!router.unsubscribe("b", "sport/tennis/+"));
+        {
+            let index = router.index.read().unwrap();
+            let sport = &index.root.children["sport"];
+            assert_eq!(sport.children.keys().collect::<Vec<_>>(), vec![&"#".into()]);
+        }
+
+        router.remove("b");
+        let index = router.index.read().unwrap();
+        assert!(index.root.children.is_empty());
+        assert!(index.clients.is_empty());
+    }
+
+    #[test]
+    fn remove_drops_every_subscription_of_the_client() {
+        let router = Router::default();
+        router.subscribe("a", "sport/#", subscription(QoS::Zero, None));
+        router.subscribe("a", "finance/+", subscription(QoS::Zero, None));
+        router.subscribe("b", "finance/+", subscription(QoS::Zero, None));
+
+        router.remove("a");
+        assert!(router.route("sport/tennis", "c").is_empty());
+
+        let routes = router.route("finance/stocks", "c");
+        assert_eq!(routes.len(), 1);
+        assert_eq!(&*routes[0].client_id, "b");
+    }
+}
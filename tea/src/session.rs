@@ -0,0 +1,588 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::{Arc, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+use mqttea_core::v5::{
+    commons::{packet::Packet, packet_type::PacketType, qos::QoS},
+    packet::{publish::Publish, pubrel::PubRel},
+};
+use tokio::sync::Notify;
+
+use crate::config::DropPolicy;
+
+/// 3.1.2.11.2 a Session Expiry Interval of 0xFFFFFFFF means the session never expires
+pub(crate) const NEVER_EXPIRES: u32 = u32::MAX;
+
+/// What the broker keeps of a client, on its connections and between them
+#[derive(Debug)]
+pub(crate) struct Session {
+    state: Mutex<State>,
+}
+
+#[derive(Debug)]
+pub(crate) struct State {
+    /// The last connection the session was attached to
+    connection: u64,
+    /// Woken when messages are queued for the connection, or when another connection takes the session over.
+    /// `None` while the client is offline
+    wake: Option<Arc<Notify>>,
+    /// 3.1.2.11.2 how long the session outlives its connection, in seconds
+    pub(crate) expiry_interval: u32,
+    /// QoS 2 PUBLISH received whose PUBREL hasn't arrived yet (4.3.3)
+    pub(crate) incoming: HashSet<u16>,
+    pub(crate) outgoing: Outgoing,
+}
+
+impl Session {
+    pub(crate) fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap()
+    }
+
+    /// Queues a PUBLISH routed to the client, waking the connection it is attached to.
+    /// QoS 0 messages are dropped while the client is offline
+    pub(crate) fn deliver(&self, publish: Publish, limit: usize, policy: DropPolicy) {
+        let mut state = self.state();
+        let wake = state.wake.clone();
+        if wake.is_none() && publish.qos == QoS::Zero {
+            return;
+        }
+
+        state.outgoing.queue(publish, limit, policy);
+        if let Some(wake) = wake {
+            wake.notify_one();
+        }
+    }
+}
+
+impl State {
+    /// The last connection the session was attached to
+    pub(crate) fn connection(&self) -> u64 {
+        self.connection
+    }
+
+    /// Whether the session is still attached to `connection`
+    pub(crate) fn is_attached(&self, connection: u64) -> bool {
+        self.connection == connection && self.wake.is_some()
+    }
+}
+
+/// What happens to a session once its connection is gone
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Detached {
+    /// Another connection took the session over
+    TakenOver,
+    /// The session ended along with its connection
+    Ended,
+    /// The session is kept for as long as its Session Expiry Interval, forever if there is none
+    Expires(Option<Duration>),
+}
+
+/// Sessions of every client, connected or not, by Client Identifier
+#[derive(Debug, Default)]
+pub(crate) struct Sessions {
+    sessions: HashMap<String, Arc<Session>>,
+}
+
+impl Sessions {
+    /// Attaches the connection to the client's session, starting a new one on Clean Start or when there is none.
+    /// The connection the session was attached to is woken up to find it lost it.
+    /// Returns the session and whether it existed already
+    pub(crate) fn attach(
+        &mut self,
+        client_id: &str,
+        connection: u64,
+        wake: Arc<Notify>,
+        clean_start: bool,
+        expiry_interval: u32,
+    ) -> (Arc<Session>, bool) {
+        if let Some(session) = self.sessions.get(client_id) {
+            let mut state = session.state();
+            if let Some(previous) = state.wake.take() {
+                previous.notify_one();
+            }
+
+            // MQTT-3.1.2-5 the session is resumed, MQTT-3.1.2-4 unless the client asked for a new one
+            if !clean_start {
+                state.connection = connection;
+                state.wake = Some(wake);
+                state.expiry_interval = expiry_interval;
+                return (session.clone(), true);
+            }
+        }
+
+        let session = Arc::new(Session {
+            state: Mutex::new(State {
+                connection,
+                wake: Some(wake),
+                expiry_interval,
+                incoming: HashSet::new(),
+                outgoing: Outgoing::new(),
+            }),
+        });
+        self.sessions.insert(client_id.to_string(), session.clone());
+        (session, false)
+    }
+
+    /// Detaches the connection from the client's session, unless another connection already took it over
+    pub(crate) fn detach(&mut self, client_id: &str, connection: u64) -> Detached {
+        let Some(session) = self.sessions.get(client_id) else {
+            return Detached::TakenOver;
+        };
+        let mut state = session.state();
+        if !state.is_attached(connection) {
+            return Detached::TakenOver;
+        }
+
+        state.wake = None;
+        match state.expiry_interval {
+            0 => {
+                drop(state);
+                self.sessions.remove(client_id);
+                Detached::Ended
+            }
+            NEVER_EXPIRES => Detached::Expires(None),
+            interval => Detached::Expires(Some(Duration::from_secs(interval as u64))),
+        }
+    }
+
+    /// Ends the session the connection left behind, unless the client came back since.
+    /// Returns whether the session ended
+    pub(crate) fn expire(&mut self, client_id: &str, connection: u64) -> bool {
+        let expired = self.sessions.get(client_id).is_some_and(|session| {
+            let state = session.state();
+            state.connection == connection && state.wake.is_none()
+        });
+        if expired {
+            self.sessions.remove(client_id);
+        }
+        expired
+    }
+
+    pub(crate) fn get(&self, client_id: &str) -> Option<Arc<Session>> {
+        self.sessions.get(client_id).cloned()
+    }
+}
+
+/// A QoS 1 or QoS 2 PUBLISH sent to the client and not yet acknowledged
+#[derive(Debug)]
+struct InFlight {
+    /// The acknowledgement the PUBLISH is waiting on (4.3.2, 4.3.3)
+    awaiting: PacketType,
+    /// Order in which the PUBLISH was sent, they are sent again in that order (MQTT-4.6.0-1)
+    sequence: u64,
+    publish: Publish,
+}
+
+#[derive(Debug)]
+struct Queued {
+    publish: Publish,
+    at: Instant,
+}
+
+/// Messages routed to the client and not yet acknowledged by it.
+/// Once the client's Receive Maximum is reached QoS 1 and QoS 2 PUBLISH are queued until an acknowledgement frees a slot (MQTT-3.3.4-9)
+#[derive(Debug)]
+pub(crate) struct Outgoing {
+    receive_maximum: u16,
+    /// 3.1.2.11.4 packets larger than this are never sent to the client
+    maximum_packet_size: usize,
+    next_pkid: u16,
+    sequence: u64,
+    in_flight: HashMap<u16, InFlight>,
+    queued: VecDeque<Queued>,
+}
+
+impl Outgoing {
+    pub(crate) fn new() -> Self {
+        Self {
+            receive_maximum: u16::MAX,
+            maximum_packet_size: usize::MAX,
+            next_pkid: 0,
+            sequence: 0,
+            in_flight: HashMap::new(),
+            queued: VecDeque::new(),
+        }
+    }
+
+    /// Takes on the limits of the connection the session is attached to.
+    /// Returns what has to be sent again for the messages still in flight, in the order they were first sent (MQTT-4.4.0-1)
+    pub(crate) fn resume(
+        &mut self,
+        receive_maximum: u16,
+        maximum_packet_size: usize,
+    ) -> Vec<Packet> {
+        self.receive_maximum = receive_maximum;
+        self.maximum_packet_size = maximum_packet_size;
+
+        let mut in_flight: Vec<(&u16, &InFlight)> = self.in_flight.iter().collect();
+        in_flight.sort_by_key(|(_, in_flight)| in_flight.sequence);
+        in_flight
+            .into_iter()
+            .map(|(&pkid, in_flight)| match in_flight.awaiting {
+                PacketType::PubComp => Packet::PubRel(PubRel {
+                    pkid,
+                    ..Default::default()
+                }),
+                _ => Packet::Publish(Publish {
+                    // MQTT-3.3.1-1
+                    dup: true,
+                    ..in_flight.publish.clone()
+                }),
+            })
+            .collect()
+    }
+
+    /// Queues a PUBLISH, to be picked up by `next`.
+    /// Once `limit` messages are queued either the oldest one or this one is dropped, depending on `policy`
+    pub(crate) fn queue(&mut self, publish: Publish, limit: usize, policy: DropPolicy) {
+        if self.queued.len() >= limit {
+            match policy {
+                DropPolicy::Oldest => {
+                    self.queued.pop_front();
+                }
+                DropPolicy::Newest => return,
+            }
+        }
+        if limit > 0 {
+            self.queued.push_back(Queued {
+                publish,
+                at: Instant::now(),
+            });
+        }
+    }
+
+    /// The next queued PUBLISH the client has room for, with its Packet Identifier when it needs one.
+    /// QoS 0 messages are never held back, messages that expired or are too large for the client are dropped
+    pub(crate) fn next(&mut self) -> Option<Publish> {
+        loop {
+            let position = match self.is_full() {
+                true => self
+                    .queued
+                    .iter()
+                    .position(|queued| queued.publish.qos == QoS::Zero)?,
+                false => 0,
+            };
+            let Queued { mut publish, at } = self.queued.remove(position)?;
+
+            // MQTT-3.3.2-5 and MQTT-3.3.2-6 the interval is reduced by how long the message has been waiting
+            if let Some(interval) = publish.properties.message_expiry_internal {
+                let remaining = interval.saturating_sub(at.elapsed().as_secs() as u32);
+                if remaining == 0 {
+                    continue;
+                }
+                publish.properties.message_expiry_internal = Some(remaining);
+            }
+
+            // MQTT-3.1.2-24 a message too large for the client is dropped as if it had been delivered
+            let packet = Packet::Publish(publish);
+            if packet.size() > self.maximum_packet_size {
+                continue;
+            }
+            let Packet::Publish(publish) = packet else {
+                unreachable!()
+            };
+
+            return match publish.qos {
+                QoS::Zero => Some(publish),
+                _ => Some(self.assign(publish)),
+            };
+        }
+    }
+
+    /// Returns whether `pkid` was awaiting a PUBACK
+    pub(crate) fn puback(&mut self, pkid: u16) -> bool {
+        self.take(pkid, PacketType::PubAck).is_some()
+    }
+
+    /// Returns whether `pkid` was awaiting a PUBREC.
+    /// A failed PUBREC ends the exchange, a successful one is followed by PUBREL and then waits on PUBCOMP
+    pub(crate) fn pubrec(&mut self, pkid: u16, success: bool) -> bool {
+        let Some(mut in_flight) = self.take(pkid, PacketType::PubRec) else {
+            return false;
+        };
+        if success {
+            in_flight.awaiting = PacketType::PubComp;
+            self.in_flight.insert(pkid, in_flight);
+        }
+        true
+    }
+
+    /// Returns whether `pkid` was awaiting a PUBCOMP
+    pub(crate) fn pubcomp(&mut self, pkid: u16) -> bool {
+        self.take(pkid, PacketType::PubComp).is_some()
+    }
+
+    fn take(&mut self, pkid: u16, expected: PacketType) -> Option<InFlight> {
+        if self.in_flight.get(&pkid)?.awaiting != expected {
+            return None;
+        }
+        self.in_flight.remove(&pkid)
+    }
+
+    fn is_full(&self) -> bool {
+        self.in_flight.len() >= self.receive_maximum as usize
+    }
+
+    fn assign(&mut self, mut publish: Publish) -> Publish {
+        // Packet Identifiers are non-zero and must not be reused while in flight (MQTT-2.2.1-4)
+        loop {
+            self.next_pkid = self.next_pkid.checked_add(1).unwrap_or(1);
+            if !self.in_flight.contains_key(&self.next_pkid) {
+                break;
+            }
+        }
+
+        let awaiting = match publish.qos {
+            QoS::One => PacketType::PubAck,
+            _ => PacketType::PubRec,
+        };
+        publish.pkid = Some(self.next_pkid);
+        self.sequence += 1;
+        self.in_flight.insert(
+            self.next_pkid,
+            InFlight {
+                awaiting,
+                sequence: self.sequence,
+                publish: publish.clone(),
+            },
+        );
+        publish
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mqttea_core::v5::packet::publish::PublishProperties;
+
+    use super::*;
+
+    fn publish(qos: QoS) -> Publish {
+        Publish {
+            qos,
+            topic: String::from("t"),
+            ..Default::default()
+        }
+    }
+
+    fn outgoing(receive_maximum: u16) -> Outgoing {
+        let mut outgoing = Outgoing::new();
+        outgoing.resume(receive_maximum, usize::MAX);
+        outgoing
+    }
+
+    /// Queues the PUBLISH and returns it if it can be sent right away
+    fn send(outgoing: &mut Outgoing, publish: Publish) -> Option<Publish> {
+        outgoing.queue(publish, usize::MAX, DropPolicy::Newest);
+        outgoing.next()
+    }
+
+    #[test]
+    fn qos0_is_never_held_back() {
+        let mut outgoing = outgoing(1);
+        assert_eq!(
+            send(&mut outgoing, publish(QoS::One)).unwrap().pkid,
+            Some(1)
+        );
+        assert!(send(&mut outgoing, publish(QoS::One)).is_none());
+
+        let sent = send(&mut outgoing, publish(QoS::Zero)).unwrap();
+        assert_eq!(sent.pkid, None);
+    }
+
+    #[test]
+    fn respects_the_receive_maximum() {
+        let mut outgoing = outgoing(2);
+        assert_eq!(
+            send(&mut outgoing, publish(QoS::One)).unwrap().pkid,
+            Some(1)
+        );
+        assert_eq!(
+            send(&mut outgoing, publish(QoS::Two)).unwrap().pkid,
+            Some(2)
+        );
+        assert!(send(&mut outgoing, publish(QoS::One)).is_none());
+        assert!(outgoing.next().is_none());
+
+        assert!(outgoing.puback(1));
+        assert!(!outgoing.puback(1));
+        assert_eq!(outgoing.next().unwrap().pkid, Some(3));
+        assert!(outgoing.next().is_none());
+    }
+
+    #[test]
+    fn qos2_holds_its_slot_until_pubcomp() {
+        let mut outgoing = outgoing(1);
+        send(&mut outgoing, publish(QoS::Two)).unwrap();
+        assert!(send(&mut outgoing, publish(QoS::One)).is_none());
+
+        assert!(!outgoing.puback(1));
+        assert!(!outgoing.pubcomp(1));
+        assert!(outgoing.pubrec(1, true));
+        assert!(outgoing.next().is_none());
+
+        assert!(outgoing.pubcomp(1));
+        assert_eq!(outgoing.next().unwrap().pkid, Some(2));
+    }
+
+    #[test]
+    fn failed_pubrec_ends_the_exchange() {
+        let mut outgoing = outgoing(1);
+        send(&mut outgoing, publish(QoS::Two)).unwrap();
+
+        assert!(outgoing.pubrec(1, false));
+        assert!(!outgoing.pubcomp(1));
+        assert_eq!(
+            send(&mut outgoing, publish(QoS::One)).unwrap().pkid,
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn skips_packet_ids_still_in_flight() {
+        let mut outgoing = outgoing(u16::MAX);
+        for _ in 0..u16::MAX {
+            send(&mut outgoing, publish(QoS::One)).unwrap();
+        }
+        for pkid in 2..=u16::MAX {
+            assert!(outgoing.puback(pkid));
+        }
+
+        // the identifiers wrap around, and 1 is still waiting on its PUBACK
+        assert_eq!(
+            send(&mut outgoing, publish(QoS::One)).unwrap().pkid,
+            Some(2)
+        );
+    }
+
+    #[test]
+    fn drops_messages_past_the_queue_limit() {
+        let payloads = |outgoing: &mut Outgoing| {
+            std::iter::from_fn(|| outgoing.next())
+                .map(|publish| publish.payload)
+                .collect::<Vec<_>>()
+        };
+        let message = |payload: &'static str| Publish {
+            payload: payload.into(),
+            ..publish(QoS::One)
+        };
+
+        for (policy, kept) in [
+            (DropPolicy::Newest, ["first", "second"]),
+            (DropPolicy::Oldest, ["second", "third"]),
+        ] {
+            let mut outgoing = outgoing(u16::MAX);
+            for payload in ["first", "second", "third"] {
+                outgoing.queue(message(payload), 2, policy);
+            }
+            assert_eq!(payloads(&mut outgoing), kept);
+        }
+    }
+
+    #[test]
+    fn drops_expired_and_oversized_messages() {
+        let mut outgoing = Outgoing::new();
+        outgoing.resume(u16::MAX, 64);
+
+        let expired = Publish {
+            properties: PublishProperties {
+                message_expiry_internal: Some(0),
+                ..Default::default()
+            },
+            ..publish(QoS::One)
+        };
+        let oversized = Publish {
+            payload: vec![0; 64].into(),
+            ..publish(QoS::One)
+        };
+        let expiring = Publish {
+            properties: PublishProperties {
+                message_expiry_internal: Some(60),
+                ..Default::default()
+            },
+            ..publish(QoS::One)
+        };
+        for publish in [expired, oversized, expiring] {
+            outgoing.queue(publish, usize::MAX, DropPolicy::Newest);
+        }
+
+        let sent = outgoing.next().unwrap();
+        assert_eq!(sent.pkid, Some(1));
+        assert_eq!(sent.properties.message_expiry_internal, Some(60));
+        assert!(outgoing.next().is_none());
+    }
+
+    #[test]
+    fn resends_what_is_in_flight_in_order() {
+        let mut outgoing = outgoing(u16::MAX);
+        for qos in [QoS::Two, QoS::One, QoS::Two] {
+            send(&mut outgoing, publish(qos)).unwrap();
+        }
+        assert!(outgoing.pubrec(1, true));
+
+        let packets = outgoing.resume(10, usize::MAX);
+        assert_eq!(packets.len(), 3);
+        assert!(matches!(&packets[0], Packet::PubRel(pubrel) if pubrel.pkid == 1));
+        for (packet, pkid) in packets[1..].iter().zip([2, 3]) {
+            let Packet::Publish(publish) = packet else {
+                panic!("expected a PUBLISH");
+            };
+            assert!(publish.dup);
+            assert_eq!(publish.pkid, Some(pkid));
+        }
+    }
+
+    #[test]
+    fn sessions_outlive_their_connection_until_they_expire() {
+        let mut sessions = Sessions::default();
+        let wake = Arc::new(Notify::new());
+
+        let (_, present) = sessions.attach("a", 1, wake.clone(), false, 0);
+        assert!(!present);
+        assert_eq!(sessions.detach("a", 1), Detached::Ended);
+        assert!(sessions.get("a").is_none());
+
+        sessions.attach("a", 2, wake.clone(), false, 60);
+        assert_eq!(
+            sessions.detach("a", 2),
+            Detached::Expires(Some(Duration::from_secs(60)))
+        );
+        let (_, present) = sessions.attach("a", 3, wake.clone(), false, NEVER_EXPIRES);
+        assert!(present);
+        // the timer started when connection 2 went away doesn't end the session resumed since
+        assert!(!sessions.expire("a", 2));
+
+        assert_eq!(sessions.detach("a", 3), Detached::Expires(None));
+        assert!(sessions.expire("a", 3));
+        assert!(sessions.get("a").is_none());
+    }
+
+    #[test]
+    fn clean_start_discards_the_session() {
+        let mut sessions = Sessions::default();
+        let first = Arc::new(Notify::new());
+        let (session, _) = sessions.attach("a", 1, first.clone(), false, 60);
+        session.state().incoming.insert(1);
+
+        let (session, present) = sessions.attach("a", 2, Arc::new(Notify::new()), true, 60);
+        assert!(!present);
+        assert!(session.state().incoming.is_empty());
+        assert_eq!(sessions.detach("a", 1), Detached::TakenOver);
+    }
+
+    #[test]
+    fn offline_sessions_only_queue_qos1_and_qos2() {
+        let mut sessions = Sessions::default();
+        let (session, _) = sessions.attach("a", 1, Arc::new(Notify::new()), false, 60);
+        sessions.detach("a", 1);
+
+        session.deliver(publish(QoS::Zero), 10, DropPolicy::Newest);
+        session.deliver(publish(QoS::One), 10, DropPolicy::Newest);
+
+        let mut state = session.state();
+        state.outgoing.resume(10, usize::MAX);
+        assert_eq!(state.outgoing.next().unwrap().qos, QoS::One);
+        assert!(state.outgoing.next().is_none());
+    }
+}
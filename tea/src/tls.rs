@@ -0,0 +1,316 @@
+use std::{
+    fs,
+    future::Future,
+    io,
+    path::PathBuf,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use mqttea_core::v5::client::network::tls::{certificates, private_key, roots, watch_files, ALPN};
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    time::{self, Instant},
+};
+use tokio_rustls::{
+    rustls::{crypto::ring, server::WebPkiClientVerifier, version::TLS13, ServerConfig},
+    server::TlsStream,
+    TlsAcceptor,
+};
+use x509_cert::{
+    der::{
+        asn1::{ObjectIdentifier, PrintableStringRef, Utf8StringRef},
+        Decode,
+    },
+    ext::pkix::{name::GeneralName, SubjectAltName},
+    Certificate,
+};
+
+use crate::connection::Peer;
+
+/// 2.5.4.3 the commonName attribute type (RFC 4519)
+const COMMON_NAME: ObjectIdentifier = ObjectIdentifier::new_unwrap("2.5.4.3");
+
+/// The certificate the broker presents on its TLS listener, and what clients have to present in return, PEM encoded
+#[derive(Debug, Clone)]
+pub struct TlsConfig {
+    /// The broker's certificate, followed by the intermediates up to the certificate authority
+    pub certificate_chain: Vec<u8>,
+    pub private_key: Vec<u8>,
+    /// The certificate authorities client certificates are checked against.
+    /// Every client has to present a certificate when set (mutual TLS)
+    pub client_ca: Option<Vec<u8>>,
+    /// What the identity read off a client certificate stands for
+    pub client_identity: ClientIdentity,
+}
+
+/// What the identity a client certificate carries stands for.
+///
+/// The identity is the certificate's SPIFFE ID (a `spiffe://` URI Subject Alternative Name) when it has one,
+/// its subject Common Name otherwise, and its first DNS Subject Alternative Name as a last resort
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ClientIdentity {
+    /// The Client Identifier, which the CONNECT must carry, or leave empty to be assigned it
+    #[default]
+    ClientId,
+    /// The username authentication and authorization hooks are given, which the CONNECT may only repeat
+    Username,
+}
+
+/// A TLS listener ready to take connections on
+#[derive(Debug, Clone)]
+pub(crate) struct Acceptor {
+    config: Arc<ServerConfig>,
+    /// Set when clients authenticate with a certificate
+    client_identity: Option<ClientIdentity>,
+}
+
+/// Where the PEM files making up a `TlsConfig` are, so that they can be read again once rotated
+#[derive(Debug, Clone, Default)]
+pub struct TlsFiles {
+    pub certificate_chain: PathBuf,
+    pub private_key: PathBuf,
+    pub client_ca: Option<PathBuf>,
+    pub client_identity: ClientIdentity,
+}
+
+impl TlsFiles {
+    pub fn load(&self) -> io::Result<TlsConfig> {
+        Ok(TlsConfig {
+            certificate_chain: fs::read(&self.certificate_chain)?,
+            private_key: fs::read(&self.private_key)?,
+            client_ca: self.client_ca.as_ref().map(fs::read).transpose()?,
+            client_identity: self.client_identity,
+        })
+    }
+
+    fn paths(&self) -> Vec<PathBuf> {
+        [
+            Some(&self.certificate_chain),
+            Some(&self.private_key),
+            self.client_ca.as_ref(),
+        ]
+        .into_iter()
+        .flatten()
+        .cloned()
+        .collect()
+    }
+}
+
+/// Rotates the certificates of a running TLS listener.
+/// Handshakes are made with the material it was last reloaded with, connections already established carry on with theirs
+#[derive(Debug, Clone)]
+pub struct TlsReloader {
+    acceptor: Arc<RwLock<Acceptor>>,
+}
+
+impl TlsReloader {
+    pub(crate) fn new(acceptor: Acceptor) -> Self {
+        Self {
+            acceptor: Arc::new(RwLock::new(acceptor)),
+        }
+    }
+
+    pub(crate) fn acceptor(&self) -> Acceptor {
+        self.acceptor.read().unwrap().clone()
+    }
+
+    /// Replaces the material of the listener, which is left as is when `tls` is invalid
+    pub fn reload(&self, tls: &TlsConfig) -> io::Result<()> {
+        let acceptor = tls.acceptor()?;
+        *self.acceptor.write().unwrap() = acceptor;
+        Ok(())
+    }
+
+    /// Reloads `files` whenever one of them is modified from now on, checking every `interval`, until the future is dropped.
+    /// Files caught halfway through being rewritten are read again on the next check, the previous material being kept meanwhile
+    pub fn watch(self, files: TlsFiles, interval: Duration) -> impl Future<Output = ()> {
+        watch_files(files.paths(), interval, move || self.reload(&files.load()?))
+    }
+}
+
+impl TlsConfig {
+    /// Only TLS 1.3 is offered, and `mqtt` is the only protocol agreed to through ALPN
+    pub(crate) fn acceptor(&self) -> io::Result<Acceptor> {
+        let chain = certificates(&self.certificate_chain, "certificate_chain")?;
+        let key = private_key(&self.private_key, "private_key")?;
+
+        let provider = Arc::new(ring::default_provider());
+        let builder = ServerConfig::builder_with_provider(provider.clone())
+            .with_protocol_versions(&[&TLS13])
+            .map_err(|err| invalid(err.to_string()))?;
+        let builder = match &self.client_ca {
+            Some(client_ca) => {
+                let roots = roots(client_ca, "client_ca")?;
+                let verifier =
+                    WebPkiClientVerifier::builder_with_provider(Arc::new(roots), provider)
+                        .build()
+                        .map_err(|err| invalid(format!("client_ca: {err}")))?;
+                builder.with_client_cert_verifier(verifier)
+            }
+            None => builder.with_no_client_auth(),
+        };
+        let mut config = builder
+            .with_single_cert(chain, key)
+            .map_err(|err| invalid(err.to_string()))?;
+        config.alpn_protocols = vec![ALPN.to_vec()];
+
+        Ok(Acceptor {
+            config: Arc::new(config),
+            client_identity: self.client_ca.is_some().then_some(self.client_identity),
+        })
+    }
+}
+
+/// Performs the TLS handshake of a client, which has to be done by `deadline`, the one its CONNECT has to arrive by.
+/// `None` when the handshake fails, or when the client certificate doesn't say who the client is
+pub(crate) async fn accept<S>(
+    acceptor: Acceptor,
+    stream: S,
+    deadline: Instant,
+) -> Option<(TlsStream<S>, Peer)>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let handshake = TlsAcceptor::from(acceptor.config).accept(stream);
+    let Ok(Ok(stream)) = time::timeout_at(deadline, handshake).await else {
+        return None;
+    };
+
+    let mut peer = Peer::default();
+    if let Some(client_identity) = acceptor.client_identity {
+        // the handshake only succeeds with a certificate, which may still not say who the client is
+        let (_, session) = stream.get_ref();
+        let identity = session
+            .peer_certificates()
+            .and_then(|chain| chain.first())
+            .and_then(|cert| identity(cert))?;
+        match client_identity {
+            ClientIdentity::ClientId => peer.client_id = Some(identity),
+            ClientIdentity::Username => peer.username = Some(identity),
+        }
+    }
+
+    Some((stream, peer))
+}
+
+/// The identity a certificate carries, as described on [`ClientIdentity`]
+fn identity(cert: &[u8]) -> Option<String> {
+    let cert = Certificate::from_der(cert).ok()?;
+    let tbs = &cert.tbs_certificate;
+    let names = match tbs.get::<SubjectAltName>() {
+        Ok(Some((_, names))) => names.0,
+        _ => Vec::new(),
+    };
+
+    let spiffe = names.iter().find_map(|name| match name {
+        GeneralName::UniformResourceIdentifier(uri) if uri.as_str().starts_with("spiffe://") => {
+            Some(uri.to_string())
+        }
+        _ => None,
+    });
+    let common_name = || {
+        tbs.subject
+            .0
+            .iter()
+            .flat_map(|rdn| rdn.0.iter())
+            .filter(|attribute| attribute.oid == COMMON_NAME)
+            .find_map(|attribute| {
+                let value = &attribute.value;
+                Utf8StringRef::try_from(value)
+                    .map(|name| name.to_string())
+                    .or_else(|_| PrintableStringRef::try_from(value).map(|name| name.to_string()))
+                    .ok()
+            })
+    };
+    let dns_name = || {
+        names.iter().find_map(|name| match name {
+            GeneralName::DnsName(dns) => Some(dns.to_string()),
+            _ => None,
+        })
+    };
+
+    spiffe
+        .or_else(common_name)
+        .or_else(dns_name)
+        .filter(|identity| !identity.is_empty())
+}
+
+fn invalid(reason: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, reason)
+}
+
+#[cfg(test)]
+mod tests {
+    use rcgen::{CertificateParams, DistinguishedName, DnType, KeyPair, SanType};
+
+    use super::*;
+
+    fn certificate(common_name: Option<&str>, subject_alt_names: Vec<SanType>) -> Vec<u8> {
+        let mut params = CertificateParams::new(Vec::new()).unwrap();
+        params.distinguished_name = DistinguishedName::new();
+        if let Some(common_name) = common_name {
+            params
+                .distinguished_name
+                .push(DnType::CommonName, common_name);
+        }
+        params.subject_alt_names = subject_alt_names;
+        let key = KeyPair::generate().unwrap();
+        params.self_signed(&key).unwrap().der().to_vec()
+    }
+
+    fn uri(uri: &str) -> SanType {
+        SanType::URI(uri.try_into().unwrap())
+    }
+
+    fn dns(name: &str) -> SanType {
+        SanType::DnsName(name.try_into().unwrap())
+    }
+
+    #[test]
+    fn prefers_the_spiffe_id() {
+        let cert = certificate(
+            Some("sensor"),
+            vec![
+                dns("sensor.example.org"),
+                uri("https://example.org"),
+                uri("spiffe://example.org/sensor"),
+            ],
+        );
+        assert_eq!(
+            identity(&cert).as_deref(),
+            Some("spiffe://example.org/sensor")
+        );
+    }
+
+    #[test]
+    fn falls_back_to_the_common_name_then_the_dns_name() {
+        let cert = certificate(Some("sensor"), vec![dns("sensor.example.org")]);
+        assert_eq!(identity(&cert).as_deref(), Some("sensor"));
+
+        let cert = certificate(None, vec![dns("sensor.example.org")]);
+        assert_eq!(identity(&cert).as_deref(), Some("sensor.example.org"));
+
+        let cert = certificate(None, vec![uri("https://example.org")]);
+        assert_eq!(identity(&cert), None);
+    }
+
+    #[test]
+    fn refuses_client_authorities_without_certificates() {
+        let key = KeyPair::generate().unwrap();
+        let certificate = CertificateParams::new(vec![String::from("localhost")])
+            .unwrap()
+            .self_signed(&key)
+            .unwrap();
+        let config = TlsConfig {
+            certificate_chain: certificate.pem().into_bytes(),
+            private_key: key.serialize_pem().into_bytes(),
+            client_ca: Some(Vec::new()),
+            client_identity: ClientIdentity::ClientId,
+        };
+
+        let err = config.acceptor().unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(err.to_string().starts_with("client_ca:"));
+    }
+}
@@ -0,0 +1,72 @@
+use std::sync::Arc;
+
+#[cfg(feature = "websocket")]
+use tokio::time;
+use tokio::{
+    io::{AsyncRead, AsyncWrite},
+    time::Instant,
+};
+#[cfg(feature = "websocket")]
+use tokio_util::compat::{FuturesAsyncReadCompatExt, TokioAsyncReadCompatExt};
+
+#[cfg(feature = "websocket")]
+use mqttea_core::v5::client::network::websocket;
+
+#[cfg(feature = "tls")]
+use crate::tls::{self, TlsReloader};
+use crate::{
+    broker::Shared,
+    connection::{self, Peer},
+};
+
+/// What the connections to a listener go through before MQTT packets flow, from the connection up
+#[derive(Debug, Clone, Default)]
+pub(crate) struct Transport {
+    /// Set when every connection is made over TLS
+    #[cfg(feature = "tls")]
+    pub(crate) tls: Option<TlsReloader>,
+    /// Whether packets are carried in WebSocket frames, over TLS when it is set
+    #[cfg(feature = "websocket")]
+    pub(crate) websocket: bool,
+}
+
+impl Transport {
+    /// Serves a client over `stream` once the handshakes of every layer are done.
+    /// The handshakes and the CONNECT all have to be through within the connect timeout, counted from here
+    pub(crate) async fn serve<S>(self, stream: S, shared: Arc<Shared>)
+    where
+        S: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+    {
+        let deadline = Instant::now() + shared.config.connect_timeout;
+
+        #[cfg(feature = "tls")]
+        if let Some(reloader) = &self.tls {
+            let Some((stream, peer)) = tls::accept(reloader.acceptor(), stream, deadline).await
+            else {
+                return;
+            };
+            return self.serve_mqtt(stream, peer, shared, deadline).await;
+        }
+
+        self.serve_mqtt(stream, Peer::default(), shared, deadline)
+            .await
+    }
+
+    async fn serve_mqtt<S>(&self, stream: S, peer: Peer, shared: Arc<Shared>, deadline: Instant)
+    where
+        S: AsyncRead + AsyncWrite + Send + Unpin + 'static,
+    {
+        #[cfg(feature = "websocket")]
+        if self.websocket {
+            let handshake = websocket::accept(stream.compat());
+            let Ok(Ok(stream)) = time::timeout_at(deadline, handshake).await else {
+                return;
+            };
+            let (reader, writer) = tokio::io::split(stream.compat());
+            return connection::serve(reader, writer, shared, peer, deadline).await;
+        }
+
+        let (reader, writer) = tokio::io::split(stream);
+        connection::serve(reader, writer, shared, peer, deadline).await;
+    }
+}
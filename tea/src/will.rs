@@ -0,0 +1,115 @@
+use std::{sync::Arc, time::Duration};
+
+use mqttea_core::v5::packet::{
+    connect::will::Will,
+    publish::{Publish, PublishProperties},
+};
+use tokio::time;
+
+use crate::{
+    broker::Shared,
+    connection::route,
+    session::{Detached, Session},
+};
+
+/// 3.1.3.2.2 How long the broker waits before publishing the will of a connection that is gone.
+/// The will is published right away once its session ended, and never if the client came back in the meantime
+pub(crate) fn delay(will: &Will, detached: &Detached) -> Duration {
+    let delay = Duration::from_secs(will.properties.delay_interval.unwrap_or(0) as u64);
+    match detached {
+        // MQTT-3.1.3-9 or when the session ends, whichever happens first
+        Detached::Ended => Duration::ZERO,
+        Detached::Expires(Some(expiry)) => delay.min(*expiry),
+        Detached::Expires(None) | Detached::TakenOver => delay,
+    }
+}
+
+/// Publishes the will `connection` left behind after `delay`,
+/// unless a new connection to its session was made before then (MQTT-3.1.3-9)
+pub(crate) async fn publish(
+    shared: Arc<Shared>,
+    session: Arc<Session>,
+    connection: u64,
+    client_id: String,
+    will: Will,
+    delay: Duration,
+) {
+    time::sleep(delay).await;
+
+    if session.state().connection() == connection {
+        route(&shared, &publish_of(will), &client_id);
+    }
+}
+
+/// The PUBLISH a will is sent as (3.1.3.2)
+pub(crate) fn publish_of(will: Will) -> Publish {
+    let properties = will.properties;
+
+    Publish {
+        dup: false,
+        retain: will.retain,
+        qos: will.qos,
+        topic: will.topic,
+        pkid: None,
+        payload: will.payload,
+        properties: PublishProperties {
+            payload_format_indicator: properties.payload_format_indicator,
+            message_expiry_internal: properties.message_expiry_interval,
+            content_type: properties.content_type,
+            response_topic: properties.response_topic,
+            correlation_data: properties.correlation_data,
+            user_property: properties.user_property,
+            ..Default::default()
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use mqttea_core::v5::{commons::qos::QoS, packet::connect::will::WillProperties};
+
+    use super::*;
+
+    fn will(delay_interval: Option<u32>) -> Will {
+        Will {
+            properties: WillProperties {
+                delay_interval,
+                message_expiry_interval: Some(30),
+                ..Default::default()
+            },
+            topic: String::from("clients/sensor/status"),
+            payload: "offline".into(),
+            qos: QoS::One,
+            retain: true,
+        }
+    }
+
+    #[test]
+    fn waits_for_the_delay_or_the_end_of_the_session() {
+        let secs = Duration::from_secs;
+
+        assert_eq!(delay(&will(None), &Detached::Expires(None)), Duration::ZERO);
+        assert_eq!(delay(&will(Some(10)), &Detached::Expires(None)), secs(10));
+        assert_eq!(delay(&will(Some(10)), &Detached::TakenOver), secs(10));
+        assert_eq!(
+            delay(&will(Some(10)), &Detached::Expires(Some(secs(5)))),
+            secs(5)
+        );
+        assert_eq!(
+            delay(&will(Some(10)), &Detached::Expires(Some(secs(60)))),
+            secs(10)
+        );
+        assert_eq!(delay(&will(Some(10)), &Detached::Ended), Duration::ZERO);
+    }
+
+    #[test]
+    fn keeps_the_flags_and_properties_of_the_will() {
+        let publish = publish_of(will(Some(10)));
+
+        assert!(publish.retain);
+        assert_eq!(publish.qos, QoS::One);
+        assert_eq!(publish.topic, "clients/sensor/status");
+        assert_eq!(&publish.payload[..], b"offline");
+        assert_eq!(publish.properties.message_expiry_internal, Some(30));
+    }
+}
@@ -0,0 +1,139 @@
+//! Authentication of the clients let in, and authorization of the topics they use
+
+mod common;
+
+use std::{net::SocketAddr, sync::Arc};
+
+use common::{broker, connect, hash, login, with_will, Client, Raw};
+use mqttea::{AclFile, Config, PasswordFile};
+use mqttea_core::v5::{
+    commons::{packet::Packet, qos::QoS},
+    packet::{
+        connack::ConnAckReasonCode,
+        puback::PubAckReasonCode,
+        publish::Publish,
+        pubrec::PubRecReasonCode,
+        suback::SubAckReasonCode,
+        subscribe::{Subscribe, SubscriptionOptions},
+    },
+};
+
+/// A broker where each client may only use the topics under `clients/<its Client Identifier>`
+async fn restricted() -> SocketAddr {
+    let config = Config {
+        authorizer: Some(Arc::new(
+            AclFile::parse("pattern readwrite clients/%c/#").unwrap(),
+        )),
+        ..Default::default()
+    };
+    broker(config).await
+}
+
+#[tokio::test]
+async fn refuses_clients_with_the_wrong_password() {
+    let passwords = format!("alice:{}", hash("wonderland"));
+    let config = Config {
+        authenticator: Some(Arc::new(PasswordFile::parse(&passwords).unwrap())),
+        ..Default::default()
+    };
+    let addr = broker(config).await;
+
+    let (_, connack) = Raw::connect(addr, login("a", "alice", "builder")).await;
+    assert_eq!(connack.reason, ConnAckReasonCode::BadUserNameOrPassword);
+    let (_, connack) = Raw::connect(addr, connect("a")).await;
+    assert_eq!(connack.reason, ConnAckReasonCode::BadUserNameOrPassword);
+
+    let (_, connack) = Raw::connect(addr, login("a", "alice", "wonderland")).await;
+    assert_eq!(connack.reason, ConnAckReasonCode::Success);
+}
+
+#[tokio::test]
+async fn refuses_enhanced_authentication_without_an_authenticator() {
+    let addr = broker(Config::default()).await;
+    let mut connect = connect("a");
+    connect.properties.authentication_method = Some(String::from("SCRAM-SHA-256"));
+
+    let (_, connack) = Raw::connect(addr, connect).await;
+    assert_eq!(connack.reason, ConnAckReasonCode::BadAuthenticationMethod);
+}
+
+#[tokio::test]
+async fn acknowledges_unauthorized_publishes_with_not_authorized() {
+    let addr = restricted().await;
+    let mut subscriber = Client::connect(addr, "subscriber").await;
+    subscriber.subscribe("clients/subscriber/#", QoS::Two).await;
+    let (mut raw, _) = Raw::connect(addr, connect("sensor")).await;
+
+    let publish = |topic: &str, qos| Publish {
+        qos,
+        topic: topic.to_string(),
+        pkid: (qos != QoS::Zero).then_some(1),
+        payload: "21.5".into(),
+        ..Default::default()
+    };
+    raw.send(Packet::Publish(publish(
+        "clients/subscriber/temperature",
+        QoS::One,
+    )))
+    .await;
+    let Some(Packet::PubAck(puback)) = raw.recv().await else {
+        panic!("expected a PUBACK");
+    };
+    assert_eq!(puback.reason_code, PubAckReasonCode::NotAuthorized);
+
+    raw.send(Packet::Publish(publish(
+        "clients/subscriber/temperature",
+        QoS::Two,
+    )))
+    .await;
+    let Some(Packet::PubRec(pubrec)) = raw.recv().await else {
+        panic!("expected a PUBREC");
+    };
+    assert_eq!(pubrec.reason_code, PubRecReasonCode::NotAuthorized);
+
+    raw.send(Packet::Publish(publish(
+        "clients/subscriber/temperature",
+        QoS::Zero,
+    )))
+    .await;
+    subscriber.assert_silent().await;
+}
+
+#[tokio::test]
+async fn acknowledges_unauthorized_subscriptions_with_not_authorized() {
+    let addr = restricted().await;
+    let (mut raw, _) = Raw::connect(addr, connect("sensor")).await;
+
+    raw.send(Packet::Subscribe(Subscribe {
+        pkid: 1,
+        payload: ["clients/sensor/#", "clients/+/status", "#"]
+            .map(|filter| (filter.to_string(), SubscriptionOptions::default()))
+            .to_vec(),
+        ..Default::default()
+    }))
+    .await;
+    let Some(Packet::SubAck(suback)) = raw.recv().await else {
+        panic!("expected a SUBACK");
+    };
+    assert_eq!(
+        suback.payload,
+        [
+            SubAckReasonCode::GrantedQoS0,
+            SubAckReasonCode::NotAuthorized,
+            SubAckReasonCode::NotAuthorized
+        ]
+    );
+}
+
+#[tokio::test]
+async fn refuses_wills_the_client_may_not_publish() {
+    let addr = restricted().await;
+    let mut will = with_will(connect("sensor"), None, false);
+    let (_, connack) = Raw::connect(addr, will).await;
+    assert_eq!(connack.reason, ConnAckReasonCode::NotAuthorized);
+
+    will = with_will(connect("sensor"), None, false);
+    will.will.as_mut().unwrap().topic = String::from("clients/sensor/status");
+    let (_, connack) = Raw::connect(addr, will).await;
+    assert_eq!(connack.reason, ConnAckReasonCode::Success);
+}
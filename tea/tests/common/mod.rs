@@ -0,0 +1,317 @@
+//! Brokers and connections shared by the end-to-end tests, each test file using its own part of them
+#![allow(dead_code)]
+
+#[cfg(unix)]
+use std::path::Path;
+use std::{net::SocketAddr, time::Duration};
+
+use argon2::{
+    password_hash::{PasswordHasher, SaltString},
+    Algorithm, Argon2, Params, Version,
+};
+use mqttea::{Broker, Config, DuplexConnector};
+#[cfg(feature = "tls")]
+use mqttea_core::v5::client::network::tls::TlsConnector;
+use mqttea_core::v5::{
+    client::{
+        handler::AsyncHandler, network::asyncx::Network, network::outgoing::OutgoingBuffer,
+        packet_id::PacketIdManager, ConnectOptions, MqttClient,
+    },
+    commons::{packet::Packet, qos::QoS},
+    packet::{
+        connack::ConnAck,
+        connect::{
+            will::{Will, WillProperties},
+            Connect,
+        },
+        disconnect::Disconnect,
+        publish::Publish,
+        subscribe::{Subscribe, SubscriptionOptions},
+    },
+};
+use tokio::{net::TcpStream, sync::mpsc, time::timeout};
+use tokio_util::compat::{Compat, TokioAsyncReadCompatExt};
+
+#[cfg(feature = "tls")]
+pub mod tls;
+
+/// Long enough for anything sent on localhost to have arrived
+pub const WAIT: Duration = Duration::from_millis(200);
+
+pub async fn broker(config: Config) -> SocketAddr {
+    let broker = Broker::bind("127.0.0.1:0", config).await.unwrap();
+    let addr = broker.local_addr().unwrap();
+    tokio::spawn(broker.run());
+    addr
+}
+
+/// Hands every packet the client receives over to the test
+pub struct Forward(mpsc::UnboundedSender<Packet>);
+
+impl AsyncHandler for Forward {
+    async fn handle(&mut self, packet: Packet) {
+        let _ = self.0.send(packet);
+    }
+}
+
+/// A connection through the mqttea client, which acknowledges what it receives on its own
+pub struct Client {
+    pub client: MqttClient<PacketIdManager>,
+    packets: mpsc::UnboundedReceiver<Packet>,
+}
+
+impl Client {
+    pub async fn connect(addr: SocketAddr, client_id: &str) -> Self {
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let options = ConnectOptions {
+            client_id: client_id.to_string(),
+            ..Default::default()
+        };
+        let (network, client) = Network::new(options, stream.compat()).await.unwrap();
+        Self::start(network, client)
+    }
+
+    /// Connects over a TLS session made with `connector`
+    #[cfg(feature = "tls")]
+    pub async fn connect_tls(addr: SocketAddr, client_id: &str, connector: &TlsConnector) -> Self {
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let options = ConnectOptions {
+            client_id: client_id.to_string(),
+            ..Default::default()
+        };
+        let (network, client) = Network::with_tls(options, stream.compat(), connector)
+            .await
+            .unwrap();
+        Self::start(network, client)
+    }
+
+    /// Connects through a WebSocket to `/mqtt`
+    #[cfg(feature = "websocket")]
+    pub async fn connect_websocket(addr: SocketAddr, client_id: &str) -> Self {
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let options = ConnectOptions {
+            client_id: client_id.to_string(),
+            ..Default::default()
+        };
+        let url = format!("ws://{addr}/mqtt");
+        let (network, client) = Network::with_websocket(options, &url, stream.compat())
+            .await
+            .unwrap();
+        Self::start(network, client)
+    }
+
+    /// Connects over the Unix domain socket at `path`
+    #[cfg(unix)]
+    pub async fn connect_unix(path: &Path, client_id: &str) -> Self {
+        let options = ConnectOptions {
+            client_id: client_id.to_string(),
+            ..Default::default()
+        };
+        let (network, client) = Network::connect_unix(options, path).await.unwrap();
+        Self::start(network, client)
+    }
+
+    /// Connects to an in-memory broker through `connector`
+    pub async fn connect_in_memory(connector: &DuplexConnector, client_id: &str) -> Self {
+        let options = ConnectOptions {
+            client_id: client_id.to_string(),
+            ..Default::default()
+        };
+        let stream = connector.connect().unwrap();
+        let (network, client) = Network::new(options, stream).await.unwrap();
+        Self::start(network, client)
+    }
+
+    pub fn start<S>(mut network: Network<S>, client: MqttClient<PacketIdManager>) -> Self
+    where
+        S: futures::AsyncRead + futures::AsyncWrite + Send + Unpin + 'static,
+    {
+        let (tx, packets) = mpsc::unbounded_channel();
+        tokio::spawn(async move { network.run(&mut Forward(tx)).await });
+
+        Self { client, packets }
+    }
+
+    pub async fn recv(&mut self) -> Packet {
+        timeout(WAIT * 10, self.packets.recv())
+            .await
+            .expect("no packet received")
+            .expect("connection closed")
+    }
+
+    pub async fn publish(&mut self) -> Publish {
+        match self.recv().await {
+            Packet::Publish(publish) => publish,
+            packet => panic!("expected a PUBLISH, got {packet:?}"),
+        }
+    }
+
+    pub async fn subscribe(&mut self, filter: &str, qos: QoS) {
+        let options = SubscriptionOptions {
+            qos,
+            ..Default::default()
+        };
+        self.subscribe_with(filter, options).await
+    }
+
+    pub async fn subscribe_with(&mut self, filter: &str, options: SubscriptionOptions) {
+        self.client
+            .subscribe(vec![(filter.to_string(), options)], None)
+            .await
+            .unwrap();
+        assert!(matches!(self.recv().await, Packet::SubAck(_)));
+    }
+
+    /// Publishes a retained message, waiting for the broker to have taken it
+    pub async fn retain(&mut self, topic: &str, payload: &'static str) {
+        self.client
+            .publish(topic, QoS::One, true, payload, None)
+            .await
+            .unwrap();
+        assert!(matches!(self.recv().await, Packet::PubAck(_)));
+    }
+
+    pub async fn assert_silent(&mut self) {
+        tokio::time::sleep(WAIT).await;
+        assert_eq!(self.packets.try_recv().ok(), None);
+    }
+}
+
+/// A connection driven packet by packet, for what the client never does on its own
+pub struct Raw<S = Compat<TcpStream>> {
+    pub stream: S,
+    pub buffer: OutgoingBuffer,
+}
+
+impl Raw {
+    pub async fn connect(addr: SocketAddr, connect: Connect) -> (Self, ConnAck) {
+        let stream = TcpStream::connect(addr).await.unwrap();
+        Raw::connect_over(stream.compat(), connect).await
+    }
+}
+
+impl<S> Raw<S>
+where
+    S: futures::AsyncRead + futures::AsyncWrite + Unpin,
+{
+    /// Sends the CONNECT over a connection the transport already set up
+    pub async fn connect_over(stream: S, connect: Connect) -> (Self, ConnAck) {
+        let mut raw = Self {
+            stream,
+            buffer: OutgoingBuffer::with_capacity(1024),
+        };
+
+        raw.send(Packet::Connect(connect)).await;
+        match raw.recv().await {
+            Some(Packet::ConnAck(connack)) => (raw, connack),
+            packet => panic!("expected a CONNACK, got {packet:?}"),
+        }
+    }
+
+    pub async fn send(&mut self, packet: Packet) {
+        self.buffer.push(&packet).unwrap();
+        self.buffer.flush(&mut self.stream).await.unwrap();
+    }
+
+    pub async fn subscribe(&mut self, filter: &str, qos: QoS) {
+        self.send(Packet::Subscribe(Subscribe {
+            pkid: 1,
+            payload: vec![(
+                filter.to_string(),
+                SubscriptionOptions {
+                    qos,
+                    ..Default::default()
+                },
+            )],
+            ..Default::default()
+        }))
+        .await;
+        assert!(matches!(self.recv().await, Some(Packet::SubAck(_))));
+    }
+
+    pub async fn publish(&mut self) -> Publish {
+        match self.recv().await {
+            Some(Packet::Publish(publish)) => publish,
+            packet => panic!("expected a PUBLISH, got {packet:?}"),
+        }
+    }
+
+    /// Sends a DISCONNECT and waits for the broker to have closed the connection
+    pub async fn disconnect(mut self, disconnect: Disconnect) {
+        self.send(Packet::Disconnect(disconnect)).await;
+        assert_eq!(self.recv().await, None);
+    }
+
+    /// The next packet, `None` once the broker closed the connection
+    pub async fn recv(&mut self) -> Option<Packet> {
+        timeout(WAIT * 10, Packet::read_async(&mut self.stream, usize::MAX))
+            .await
+            .expect("no packet received")
+            .ok()
+    }
+}
+
+pub fn connect(client_id: &str) -> Connect {
+    Connect::from(&ConnectOptions {
+        client_id: client_id.to_string(),
+        keep_alive: 0,
+        ..Default::default()
+    })
+}
+
+/// A CONNECT resuming the client's session, which outlives the connection by `expiry_interval` seconds
+pub fn resume(client_id: &str, expiry_interval: u32) -> Connect {
+    let mut connect = connect(client_id);
+    connect.clean_start = false;
+    connect.properties.session_expiry_interval = Some(expiry_interval);
+    connect
+}
+
+/// A CONNECT with a will announcing the client went offline, published `delay_interval` seconds after the connection is gone
+pub fn with_will(mut connect: Connect, delay_interval: Option<u32>, retain: bool) -> Connect {
+    connect.will = Some(Will {
+        properties: WillProperties {
+            delay_interval,
+            ..Default::default()
+        },
+        topic: format!("status/{}", connect.client_id),
+        payload: "offline".into(),
+        qos: QoS::One,
+        retain,
+    });
+    connect
+}
+
+/// Publishes a message, waiting for the broker to acknowledge it when it is sent at QoS 1
+pub async fn publish(client: &mut Client, topic: &str, qos: QoS, payload: &'static str) {
+    client
+        .client
+        .publish(topic, qos, false, payload, None)
+        .await
+        .unwrap();
+    if qos == QoS::One {
+        assert!(matches!(client.recv().await, Packet::PubAck(_)));
+    }
+}
+
+/// A CONNECT with a username and password
+pub fn login(client_id: &str, username: &str, password: &str) -> Connect {
+    let mut connect = connect(client_id);
+    connect.username = Some(username.to_string());
+    connect.password = Some(password.to_string());
+    connect
+}
+
+/// A password hash cheap enough to compute in tests, for a password file
+pub fn hash(password: &str) -> String {
+    let argon2 = Argon2::new(
+        Algorithm::Argon2id,
+        Version::V0x13,
+        Params::new(8, 1, 1, None).unwrap(),
+    );
+    let salt = SaltString::from_b64("c2FsdHNhbHRzYWx0").unwrap();
+    argon2
+        .hash_password(password.as_bytes(), &salt)
+        .unwrap()
+        .to_string()
+}
@@ -0,0 +1,135 @@
+//! A certificate authority and the TLS brokers and connections made with its certificates
+
+use std::{net::SocketAddr, path::PathBuf};
+
+use mqttea::{Broker, ClientIdentity, Config, TlsConfig};
+use mqttea_core::v5::{
+    client::network::tls::{TlsConnector, TlsOptions, TlsStream},
+    packet::{connack::ConnAck, connect::Connect},
+};
+use tokio::net::TcpStream;
+use tokio_util::compat::{Compat, TokioAsyncReadCompatExt};
+
+use super::Raw;
+
+/// A certificate authority, signing the certificates the broker and its clients present
+pub struct Authority {
+    certificate: rcgen::Certificate,
+    key: rcgen::KeyPair,
+}
+
+impl Authority {
+    pub fn new() -> Self {
+        let key = rcgen::KeyPair::generate().unwrap();
+        let mut params = rcgen::CertificateParams::new(Vec::new()).unwrap();
+        params.is_ca = rcgen::IsCa::Ca(rcgen::BasicConstraints::Unconstrained);
+        let certificate = params.self_signed(&key).unwrap();
+        Self { certificate, key }
+    }
+
+    pub fn pem(&self) -> String {
+        self.certificate.pem()
+    }
+
+    /// A certificate made from `params` signed by the authority, along with its private key
+    pub fn sign(&self, params: rcgen::CertificateParams) -> (String, String) {
+        let key = rcgen::KeyPair::generate().unwrap();
+        let certificate = params
+            .signed_by(&key, &self.certificate, &self.key)
+            .unwrap();
+        (certificate.pem(), key.serialize_pem())
+    }
+
+    /// What the broker presents, a certificate for `localhost`
+    pub fn broker(&self) -> TlsConfig {
+        let params = rcgen::CertificateParams::new(vec![String::from("localhost")]).unwrap();
+        let (certificate, key) = self.sign(params);
+        TlsConfig {
+            certificate_chain: certificate.into_bytes(),
+            private_key: key.into_bytes(),
+            client_ca: None,
+            client_identity: ClientIdentity::default(),
+        }
+    }
+
+    /// What a client presents, a certificate for `common_name`, and `uri` among its Subject Alternative Names
+    pub fn client(&self, common_name: &str, uri: Option<&str>) -> (String, String) {
+        let mut params = rcgen::CertificateParams::new(Vec::new()).unwrap();
+        params.distinguished_name = rcgen::DistinguishedName::new();
+        params
+            .distinguished_name
+            .push(rcgen::DnType::CommonName, common_name);
+        params.extended_key_usages = vec![rcgen::ExtendedKeyUsagePurpose::ClientAuth];
+        if let Some(uri) = uri {
+            params.subject_alt_names = vec![rcgen::SanType::URI(uri.try_into().unwrap())];
+        }
+        self.sign(params)
+    }
+}
+
+pub fn connector(ca: &Authority, client: Option<(String, String)>) -> TlsConnector {
+    let (client_cert, client_key) = client
+        .map(|(cert, key)| (cert.into_bytes(), key.into_bytes()))
+        .unzip();
+    TlsConnector::new(&TlsOptions {
+        host: String::from("localhost"),
+        ca: ca.pem().into_bytes(),
+        client_cert,
+        client_key,
+    })
+    .unwrap()
+}
+
+pub async fn tls_broker(config: Config, tls: TlsConfig) -> SocketAddr {
+    let broker = Broker::bind_tls("127.0.0.1:0", config, tls).await.unwrap();
+    let addr = broker.local_addr().unwrap();
+    tokio::spawn(broker.run());
+    addr
+}
+
+/// A broker where every client has to present a certificate `ca` signed, standing for `client_identity`
+pub async fn mtls_broker(
+    config: Config,
+    ca: &Authority,
+    client_identity: ClientIdentity,
+) -> SocketAddr {
+    let tls = TlsConfig {
+        client_ca: Some(ca.pem().into_bytes()),
+        client_identity,
+        ..ca.broker()
+    };
+    tls_broker(config, tls).await
+}
+
+/// A directory of its own under the system's temporary one, removed once dropped
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("mqttea-{}-{name}", std::process::id()));
+        std::fs::create_dir_all(&path).unwrap();
+        Self(path)
+    }
+
+    pub fn write(&self, file: &str, contents: impl AsRef<[u8]>) -> PathBuf {
+        let path = self.0.join(file);
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+pub async fn connect_mtls(
+    addr: SocketAddr,
+    connector: &TlsConnector,
+    connect: Connect,
+) -> (Raw<TlsStream<Compat<TcpStream>>>, ConnAck) {
+    let stream = TcpStream::connect(addr).await.unwrap();
+    let stream = connector.connect(stream.compat()).await.unwrap();
+    Raw::connect_over(stream, connect).await
+}
@@ -0,0 +1,114 @@
+//! Connections: how they start, what they may send and how they end
+
+mod common;
+
+use std::num::NonZero;
+
+use common::{broker, connect, Client, Raw};
+use mqttea::Config;
+use mqttea_core::v5::{
+    client::network::outgoing::OutgoingBuffer,
+    commons::{packet::Packet, qos::QoS},
+    packet::{
+        connack::ConnAckReasonCode,
+        disconnect::DisconnectReasonCode,
+        publish::Publish,
+        subscribe::{Subscribe, SubscriptionOptions},
+    },
+};
+use tokio::net::TcpStream;
+use tokio_util::compat::TokioAsyncReadCompatExt;
+
+#[tokio::test]
+async fn a_new_connection_takes_the_session_over() {
+    let addr = broker(Config::default()).await;
+    let mut first = Client::connect(addr, "sensor").await;
+    first.subscribe("commands", QoS::Zero).await;
+
+    let mut second = Client::connect(addr, "sensor").await;
+    let Packet::Disconnect(disconnect) = first.recv().await else {
+        panic!("expected a DISCONNECT");
+    };
+    assert_eq!(
+        disconnect.reason_code,
+        DisconnectReasonCode::SessionTakenOver
+    );
+
+    // the subscriptions went with the session
+    let publisher = Client::connect(addr, "publisher").await;
+    publisher
+        .client
+        .publish("commands", QoS::Zero, false, "reboot", None)
+        .await
+        .unwrap();
+    second.assert_silent().await;
+}
+
+#[tokio::test]
+async fn assigns_a_client_identifier() {
+    let addr = broker(Config::default()).await;
+    let (_raw, connack) = Raw::connect(addr, connect("")).await;
+
+    assert_eq!(connack.reason, ConnAckReasonCode::Success);
+    assert!(!connack.session_present);
+    assert!(connack
+        .properties
+        .assigned_client_id
+        .is_some_and(|id| !id.is_empty()));
+}
+
+#[tokio::test]
+async fn closes_connections_that_do_not_start_with_connect() {
+    let addr = broker(Config::default()).await;
+    let stream = TcpStream::connect(addr).await.unwrap();
+    let mut raw = Raw {
+        stream: stream.compat(),
+        buffer: OutgoingBuffer::with_capacity(1024),
+    };
+
+    raw.send(Packet::Subscribe(Subscribe {
+        pkid: 1,
+        payload: vec![(String::from("#"), SubscriptionOptions::default())],
+        ..Default::default()
+    }))
+    .await;
+    assert_eq!(raw.recv().await, None);
+}
+
+#[tokio::test]
+async fn refuses_packets_over_the_maximum_size() {
+    let config = Config {
+        maximum_packet_size: NonZero::new(64).unwrap(),
+        ..Default::default()
+    };
+    let addr = broker(config).await;
+    let (mut raw, connack) = Raw::connect(addr, connect("publisher")).await;
+    assert_eq!(connack.properties.maximum_packet_size, Some(64));
+
+    raw.send(Packet::Publish(Publish {
+        topic: String::from("big"),
+        payload: vec![0; 64].into(),
+        ..Default::default()
+    }))
+    .await;
+    let Some(Packet::Disconnect(disconnect)) = raw.recv().await else {
+        panic!("expected a DISCONNECT");
+    };
+    assert_eq!(disconnect.reason_code, DisconnectReasonCode::PacketTooLarge);
+}
+
+#[tokio::test]
+async fn disconnects_clients_that_exceed_their_keep_alive() {
+    let addr = broker(Config::default()).await;
+    let mut connect = connect("sleepy");
+    connect.keep_alive = 1;
+    let (mut raw, _) = Raw::connect(addr, connect).await;
+
+    let Some(Packet::Disconnect(disconnect)) = raw.recv().await else {
+        panic!("expected a DISCONNECT");
+    };
+    assert_eq!(
+        disconnect.reason_code,
+        DisconnectReasonCode::KeepAliveTimeout
+    );
+}
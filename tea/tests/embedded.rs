@@ -0,0 +1,81 @@
+//! The no_std client of mqttea-core, talking to the broker
+
+mod common;
+
+use common::{broker, Client, WAIT};
+use mqttea::Config;
+use mqttea_core::v5::{
+    borrowed,
+    commons::qos::QoS,
+    embedded,
+    packet::{connack::ConnAckReasonCode, subscribe::SubscriptionOptions},
+};
+use tokio::{net::TcpStream, time::timeout};
+use tokio_util::compat::TokioAsyncReadCompatExt;
+
+#[tokio::test]
+async fn serves_embedded_clients() {
+    let addr = broker(Config::default()).await;
+    let stream = TcpStream::connect(addr).await.unwrap();
+    let (mut incoming, mut outgoing) = ([0u8; 256], [0u8; 256]);
+    let mut node = embedded::Client::new(
+        embedded::FromFutures::new(stream.compat()),
+        &mut incoming,
+        &mut outgoing,
+    );
+
+    let receive_maximum = [borrowed::Property::ReceiveMaximum(
+        embedded::IN_FLIGHT as u16,
+    )];
+    let connect = borrowed::Connect {
+        client_id: "node",
+        keep_alive: 30,
+        properties: borrowed::List::new(&receive_maximum),
+        ..Default::default()
+    };
+    let connack = node.connect(&connect).await.unwrap();
+    assert_eq!(connack.reason, ConnAckReasonCode::Success);
+
+    let options = SubscriptionOptions {
+        qos: QoS::One,
+        ..Default::default()
+    };
+    let pkid = node.subscribe(&[("lights/#", options)]).await.unwrap();
+    match timeout(WAIT * 10, node.poll()).await.unwrap().unwrap() {
+        borrowed::Packet::SubAck(suback) => assert_eq!(suback.pkid, pkid),
+        packet => panic!("expected a SUBACK, got {packet:?}"),
+    }
+
+    let publisher = Client::connect(addr, "publisher").await;
+    publisher
+        .client
+        .publish("lights/kitchen", QoS::One, false, "on", None)
+        .await
+        .unwrap();
+    match timeout(WAIT * 10, node.poll()).await.unwrap().unwrap() {
+        borrowed::Packet::Publish(publish) => {
+            assert_eq!(publish.topic, "lights/kitchen");
+            assert_eq!(publish.payload, b"on");
+        }
+        packet => panic!("expected a PUBLISH, got {packet:?}"),
+    }
+
+    // the node releases its own QoS 2 message once the broker has received it
+    let publish = borrowed::Publish {
+        qos: QoS::Two,
+        topic: "doors/hall",
+        payload: b"locked",
+        ..Default::default()
+    };
+    let pkid = node.publish(publish).await.unwrap();
+    match timeout(WAIT * 10, node.poll()).await.unwrap().unwrap() {
+        borrowed::Packet::PubRec(pubrec) => assert_eq!(Some(pubrec.pkid), pkid),
+        packet => panic!("expected a PUBREC, got {packet:?}"),
+    }
+    match timeout(WAIT * 10, node.poll()).await.unwrap().unwrap() {
+        borrowed::Packet::PubComp(pubcomp) => assert_eq!(Some(pubcomp.pkid), pkid),
+        packet => panic!("expected a PUBCOMP, got {packet:?}"),
+    }
+
+    node.disconnect().await.unwrap();
+}
@@ -0,0 +1,119 @@
+//! Retained messages, replayed to the subscriptions made after them
+
+mod common;
+
+use common::{broker, connect, Client, Raw};
+use mqttea::Config;
+use mqttea_core::v5::{
+    commons::{packet::Packet, qos::QoS},
+    packet::{
+        disconnect::DisconnectReasonCode,
+        publish::Publish,
+        subscribe::{RetainHandling, SubscriptionOptions},
+    },
+};
+
+#[tokio::test]
+async fn replays_retained_messages_on_subscribe() {
+    let addr = broker(Config::default()).await;
+    let mut publisher = Client::connect(addr, "publisher").await;
+    publisher.retain("rooms/kitchen/temperature", "21.5").await;
+    publisher.retain("rooms/garage/temperature", "12.0").await;
+    // an empty payload clears what the topic retained
+    publisher.retain("rooms/garage/temperature", "").await;
+
+    let mut subscriber = Client::connect(addr, "subscriber").await;
+    subscriber.subscribe("rooms/+/temperature", QoS::Two).await;
+    let publish = subscriber.publish().await;
+    assert!(publish.retain);
+    // delivered at the QoS it was published with
+    assert_eq!(publish.qos, QoS::One);
+    assert_eq!(publish.topic, "rooms/kitchen/temperature");
+    assert_eq!(&publish.payload[..], b"21.5");
+    subscriber.assert_silent().await;
+
+    // the flag is cleared on messages forwarded to existing subscriptions
+    publisher.retain("rooms/kitchen/temperature", "22.0").await;
+    let publish = subscriber.publish().await;
+    assert!(!publish.retain);
+    assert_eq!(&publish.payload[..], b"22.0");
+}
+
+#[tokio::test]
+async fn retain_handling_decides_when_retained_messages_are_sent() {
+    let addr = broker(Config::default()).await;
+    let mut publisher = Client::connect(addr, "publisher").await;
+    publisher.retain("status", "online").await;
+
+    let mut subscriber = Client::connect(addr, "subscriber").await;
+    let options = |retain_handling| SubscriptionOptions {
+        qos: QoS::Zero,
+        retain_handling,
+        ..Default::default()
+    };
+
+    subscriber
+        .subscribe_with("status", options(RetainHandling::One))
+        .await;
+    assert_eq!(&subscriber.publish().await.payload[..], b"online");
+    // the subscription already exists
+    subscriber
+        .subscribe_with("status", options(RetainHandling::One))
+        .await;
+    subscriber.assert_silent().await;
+
+    subscriber
+        .subscribe_with("+", options(RetainHandling::Two))
+        .await;
+    subscriber.assert_silent().await;
+
+    subscriber
+        .subscribe_with("#", options(RetainHandling::Zero))
+        .await;
+    assert_eq!(&subscriber.publish().await.payload[..], b"online");
+}
+
+#[tokio::test]
+async fn retain_as_published_keeps_the_flag() {
+    let addr = broker(Config::default()).await;
+    let mut subscriber = Client::connect(addr, "subscriber").await;
+    subscriber
+        .subscribe_with(
+            "status",
+            SubscriptionOptions {
+                retain_as_published: true,
+                ..Default::default()
+            },
+        )
+        .await;
+
+    let mut publisher = Client::connect(addr, "publisher").await;
+    publisher.retain("status", "online").await;
+    assert!(subscriber.publish().await.retain);
+}
+
+#[tokio::test]
+async fn refuses_retained_messages_when_retain_is_unavailable() {
+    let config = Config {
+        retain_available: false,
+        ..Default::default()
+    };
+    let addr = broker(config).await;
+    let (mut raw, connack) = Raw::connect(addr, connect("publisher")).await;
+    assert_eq!(connack.properties.retain_available, Some(false));
+
+    raw.send(Packet::Publish(Publish {
+        retain: true,
+        topic: String::from("status"),
+        payload: "online".into(),
+        ..Default::default()
+    }))
+    .await;
+    let Some(Packet::Disconnect(disconnect)) = raw.recv().await else {
+        panic!("expected a DISCONNECT");
+    };
+    assert_eq!(
+        disconnect.reason_code,
+        DisconnectReasonCode::RetainNotSupported
+    );
+}
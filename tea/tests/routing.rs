@@ -0,0 +1,174 @@
+//! Routing PUBLISH packets to the subscriptions matching their topic
+
+mod common;
+
+use common::{broker, connect, Client, Raw, WAIT};
+use mqttea::Config;
+use mqttea_core::v5::{
+    commons::{packet::Packet, qos::QoS},
+    packet::{
+        puback::{PubAck, PubAckReasonCode},
+        subscribe::{Subscribe, SubscriptionOptions},
+        unsuback::UnSubAckReasonCode,
+    },
+};
+use tokio::time::timeout;
+
+#[tokio::test]
+async fn delivers_at_the_qos_granted() {
+    let addr = broker(Config::default()).await;
+    let mut subscribers = Vec::new();
+    for qos in [QoS::Zero, QoS::One, QoS::Two] {
+        let mut subscriber = Client::connect(addr, &format!("subscriber-{qos:?}")).await;
+        subscriber.subscribe("factory/+/temperature", qos).await;
+        subscribers.push((qos, subscriber));
+    }
+
+    let mut publisher = Client::connect(addr, "publisher").await;
+    publisher
+        .client
+        .publish("factory/line-1/temperature", QoS::Two, false, "21.5", None)
+        .await
+        .unwrap();
+    assert!(matches!(publisher.recv().await, Packet::PubRec(_)));
+    assert!(matches!(publisher.recv().await, Packet::PubComp(_)));
+
+    for (qos, subscriber) in &mut subscribers {
+        let publish = subscriber.publish().await;
+        assert_eq!(publish.qos, *qos);
+        assert_eq!(publish.topic, "factory/line-1/temperature");
+        assert_eq!(&publish.payload[..], b"21.5");
+    }
+
+    // the QoS 2 subscriber's PUBREC is followed by the broker's PUBREL
+    let (_, subscriber) = &mut subscribers[2];
+    assert!(matches!(subscriber.recv().await, Packet::PubRel(_)));
+}
+
+#[tokio::test]
+async fn publish_is_downgraded_to_its_own_qos() {
+    let addr = broker(Config::default()).await;
+    let mut subscriber = Client::connect(addr, "subscriber").await;
+    subscriber.subscribe("alerts/#", QoS::Two).await;
+
+    let mut publisher = Client::connect(addr, "publisher").await;
+    publisher
+        .client
+        .publish("alerts/fire", QoS::One, false, "evacuate", None)
+        .await
+        .unwrap();
+
+    let Packet::PubAck(puback) = publisher.recv().await else {
+        panic!("expected a PUBACK");
+    };
+    assert_eq!(puback.reason_code, PubAckReasonCode::Success);
+    assert_eq!(subscriber.publish().await.qos, QoS::One);
+}
+
+#[tokio::test]
+async fn reports_when_nobody_is_subscribed() {
+    let addr = broker(Config::default()).await;
+    let mut publisher = Client::connect(addr, "publisher").await;
+    publisher
+        .client
+        .publish("nobody/listens", QoS::One, false, "hello", None)
+        .await
+        .unwrap();
+
+    let Packet::PubAck(puback) = publisher.recv().await else {
+        panic!("expected a PUBACK");
+    };
+    assert_eq!(puback.reason_code, PubAckReasonCode::NoMatchingSubscribers);
+}
+
+#[tokio::test]
+async fn stops_delivering_after_unsubscribe() {
+    let addr = broker(Config::default()).await;
+    let mut subscriber = Client::connect(addr, "subscriber").await;
+    subscriber.subscribe("chat/#", QoS::Zero).await;
+
+    let publisher = Client::connect(addr, "publisher").await;
+    publisher
+        .client
+        .publish("chat/general", QoS::Zero, false, "first", None)
+        .await
+        .unwrap();
+    assert_eq!(&subscriber.publish().await.payload[..], b"first");
+
+    subscriber
+        .client
+        .unsubscribe(
+            vec![String::from("chat/#"), String::from("chat/random")],
+            None,
+        )
+        .await
+        .unwrap();
+    let Packet::UnSubAck(unsuback) = subscriber.recv().await else {
+        panic!("expected an UNSUBACK");
+    };
+    assert_eq!(
+        unsuback.payload,
+        vec![
+            UnSubAckReasonCode::Success,
+            UnSubAckReasonCode::NoSubscriptionExisted
+        ]
+    );
+
+    publisher
+        .client
+        .publish("chat/general", QoS::Zero, false, "second", None)
+        .await
+        .unwrap();
+    subscriber.assert_silent().await;
+}
+
+#[tokio::test]
+async fn holds_messages_back_at_the_client_receive_maximum() {
+    let addr = broker(Config::default()).await;
+    let mut connect = connect("subscriber");
+    connect.properties.receive_maximum = Some(1);
+    let (mut subscriber, _) = Raw::connect(addr, connect).await;
+
+    subscriber
+        .send(Packet::Subscribe(Subscribe {
+            pkid: 1,
+            payload: vec![(
+                String::from("jobs"),
+                SubscriptionOptions {
+                    qos: QoS::One,
+                    ..Default::default()
+                },
+            )],
+            ..Default::default()
+        }))
+        .await;
+    assert!(matches!(subscriber.recv().await, Some(Packet::SubAck(_))));
+
+    let publisher = Client::connect(addr, "publisher").await;
+    for job in ["first", "second"] {
+        publisher
+            .client
+            .publish("jobs", QoS::One, false, job, None)
+            .await
+            .unwrap();
+    }
+
+    let Some(Packet::Publish(first)) = subscriber.recv().await else {
+        panic!("expected a PUBLISH");
+    };
+    assert_eq!(&first.payload[..], b"first");
+    // nothing more until the first one is acknowledged
+    let next = timeout(WAIT, Packet::read_async(&mut subscriber.stream, usize::MAX)).await;
+    assert!(next.is_err());
+
+    subscriber
+        .send(Packet::PubAck(PubAck {
+            pkid: first.pkid.unwrap(),
+            ..Default::default()
+        }))
+        .await;
+    let Some(Packet::Publish(second)) = subscriber.recv().await else {
+        panic!("expected a PUBLISH");
+    };
+    assert_eq!(&second.payload[..], b"second");
+}
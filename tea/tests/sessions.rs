@@ -0,0 +1,160 @@
+//! Sessions outliving their connection, and the messages queued for them meanwhile
+
+mod common;
+
+use std::time::Duration;
+
+use common::{broker, connect, publish, resume, Client, Raw, WAIT};
+use mqttea::{Config, DropPolicy};
+use mqttea_core::v5::{
+    commons::{packet::Packet, qos::QoS},
+    packet::disconnect::{Disconnect, DisconnectProperties, DisconnectReasonCode},
+};
+
+#[tokio::test]
+async fn queues_messages_while_the_client_is_offline() {
+    let addr = broker(Config::default()).await;
+    let (mut subscriber, connack) = Raw::connect(addr, resume("subscriber", 60)).await;
+    assert!(!connack.session_present);
+    subscriber.subscribe("jobs", QoS::One).await;
+    subscriber.disconnect(Disconnect::default()).await;
+
+    let mut publisher = Client::connect(addr, "publisher").await;
+    publish(&mut publisher, "jobs", QoS::One, "first").await;
+    // QoS 0 messages are not kept for offline clients
+    publish(&mut publisher, "jobs", QoS::Zero, "second").await;
+    publish(&mut publisher, "jobs", QoS::One, "third").await;
+
+    let (mut subscriber, connack) = Raw::connect(addr, resume("subscriber", 60)).await;
+    assert!(connack.session_present);
+    assert_eq!(&subscriber.publish().await.payload[..], b"first");
+    assert_eq!(&subscriber.publish().await.payload[..], b"third");
+
+    // the subscription is still there
+    publish(&mut publisher, "jobs", QoS::Zero, "fourth").await;
+    assert_eq!(&subscriber.publish().await.payload[..], b"fourth");
+}
+
+#[tokio::test]
+async fn drops_messages_past_the_queue_limit() {
+    let config = Config {
+        maximum_queued_messages: 2,
+        queue_drop_policy: DropPolicy::Oldest,
+        ..Default::default()
+    };
+    let addr = broker(config).await;
+    let (mut subscriber, _) = Raw::connect(addr, resume("subscriber", 60)).await;
+    subscriber.subscribe("jobs", QoS::One).await;
+    subscriber.disconnect(Disconnect::default()).await;
+
+    let mut publisher = Client::connect(addr, "publisher").await;
+    for payload in ["first", "second", "third"] {
+        publish(&mut publisher, "jobs", QoS::One, payload).await;
+    }
+
+    let (mut subscriber, _) = Raw::connect(addr, resume("subscriber", 60)).await;
+    assert_eq!(&subscriber.publish().await.payload[..], b"second");
+    assert_eq!(&subscriber.publish().await.payload[..], b"third");
+}
+
+#[tokio::test]
+async fn sends_unacknowledged_messages_again_on_resume() {
+    let addr = broker(Config::default()).await;
+    let (mut subscriber, _) = Raw::connect(addr, resume("subscriber", 60)).await;
+    subscriber.subscribe("jobs", QoS::One).await;
+
+    let mut publisher = Client::connect(addr, "publisher").await;
+    publish(&mut publisher, "jobs", QoS::One, "first").await;
+    let first = subscriber.publish().await;
+    assert!(!first.dup);
+    // the connection drops before the PUBACK is sent
+    drop(subscriber);
+    tokio::time::sleep(WAIT).await;
+
+    let (mut subscriber, connack) = Raw::connect(addr, resume("subscriber", 60)).await;
+    assert!(connack.session_present);
+    let again = subscriber.publish().await;
+    assert!(again.dup);
+    assert_eq!(again.pkid, first.pkid);
+    assert_eq!(&again.payload[..], b"first");
+}
+
+#[tokio::test]
+async fn clean_start_discards_the_session() {
+    let addr = broker(Config::default()).await;
+    let (mut subscriber, _) = Raw::connect(addr, resume("subscriber", 60)).await;
+    subscriber.subscribe("jobs", QoS::One).await;
+    subscriber.disconnect(Disconnect::default()).await;
+
+    let mut connect = resume("subscriber", 60);
+    connect.clean_start = true;
+    let (_, connack) = Raw::connect(addr, connect).await;
+    assert!(!connack.session_present);
+
+    let mut publisher = Client::connect(addr, "publisher").await;
+    publish(&mut publisher, "jobs", QoS::One, "first").await;
+    let (_, connack) = Raw::connect(addr, resume("subscriber", 60)).await;
+    assert!(connack.session_present);
+}
+
+#[tokio::test]
+async fn sessions_expire_after_their_interval() {
+    let addr = broker(Config::default()).await;
+    let (subscriber, _) = Raw::connect(addr, resume("subscriber", 1)).await;
+    subscriber.disconnect(Disconnect::default()).await;
+
+    tokio::time::sleep(Duration::from_millis(1500)).await;
+    let (_, connack) = Raw::connect(addr, resume("subscriber", 1)).await;
+    assert!(!connack.session_present);
+}
+
+#[tokio::test]
+async fn disconnect_can_end_the_session() {
+    let addr = broker(Config::default()).await;
+    let (subscriber, _) = Raw::connect(addr, resume("subscriber", 60)).await;
+    subscriber
+        .disconnect(Disconnect {
+            properties: DisconnectProperties {
+                session_expiry_interval: Some(0),
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .await;
+
+    let (_, connack) = Raw::connect(addr, resume("subscriber", 60)).await;
+    assert!(!connack.session_present);
+}
+
+#[tokio::test]
+async fn caps_the_session_expiry_interval() {
+    let config = Config {
+        maximum_session_expiry_interval: 10,
+        ..Default::default()
+    };
+    let addr = broker(config).await;
+    let (_, connack) = Raw::connect(addr, resume("capped", 60)).await;
+    assert_eq!(connack.properties.session_expiry_interval, Some(10));
+
+    let (_, connack) = Raw::connect(addr, resume("granted", 5)).await;
+    assert_eq!(connack.properties.session_expiry_interval, None);
+}
+
+#[tokio::test]
+async fn refuses_a_session_expiry_interval_set_on_disconnect_only() {
+    let addr = broker(Config::default()).await;
+    let (mut raw, _) = Raw::connect(addr, connect("client")).await;
+    raw.send(Packet::Disconnect(Disconnect {
+        properties: DisconnectProperties {
+            session_expiry_interval: Some(60),
+            ..Default::default()
+        },
+        ..Default::default()
+    }))
+    .await;
+
+    let Some(Packet::Disconnect(disconnect)) = raw.recv().await else {
+        panic!("expected a DISCONNECT");
+    };
+    assert_eq!(disconnect.reason_code, DisconnectReasonCode::ProtocolError);
+}
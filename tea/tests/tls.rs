@@ -0,0 +1,280 @@
+//! Listeners serving MQTT over TLS 1.3, with and without client certificates
+#![cfg(feature = "tls")]
+
+mod common;
+
+use std::{sync::Arc, time::Duration};
+
+use common::{
+    connect, login, publish,
+    tls::{connect_mtls, connector, mtls_broker, tls_broker, Authority, TempDir},
+    Client, Raw, WAIT,
+};
+use mqttea::{AclFile, Broker, ClientIdentity, Config, TlsConfig, TlsFiles};
+use mqttea_core::v5::{
+    client::{
+        network::{
+            asyncx::Network,
+            outgoing::OutgoingBuffer,
+            tls::{self as client_tls, TlsConnector},
+        },
+        ConnectOptions,
+    },
+    commons::{packet::Packet, qos::QoS},
+    packet::{
+        connack::ConnAckReasonCode, ping::PingReq, puback::PubAckReasonCode, publish::Publish,
+    },
+};
+use tokio::net::TcpStream;
+use tokio_rustls::rustls::ProtocolVersion;
+use tokio_util::compat::TokioAsyncReadCompatExt;
+
+#[tokio::test]
+async fn serves_clients_over_tls() {
+    let ca = Authority::new();
+    let addr = tls_broker(Config::default(), ca.broker()).await;
+    let connector = connector(&ca, None);
+
+    let mut subscriber = Client::connect_tls(addr, "subscriber", &connector).await;
+    subscriber.subscribe("sensors/+", QoS::One).await;
+    let mut publisher = Client::connect_tls(addr, "publisher", &connector).await;
+    publish(&mut publisher, "sensors/temperature", QoS::One, "21.5").await;
+
+    assert_eq!(&subscriber.publish().await.payload[..], b"21.5");
+}
+
+#[tokio::test]
+async fn negotiates_tls_1_3_and_mqtt_through_alpn() {
+    let ca = Authority::new();
+    let addr = tls_broker(Config::default(), ca.broker()).await;
+
+    let stream = TcpStream::connect(addr).await.unwrap();
+    let stream = connector(&ca, None).connect(stream.compat()).await.unwrap();
+    let (_, session) = stream.get_ref();
+    assert_eq!(session.protocol_version(), Some(ProtocolVersion::TLSv1_3));
+    assert_eq!(session.alpn_protocol(), Some(&b"mqtt"[..]));
+}
+
+#[tokio::test]
+async fn the_handshake_and_connect_share_the_connect_timeout() {
+    let config = Config {
+        connect_timeout: Duration::from_millis(400),
+        ..Default::default()
+    };
+    let ca = Authority::new();
+    let addr = tls_broker(config, ca.broker()).await;
+    let connector = connector(&ca, None);
+    let slow = Duration::from_millis(250);
+
+    let connect_slowly = |connect_after| {
+        let connector = &connector;
+        async move {
+            let stream = TcpStream::connect(addr).await.unwrap();
+            tokio::time::sleep(slow).await;
+            let stream = connector.connect(stream.compat()).await.unwrap();
+            tokio::time::sleep(connect_after).await;
+
+            let mut raw = Raw {
+                stream,
+                buffer: OutgoingBuffer::with_capacity(1024),
+            };
+            raw.buffer
+                .push(&Packet::Connect(connect("sensor")))
+                .unwrap();
+            // the broker may already be gone
+            let _ = raw.buffer.flush(&mut raw.stream).await;
+            raw.recv().await
+        }
+    };
+
+    let connack = connect_slowly(Duration::ZERO).await;
+    assert!(matches!(connack, Some(Packet::ConnAck(_))));
+    // each one is within the timeout, both together are not
+    assert_eq!(connect_slowly(slow).await, None);
+}
+
+#[tokio::test]
+async fn clients_refuse_brokers_they_do_not_trust() {
+    let addr = tls_broker(Config::default(), Authority::new().broker()).await;
+
+    let stream = TcpStream::connect(addr).await.unwrap();
+    let connector = connector(&Authority::new(), None);
+    let result = Network::with_tls(ConnectOptions::default(), stream.compat(), &connector).await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn mutual_tls_requires_a_trusted_client_certificate() {
+    let ca = Authority::new();
+    let addr = mtls_broker(Config::default(), &ca, ClientIdentity::ClientId).await;
+
+    for connector in [
+        connector(&ca, None),
+        connector(&ca, Some(Authority::new().client("sensor", None))),
+    ] {
+        let stream = TcpStream::connect(addr).await.unwrap();
+        let options = ConnectOptions {
+            client_id: String::from("sensor"),
+            ..Default::default()
+        };
+        let result = Network::with_tls(options, stream.compat(), &connector).await;
+        assert!(result.is_err());
+    }
+
+    let connector = connector(&ca, Some(ca.client("sensor", None)));
+    let (_, connack) = connect_mtls(addr, &connector, connect("sensor")).await;
+    assert_eq!(connack.reason, ConnAckReasonCode::Success);
+}
+
+#[tokio::test]
+async fn binds_the_client_identifier_to_the_certificate() {
+    let ca = Authority::new();
+    let addr = mtls_broker(Config::default(), &ca, ClientIdentity::ClientId).await;
+    let connector = connector(&ca, Some(ca.client("sensor-1", None)));
+
+    let (_, connack) = connect_mtls(addr, &connector, connect("sensor-2")).await;
+    assert_eq!(connack.reason, ConnAckReasonCode::ClientIdentifierNotValid);
+
+    let (_, connack) = connect_mtls(addr, &connector, connect("")).await;
+    assert_eq!(connack.reason, ConnAckReasonCode::Success);
+    assert_eq!(
+        connack.properties.assigned_client_id.as_deref(),
+        Some("sensor-1")
+    );
+}
+
+#[tokio::test]
+async fn hands_the_spiffe_id_to_authorization_as_the_username() {
+    let spiffe_id = "spiffe://example.org/sensor";
+    let acl = AclFile::parse(&format!("user {spiffe_id}\ntopic write sensors/#")).unwrap();
+    let config = Config {
+        authorizer: Some(Arc::new(acl)),
+        ..Default::default()
+    };
+    let ca = Authority::new();
+    let addr = mtls_broker(config, &ca, ClientIdentity::Username).await;
+    let connector = connector(&ca, Some(ca.client("sensor", Some(spiffe_id))));
+
+    let (_, connack) = connect_mtls(addr, &connector, login("sensor", "alice", "wonderland")).await;
+    assert_eq!(connack.reason, ConnAckReasonCode::BadUserNameOrPassword);
+
+    let (mut raw, _) = connect_mtls(addr, &connector, connect("sensor")).await;
+    for (topic, reason_code) in [
+        (
+            "sensors/temperature",
+            PubAckReasonCode::NoMatchingSubscribers,
+        ),
+        ("actuators/valve", PubAckReasonCode::NotAuthorized),
+    ] {
+        raw.send(Packet::Publish(Publish {
+            qos: QoS::One,
+            topic: topic.to_string(),
+            pkid: Some(1),
+            payload: "x".into(),
+            ..Default::default()
+        }))
+        .await;
+        let Some(Packet::PubAck(puback)) = raw.recv().await else {
+            panic!("expected a PUBACK");
+        };
+        assert_eq!(puback.reason_code, reason_code, "{topic}");
+    }
+}
+
+#[tokio::test]
+async fn rotates_the_broker_certificate_without_dropping_connections() {
+    let (old, new) = (Authority::new(), Authority::new());
+    let broker = Broker::bind_tls("127.0.0.1:0", Config::default(), old.broker())
+        .await
+        .unwrap();
+    let addr = broker.local_addr().unwrap();
+    let reloader = broker.tls_reloader().unwrap();
+    tokio::spawn(broker.run());
+
+    let mut subscriber = Client::connect_tls(addr, "subscriber", &connector(&old, None)).await;
+    subscriber.subscribe("sensors/+", QoS::One).await;
+
+    let err = reloader.reload(&TlsConfig {
+        private_key: Vec::new(),
+        ..new.broker()
+    });
+    assert!(err.is_err());
+    reloader.reload(&new.broker()).unwrap();
+
+    let stream = TcpStream::connect(addr).await.unwrap();
+    let result = Network::with_tls(
+        ConnectOptions::default(),
+        stream.compat(),
+        &connector(&old, None),
+    )
+    .await;
+    assert!(result.is_err());
+
+    let mut publisher = Client::connect_tls(addr, "publisher", &connector(&new, None)).await;
+    publish(&mut publisher, "sensors/temperature", QoS::One, "21.5").await;
+    assert_eq!(&subscriber.publish().await.payload[..], b"21.5");
+}
+
+#[tokio::test]
+async fn reloads_the_broker_certificate_once_its_files_change() {
+    let dir = TempDir::new("broker");
+    let (old, new) = (Authority::new(), Authority::new());
+    let tls = old.broker();
+    let files = TlsFiles {
+        certificate_chain: dir.write("broker.pem", &tls.certificate_chain),
+        private_key: dir.write("broker.key", &tls.private_key),
+        ..Default::default()
+    };
+    let broker = Broker::bind_tls("127.0.0.1:0", Config::default(), files.load().unwrap())
+        .await
+        .unwrap();
+    let addr = broker.local_addr().unwrap();
+    let reloader = broker.tls_reloader().unwrap();
+    tokio::spawn(broker.run());
+    tokio::spawn(reloader.watch(files, Duration::from_millis(20)));
+
+    let tls = new.broker();
+    dir.write("broker.pem", &tls.certificate_chain);
+    dir.write("broker.key", &tls.private_key);
+    tokio::time::sleep(WAIT).await;
+
+    let mut client = Client::connect_tls(addr, "sensor", &connector(&new, None)).await;
+    client.subscribe("sensors/+", QoS::One).await;
+}
+
+#[tokio::test]
+async fn reconnects_with_the_client_certificate_it_was_rotated_to() {
+    let dir = TempDir::new("client");
+    let ca = Authority::new();
+    let addr = mtls_broker(Config::default(), &ca, ClientIdentity::ClientId).await;
+    let (cert, key) = ca.client("sensor-1", None);
+    let files = client_tls::TlsFiles {
+        host: String::from("localhost"),
+        ca: dir.write("ca.pem", ca.pem()),
+        client_cert: Some(dir.write("client.pem", cert)),
+        client_key: Some(dir.write("client.key", key)),
+    };
+    let connector = TlsConnector::new(&files.load().unwrap()).unwrap();
+    tokio::spawn(connector.clone().watch(files, Duration::from_millis(20)));
+
+    let (mut raw, connack) = connect_mtls(addr, &connector, connect("")).await;
+    assert_eq!(
+        connack.properties.assigned_client_id.as_deref(),
+        Some("sensor-1")
+    );
+
+    let (cert, key) = ca.client("sensor-2", None);
+    dir.write("client.pem", cert);
+    dir.write("client.key", key);
+    tokio::time::sleep(WAIT).await;
+
+    // the session made with the former certificate carries on
+    raw.send(Packet::PingReq(PingReq {})).await;
+    assert!(matches!(raw.recv().await, Some(Packet::PingResp(_))));
+
+    let (_, connack) = connect_mtls(addr, &connector, connect("")).await;
+    assert_eq!(
+        connack.properties.assigned_client_id.as_deref(),
+        Some("sensor-2")
+    );
+}
@@ -0,0 +1,49 @@
+//! Connections made without TCP: over Unix domain sockets and in memory
+
+mod common;
+
+#[cfg(unix)]
+use std::io;
+
+use common::{publish, Client, WAIT};
+use mqttea::{Broker, Config};
+use mqttea_core::v5::commons::qos::QoS;
+use tokio::time::timeout;
+
+#[cfg(unix)]
+#[tokio::test]
+async fn serves_clients_over_unix_sockets() {
+    let path = std::env::temp_dir().join(format!("mqttea-{}.sock", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let broker = Broker::bind_unix(&path, Config::default()).await.unwrap();
+    assert_eq!(
+        broker.local_addr().unwrap_err().kind(),
+        io::ErrorKind::Unsupported
+    );
+    tokio::spawn(broker.run());
+
+    let mut subscriber = Client::connect_unix(&path, "subscriber").await;
+    subscriber.subscribe("sensors/+", QoS::One).await;
+    let mut publisher = Client::connect_unix(&path, "publisher").await;
+    publish(&mut publisher, "sensors/temperature", QoS::One, "21.5").await;
+
+    assert_eq!(&subscriber.publish().await.payload[..], b"21.5");
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[tokio::test]
+async fn serves_clients_in_memory() {
+    let (broker, connector) = Broker::in_memory(Config::default()).unwrap();
+    let running = tokio::spawn(broker.run());
+
+    let mut subscriber = Client::connect_in_memory(&connector, "subscriber").await;
+    subscriber.subscribe("sensors/+", QoS::Two).await;
+    let mut publisher = Client::connect_in_memory(&connector, "publisher").await;
+    publish(&mut publisher, "sensors/temperature", QoS::Two, "21.5").await;
+
+    assert_eq!(&subscriber.publish().await.payload[..], b"21.5");
+
+    // the broker stops once nobody can connect to it anymore
+    drop(connector);
+    timeout(WAIT, running).await.unwrap().unwrap().unwrap();
+}
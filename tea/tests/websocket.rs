@@ -0,0 +1,147 @@
+//! Listeners serving MQTT over WebSocket, in binary frames
+#![cfg(feature = "websocket")]
+
+mod common;
+
+use std::{net::SocketAddr, time::Duration};
+
+#[cfg(feature = "tls")]
+use common::tls::{connector, Authority};
+use common::{connect, publish, Client, Raw};
+use futures::AsyncWriteExt;
+use mqttea::{Broker, Config};
+#[cfg(feature = "tls")]
+use mqttea_core::v5::client::{network::asyncx::Network, ConnectOptions};
+use mqttea_core::v5::{
+    client::network::{outgoing::OutgoingBuffer, websocket},
+    commons::{packet::Packet, qos::QoS},
+    packet::subscribe::{Subscribe, SubscriptionOptions},
+};
+use tokio::net::TcpStream;
+use tokio_util::compat::TokioAsyncReadCompatExt;
+
+async fn websocket_broker(config: Config) -> SocketAddr {
+    let broker = Broker::bind_websocket("127.0.0.1:0", config).await.unwrap();
+    let addr = broker.local_addr().unwrap();
+    tokio::spawn(broker.run());
+    addr
+}
+
+#[tokio::test]
+async fn serves_clients_over_websocket() {
+    let addr = websocket_broker(Config::default()).await;
+
+    let mut subscriber = Client::connect_websocket(addr, "subscriber").await;
+    subscriber.subscribe("sensors/+", QoS::One).await;
+    let mut publisher = Client::connect_websocket(addr, "publisher").await;
+    publish(&mut publisher, "sensors/temperature", QoS::One, "21.5").await;
+
+    assert_eq!(&subscriber.publish().await.payload[..], b"21.5");
+}
+
+#[tokio::test]
+async fn reads_packets_split_across_and_coalesced_within_frames() {
+    let addr = websocket_broker(Config::default()).await;
+    let stream = TcpStream::connect(addr).await.unwrap();
+    let url = format!("ws://{addr}/mqtt");
+    let mut stream = websocket::connect(&url, stream.compat()).await.unwrap();
+
+    let mut buffer = OutgoingBuffer::with_capacity(1024);
+    let mut encoded = futures::io::Cursor::new(Vec::new());
+    buffer.push(&Packet::Connect(connect("sensor"))).unwrap();
+    buffer
+        .push(&Packet::Subscribe(Subscribe {
+            pkid: 1,
+            payload: vec![(String::from("sensors/+"), SubscriptionOptions::default())],
+            ..Default::default()
+        }))
+        .unwrap();
+    buffer.flush(&mut encoded).await.unwrap();
+
+    // every write is a frame of its own, the first one ending halfway through the CONNECT
+    let encoded = encoded.into_inner();
+    let (head, tail) = encoded.split_at(5);
+    for frame in [head, tail] {
+        stream.write_all(frame).await.unwrap();
+        stream.flush().await.unwrap();
+    }
+
+    let mut raw = Raw {
+        stream,
+        buffer: OutgoingBuffer::with_capacity(1024),
+    };
+    assert!(matches!(raw.recv().await, Some(Packet::ConnAck(_))));
+    assert!(matches!(raw.recv().await, Some(Packet::SubAck(_))));
+}
+
+/// Opens a WebSocket `handshake_after` the connection is made, and sends a CONNECT over it `connect_after` that
+async fn connect_slowly(
+    addr: SocketAddr,
+    handshake_after: Duration,
+    connect_after: Duration,
+) -> Option<Packet> {
+    let stream = TcpStream::connect(addr).await.unwrap();
+    tokio::time::sleep(handshake_after).await;
+    let url = format!("ws://{addr}/mqtt");
+    let stream = websocket::connect(&url, stream.compat()).await.unwrap();
+    tokio::time::sleep(connect_after).await;
+
+    let mut raw = Raw {
+        stream,
+        buffer: OutgoingBuffer::with_capacity(1024),
+    };
+    raw.buffer
+        .push(&Packet::Connect(connect("sensor")))
+        .unwrap();
+    // the broker may already be gone
+    let _ = raw.buffer.flush(&mut raw.stream).await;
+    raw.recv().await
+}
+
+#[tokio::test]
+async fn the_handshake_and_connect_share_the_connect_timeout() {
+    let config = Config {
+        connect_timeout: Duration::from_millis(400),
+        ..Default::default()
+    };
+    let addr = websocket_broker(config).await;
+    let slow = Duration::from_millis(250);
+
+    let connack = connect_slowly(addr, slow, Duration::ZERO).await;
+    assert!(matches!(connack, Some(Packet::ConnAck(_))));
+    // each one is within the timeout, both together are not
+    assert_eq!(connect_slowly(addr, slow, slow).await, None);
+}
+
+#[tokio::test]
+async fn refuses_websockets_without_the_mqtt_subprotocol() {
+    let addr = websocket_broker(Config::default()).await;
+    let stream = TcpStream::connect(addr).await.unwrap();
+
+    let result =
+        async_tungstenite::client_async(format!("ws://{addr}/mqtt"), stream.compat()).await;
+    assert!(result.is_err());
+}
+
+#[cfg(feature = "tls")]
+#[tokio::test]
+async fn serves_clients_over_secure_websocket() {
+    let ca = Authority::new();
+    let broker = Broker::bind_websocket_tls("127.0.0.1:0", Config::default(), ca.broker())
+        .await
+        .unwrap();
+    let addr = broker.local_addr().unwrap();
+    tokio::spawn(broker.run());
+
+    let stream = TcpStream::connect(addr).await.unwrap();
+    let stream = connector(&ca, None).connect(stream.compat()).await.unwrap();
+    let options = ConnectOptions {
+        client_id: String::from("sensor"),
+        ..Default::default()
+    };
+    let (network, client) = Network::with_websocket(options, "wss://localhost/mqtt", stream)
+        .await
+        .unwrap();
+    let mut client = Client::start(network, client);
+    client.subscribe("sensors/+", QoS::One).await;
+}
@@ -0,0 +1,108 @@
+//! Will messages, published once a client is given up on
+
+mod common;
+
+use std::time::Duration;
+
+use common::{broker, connect, resume, with_will, Client, Raw, WAIT};
+use mqttea::Config;
+use mqttea_core::v5::{
+    commons::qos::QoS,
+    packet::disconnect::{Disconnect, DisconnectReasonCode},
+};
+
+#[tokio::test]
+async fn publishes_the_will_when_the_connection_drops() {
+    let addr = broker(Config::default()).await;
+    let mut subscriber = Client::connect(addr, "subscriber").await;
+    subscriber.subscribe("status/+", QoS::One).await;
+
+    let (sensor, _) = Raw::connect(addr, with_will(connect("sensor"), None, false)).await;
+    drop(sensor);
+
+    let publish = subscriber.publish().await;
+    assert_eq!(publish.topic, "status/sensor");
+    assert_eq!(&publish.payload[..], b"offline");
+    assert_eq!(publish.qos, QoS::One);
+}
+
+#[tokio::test]
+async fn publishes_the_will_when_keep_alive_runs_out() {
+    let addr = broker(Config::default()).await;
+    let mut subscriber = Client::connect(addr, "subscriber").await;
+    subscriber.subscribe("status/+", QoS::One).await;
+
+    let mut connect = with_will(connect("sensor"), None, false);
+    connect.keep_alive = 1;
+    let (_sensor, _) = Raw::connect(addr, connect).await;
+
+    assert_eq!(subscriber.publish().await.topic, "status/sensor");
+}
+
+#[tokio::test]
+async fn normal_disconnect_discards_the_will() {
+    let addr = broker(Config::default()).await;
+    let mut subscriber = Client::connect(addr, "subscriber").await;
+    subscriber.subscribe("status/+", QoS::One).await;
+
+    let (sensor, _) = Raw::connect(addr, with_will(connect("sensor"), None, false)).await;
+    sensor.disconnect(Disconnect::default()).await;
+    subscriber.assert_silent().await;
+
+    let (sensor, _) = Raw::connect(addr, with_will(connect("sensor"), None, false)).await;
+    sensor
+        .disconnect(Disconnect {
+            reason_code: DisconnectReasonCode::DisconnectWithWillMessage,
+            ..Default::default()
+        })
+        .await;
+    assert_eq!(subscriber.publish().await.topic, "status/sensor");
+}
+
+#[tokio::test]
+async fn delays_the_will_until_the_client_is_given_up_on() {
+    let addr = broker(Config::default()).await;
+    let mut subscriber = Client::connect(addr, "subscriber").await;
+    subscriber.subscribe("status/+", QoS::One).await;
+
+    let connect = || with_will(resume("sensor", 60), Some(1), false);
+    let (sensor, _) = Raw::connect(addr, connect()).await;
+    drop(sensor);
+    subscriber.assert_silent().await;
+    assert_eq!(subscriber.publish().await.topic, "status/sensor");
+
+    // coming back before the delay is over cancels the will
+    let (sensor, _) = Raw::connect(addr, connect()).await;
+    drop(sensor);
+    tokio::time::sleep(WAIT).await;
+    let (_sensor, connack) = Raw::connect(addr, connect()).await;
+    assert!(connack.session_present);
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    subscriber.assert_silent().await;
+}
+
+#[tokio::test]
+async fn publishes_the_will_as_soon_as_the_session_ends() {
+    let addr = broker(Config::default()).await;
+    let mut subscriber = Client::connect(addr, "subscriber").await;
+    subscriber.subscribe("status/+", QoS::One).await;
+
+    // the session doesn't outlive the connection, so the delay is cut short
+    let (sensor, _) = Raw::connect(addr, with_will(connect("sensor"), Some(60), false)).await;
+    drop(sensor);
+    assert_eq!(subscriber.publish().await.topic, "status/sensor");
+}
+
+#[tokio::test]
+async fn retains_the_will_when_asked_to() {
+    let addr = broker(Config::default()).await;
+    let (sensor, _) = Raw::connect(addr, with_will(connect("sensor"), None, true)).await;
+    drop(sensor);
+    tokio::time::sleep(WAIT).await;
+
+    let mut subscriber = Client::connect(addr, "subscriber").await;
+    subscriber.subscribe("status/+", QoS::One).await;
+    let publish = subscriber.publish().await;
+    assert!(publish.retain);
+    assert_eq!(&publish.payload[..], b"offline");
+}